@@ -1,10 +1,10 @@
-function update(scene_id, entity_id)
-    --print("Updating entity " .. entity_id .. " in scene " .. scene_id)
+function update(self, dt)
+    --print("Updating entity " .. self.id .. " in scene " .. self.scene_id)
 
     local velocity_x = -50.0
     local velocity_y = 0.0
 
-    set_velocity(entity_id, velocity_x, velocity_y)
+    set_velocity(self, velocity_x, velocity_y)
 
-    --print("Velocity set to (" .. velocity_x .. ", " .. velocity_y .. ") for entity " .. entity_id)
-end
\ No newline at end of file
+    --print("Velocity set to (" .. velocity_x .. ", " .. velocity_y .. ") for entity " .. self.id)
+end
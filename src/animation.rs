@@ -0,0 +1,502 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+// SpriteAnimation (component of an Entity)
+// └── FrameSource: where the frames come from (the entity's images, a grid or rectangles of a sprite sheet)
+// └── AnimationClip: named sequence of frames with their durations and loop mode
+// └── Animation: playback of the current clip, not saved
+
+/// What happens when a clip reaches its last frame
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopMode {
+    #[default]
+    Loop,
+    /// Stops on the last frame and reports the animation as finished
+    Once,
+    /// Plays forwards, then backwards, forever
+    PingPong,
+}
+
+impl LoopMode {
+    pub const ALL: [LoopMode; 3] = [LoopMode::Loop, LoopMode::Once, LoopMode::PingPong];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoopMode::Loop => "loop",
+            LoopMode::Once => "once",
+            LoopMode::PingPong => "ping_pong",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.as_str() == name)
+    }
+}
+
+/// Rectangle of a sprite sheet in pixels, from its top left corner
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FrameRect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+}
+
+/// Where the frames of an animation come from. Images are given by their index in the entity's images.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrameSource {
+    /// Each of the entity's images is one frame
+    #[default]
+    Images,
+    /// An image cut into equal cells, numbered row by row. `count` leaves out empty cells at the end.
+    Grid {
+        image: usize,
+        columns: u32,
+        rows: u32,
+        #[serde(default)]
+        count: Option<u32>,
+    },
+    /// Rectangles of an image, one per frame
+    Rects { image: usize, rects: Vec<FrameRect> },
+}
+
+/// Part of a texture that makes up one frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameRegion {
+    Whole,
+    Cell { column: u32, row: u32, columns: u32, rows: u32 },
+    Rect(FrameRect),
+}
+
+impl FrameRegion {
+    /// Texture coordinates of the region, from 0 to 1
+    pub fn uv_rect(&self, texture_size: (u32, u32)) -> ((f32, f32), (f32, f32)) {
+        match *self {
+            FrameRegion::Whole => ((0.0, 0.0), (1.0, 1.0)),
+            FrameRegion::Cell { column, row, columns, rows } => {
+                let (width, height) = (1.0 / columns as f32, 1.0 / rows as f32);
+                let min = (column as f32 * width, row as f32 * height);
+                (min, (min.0 + width, min.1 + height))
+            }
+            FrameRegion::Rect(rect) => {
+                let (width, height) = (texture_size.0.max(1) as f32, texture_size.1.max(1) as f32);
+                (
+                    (rect.x as f32 / width, rect.y as f32 / height),
+                    ((rect.x + rect.width) as f32 / width, (rect.y + rect.height) as f32 / height),
+                )
+            }
+        }
+    }
+
+    /// Size of the region in pixels
+    pub fn pixel_size(&self, texture_size: (u32, u32)) -> (f32, f32) {
+        match *self {
+            FrameRegion::Whole => (texture_size.0 as f32, texture_size.1 as f32),
+            FrameRegion::Cell { columns, rows, .. } => {
+                (texture_size.0 as f32 / columns as f32, texture_size.1 as f32 / rows as f32)
+            }
+            FrameRegion::Rect(rect) => (rect.width as f32, rect.height as f32),
+        }
+    }
+}
+
+impl FrameSource {
+    pub fn frame_count(&self, image_count: usize) -> usize {
+        match self {
+            FrameSource::Images => image_count,
+            FrameSource::Grid { columns, rows, count, .. } => {
+                let cells = (*columns as usize) * (*rows as usize);
+                count.map_or(cells, |count| (count as usize).min(cells))
+            }
+            FrameSource::Rects { rects, .. } => rects.len(),
+        }
+    }
+
+    /// Image and region of a frame, None if the frame or its image doesn't exist
+    pub fn frame<'a>(&self, index: usize, images: &'a [PathBuf]) -> Option<(&'a Path, FrameRegion)> {
+        if index >= self.frame_count(images.len()) {
+            return None;
+        }
+        match self {
+            FrameSource::Images => Some((images[index].as_path(), FrameRegion::Whole)),
+            FrameSource::Grid { image, columns, rows, .. } => {
+                let region = FrameRegion::Cell {
+                    column: index as u32 % columns,
+                    row: index as u32 / columns,
+                    columns: *columns,
+                    rows: *rows,
+                };
+                Some((images.get(*image)?.as_path(), region))
+            }
+            FrameSource::Rects { image, rects } => Some((images.get(*image)?.as_path(), FrameRegion::Rect(rects[index]))),
+        }
+    }
+}
+
+/// Parses a list of frames written as `0, 1, 4-7` (ranges include both ends).
+pub fn parse_frame_list(text: &str) -> Result<Vec<usize>, String> {
+    let mut frames = Vec::new();
+    for item in text.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let parse = |number: &str| number.trim().parse::<usize>().map_err(|_| format!("Invalid frame '{}'", item));
+        match item.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first <= last {
+                    frames.extend(first..=last);
+                } else {
+                    frames.extend((last..=first).rev());
+                }
+            }
+            None => frames.push(parse(item)?),
+        }
+    }
+    Ok(frames)
+}
+
+/// Formats frames the way `parse_frame_list` reads them.
+pub fn format_frame_list(frames: &[usize]) -> String {
+    frames.iter().map(|frame| frame.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Parses frame rectangles written as `x y width height, ...`.
+pub fn parse_frame_rects(text: &str) -> Result<Vec<FrameRect>, String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|rect| !rect.is_empty())
+        .map(|rect| {
+            let values: Result<Vec<u32>, _> = rect.split_whitespace().map(str::parse::<u32>).collect();
+            match values.as_deref() {
+                Ok([x, y, width, height]) => Ok(FrameRect::new(*x, *y, *width, *height)),
+                Ok(_) => Err(format!("Frame rectangle '{}' should be 'x y width height'", rect)),
+                Err(_) => Err(format!("Invalid frame rectangle '{}'", rect)),
+            }
+        })
+        .collect()
+}
+
+/// Formats frame rectangles the way `parse_frame_rects` reads them.
+pub fn format_frame_rects(rects: &[FrameRect]) -> String {
+    rects
+        .iter()
+        .map(|rect| format!("{} {} {} {}", rect.x, rect.y, rect.width, rect.height))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A named sequence of frames, such as "idle" or "flap"
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AnimationClip {
+    /// Indices into the frames of the source
+    pub frames: Vec<usize>,
+    /// Seconds each frame is shown
+    pub frame_duration: f32,
+    /// Durations of single frames, used instead of `frame_duration` where given
+    #[serde(default)]
+    pub frame_durations: Vec<f32>,
+    #[serde(default)]
+    pub loop_mode: LoopMode,
+}
+
+impl AnimationClip {
+    pub fn new(frames: Vec<usize>, frame_duration: f32) -> Self {
+        Self {
+            frames,
+            frame_duration,
+            frame_durations: Vec::new(),
+            loop_mode: LoopMode::Loop,
+        }
+    }
+
+    pub fn with_loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    pub fn with_frame_durations(mut self, frame_durations: Vec<f32>) -> Self {
+        self.frame_durations = frame_durations;
+        self
+    }
+
+    pub fn duration_of(&self, frame: usize) -> f32 {
+        self.frame_durations.get(frame).copied().unwrap_or(self.frame_duration)
+    }
+
+    /// Playback of this clip from its first frame
+    pub fn player(&self) -> Animation {
+        let durations = (0..self.frames.len()).map(|frame| self.duration_of(frame)).collect();
+        let mut animation = Animation::with_frame_durations(durations);
+        animation.set_loop_mode(self.loop_mode);
+        animation
+    }
+}
+
+/// Animation component of an entity
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct SpriteAnimation {
+    pub source: FrameSource,
+    pub clips: IndexMap<String, AnimationClip>,
+    /// Clip started when the game starts
+    #[serde(default)]
+    pub autoplay: Option<String>,
+    // Name and playback of the current clip
+    #[serde(skip)]
+    playing: Option<(String, Animation)>,
+}
+
+/// Sent when a clip that doesn't loop reaches its last frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationFinished {
+    pub entity_id: Uuid,
+    pub clip: String,
+}
+
+impl SpriteAnimation {
+    pub fn new(source: FrameSource) -> Self {
+        Self {
+            source,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_clip(&mut self, name: &str, clip: AnimationClip) -> Result<(), String> {
+        if name.trim().is_empty() {
+            return Err("Clip name cannot be empty".to_string());
+        }
+        if clip.frames.is_empty() {
+            return Err(format!("Clip '{}' has no frames", name));
+        }
+        if clip.frame_duration <= 0.0 || clip.frame_durations.iter().any(|duration| *duration <= 0.0) {
+            return Err(format!("Frame durations of clip '{}' must be positive", name));
+        }
+        self.clips.insert(name.to_string(), clip);
+        Ok(())
+    }
+
+    /// Starts a clip from its first frame. A clip that is already playing keeps going.
+    pub fn play(&mut self, name: &str) -> Result<(), String> {
+        let clip = self.clips.get(name).ok_or_else(|| format!("Animation clip '{}' not found", name))?;
+        if let Some((current, animation)) = &mut self.playing {
+            if current == name && animation.is_playing() {
+                return Ok(());
+            }
+        }
+        self.playing = Some((name.to_string(), clip.player()));
+        Ok(())
+    }
+
+    pub fn pause(&mut self) {
+        if let Some((_, animation)) = &mut self.playing {
+            animation.pause();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some((_, animation)) = &mut self.playing {
+            if !animation.is_finished() {
+                animation.play();
+            }
+        }
+    }
+
+    /// Stops on the first frame of the current clip
+    pub fn stop(&mut self) {
+        if let Some((_, animation)) = &mut self.playing {
+            animation.stop();
+        }
+    }
+
+    pub fn current_clip(&self) -> Option<&str> {
+        self.playing.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing.as_ref().is_some_and(|(_, animation)| animation.is_playing())
+    }
+
+    /// Index of the source frame to draw. Before anything plays this is the first frame of the autoplay clip.
+    pub fn current_frame(&self) -> usize {
+        let (clip, frame) = match &self.playing {
+            Some((name, animation)) => (Some(name), animation.get_current_frame_index()),
+            None => (self.autoplay.as_ref(), 0),
+        };
+        clip.and_then(|name| self.clips.get(name))
+            .and_then(|clip| clip.frames.get(frame).copied())
+            .unwrap_or(0)
+    }
+
+    /// Advances the current clip, starting the autoplay clip first if nothing played yet.
+    /// Returns the name of the clip if it finished during this update.
+    pub fn update(&mut self, delta_time: f32) -> Option<String> {
+        if self.playing.is_none() {
+            if let Some(autoplay) = self.autoplay.clone() {
+                if let Err(err) = self.play(&autoplay) {
+                    eprintln!("Cannot autoplay animation: {}", err);
+                    self.autoplay = None;
+                }
+            }
+        }
+
+        let (name, animation) = self.playing.as_mut()?;
+        animation.update(delta_time).then(|| name.clone())
+    }
+}
+
+/// Playback of a sequence of frames with their own durations
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    frame_durations: Vec<f32>,
+    current_frame: usize,
+    elapsed_time: f32,
+    is_playing: bool,
+    loop_mode: LoopMode,
+    playback_speed: f32,
+    // Ping-pong animations play backwards on every other pass
+    reversed: bool,
+    finished: bool,
+}
+
+impl Animation {
+    pub fn new(frame_count: usize, frame_duration: f32) -> Self {
+        Self::with_frame_durations(vec![frame_duration; frame_count])
+    }
+
+    pub fn with_frame_durations(frame_durations: Vec<f32>) -> Self {
+        Self {
+            frame_durations,
+            current_frame: 0,
+            elapsed_time: 0.0,
+            is_playing: true,
+            loop_mode: LoopMode::Loop,
+            playback_speed: 1.0,
+            reversed: false,
+            finished: false,
+        }
+    }
+
+    /// Advances by `delta_time` seconds, skipping frames if needed.
+    /// Returns true if the animation finished during this update.
+    pub fn update(&mut self, delta_time: f32) -> bool {
+        if !self.is_playing || self.frame_durations.is_empty() {
+            return false;
+        }
+
+        self.elapsed_time += delta_time * self.playback_speed;
+
+        // Zero durations would never let the loop end
+        while self.elapsed_time >= self.frame_durations[self.current_frame].max(f32::EPSILON) {
+            self.elapsed_time -= self.frame_durations[self.current_frame].max(f32::EPSILON);
+            if !self.advance() {
+                self.is_playing = false;
+                self.finished = true;
+                self.elapsed_time = 0.0;
+                return true;
+            }
+        }
+        false
+    }
+
+    // Moves to the next frame, false if a clip played once is at its end
+    fn advance(&mut self) -> bool {
+        let last = self.frame_durations.len() - 1;
+        match self.loop_mode {
+            LoopMode::Loop => self.current_frame = if self.current_frame >= last { 0 } else { self.current_frame + 1 },
+            LoopMode::Once => {
+                if self.current_frame >= last {
+                    return false;
+                }
+                self.current_frame += 1;
+            }
+            LoopMode::PingPong if last == 0 => {}
+            LoopMode::PingPong => {
+                if self.reversed && self.current_frame == 0 {
+                    self.reversed = false;
+                } else if !self.reversed && self.current_frame >= last {
+                    self.reversed = true;
+                }
+                if self.reversed {
+                    self.current_frame -= 1;
+                } else {
+                    self.current_frame += 1;
+                }
+            }
+        }
+        true
+    }
+
+    // Control methods
+    pub fn play(&mut self) {
+        if self.finished {
+            self.stop();
+        }
+        self.is_playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.is_playing = false;
+    }
+
+    pub fn stop(&mut self) {
+        self.is_playing = false;
+        self.current_frame = 0;
+        self.elapsed_time = 0.0;
+        self.reversed = false;
+        self.finished = false;
+    }
+
+    pub fn set_looping(&mut self, looping: bool) {
+        self.loop_mode = if looping { LoopMode::Loop } else { LoopMode::Once };
+    }
+
+    pub fn set_loop_mode(&mut self, loop_mode: LoopMode) {
+        self.loop_mode = loop_mode;
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.playback_speed = speed.max(0.0); // Prevent negative speed
+    }
+
+    pub fn set_frame(&mut self, frame: usize) {
+        if frame < self.frame_durations.len() {
+            self.current_frame = frame;
+            self.elapsed_time = 0.0;
+        }
+    }
+
+    // State queries
+    pub fn is_playing(&self) -> bool {
+        self.is_playing
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn loop_mode(&self) -> LoopMode {
+        self.loop_mode
+    }
+
+    pub fn get_frame_count(&self) -> usize {
+        self.frame_durations.len()
+    }
+
+    pub fn get_current_frame_index(&self) -> usize {
+        self.current_frame
+    }
+
+    pub fn get_progress(&self) -> f32 {
+        if self.frame_durations.len() <= 1 {
+            return 0.0;
+        }
+        self.current_frame as f32 / (self.frame_durations.len() - 1) as f32
+    }
+}
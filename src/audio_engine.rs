@@ -8,8 +8,9 @@ use crate::ecs::{Scene, Entity};
 use lofty::{Probe, AudioFile};
 
 pub struct AudioEngine {
-    stream: OutputStream,
-    stream_handle: OutputStreamHandle,
+    // Both None for the null backend, which decodes sounds but never outputs them
+    stream: Option<OutputStream>,
+    stream_handle: Option<OutputStreamHandle>,
     active_sounds: HashMap<Uuid, Sink>,
     sound_cache: HashMap<Uuid, Vec<u8>>,  // Path hash -> sound data
     immediate_sink: Option<Sink>,
@@ -17,8 +18,23 @@ pub struct AudioEngine {
 }
 
 impl AudioEngine {
+    // Uses the default output device, or the null backend if there is none
     pub fn new() -> Self {
-        let (stream, stream_handle) = OutputStream::try_default().unwrap();
+        match OutputStream::try_default() {
+            Ok((stream, stream_handle)) => Self::with_output(Some(stream), Some(stream_handle)),
+            Err(e) => {
+                eprintln!("No audio output available, sounds will not be heard: {}", e);
+                Self::new_null()
+            }
+        }
+    }
+
+    // Audio engine without an output device, for headless runs and tests
+    pub fn new_null() -> Self {
+        Self::with_output(None, None)
+    }
+
+    fn with_output(stream: Option<OutputStream>, stream_handle: Option<OutputStreamHandle>) -> Self {
         AudioEngine {
             stream,
             stream_handle,
@@ -29,6 +45,20 @@ impl AudioEngine {
         }
     }
 
+    pub fn is_null(&self) -> bool {
+        self.stream_handle.is_none()
+    }
+
+    // Sink to play a decoded sound on, None with the null backend
+    fn create_sink(&self) -> Result<Option<Sink>, String> {
+        match &self.stream_handle {
+            Some(stream_handle) => Sink::try_new(stream_handle)
+                .map(Some)
+                .map_err(|e| format!("Failed to create sink: {}", e)),
+            None => Ok(None),
+        }
+    }
+
     // Generate deterministic UUID from path
     fn path_to_uuid(path: &Path) -> Uuid {
         use sha2::{Sha256, Digest};
@@ -97,13 +127,12 @@ impl AudioEngine {
         let source = Decoder::new(cursor)
             .map_err(|e| format!("Failed to decode sound: {}", e))?;
 
-        let sink = Sink::try_new(&self.stream_handle)
-            .map_err(|e| format!("Failed to create sink: {}", e))?;
-        
-        sink.append(source);
-        
+        // With the null backend the sound is finished as soon as it is decoded
         let play_id = Uuid::new_v4();
-        self.active_sounds.insert(play_id, sink);
+        if let Some(sink) = self.create_sink()? {
+            sink.append(source);
+            self.active_sounds.insert(play_id, sink);
+        }
         
         Ok(play_id)
     }
@@ -122,11 +151,11 @@ impl AudioEngine {
         let source = Decoder::new(cursor)
             .map_err(|e| format!("Failed to decode sound: {}", e))?;
 
-        let sink = Sink::try_new(&self.stream_handle)
-            .map_err(|e| format!("Failed to create sink: {}", e))?;
-        
-        sink.append(source);
-        self.immediate_sink = Some(sink);
+        let sink = self.create_sink()?;
+        if let Some(sink) = &sink {
+            sink.append(source);
+        }
+        self.immediate_sink = sink;
         
         Ok(())
     }
@@ -0,0 +1,216 @@
+use uuid::Uuid;
+use crate::ecs::{AttributeType, AttributeValue, Entity, Scene, Transform};
+use crate::render_engine::Camera;
+
+/// What a camera entity sees: `size` (its width and height attributes) of the world at zoom 1
+/// around `center`, turned by `rotation`. A zoom of 2 shows half the width and height.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneCamera {
+    pub center: (f32, f32),
+    pub size: (f32, f32),
+    pub zoom: f32,
+    pub rotation: f32, // In radians
+}
+
+impl SceneCamera {
+    /// The active camera of the scene, its default camera
+    pub fn active(scene: &Scene) -> Option<Self> {
+        Self::from_entity(scene, scene.default_camera?)
+    }
+
+    pub fn from_entity(scene: &Scene, camera_id: Uuid) -> Option<Self> {
+        let entity = scene.get_entity(camera_id).ok().filter(|entity| entity.is_camera())?;
+        let world = scene.world_transform(camera_id).ok()?;
+        let positive = |value: f32, default: f32| if value > 0.0 { value } else { default };
+
+        Some(Self {
+            center: world.position,
+            size: (positive(entity.get_camera_width(), 800.0), positive(entity.get_camera_height(), 600.0)),
+            zoom: positive(entity.get_camera_zoom(), 1.0),
+            rotation: world.rotation,
+        })
+    }
+
+    /// Width and height of the world that is visible
+    pub fn visible_size(&self) -> (f32, f32) {
+        (self.size.0 / self.zoom, self.size.1 / self.zoom)
+    }
+
+    /// Corners of the visible area in the world: top left, top right, bottom right, bottom left
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        let (half_width, half_height) = (self.visible_size().0 / 2.0, self.visible_size().1 / 2.0);
+        let (sin, cos) = self.rotation.sin_cos();
+        [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(sx, sy)| {
+            let (x, y) = (sx * half_width, sy * half_height);
+            (self.center.0 + x * cos - y * sin, self.center.1 + x * sin + y * cos)
+        })
+    }
+
+    /// Largest part (x, y, width, height) of a viewport with the camera's aspect ratio, centered,
+    /// the rest of the viewport being letterbox bars
+    pub fn letterbox(&self, viewport_size: (f32, f32)) -> (f32, f32, f32, f32) {
+        let scale = (viewport_size.0 / self.size.0).min(viewport_size.1 / self.size.1);
+        let (width, height) = (self.size.0 * scale, self.size.1 * scale);
+        ((viewport_size.0 - width) / 2.0, (viewport_size.1 - height) / 2.0, width, height)
+    }
+
+    /// Render camera that draws this view into its letterboxed part of a viewport,
+    /// returned with it. Screen coordinates of the camera start at that part's top left corner.
+    pub fn view(&self, viewport_size: (f32, f32)) -> (Camera, (f32, f32, f32, f32)) {
+        let rect = self.letterbox(viewport_size);
+        let top_left = self.corners()[0];
+        let camera = Camera {
+            position: top_left,
+            zoom: rect.2 / self.visible_size().0,
+            rotation: self.rotation,
+        };
+        (camera, rect)
+    }
+}
+
+/// How a camera entity follows its target, from its optional attributes:
+/// `follow_target` (String, UUID or name of the entity to follow, empty for none),
+/// `follow_offset` (Vector2, point followed relative to the target's position),
+/// `follow_dead_zone` (Vector2, width and height of the area around the center the target
+/// can move in without moving the camera), `follow_smoothing` (Float, seconds the camera
+/// takes to catch up about two thirds of the way, 0 to snap) and `bounds_min` / `bounds_max`
+/// (Vector2, world area the view is kept in). Dead zone and bounds are axis aligned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraFollow {
+    pub target: Option<Uuid>,
+    pub offset: (f32, f32),
+    pub dead_zone: (f32, f32),
+    pub smoothing: f32,
+    pub bounds: Option<((f32, f32), (f32, f32))>,
+}
+
+impl CameraFollow {
+    pub fn from_entity(scene: &Scene, camera: &Entity) -> Self {
+        let attribute = |name: &str| camera.get_attribute_by_name(name).ok().map(|attr| &attr.value);
+        let vector2 = |name: &str| match attribute(name) {
+            Some(AttributeValue::Vector2(x, y)) => Some((*x, *y)),
+            _ => None,
+        };
+
+        let target = match attribute("follow_target") {
+            Some(AttributeValue::String(target)) if !target.trim().is_empty() => {
+                let target = target.trim();
+                Uuid::parse_str(target).ok()
+                    .filter(|id| scene.entities.contains_key(id))
+                    .or_else(|| scene.entities.values().find(|entity| entity.name == target).map(|entity| entity.id))
+            }
+            _ => None,
+        };
+
+        Self {
+            target,
+            offset: vector2("follow_offset").unwrap_or((0.0, 0.0)),
+            dead_zone: vector2("follow_dead_zone").unwrap_or((0.0, 0.0)),
+            smoothing: match attribute("follow_smoothing") {
+                Some(AttributeValue::Float(smoothing)) => smoothing.max(0.0),
+                _ => 0.0,
+            },
+            bounds: vector2("bounds_min").zip(vector2("bounds_max")),
+        }
+    }
+
+    /// Center of the camera after `delta_time` seconds, moving from `center` after `target`
+    /// (the followed point, if any). `visible_size` is the part of the world the camera shows.
+    pub fn next_center(&self, center: (f32, f32), target: Option<(f32, f32)>, visible_size: (f32, f32), delta_time: f32) -> (f32, f32) {
+        let mut next = center;
+
+        if let Some(target) = target {
+            // Only the part of the move that leaves the dead zone is followed
+            let follow_axis = |center: f32, target: f32, dead_zone: f32| {
+                let half = dead_zone.max(0.0) / 2.0;
+                if target > center + half {
+                    target - half
+                } else if target < center - half {
+                    target + half
+                } else {
+                    center
+                }
+            };
+            let desired = (
+                follow_axis(center.0, target.0, self.dead_zone.0),
+                follow_axis(center.1, target.1, self.dead_zone.1),
+            );
+
+            let amount = if self.smoothing > 0.0 {
+                1.0 - (-delta_time.max(0.0) / self.smoothing).exp()
+            } else {
+                1.0
+            };
+            next = (
+                center.0 + (desired.0 - center.0) * amount,
+                center.1 + (desired.1 - center.1) * amount,
+            );
+        }
+
+        if let Some((min, max)) = self.bounds {
+            // A view larger than the bounds is centered on them
+            let clamp_axis = |value: f32, min: f32, max: f32, visible: f32| {
+                let half = visible / 2.0;
+                if max - min <= visible {
+                    (min + max) / 2.0
+                } else {
+                    value.clamp(min + half, max - half)
+                }
+            };
+            next = (
+                clamp_axis(next.0, min.0, max.0, visible_size.0),
+                clamp_axis(next.1, min.1, max.1, visible_size.1),
+            );
+        }
+
+        next
+    }
+}
+
+/// Moves the active camera of the scene after its follow target and keeps it in its bounds
+pub fn update_follow(scene: &mut Scene, delta_time: f32) -> Result<(), String> {
+    let Some(camera_id) = scene.default_camera else {
+        return Ok(());
+    };
+    let Some(view) = SceneCamera::active(scene) else {
+        return Ok(());
+    };
+    let follow = CameraFollow::from_entity(scene, scene.get_entity(camera_id)?);
+
+    let target = match follow.target {
+        Some(target_id) => {
+            let world = scene.world_transform(target_id)?;
+            Some((world.position.0 + follow.offset.0, world.position.1 + follow.offset.1))
+        }
+        None => None,
+    };
+    if target.is_none() && follow.bounds.is_none() {
+        return Ok(());
+    }
+
+    let center = follow.next_center(view.center, target, view.visible_size(), delta_time);
+    if center == view.center {
+        return Ok(());
+    }
+
+    // The camera's attributes are relative to its parent
+    let local = match scene.parent_of(camera_id) {
+        Some(parent) => Transform::new().with_position(center.0, center.1).relative_to(&scene.world_transform(parent)?).position,
+        None => center,
+    };
+    let camera = scene.get_entity_mut(camera_id)?;
+    camera.set_x(local.0)?;
+    camera.set_y(local.1)
+}
+
+/// Makes a camera entity follow `target`, or stop following with None
+pub fn set_follow_target(camera: &mut Entity, target: Option<Uuid>) -> Result<(), String> {
+    if !camera.is_camera() {
+        return Err(format!("Entity '{}' is not a camera", camera.name));
+    }
+    let value = AttributeValue::String(target.map(|id| id.to_string()).unwrap_or_default());
+    match camera.get_attribute_by_name("follow_target").map(|attr| attr.id) {
+        Ok(id) => camera.modify_attribute(id, None, None, Some(value)),
+        Err(_) => camera.create_attribute("follow_target", AttributeType::String, value).map(|_| ()),
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+// CharacterController (optional component of an Entity with a physics body)
+// └── Moves the entity with `move_and_slide` instead of forces: it stops at walls, slides along them,
+//     climbs slopes and steps, and sticks to the ground when walking down
+// └── The entity's body is always kinematic (position based), whatever its `body_type`
+// Lengths are in world units, like positions.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CharacterController {
+    #[serde(default = "default_max_slope")]
+    pub max_slope: f32,      // Steepest slope it walks up, in degrees; steeper ones are walls
+    #[serde(default)]
+    pub step_height: f32,    // Highest step it climbs without jumping, 0 for none
+    #[serde(default)]
+    pub snap_to_ground: f32, // Distance to the ground it snaps down to when walking down, 0 to not snap
+}
+
+fn default_max_slope() -> f32 {
+    45.0
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self {
+            max_slope: default_max_slope(),
+            step_height: 0.0,
+            snap_to_ground: 4.0,
+        }
+    }
+}
+
+/// What a `move_and_slide` did
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CharacterMovement {
+    pub translation: (f32, f32), // How far the entity moved, after sliding
+    pub grounded: bool,          // Whether it stands on the ground at the end of the move
+}
@@ -3,7 +3,15 @@ use serde::{Serialize, Deserialize};
 use std::fmt;
 use indexmap::IndexMap;
 use rayon::prelude::*;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use crate::animation::{AnimationFinished, SpriteAnimation};
+use crate::text::Text;
+use crate::tilemap::Tilemap;
+use crate::joint::Joint;
+use crate::character_controller::CharacterController;
+use crate::gravity_source::GravitySource;
+use crate::physics_settings::PhysicsSettings;
 
 //SceneManager
 // └── Manages multiple Scenes
@@ -12,6 +20,12 @@ use std::path::PathBuf;
 //          Entity
 //          └── Manages its own Attributes
 //          └── Resource
+//          └── Optional parent Entity in the same Scene, its x/y/z are relative to the parent
+//          └── Optional link to the Prefab it was instantiated from
+//          └── Optional SpriteAnimation drawn from its images
+//      └── Scenes of a lazily loaded project stay unloaded until ProjectManager::load_scene reads them
+//      Prefab
+//      └── Template Entity, saved under assets/prefabs
 
 // =============== Scene Manager (Top Level) ===============
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -19,6 +33,10 @@ pub struct SceneManager {
     pub scenes: IndexMap<Uuid, Scene>,
     pub shared_entities: IndexMap<Uuid, Entity>,
     pub active_scene: Option<Uuid>,  // Track currently active scene
+    #[serde(skip)]
+    pub prefabs: IndexMap<Uuid, Prefab>, // Saved as separate files by the project manager
+    #[serde(skip)]
+    pub unloaded_scenes: IndexMap<Uuid, String>, // Scenes of the project not read yet, by name
 }
 
 impl SceneManager {
@@ -27,6 +45,8 @@ impl SceneManager {
             scenes: IndexMap::new(),
             shared_entities: IndexMap::new(),
             active_scene: None,
+            prefabs: IndexMap::new(),
+            unloaded_scenes: IndexMap::new(),
         }
     }
 
@@ -41,7 +61,20 @@ impl SceneManager {
         if self.active_scene == Some(id) {
             return Err("Cannot delete active scene".to_string());
         }
-        Ok(self.scenes.shift_remove(&id).is_some())
+        Ok(self.scenes.shift_remove(&id).is_some() || self.unloaded_scenes.shift_remove(&id).is_some())
+    }
+
+    /// False for scenes of a lazily loaded project that haven't been read yet
+    pub fn is_scene_loaded(&self, id: Uuid) -> bool {
+        self.scenes.contains_key(&id)
+    }
+
+    // Checks for references across all scenes would miss those in scenes that aren't loaded
+    fn require_all_scenes_loaded(&self, action: &str) -> Result<(), String> {
+        match self.unloaded_scenes.values().next() {
+            Some(name) => Err(format!("Load every scene before {} ('{}' isn't loaded yet)", action, name)),
+            None => Ok(()),
+        }
     }
 
     pub fn list_scene(&self) -> Vec<(Uuid, &str)> {
@@ -74,6 +107,7 @@ impl SceneManager {
     }
 
     pub fn delete_shared_entity(&mut self, id: Uuid) -> Result<bool, String> {
+        self.require_all_scenes_loaded("deleting a shared entity")?;
         for scene in self.scenes.values() {
             if scene.shared_entity_refs.contains(&id) {
                 return Err("Entity is still referenced by a scene".to_string());
@@ -105,11 +139,12 @@ impl SceneManager {
     }
 
     // Helper to get all scenes using a shared entity
-    pub fn get_scenes_using_entity(&self, entity_id: Uuid) -> Vec<&Scene> {
-        self.scenes
+    pub fn get_scenes_using_entity(&self, entity_id: Uuid) -> Result<Vec<&Scene>, String> {
+        self.require_all_scenes_loaded("looking for the scenes using a shared entity")?;
+        Ok(self.scenes
             .values()
             .filter(|scene| scene.shared_entity_refs.contains(&entity_id))
-            .collect()
+            .collect())
     }
 
     // Add these methods for active scene management
@@ -117,6 +152,8 @@ impl SceneManager {
         if self.scenes.contains_key(&id) {
             self.active_scene = Some(id);
             Ok(())
+        } else if let Some(name) = self.unloaded_scenes.get(&id) {
+            Err(format!("Scene '{}' isn't loaded yet", name))
         } else {
             Err("Scene not found".to_string())
         }
@@ -133,6 +170,148 @@ impl SceneManager {
     pub fn clear_active_scene(&mut self) {
         self.active_scene = None;
     }
+
+    // Prefab management
+    /// Turns an entity into a prefab; the entity becomes the prefab's first instance
+    pub fn create_prefab(&mut self, scene_id: Uuid, entity_id: Uuid, name: &str) -> Result<Uuid, String> {
+        let name = name.trim();
+        if name.is_empty() || name.contains(['/', '\\', '.']) {
+            return Err(format!("Invalid prefab name '{}'", name));
+        }
+        // Scripts spawn prefabs by name, and their files can't differ only by case on every system
+        if let Some(prefab) = self.prefabs.values().find(|prefab| prefab.name.to_lowercase() == name.to_lowercase()) {
+            return Err(format!("Prefab '{}' already exists", prefab.name));
+        }
+
+        let scene = self.scenes.get_mut(&scene_id).ok_or("Scene not found")?;
+        let entity = scene.get_entity_mut(entity_id)?;
+        if entity.prefab.is_some() {
+            return Err(format!("Entity '{}' is already a prefab instance", entity.name));
+        }
+
+        let prefab = Prefab::from_entity(name, entity);
+        entity.prefab = Some(PrefabLink::new(prefab.id));
+        let prefab_id = prefab.id;
+        self.prefabs.insert(prefab_id, prefab);
+        Ok(prefab_id)
+    }
+
+    /// Deletes a prefab that no entity is instantiated from anymore
+    pub fn delete_prefab(&mut self, prefab_id: Uuid) -> Result<bool, String> {
+        self.require_all_scenes_loaded("deleting a prefab")?;
+        if !self.instances_of(prefab_id).is_empty() {
+            return Err("Prefab still has instances".to_string());
+        }
+        Ok(self.prefabs.shift_remove(&prefab_id).is_some())
+    }
+
+    pub fn get_prefab(&self, prefab_id: Uuid) -> Option<&Prefab> {
+        self.prefabs.get(&prefab_id)
+    }
+
+    pub fn get_prefab_by_name(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.values().find(|prefab| prefab.name == name)
+    }
+
+    pub fn list_prefabs(&self) -> Vec<(Uuid, &str)> {
+        self.prefabs
+            .iter()
+            .map(|(id, prefab)| (*id, prefab.name.as_str()))
+            .collect()
+    }
+
+    /// (scene id, entity id) of every instance of the prefab
+    pub fn instances_of(&self, prefab_id: Uuid) -> Vec<(Uuid, Uuid)> {
+        self.scenes
+            .iter()
+            .flat_map(|(scene_id, scene)| {
+                scene.entities
+                    .values()
+                    .filter(move |entity| entity.prefab.as_ref().is_some_and(|link| link.prefab_id == prefab_id))
+                    .map(move |entity| (*scene_id, entity.id))
+            })
+            .collect()
+    }
+
+    pub fn instantiate_prefab(&mut self, scene_id: Uuid, prefab_id: Uuid, x: f32, y: f32) -> Result<Uuid, String> {
+        let prefab = self.prefabs.get(&prefab_id).ok_or("Prefab not found")?;
+        let entity = prefab.instantiate(x, y)?;
+        let entity_id = entity.id;
+        let scene = self.scenes.get_mut(&scene_id).ok_or("Scene not found")?;
+        scene.entities.insert(entity_id, entity);
+        Ok(entity_id)
+    }
+
+    /// Changes the prefab's template and updates its instances,
+    /// except for the attributes and assets they override
+    pub fn edit_prefab(&mut self, prefab_id: Uuid, edit: impl FnOnce(&mut Entity)) -> Result<(), String> {
+        self.require_all_scenes_loaded("editing a prefab")?;
+        let prefab = self.prefabs.get_mut(&prefab_id).ok_or("Prefab not found")?;
+        let previous = prefab.entity.clone();
+        edit(&mut prefab.entity);
+
+        let prefab = &self.prefabs[&prefab_id];
+        for scene in self.scenes.values_mut() {
+            for entity in scene.entities.values_mut() {
+                if entity.prefab.as_ref().is_some_and(|link| link.prefab_id == prefab_id) {
+                    prefab.sync_instance(entity, Some(&previous));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the instance's overridden attributes and its assets to the prefab, then updates the other instances.
+    /// Its joints stay on the instance.
+    pub fn apply_overrides_to_prefab(&mut self, scene_id: Uuid, entity_id: Uuid) -> Result<(), String> {
+        let scene = self.scenes.get_mut(&scene_id).ok_or("Scene not found")?;
+        let entity = scene.get_entity_mut(entity_id)?;
+        let link = entity.prefab.as_mut().ok_or_else(|| format!("Entity '{}' is not a prefab instance", entity.name))?;
+        let overrides = std::mem::take(&mut link.overrides);
+        let prefab_id = link.prefab_id;
+        let instance = entity.clone();
+
+        self.edit_prefab(prefab_id, |template| {
+            for name in &overrides {
+                let existing = template.get_attribute_by_name(name).map(|attr| attr.id);
+                match (instance.get_attribute_by_name(name), existing) {
+                    (Ok(attr), Ok(id)) => {
+                        if let Some(template_attr) = template.attributes.get_mut(&id) {
+                            template_attr.data_type = attr.data_type.clone();
+                            template_attr.value = attr.value.clone();
+                        }
+                    }
+                    (Ok(attr), Err(_)) => {
+                        let _ = template.create_attribute(name, attr.data_type.clone(), attr.value.clone());
+                    }
+                    // Deleted on the instance
+                    (Err(_), Ok(id)) => {
+                        template.attributes.shift_remove(&id);
+                    }
+                    (Err(_), Err(_)) => {}
+                }
+            }
+            template.images = instance.images.clone();
+            template.sounds = instance.sounds.clone();
+            template.script = instance.script.clone();
+            template.animation = instance.animation.clone();
+            template.text = instance.text.clone();
+            template.tilemap = instance.tilemap.clone();
+            template.character_controller = instance.character_controller.clone();
+            template.gravity_source = instance.gravity_source.clone();
+        })
+    }
+
+    /// Drops the instance's overrides, so it matches the prefab again
+    pub fn revert_prefab_instance(&mut self, scene_id: Uuid, entity_id: Uuid) -> Result<(), String> {
+        let scene = self.scenes.get_mut(&scene_id).ok_or("Scene not found")?;
+        let entity = scene.get_entity_mut(entity_id)?;
+        let link = entity.prefab.as_mut().ok_or_else(|| format!("Entity '{}' is not a prefab instance", entity.name))?;
+        link.overrides.clear();
+        let prefab = self.prefabs.get(&link.prefab_id).ok_or("Prefab not found")?;
+        prefab.sync_instance(entity, None);
+        Ok(())
+    }
 }
 
 // =============== Scene (Manages Entities and Resources) ===============
@@ -143,6 +322,8 @@ pub struct Scene {
     pub entities: IndexMap<Uuid, Entity>,
     pub shared_entity_refs: Vec<Uuid>,
     pub default_camera: Option<Uuid>,
+    #[serde(default)]
+    pub physics: PhysicsSettings,
 }
 
 impl Scene {
@@ -153,6 +334,7 @@ impl Scene {
             entities: IndexMap::new(),
             shared_entity_refs: Vec::new(),
             default_camera: None,
+            physics: PhysicsSettings::default(),
         };
 
         // Create default camera
@@ -179,11 +361,24 @@ impl Scene {
         Ok(id)
     }
 
+    /// Deletes the entity together with all of its descendants
     pub fn delete_entity(&mut self, id: Uuid) -> Result<bool, String> {
         if Some(id) == self.default_camera {
             return Err("Cannot delete default camera".to_string());
         }
-        Ok(self.entities.shift_remove(&id).is_some())
+        if !self.entities.contains_key(&id) {
+            return Ok(false);
+        }
+        let descendants = self.descendants_of(id);
+        if self.default_camera.is_some_and(|camera| descendants.contains(&camera)) {
+            return Err("Cannot delete the parent of the default camera".to_string());
+        }
+
+        self.entities.shift_remove(&id);
+        for descendant in descendants {
+            self.entities.shift_remove(&descendant);
+        }
+        Ok(true)
     }
 
     pub fn list_entity(&self) -> Vec<(Uuid, &str)> {
@@ -203,6 +398,140 @@ impl Scene {
             .ok_or_else(|| format!("Entity {} not found", id))
     }
 
+    // Hierarchy
+    pub fn parent_of(&self, id: Uuid) -> Option<Uuid> {
+        self.entities.get(&id)
+            .and_then(|entity| entity.parent)
+            .filter(|parent| self.entities.contains_key(parent))
+    }
+
+    /// Direct children, in entity order
+    pub fn children_of(&self, id: Uuid) -> Vec<Uuid> {
+        self.entities
+            .values()
+            .filter(|entity| entity.parent == Some(id))
+            .map(|entity| entity.id)
+            .collect()
+    }
+
+    /// Entities without a parent in this scene, in entity order
+    pub fn root_entities(&self) -> Vec<Uuid> {
+        self.entities
+            .keys()
+            .filter(|id| self.parent_of(**id).is_none())
+            .copied()
+            .collect()
+    }
+
+    /// Children, grandchildren and so on, depth first
+    pub fn descendants_of(&self, id: Uuid) -> Vec<Uuid> {
+        let mut descendants = Vec::new();
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            for child in self.children_of(current).into_iter().rev() {
+                if child != id && !descendants.contains(&child) {
+                    descendants.push(child);
+                    stack.push(child);
+                }
+            }
+        }
+        descendants
+    }
+
+    pub fn is_ancestor(&self, ancestor: Uuid, id: Uuid) -> bool {
+        let mut current = self.parent_of(id);
+        let mut depth = 0;
+        while let Some(parent) = current {
+            if parent == ancestor {
+                return true;
+            }
+            depth += 1;
+            if depth > self.entities.len() {
+                break;
+            }
+            current = self.parent_of(parent);
+        }
+        false
+    }
+
+    /// Moves the entity under `parent`, or to the top level with `None`.
+    /// The entity keeps its world transform, so its local transform is recomputed.
+    pub fn set_parent(&mut self, id: Uuid, parent: Option<Uuid>) -> Result<(), String> {
+        if !self.entities.contains_key(&id) {
+            return Err(format!("Entity {} not found", id));
+        }
+        if let Some(parent) = parent {
+            if parent == id {
+                return Err("An entity cannot be its own parent".to_string());
+            }
+            if !self.entities.contains_key(&parent) {
+                return Err(format!("Parent entity {} not found", parent));
+            }
+            if self.is_ancestor(id, parent) {
+                return Err("Cannot move an entity under one of its descendants".to_string());
+            }
+        }
+
+        let world = self.world_transform(id)?;
+        let local = match parent {
+            Some(parent) => world.relative_to(&self.world_transform(parent)?),
+            None => world,
+        };
+        let entity = self.get_entity_mut(id)?;
+        entity.parent = parent;
+        entity.set_local_transform(&local)
+    }
+
+    /// The entity's transform in world space, combining the local transforms of its ancestors
+    pub fn world_transform(&self, id: Uuid) -> Result<Transform, String> {
+        let mut transform = self.get_entity(id)?.local_transform();
+        let mut current = self.parent_of(id);
+        let mut depth = 0;
+        while let Some(parent) = current {
+            depth += 1;
+            if depth > self.entities.len() {
+                return Err(format!("Entity {} is part of a parent cycle", id));
+            }
+            let parent_entity = self.get_entity(parent)?;
+            transform = transform.combine(&parent_entity.local_transform());
+            current = self.parent_of(parent);
+        }
+        Ok(transform)
+    }
+
+    /// World transforms of all entities, computing each ancestor once
+    pub fn world_transforms(&self) -> HashMap<Uuid, Transform> {
+        self.world_transforms_with(&HashMap::new(), &HashMap::new())
+    }
+
+    /// Same as world_transforms, but entities in `positions` and `rotations` are placed at that
+    /// world position and rotation, and their descendants follow them
+    pub fn world_transforms_with(
+        &self,
+        positions: &HashMap<Uuid, (f32, f32)>,
+        rotations: &HashMap<Uuid, f32>,
+    ) -> HashMap<Uuid, Transform> {
+        let mut transforms = HashMap::with_capacity(self.entities.len());
+        let mut pending = self.root_entities();
+        while let Some(id) = pending.pop() {
+            if let Ok(entity) = self.get_entity(id) {
+                let mut world = match self.parent_of(id).and_then(|parent| transforms.get(&parent)) {
+                    Some(parent_world) => entity.local_transform().combine(parent_world),
+                    None => entity.local_transform(),
+                };
+                if let Some(&position) = positions.get(&id) {
+                    world.position = position;
+                }
+                if let Some(&rotation) = rotations.get(&id) {
+                    world.rotation = rotation;
+                }
+                transforms.insert(id, world);
+                pending.extend(self.children_of(id));
+            }
+        }
+        transforms
+    }
+
     // Add methods to work with shared entities
     pub fn add_shared_entity_ref(&mut self, shared_entity_id: Uuid) -> Result<(), String> {
         if self.shared_entity_refs.contains(&shared_entity_id) {
@@ -263,6 +592,23 @@ impl Scene {
         Ok(id)
     }
 
+    /// Makes a camera entity the one the game is rendered through
+    pub fn set_default_camera(&mut self, id: Uuid) -> Result<(), String> {
+        let entity = self.get_entity(id)?;
+        if !entity.is_camera() {
+            return Err(format!("Entity '{}' is not a camera", entity.name));
+        }
+        self.default_camera = Some(id);
+        Ok(())
+    }
+
+    // Predefined: Tilemap, 20 by 15 tiles of 32 pixels
+    pub fn create_tilemap(&mut self, name: &str) -> Result<Uuid, String> {
+        let id = self.create_entity(name)?;
+        self.get_entity_mut(id)?.tilemap = Some(Box::new(Tilemap::new(20, 15, (32.0, 32.0))?));
+        Ok(id)
+    }
+
     // Predefined: Physical Entity
     pub fn create_physical_entity(
         &mut self, 
@@ -295,6 +641,17 @@ impl Scene {
         }
         Ok(())
     }
+
+    /// Advances the animations of all entities, returning the clips that finished
+    pub fn update_animations(&mut self, delta_time: f32) -> Vec<AnimationFinished> {
+        self.entities
+            .values_mut()
+            .filter_map(|entity| {
+                let clip = entity.animation.as_mut()?.update(delta_time)?;
+                Some(AnimationFinished { entity_id: entity.id, clip })
+            })
+            .collect()
+    }
 }
 
 // =============== Entity (Manages Attributes) ===============
@@ -307,6 +664,22 @@ pub struct Entity {
     pub images: Vec<PathBuf>,      // Multiple images (sprites, textures)
     pub sounds: Vec<PathBuf>,      // Multiple sounds (effects, music)
     pub script: Option<PathBuf>,   // Single script per entity
+    #[serde(default)]
+    pub parent: Option<Uuid>,      // Entity in the same scene this one moves with
+    #[serde(default)]
+    pub prefab: Option<PrefabLink>,
+    #[serde(default)]
+    pub animation: Option<Box<SpriteAnimation>>, // Boxed, most entities have none
+    #[serde(default)]
+    pub text: Option<Box<Text>>,
+    #[serde(default)]
+    pub tilemap: Option<Box<Tilemap>>,
+    #[serde(default)]
+    pub joints: Vec<Joint>,        // Joints to other entities of the scene
+    #[serde(default)]
+    pub character_controller: Option<CharacterController>,
+    #[serde(default)]
+    pub gravity_source: Option<GravitySource>,
 }
 
 impl Entity {
@@ -319,6 +692,14 @@ impl Entity {
             images: Vec::new(),
             sounds: Vec::new(),
             script: None,
+            parent: None,
+            prefab: None,
+            animation: None,
+            text: None,
+            tilemap: None,
+            joints: Vec::new(),
+            character_controller: None,
+            gravity_source: None,
         };
 
         // Core position attributes that cannot be deleted
@@ -457,9 +838,11 @@ impl Entity {
                     return Err("Cannot delete core position attributes".to_string());
                 }
                 // Physics-specific attributes
-                "is_movable" | "has_gravity" | "creates_gravity" | 
+                "is_movable" | "body_type" | "has_gravity" | "creates_gravity" | 
                 "has_collision" | "friction" | "restitution" | 
-                "density" | "can_rotate" => {
+                "density" | "can_rotate" | "collider_shape" | "collider_size" |
+                "collider_offset" | "collider_points" | "is_trigger" |
+                "collision_layer" | "collision_mask" | "velocity" | "angular_velocity" => {
                     if self.name.contains("physical") {
                         return Err("Cannot delete physics attributes from physical entity".to_string());
                     }
@@ -522,6 +905,13 @@ impl Entity {
         }
     }
 
+    /// Remembers that a prefab instance's attribute no longer follows the prefab
+    pub fn mark_override(&mut self, name: &str) {
+        if let Some(link) = self.prefab.as_mut() {
+            link.add_override(name);
+        }
+    }
+
     pub fn get_attribute_by_name(&self, name: &str) -> Result<&Attribute, String> {
         self.attributes
             .iter()
@@ -559,6 +949,8 @@ impl Entity {
         // Add physics-specific attributes
         entity.create_attribute("is_movable", AttributeType::Boolean, 
             AttributeValue::Boolean(physics.is_movable))?;
+        entity.create_attribute("body_type", AttributeType::String,
+            AttributeValue::String(physics.body_type.map_or("", |body_type| body_type.as_str()).to_string()))?;
         entity.create_attribute("has_gravity", AttributeType::Boolean, 
             AttributeValue::Boolean(physics.affected_by_gravity))?;
         entity.create_attribute("creates_gravity", AttributeType::Boolean, 
@@ -573,6 +965,25 @@ impl Entity {
             AttributeValue::Float(physics.density))?;
         entity.create_attribute("can_rotate", AttributeType::Boolean, 
             AttributeValue::Boolean(physics.can_rotate))?;
+        entity.create_attribute("collider_shape", AttributeType::String,
+            AttributeValue::String(physics.collider_shape.as_str().to_string()))?;
+        entity.create_attribute("collider_size", AttributeType::Vector2,
+            AttributeValue::Vector2(physics.collider_size.0, physics.collider_size.1))?;
+        entity.create_attribute("collider_offset", AttributeType::Vector2,
+            AttributeValue::Vector2(physics.collider_offset.0, physics.collider_offset.1))?;
+        entity.create_attribute("collider_points", AttributeType::String,
+            AttributeValue::String(String::new()))?;
+        entity.create_attribute("is_trigger", AttributeType::Boolean,
+            AttributeValue::Boolean(physics.is_trigger))?;
+        entity.create_attribute("collision_layer", AttributeType::String,
+            AttributeValue::String(physics.collision_layer))?;
+        entity.create_attribute("collision_mask", AttributeType::String,
+            AttributeValue::String(physics.collision_mask))?;
+        // Written by the physics engine on every step, and applied to the body when changed
+        entity.create_attribute("velocity", AttributeType::Vector2,
+            AttributeValue::Vector2(0.0, 0.0))?;
+        entity.create_attribute("angular_velocity", AttributeType::Float,
+            AttributeValue::Float(0.0))?;
         
         Ok(entity)
     }
@@ -634,6 +1045,47 @@ impl Entity {
         Ok((self.get_x(), self.get_y(), self.get_z()))
     }
 
+    /// Transform relative to the parent, from the x, y, z, rotation and scale attributes
+    pub fn local_transform(&self) -> Transform {
+        let rotation = self.get_attribute_by_name("rotation")
+            .and_then(|attr| if let AttributeValue::Float(r) = attr.value { Ok(r) } else { Err("Attribute value is not a float".to_string()) })
+            .unwrap_or(0.0);
+        let scale = self.get_attribute_by_name("scale")
+            .and_then(|attr| if let AttributeValue::Vector2(sx, sy) = attr.value { Ok((sx, sy)) } else { Err("Attribute value is not a vector2".to_string()) })
+            .unwrap_or((1.0, 1.0));
+
+        Transform::new()
+            .with_position(self.get_x(), self.get_y())
+            .with_z(self.get_z())
+            .with_rotation(rotation)
+            .with_scale(scale.0, scale.1)
+    }
+
+    /// Writes the transform back to the attributes. Rotation and scale attributes
+    /// are only added when the transform actually rotates or scales.
+    pub fn set_local_transform(&mut self, transform: &Transform) -> Result<(), String> {
+        self.set_position(transform.position.0, transform.position.1, transform.z)?;
+
+        let rotation = AttributeValue::Float(transform.rotation);
+        match self.get_attribute_by_name("rotation").map(|attr| attr.id) {
+            Ok(id) => self.modify_attribute(id, None, None, Some(rotation))?,
+            Err(_) if transform.rotation != 0.0 => {
+                self.create_attribute("rotation", AttributeType::Float, rotation)?;
+            }
+            Err(_) => {}
+        }
+
+        let scale = AttributeValue::Vector2(transform.scale.0, transform.scale.1);
+        match self.get_attribute_by_name("scale").map(|attr| attr.id) {
+            Ok(id) => self.modify_attribute(id, None, None, Some(scale))?,
+            Err(_) if transform.scale != (1.0, 1.0) => {
+                self.create_attribute("scale", AttributeType::Vector2, scale)?;
+            }
+            Err(_) => {}
+        }
+        Ok(())
+    }
+
     // Camera attribute getters
     pub fn get_camera_width(&self) -> f32 {
         self.get_attribute_by_name("width")
@@ -709,6 +1161,222 @@ impl Entity {
     }
 }
 
+// Transform component for positioning, scaling, and rotating
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub position: (f32, f32),
+    pub rotation: f32, // In radians
+    pub scale: (f32, f32),
+    pub z: f32,
+}
+
+impl Transform {
+    // Attributes an entity's transform is stored in, see `Entity::local_transform`
+    pub const ATTRIBUTES: [&'static str; 5] = ["x", "y", "z", "rotation", "scale"];
+
+    pub fn new() -> Self {
+        Self {
+            position: (0.0, 0.0),
+            rotation: 0.0,
+            scale: (1.0, 1.0),
+            z: 0.0,
+        }
+    }
+
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.position = (x, y);
+        self
+    }
+
+    pub fn with_rotation(mut self, angle: f32) -> Self {
+        self.rotation = angle;
+        self
+    }
+
+    pub fn with_scale(mut self, sx: f32, sy: f32) -> Self {
+        self.scale = (sx, sy);
+        self
+    }
+
+    // Helper for uniform scaling
+    pub fn with_uniform_scale(mut self, scale: f32) -> Self {
+        self.scale = (scale, scale);
+        self
+    }
+
+    pub fn with_z(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    /// This transform, relative to `parent`, expressed in the parent's space.
+    /// Non-uniform parent scale is applied along the parent's axes without shearing.
+    pub fn combine(&self, parent: &Transform) -> Transform {
+        let scaled = (self.position.0 * parent.scale.0, self.position.1 * parent.scale.1);
+        let (sin, cos) = parent.rotation.sin_cos();
+        Transform {
+            position: (
+                parent.position.0 + scaled.0 * cos - scaled.1 * sin,
+                parent.position.1 + scaled.0 * sin + scaled.1 * cos,
+            ),
+            rotation: parent.rotation + self.rotation,
+            scale: (parent.scale.0 * self.scale.0, parent.scale.1 * self.scale.1),
+            z: parent.z + self.z,
+        }
+    }
+
+    /// Inverse of `combine`: the transform that gives this one when combined with `parent`
+    pub fn relative_to(&self, parent: &Transform) -> Transform {
+        let safe_div = |value: f32, divisor: f32| if divisor == 0.0 { value } else { value / divisor };
+        let offset = (self.position.0 - parent.position.0, self.position.1 - parent.position.1);
+        let (sin, cos) = (-parent.rotation).sin_cos();
+        let rotated = (offset.0 * cos - offset.1 * sin, offset.0 * sin + offset.1 * cos);
+        Transform {
+            position: (safe_div(rotated.0, parent.scale.0), safe_div(rotated.1, parent.scale.1)),
+            rotation: self.rotation - parent.rotation,
+            scale: (safe_div(self.scale.0, parent.scale.0), safe_div(self.scale.1, parent.scale.1)),
+            z: self.z - parent.z,
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============== Prefabs ===============
+/// Template entity, instantiated into scenes as entities linked back to it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Prefab {
+    pub id: Uuid,
+    pub name: String,
+    pub entity: Entity,
+}
+
+/// Link from an instance to its prefab. Attributes named in `overrides` keep the
+/// instance's value when the prefab changes, all others follow the prefab.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PrefabLink {
+    pub prefab_id: Uuid,
+    #[serde(default)]
+    pub overrides: Vec<String>,
+}
+
+impl PrefabLink {
+    // Every instance has its own position, these are never taken from the prefab
+    pub const INSTANCE_ATTRIBUTES: [&'static str; 3] = ["x", "y", "z"];
+
+    pub fn new(prefab_id: Uuid) -> Self {
+        Self { prefab_id, overrides: Vec::new() }
+    }
+
+    pub fn is_overridden(&self, name: &str) -> bool {
+        Self::INSTANCE_ATTRIBUTES.contains(&name) || self.overrides.iter().any(|o| o == name)
+    }
+
+    pub fn add_override(&mut self, name: &str) {
+        if !self.is_overridden(name) {
+            self.overrides.push(name.to_string());
+            self.overrides.sort();
+        }
+    }
+}
+
+impl Prefab {
+    pub fn from_entity(name: &str, entity: &Entity) -> Self {
+        let mut template = entity.clone();
+        template.id = Uuid::new_v4();
+        template.parent = None;
+        template.prefab = None;
+        // Joints connect to entities of one scene, each instance gets its own
+        template.joints.clear();
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            entity: template,
+        }
+    }
+
+    /// A new entity linked to this prefab, at (x, y)
+    pub fn instantiate(&self, x: f32, y: f32) -> Result<Entity, String> {
+        let mut entity = self.entity.clone();
+        entity.id = Uuid::new_v4();
+        entity.prefab = Some(PrefabLink::new(self.id));
+        // Templates saved before joints were left out may still have some
+        entity.joints.clear();
+        // Attribute ids are unique per entity, not shared between instances
+        entity.attributes = entity.attributes
+            .into_values()
+            .map(|attribute| {
+                let id = Uuid::new_v4();
+                (id, Attribute { id, ..attribute })
+            })
+            .collect();
+
+        entity.set_x(x)?;
+        entity.set_y(y)?;
+        Ok(entity)
+    }
+
+    /// Updates an instance to match the template, keeping its overridden attributes and its joints.
+    /// Assets are only updated if the instance still has the `previous` template's assets,
+    /// without `previous` they are always replaced.
+    pub fn sync_instance(&self, instance: &mut Entity, previous: Option<&Entity>) {
+        let Some(link) = instance.prefab.clone() else {
+            return;
+        };
+
+        for attribute in self.entity.attributes.values() {
+            if link.is_overridden(&attribute.name) {
+                continue;
+            }
+            match instance.get_attribute_by_name(&attribute.name).map(|attr| attr.id) {
+                Ok(id) => {
+                    if let Some(existing) = instance.attributes.get_mut(&id) {
+                        existing.data_type = attribute.data_type.clone();
+                        existing.value = attribute.value.clone();
+                    }
+                }
+                Err(_) => {
+                    let id = Uuid::new_v4();
+                    instance.attributes.insert(id, Attribute { id, ..attribute.clone() });
+                }
+            }
+        }
+        // Attributes removed from the prefab
+        instance.attributes.retain(|_, attribute| {
+            link.is_overridden(&attribute.name) || self.entity.get_attribute_by_name(&attribute.name).is_ok()
+        });
+
+        if previous.is_none_or(|previous| instance.images == previous.images) {
+            instance.images = self.entity.images.clone();
+        }
+        if previous.is_none_or(|previous| instance.sounds == previous.sounds) {
+            instance.sounds = self.entity.sounds.clone();
+        }
+        if previous.is_none_or(|previous| instance.script == previous.script) {
+            instance.script = self.entity.script.clone();
+        }
+        if previous.is_none_or(|previous| instance.animation == previous.animation) {
+            instance.animation = self.entity.animation.clone();
+        }
+        if previous.is_none_or(|previous| instance.text == previous.text) {
+            instance.text = self.entity.text.clone();
+        }
+        if previous.is_none_or(|previous| instance.tilemap == previous.tilemap) {
+            instance.tilemap = self.entity.tilemap.clone();
+        }
+        if previous.is_none_or(|previous| instance.character_controller == previous.character_controller) {
+            instance.character_controller = self.entity.character_controller.clone();
+        }
+        if previous.is_none_or(|previous| instance.gravity_source == previous.gravity_source) {
+            instance.gravity_source = self.entity.gravity_source.clone();
+        }
+    }
+}
+
 // =============== Attribute Types ===============
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Attribute {
@@ -755,9 +1423,85 @@ impl fmt::Display for AttributeValue {
     }
 }
 
+/// Shape of a physical entity's collider, stored in its `collider_shape` attribute.
+/// An empty or missing attribute means a box.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ColliderShape {
+    #[default]
+    Box,
+    Circle,
+    Capsule,
+    ConvexPolygon,
+    Polyline,
+}
+
+impl ColliderShape {
+    pub const ALL: [ColliderShape; 5] = [
+        ColliderShape::Box,
+        ColliderShape::Circle,
+        ColliderShape::Capsule,
+        ColliderShape::ConvexPolygon,
+        ColliderShape::Polyline,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColliderShape::Box => "box",
+            ColliderShape::Circle => "circle",
+            ColliderShape::Capsule => "capsule",
+            ColliderShape::ConvexPolygon => "convex",
+            ColliderShape::Polyline => "polyline",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "" | "box" => Some(ColliderShape::Box),
+            "circle" => Some(ColliderShape::Circle),
+            "capsule" => Some(ColliderShape::Capsule),
+            "convex" => Some(ColliderShape::ConvexPolygon),
+            "polyline" => Some(ColliderShape::Polyline),
+            _ => None,
+        }
+    }
+}
+
+/// How the physics engine moves a physical entity, stored in its `body_type` attribute.
+/// An empty or missing attribute means dynamic if `is_movable` is set, fixed otherwise.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyType {
+    Fixed,             // Never moves
+    Dynamic,           // Moved by gravity, forces and collisions
+    KinematicPosition, // Moved only by setting its position, pushes dynamic bodies out of its way
+    KinematicVelocity, // Moved only by its velocity, pushes dynamic bodies out of its way
+}
+
+impl BodyType {
+    pub const ALL: [BodyType; 4] = [
+        BodyType::Fixed,
+        BodyType::Dynamic,
+        BodyType::KinematicPosition,
+        BodyType::KinematicVelocity,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BodyType::Fixed => "fixed",
+            BodyType::Dynamic => "dynamic",
+            BodyType::KinematicPosition => "kinematic_position",
+            BodyType::KinematicVelocity => "kinematic_velocity",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        BodyType::ALL.into_iter().find(|body_type| body_type.as_str() == name.trim().to_lowercase())
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PhysicsProperties {
     pub is_movable: bool,
+    pub body_type: Option<BodyType>, // None follows is_movable
     pub affected_by_gravity: bool,
     pub creates_gravity: bool,
     pub has_collision: bool,
@@ -765,12 +1509,19 @@ pub struct PhysicsProperties {
     pub restitution: f32,
     pub density: f32,
     pub can_rotate: bool,
+    pub collider_shape: ColliderShape,
+    pub collider_size: (f32, f32),   // (0, 0) fits the collider to the sprite
+    pub collider_offset: (f32, f32), // From the sprite's center
+    pub is_trigger: bool,            // Detects overlaps without pushing anything
+    pub collision_layer: String,     // Names of the collision layers it is in
+    pub collision_mask: String,      // Names of the layers it collides with, or "all"
 }
 
 impl Default for PhysicsProperties {
     fn default() -> Self {
         Self {
             is_movable: false,
+            body_type: None,
             affected_by_gravity: false,
             creates_gravity: false,
             has_collision: true,
@@ -778,6 +1529,12 @@ impl Default for PhysicsProperties {
             restitution: 0.0,
             density: 1.0,
             can_rotate: false,
+            collider_shape: ColliderShape::Box,
+            collider_size: (0.0, 0.0),
+            collider_offset: (0.0, 0.0),
+            is_trigger: false,
+            collision_layer: "default".to_string(),
+            collision_mask: "all".to_string(),
         }
     }
 }
@@ -3,10 +3,13 @@ use crate::{
     render_engine::RenderEngine,
     input_handler::{InputHandler, InputContext},
     audio_engine::AudioEngine,
-    ecs::SceneManager,
+    ecs::{SceneManager, AttributeValue},
     game_runtime::{GameRuntime, RuntimeState}
 };
-use crate::gui::gui_state::GuiState;
+use crate::gui::gui_state::{GuiState, SelectedItem};
+use crate::gui::edit_history::EditCommand;
+use crate::gui::scene_hierarchy::utils;
+use crate::gui::tilemap_editor::TileTool;
 use crate::gui::menu_bar::MenuBar;
 use crate::gui::scene_hierarchy::SceneHierarchy;
 use crate::gui::file_system::FileSystem;
@@ -15,6 +18,10 @@ use eframe::egui;
 use std::fs;
 use std::path::PathBuf;
 use crate::logger::{LOGGER, ConsoleMessageType, ConsoleMessage};
+use uuid::Uuid;
+
+// Scene ID, entity ID and the values of the entity's attributes
+type AttributeSnapshot = (Uuid, Uuid, Vec<(Uuid, AttributeValue)>);
 
 pub struct EngineGui {
     // Window States
@@ -43,6 +50,9 @@ pub struct EngineGui {
 
     editor_content: String,
     current_edited_file: Option<PathBuf>,
+
+    // Tiles were painted since the project was last saved, it is saved at the end of the stroke
+    tilemap_stroke_unsaved: bool,
 }
 
 impl EngineGui {
@@ -77,6 +87,7 @@ impl EngineGui {
             game_runtime,
             editor_content: String::new(),
             current_edited_file: None,
+            tilemap_stroke_unsaved: false,
         }
     }
 
@@ -215,7 +226,11 @@ impl EngineGui {
                         .show_inside(ui, |ui| {
                             ui.heading("Inspector");
                             ui.separator();
+                            let before = self.selected_entity_attributes();
                             self.inspector.show(ctx, ui, &mut self.gui_state);
+                            if let Some(before) = before {
+                                self.forward_inspector_edits(before);
+                            }
                         });
                 }
 
@@ -310,7 +325,7 @@ impl EngineGui {
                             // Render only the viewport content when play in the GUI
                             if self.game_runtime.get_state() == RuntimeState::Playing {
 
-                                // sync camera to runtime
+                                // sync camera to runtime, used when the scene has no camera
                                 let position = self.render_engine.camera.position;
                                 let zoom = self.render_engine.camera.zoom;
                                 self.game_runtime.set_camera_state(position, zoom);
@@ -318,23 +333,6 @@ impl EngineGui {
                                 let game_view_rect = ui.available_rect_before_wrap();
                                 self.game_runtime.update(ctx, ui, game_view_rect);
 
-
-                                // Then draw the game camera bounds
-                                if let Some(scene_manager) = &self.gui_state.scene_manager {
-                                    if let Some(active_scene) = scene_manager.get_active_scene() {
-                                        let camera_lines = self.render_engine.get_game_camera_bounds(active_scene);
-                                        for (start, end) in camera_lines {
-                                            ui.painter().line_segment(
-                                                [
-                                                    egui::pos2(content_rect.min.x + start.0, content_rect.min.y + start.1),
-                                                    egui::pos2(content_rect.min.x + end.0, content_rect.min.y + end.1)
-                                                ],
-                                                egui::Stroke::new(2.0, egui::Color32::RED)
-                                            );
-                                        }
-                                    }
-                                }
-
                             } else {
                                 // Render the game view first
                                 self.render_scene(ui);
@@ -449,6 +447,8 @@ impl EngineGui {
                                         }
                                     }
                                 }
+
+                                self.edit_tilemap(ui, viewport_rect);
                             }
 
                             // Debug overlay in the bottom-left of the game view
@@ -555,35 +555,8 @@ impl EngineGui {
                 // First render all game objects
                 let render_queue = self.render_engine.render(active_scene);
                 
-                for (texture_id, pos, size, _layer) in render_queue {
-                    if let Some(texture_info) = self.render_engine.texture_cache.get(&texture_id) {
-                        let rect = egui::Rect::from_min_size(
-                            egui::pos2(
-                                content_rect.min.x + pos.0,
-                                content_rect.min.y + pos.1,
-                            ),
-                            egui::vec2(size.0, size.1),
-                        );
-
-                        let texture = ui.ctx().load_texture(
-                            format!("texture_{}", texture_id),
-                            egui::ColorImage::from_rgba_unmultiplied(
-                                [texture_info.dimensions.0 as usize, texture_info.dimensions.1 as usize],
-                                &texture_info.data,
-                            ),
-                            Default::default()
-                        );
-
-                        ui.painter().image(
-                            texture.id(),
-                            rect,
-                            egui::Rect::from_min_max(
-                                egui::pos2(0.0, 0.0),
-                                egui::pos2(1.0, 1.0),
-                            ),
-                            egui::Color32::WHITE,
-                        );
-                    }
+                for item in &render_queue {
+                    self.render_engine.paint(ui.painter(), item, content_rect.min.to_vec2());
                 }
 
                 // Then draw the game camera bounds
@@ -601,6 +574,89 @@ impl EngineGui {
         }
     }
 
+    // Uses the tile brush chosen in the inspector on the selected tilemap, under the cursor
+    fn edit_tilemap(&mut self, ui: &egui::Ui, viewport_rect: egui::Rect) {
+        let (pressed, down, released, alt) = ui.ctx().input(|i| {
+            (i.pointer.primary_pressed(), i.pointer.primary_down(), i.pointer.primary_released(), i.modifiers.alt)
+        });
+        if released && self.tilemap_stroke_unsaved {
+            self.tilemap_stroke_unsaved = false;
+            utils::save_project(&self.gui_state);
+        }
+
+        let brush = &mut self.gui_state.tile_brush;
+        let Some(tool) = brush.tool else {
+            return;
+        };
+        let SelectedItem::Entity(scene_id, entity_id) = self.gui_state.selected_item else {
+            return;
+        };
+        let Some(scene) = self.gui_state.scene_manager.as_mut().and_then(|manager| manager.get_scene_mut(scene_id)) else {
+            return;
+        };
+        let Ok(transform) = scene.world_transform(entity_id) else {
+            return;
+        };
+        let Some(entity) = scene.get_entity_mut(entity_id).ok().filter(|entity| entity.tilemap.is_some()) else {
+            return;
+        };
+        // Only over the viewport itself, not over windows on top of it
+        let Some(cursor) = ui.ctx().input(|i| i.pointer.hover_pos())
+            .filter(|cursor| viewport_rect.contains(*cursor) && ui.ctx().layer_id_at(*cursor) == Some(ui.layer_id()))
+        else {
+            brush.rect_start = None;
+            return;
+        };
+
+        let camera = &self.render_engine.camera;
+        let cursor_world = camera.screen_to_world((cursor.x - viewport_rect.min.x, cursor.y - viewport_rect.min.y));
+        let Some(tilemap) = entity.tilemap.as_deref() else {
+            return;
+        };
+        let cell = tilemap.cell_at(&transform, cursor_world);
+
+        // Outline of the cells the tool changes
+        let from = brush.rect_start.filter(|_| tool == TileTool::Rect).unwrap_or(cell);
+        let (min, max) = ((from.0.min(cell.0), from.1.min(cell.1)), (from.0.max(cell.0) + 1, from.1.max(cell.1) + 1));
+        let outline = [(min.0, min.1), (max.0, min.1), (max.0, max.1), (min.0, max.1)]
+            .map(|(x, y)| {
+                let screen = camera.world_to_screen(tilemap.cell_corner(&transform, x, y));
+                egui::pos2(screen.0, screen.1) + viewport_rect.min.to_vec2()
+            })
+            .to_vec();
+        ui.painter().add(egui::Shape::closed_line(outline, egui::Stroke::new(1.5, egui::Color32::YELLOW)));
+
+        // Alt and left drag moves the camera
+        if alt {
+            return;
+        }
+        let apply = match tool {
+            TileTool::Paint | TileTool::Erase => down,
+            TileTool::Fill => pressed,
+            TileTool::Rect => {
+                if pressed {
+                    brush.rect_start = Some(cell);
+                }
+                released && brush.rect_start.is_some()
+            }
+        };
+        if apply {
+            let mut edited = entity.tilemap.clone();
+            if edited.as_mut().is_some_and(|tilemap| brush.apply(tilemap, cell)) {
+                self.gui_state.edit_history.record(EditCommand::set_tilemap(entity, scene_id, edited.clone()));
+                entity.tilemap = edited;
+                self.tilemap_stroke_unsaved = true;
+            }
+        }
+        if released {
+            brush.rect_start = None;
+            if self.tilemap_stroke_unsaved {
+                self.tilemap_stroke_unsaved = false;
+                utils::save_project(&self.gui_state);
+            }
+        }
+    }
+
     fn set_theme(&mut self, ctx: &egui::Context) {
         let visuals = if self.gui_state.dark_mode {
             egui::Visuals::dark()
@@ -614,11 +670,54 @@ impl EngineGui {
         }
     }
 
+    // While playing, the attributes of the entity shown in the inspector
+    fn selected_entity_attributes(&self) -> Option<AttributeSnapshot> {
+        if !self.game_runtime.is_playing() {
+            return None;
+        }
+        let SelectedItem::Entity(scene_id, entity_id) = self.gui_state.selected_item else {
+            return None;
+        };
+        let entity = self.gui_state.scene_manager.as_ref()?.get_scene(scene_id)?.get_entity(entity_id).ok()?;
+        let values = entity.attributes.values().map(|attr| (attr.id, attr.value.clone())).collect();
+        Some((scene_id, entity_id, values))
+    }
+
+    // Values changed in the inspector during play also go to the running scene,
+    // so the physics engine applies them to the entity's body on its next step
+    fn forward_inspector_edits(&mut self, (scene_id, entity_id, before): AttributeSnapshot) {
+        let Some(entity) = self.gui_state.scene_manager.as_ref()
+            .and_then(|scene_manager| scene_manager.get_scene(scene_id))
+            .and_then(|scene| scene.get_entity(entity_id).ok()) else {
+            return;
+        };
+        let changed: Vec<_> = before.into_iter()
+            .filter_map(|(attr_id, old_value)| {
+                let value = &entity.attributes.get(&attr_id)?.value;
+                (*value != old_value).then(|| (entity_id, attr_id, value.clone()))
+            })
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        if let Some(scene) = self.game_runtime.get_scene_manager_mut().get_scene_mut(scene_id) {
+            if let Err(err) = scene.update_entity_attributes(changed) {
+                eprintln!("Failed to apply the inspector edit to the running game: {}", err);
+            }
+        }
+    }
+
     fn sync_scene_manager_to_runtime(&mut self) {
         // Get the scene manager from GUI state
         if let Some(gui_scene_manager) = &self.gui_state.scene_manager {
             // Update the game runtime's scene manager
             self.game_runtime.set_scene_manager(gui_scene_manager.clone());
+            if let Err(err) = self.game_runtime.set_project_path(&self.gui_state.project_path) {
+                eprintln!("{}", err);
+            }
+            if let Some(metadata) = &self.gui_state.project_metadata {
+                self.game_runtime.set_collision_layers(metadata.collision_layers.clone());
+            }
             println!("Synced scene manager to runtime with {} scenes", 
                 self.game_runtime.get_scene_manager().list_scene().len());
         }
@@ -627,6 +726,10 @@ impl EngineGui {
 
 impl eframe::App for EngineGui {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Undo/redo only applies to the editor's scenes, not to a running game
+        if !self.game_runtime.is_playing() {
+            self.menu_bar.handle_shortcuts(ctx, &mut self.gui_state);
+        }
 
         egui::CentralPanel::default()
             .frame(egui::Frame {
@@ -1,15 +1,21 @@
 use crate::{
-    physics_engine::PhysicsEngine,
+    physics_engine::{PhysicsEngine, CollisionEvent},
+    camera::{self, SceneCamera},
+    animation::AnimationFinished,
     render_engine::RenderEngine,
-    input_handler::{InputHandler, InputContext},
+    input_handler::{InputHandler, InputContext, ScriptedInput},
     audio_engine::AudioEngine,
+    project_manager::ProjectManager,
     ecs::SceneManager,
     ecs::AttributeValue,
+    joint::JointKind,
     lua_scripting::LuaScripting,
 };
 use std::any::Any;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 use egui::Rect;
-use uuid::uuid;
+use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RuntimeState {
@@ -22,6 +28,75 @@ pub trait Game: Any {
     fn init(&mut self, scene_manager: &mut SceneManager);
     fn update(&mut self, scene_manager: &mut SceneManager, input: &InputHandler, delta_time: f32);
     fn reset(&mut self, scene_manager: &mut SceneManager);
+    // Called for every collision event of the frame, after the physics step
+    fn on_collision(&mut self, _scene_manager: &mut SceneManager, _event: &CollisionEvent) {}
+    // Called when an animation clip that doesn't loop reaches its last frame
+    fn on_animation_finished(&mut self, _scene_manager: &mut SceneManager, _event: &AnimationFinished) {}
+}
+
+/// Turns variable frame times into a whole number of fixed physics steps.
+/// The time left over is kept for the next frame and tells how far rendering
+/// is between the last two steps.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: f32,
+    accumulator: f32,
+    // Longest frame time taken into account, so a slow frame doesn't queue up
+    // more steps than can be simulated in time (spiral of death)
+    max_frame_time: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f32) -> Self {
+        Self {
+            step,
+            accumulator: 0.0,
+            max_frame_time: 0.25,
+        }
+    }
+
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    pub fn set_step(&mut self, step: f32) {
+        if step > 0.0 {
+            self.step = step;
+        }
+    }
+
+    pub fn max_frame_time(&self) -> f32 {
+        self.max_frame_time
+    }
+
+    pub fn set_max_frame_time(&mut self, max_frame_time: f32) {
+        self.max_frame_time = max_frame_time.max(0.0);
+    }
+
+    /// Frame time as the game sees it: never negative, at most `max_frame_time`.
+    pub fn clamp_frame_time(&self, frame_time: f32) -> f32 {
+        frame_time.clamp(0.0, self.max_frame_time)
+    }
+
+    /// Adds the time of a frame and returns how many fixed steps to run for it.
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += self.clamp_frame_time(frame_time);
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// How far the accumulated time is into the next step, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
 }
 
 pub struct GameRuntime {
@@ -36,6 +111,11 @@ pub struct GameRuntime {
     state: RuntimeState,
     game: Option<Box<dyn Game>>,
     lua_scripting: LuaScripting,
+    timestep: FixedTimestep,
+    last_frame_time: Option<Instant>, // None until the first frame after starting or resuming
+    frame_count: u32, // Frames played since the game was started
+    project_path: Option<PathBuf>, // Folder of the project being played
+    collision_layers: Option<Vec<String>>, // The project's layers, the physics engine keeps its own without them
 }
 
 impl GameRuntime {
@@ -50,7 +130,8 @@ impl GameRuntime {
         // Make sure we start in EngineUI mode
         let mut input_handler = input_handler;
         input_handler.set_context(InputContext::EngineUI);
-        
+        let timestep = FixedTimestep::new(physics_engine.get_time_step());
+
         Self {
             scene_manager,
             dev_state_snapshot: None,
@@ -63,9 +144,47 @@ impl GameRuntime {
             state: RuntimeState::Stopped,
             game: None,
             lua_scripting: LuaScripting::new(),
+            timestep,
+            last_frame_time: None,
+            frame_count: 0,
+            project_path: None,
+            collision_layers: None,
         }
     }
 
+    /// Runtime without a window or audio output, for tests and servers.
+    /// Loads the project lazily, only its active scene is read; the game starts on the first `tick`.
+    pub fn new_headless(project_path: &Path) -> Result<Self, String> {
+        let scene_manager = ProjectManager::load_scene_hierarchy_lazily(project_path)?;
+        let mut runtime = Self::new(
+            scene_manager,
+            PhysicsEngine::new(),
+            RenderEngine::new(),
+            InputHandler::new(),
+            AudioEngine::new_null(),
+            60,
+        );
+        runtime.set_project_path(project_path)?;
+        Ok(runtime)
+    }
+
+    /// The project being played: scripts resolve relative paths from it and its collision layers are used
+    pub fn set_project_path(&mut self, project_path: &Path) -> Result<(), String> {
+        self.lua_scripting.set_project_path(project_path)?;
+        self.collision_layers = Some(ProjectManager::load_collision_layers(project_path));
+        self.project_path = Some(project_path.to_path_buf());
+        Ok(())
+    }
+
+    pub fn get_project_path(&self) -> Option<&Path> {
+        self.project_path.as_deref()
+    }
+
+    /// Collision layers for the next `run`, e.g. the ones being edited before they are saved
+    pub fn set_collision_layers(&mut self, collision_layers: Vec<String>) {
+        self.collision_layers = Some(collision_layers);
+    }
+
     pub fn is_playing(&self) -> bool {
         matches!(self.state, RuntimeState::Playing)
     }
@@ -104,6 +223,8 @@ impl GameRuntime {
                 self.running = false;
             }
         }
+        // Time spent paused or stopped doesn't count as a frame
+        self.last_frame_time = None;
         self.state = state;
     }
 
@@ -112,6 +233,7 @@ impl GameRuntime {
         self.set_state(RuntimeState::Stopped);
 
         // Clear all engines
+        self.end_script_session();
         self.physics_engine.cleanup();
         self.render_engine.cleanup();
         self.audio_engine.cleanup();
@@ -156,10 +278,23 @@ impl GameRuntime {
             self.scene_manager.set_active_scene(scenes[0].0)?;
         }
 
-        // Load the scene into physics engine
+        // Load the scene into physics engine, with the project's collision layers
+        if let Some(collision_layers) = &self.collision_layers {
+            self.physics_engine.set_collision_layers(collision_layers.clone());
+        }
         self.physics_engine.load_scene(self.scene_manager.get_active_scene().unwrap());
 
+        // Compile scripts up front so syntax errors show up as soon as Play is pressed
+        if let Some(active_scene_id) = self.scene_manager.active_scene {
+            if let Err(err) = self.lua_scripting.compile_scripts(&self.scene_manager, active_scene_id) {
+                eprintln!("Error compiling scripts: {}", err);
+            }
+        }
+
         println!("Game starting with active scene"); // Debug print
+        self.timestep.reset();
+        self.last_frame_time = None;
+        self.frame_count = 0;
         self.running = true;
         self.state = RuntimeState::Playing;
         Ok(())
@@ -180,146 +315,93 @@ impl GameRuntime {
         if self.running && self.state == RuntimeState::Playing {
             //println!("Game is running, active inputs: {:?}", self.input_handler.get_all_active_inputs()); // Debug print
 
-            // Update game logic with the input handler
-            if let Some(game) = &mut self.game {
-                game.update(&mut self.scene_manager, &self.input_handler, 1.0/60.0);
-            }
-
-            // Run script
-            self.lua_scripting.update_global_time(1.0/self.target_fps as f32).expect("Failed to update global time");
-            match self.lua_scripting.load_scene_manager(&self.scene_manager) {
-                Ok(_) => println!("SceneManager loaded into Lua successfully."),
-                Err(err) => eprintln!("Error loading SceneManager into Lua: {}", err),
-            }
-            if let Some(active_scene_id) = self.scene_manager.active_scene {
-                self.lua_scripting.initializing_global_variables(&self.input_handler);
-                self.lua_scripting.initialize_bindings_physics_engine(&mut self.physics_engine, &mut self.scene_manager).unwrap();
-                self.lua_scripting.initialize_bindings_ecs(&mut self.scene_manager).unwrap();
-                self.lua_scripting.initialize_bindings_input_handler(&mut self.input_handler).unwrap();
-
+            // Measure the real time since the last frame, the first frame takes the target frame time
+            let now = Instant::now();
+            let frame_time = match self.last_frame_time {
+                Some(last) => now.duration_since(last).as_secs_f32(),
+                None => 1.0 / self.target_fps as f32,
+            };
+            self.last_frame_time = Some(now);
 
-                match self.lua_scripting.run_scripts_for_scene(&mut self.scene_manager, active_scene_id) {
-                    Ok(()) => {
-                        println!("SceneManager successfully updated after running scripts.");
-                    }
-                    Err(err) => {
-                        eprintln!("Error running scripts for scene {}: {}", active_scene_id, err);
-                    }
-                }
-            } else {
-                eprintln!("No active scene set in SceneManager.");
-            }
+            self.advance_frame(frame_time);
 
             if let Some(scene) = self.scene_manager.get_active_scene_mut() {
-                // Run physics
-                let physics_updates = self.physics_engine.step(scene);
-
-                // Filter out those values are NaN
-                let filtered_physics_updates: Vec<(_, _, AttributeValue)> = physics_updates
-                    .into_iter()
-                    .filter(|(_, _, attr)| match attr {
-                        AttributeValue::Float(val) => !val.is_nan(),
-                        AttributeValue::Vector2(x, y) => !x.is_nan() && !y.is_nan(),
-                        _ => true,
-                    })
-                    .collect();
-
-                if let Err(err) = scene.update_entity_attributes(filtered_physics_updates) {
-                    eprintln!("Failed to update entity attributes: {}", err);
-                }
                 // Run audio
                 self.audio_engine.update();
-                // Render
-                let render_queue = self.render_engine.render(scene);
-
-                // Function for calculate intersection
-                let calculate_intersection = |rect1: egui::Rect, rect2: egui::Rect| -> Option<egui::Rect> {
-                    let min_x = rect1.min.x.max(rect2.min.x);
-                    let min_y = rect1.min.y.max(rect2.min.y);
-                    let max_x = rect1.max.x.min(rect2.max.x);
-                    let max_y = rect1.max.y.min(rect2.max.y);
-
-                    if min_x < max_x && min_y < max_y {
-                        Some(egui::Rect::from_min_max(
-                            egui::pos2(min_x, min_y),
-                            egui::pos2(max_x, max_y),
-                        ))
-                    } else {
-                        None
+                // Render through the scene's camera, letterboxed to its aspect ratio.
+                // Without one, the camera given to set_camera_state is used.
+                let view_rect = match SceneCamera::active(scene) {
+                    Some(scene_camera) => {
+                        let (camera, (x, y, width, height)) = scene_camera.view((viewport_rect.width(), viewport_rect.height()));
+                        self.render_engine.camera = camera;
+                        Rect::from_min_size(viewport_rect.min + egui::vec2(x, y), egui::vec2(width, height))
                     }
+                    None => viewport_rect,
                 };
+                self.render_engine.update_viewport_size(view_rect.width(), view_rect.height());
+
+                let bars = [
+                    Rect::from_min_max(viewport_rect.min, egui::pos2(viewport_rect.max.x, view_rect.min.y)),
+                    Rect::from_min_max(egui::pos2(viewport_rect.min.x, view_rect.max.y), viewport_rect.max),
+                    Rect::from_min_max(viewport_rect.min, egui::pos2(view_rect.min.x, viewport_rect.max.y)),
+                    Rect::from_min_max(egui::pos2(view_rect.max.x, viewport_rect.min.y), viewport_rect.max),
+                ];
+                for bar in bars.into_iter().filter(|bar| bar.is_positive()) {
+                    ui.painter().rect_filled(bar, 0.0, egui::Color32::BLACK);
+                }
 
-                for (texture_id, pos, size, _layer) in render_queue {
-                    if let Some(texture_info) = self.render_engine.texture_cache.get(&texture_id) {
-                        let texture_rect = egui::Rect::from_min_size(
-                            egui::pos2(viewport_rect.min.x + pos.0, viewport_rect.min.y + pos.1),
-                            egui::vec2(size.0, size.1),
-                        );
-
-                        if let Some(intersection) = calculate_intersection(texture_rect, viewport_rect) {
-                            // Adjust UV coordinates for the clipped area
-                            let uv_min = (
-                                (intersection.min.x - texture_rect.min.x) / size.0,
-                                (intersection.min.y - texture_rect.min.y) / size.1,
-                            );
-                            let uv_max = (
-                                (intersection.max.x - texture_rect.min.x) / size.0,
-                                (intersection.max.y - texture_rect.min.y) / size.1,
-                            );
-
-                            // Render only the visible part
-                            let texture = ui.ctx().load_texture(
-                                format!("texture_{}", texture_id),
-                                egui::ColorImage::from_rgba_unmultiplied(
-                                    [texture_info.dimensions.0 as usize, texture_info.dimensions.1 as usize],
-                                    &texture_info.data,
-                                ),
-                                Default::default(),
-                            );
-
-                            ui.painter().image(
-                                texture.id(),
-                                intersection,
-                                egui::Rect::from_min_max(
-                                    egui::pos2(uv_min.0, uv_min.1),
-                                    egui::pos2(uv_max.0, uv_max.1),
-                                ),
-                                egui::Color32::WHITE,
-                            );
-                        }
-                    }
+                // Render, with physics bodies between the last two steps
+                let alpha = self.timestep.alpha();
+                let positions = self.physics_engine.interpolated_positions(alpha);
+                let rotations = self.physics_engine.interpolated_rotations(alpha);
+                let render_queue = self.render_engine.render_interpolated(scene, &positions, &rotations);
+
+                // Sprites are clipped to the camera's view
+                let painter = ui.painter().with_clip_rect(view_rect);
+                for item in &render_queue {
+                    self.render_engine.paint(&painter, item, view_rect.min.to_vec2());
                 }
 
                 // render colliders
                 let collider_data = self.physics_engine.get_collider_data();
                 let collider_render_queue = self.render_engine.render_colliders(&collider_data);
 
-                for (screen_position, screen_size, shape) in collider_render_queue {
-                    match shape.as_str() {
+                for collider in collider_render_queue {
+                    let to_viewport = |(x, y): (f32, f32)| egui::pos2(view_rect.min.x + x, view_rect.min.y + y);
+                    // Triggers are yellow whatever their shape
+                    let color = |shape_color| if collider.sensor { egui::Color32::YELLOW } else { shape_color };
+                    match collider.shape.as_str() {
                         "Circle" => {
-                            let center = egui::pos2(
-                                viewport_rect.min.x + screen_position.0,
-                                viewport_rect.min.y + screen_position.1,
-                            );
-                            let radius = screen_size.0 / 2.0;
-                            ui.painter()
-                                .circle_stroke(center, radius, egui::Stroke::new(1.0, egui::Color32::RED));
+                            let radius = collider.size.0 / 2.0;
+                            painter
+                                .circle_stroke(to_viewport(collider.position), radius, egui::Stroke::new(1.0, color(egui::Color32::RED)));
+                        }
+                        "Polyline" => {
+                            let points = collider.points.iter().copied().map(to_viewport).collect();
+                            painter
+                                .add(egui::Shape::line(points, egui::Stroke::new(1.0, color(egui::Color32::GREEN))));
                         }
-                        "Rectangle" => {
-                            let rect = egui::Rect::from_min_size(
-                                egui::pos2(
-                                    viewport_rect.min.x + screen_position.0 - screen_size.0 / 2.0,
-                                    viewport_rect.min.y + screen_position.1 - screen_size.1 / 2.0,
-                                ),
-                                egui::vec2(screen_size.0, screen_size.1),
-                            );
-                            ui.painter()
-                                .rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::BLUE));
+                        _ => {
+                            let points = collider.points.iter().copied().map(to_viewport).collect();
+                            painter
+                                .add(egui::Shape::closed_line(points, egui::Stroke::new(1.0, color(egui::Color32::BLUE))));
                         }
-                        _ => {}
                     }
                 }
 
+                // Joints are a line between their anchors, springs and ropes in a lighter color
+                for joint in self.render_engine.render_joints(&self.physics_engine.get_joint_data()) {
+                    let to_viewport = |(x, y): (f32, f32)| egui::pos2(view_rect.min.x + x, view_rect.min.y + y);
+                    let color = match joint.kind {
+                        JointKind::Spring | JointKind::Rope | JointKind::Distance => egui::Color32::LIGHT_BLUE,
+                        _ => egui::Color32::from_rgb(255, 140, 0),
+                    };
+                    let (anchor1, anchor2) = (to_viewport(joint.anchor1), to_viewport(joint.anchor2));
+                    painter.line_segment([anchor1, anchor2], egui::Stroke::new(1.0, color));
+                    painter.circle_stroke(anchor1, 3.0, egui::Stroke::new(1.0, color));
+                    painter.circle_filled(anchor2, 2.0, color);
+                }
+
             } else {
                 // If we lost the active scene, stop the game
                 self.cleanup_and_reset();
@@ -334,6 +416,177 @@ impl GameRuntime {
         ctx.request_repaint();
     }
 
+    /// Plays one frame of `frame_time` seconds without rendering, starting the game if it is stopped.
+    pub fn tick(&mut self, frame_time: f32) -> Result<(), String> {
+        if self.state == RuntimeState::Stopped {
+            self.run()?;
+            self.set_state(RuntimeState::Playing);
+        }
+
+        if self.running && self.state == RuntimeState::Playing {
+            self.advance_frame(frame_time);
+            self.audio_engine.update();
+        }
+        Ok(())
+    }
+
+    /// Plays `frames` frames at the target frame rate, holding the keys the input gives for each frame.
+    /// Frames are counted from the start of the game, so consecutive calls continue the input.
+    pub fn run_frames(&mut self, frames: u32, input: &ScriptedInput) -> Result<(), String> {
+        let frame_time = 1.0 / self.target_fps as f32;
+        for _ in 0..frames {
+            let frame = if self.state == RuntimeState::Stopped { 0 } else { self.frame_count };
+            self.input_handler.set_keys_down(input.keys_at(frame));
+            self.tick(frame_time)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    // Runs the game and scripts once with the frame's time, then as many fixed physics
+    // steps as the accumulated time allows
+    fn advance_frame(&mut self, frame_time: f32) {
+        let delta_time = self.timestep.clamp_frame_time(frame_time);
+        self.frame_count += 1;
+
+        // Update game logic with the input handler
+        if let Some(game) = &mut self.game {
+            game.update(&mut self.scene_manager, &self.input_handler, delta_time);
+        }
+
+        // Run scripts
+        if let Some(active_scene_id) = self.scene_manager.active_scene {
+            if !self.lua_scripting.is_session_active() {
+                self.start_script_session(active_scene_id);
+            }
+
+            self.lua_scripting.update_global_time(delta_time).expect("Failed to update global time");
+            if let Err(err) = self.lua_scripting.bind_keys_pressed(&self.input_handler) {
+                eprintln!("Error binding pressed keys: {}", err);
+            }
+
+            let scene_manager = &mut self.scene_manager;
+            let result = self.lua_scripting.with_engines(&mut self.physics_engine, &self.input_handler, |lua_scripting| {
+                lua_scripting.run_scripts_for_scene(scene_manager, active_scene_id, delta_time)
+            });
+            if let Err(err) = result {
+                eprintln!("Error running scripts for scene {}: {}", active_scene_id, err);
+            }
+        } else {
+            eprintln!("No active scene set in SceneManager.");
+        }
+
+        self.update_animations(delta_time);
+
+        // The physics time step can be changed while playing
+        self.timestep.set_step(self.physics_engine.get_time_step());
+        for _ in 0..self.timestep.advance(frame_time) {
+            self.step_physics();
+        }
+
+        // The camera follows its target where the physics left it
+        if let Some(scene) = self.scene_manager.get_active_scene_mut() {
+            if let Err(err) = camera::update_follow(scene, delta_time) {
+                eprintln!("Failed to move the camera: {}", err);
+            }
+        }
+    }
+
+    fn step_physics(&mut self) {
+        if let Some(scene) = self.scene_manager.get_active_scene_mut() {
+            // Run physics
+            self.physics_engine.remove_missing_entities(scene);
+            let physics_updates = self.physics_engine.step(scene);
+
+            // Filter out those values are NaN
+            let filtered_physics_updates: Vec<(_, _, AttributeValue)> = physics_updates
+                .into_iter()
+                .filter(|(_, _, attr)| match attr {
+                    AttributeValue::Float(val) => !val.is_nan(),
+                    AttributeValue::Vector2(x, y) => !x.is_nan() && !y.is_nan(),
+                    _ => true,
+                })
+                .collect();
+
+            if let Err(err) = scene.update_entity_attributes(filtered_physics_updates) {
+                eprintln!("Failed to update entity attributes: {}", err);
+            }
+        }
+
+        // Deliver the collisions of this step to the game and the scripts
+        let collision_events = self.physics_engine.drain_collision_events();
+        if !collision_events.is_empty() {
+            if let Some(game) = &mut self.game {
+                for event in &collision_events {
+                    game.on_collision(&mut self.scene_manager, event);
+                }
+            }
+            let scene_manager = &mut self.scene_manager;
+            let result = self.lua_scripting.with_engines(&mut self.physics_engine, &self.input_handler, |lua_scripting| {
+                lua_scripting.handle_collision_events(scene_manager, &collision_events)
+            });
+            if let Err(err) = result {
+                eprintln!("Error running collision callbacks: {}", err);
+            }
+        }
+    }
+
+    // Advances sprite animations and delivers the clips that finished to the game and the scripts
+    fn update_animations(&mut self, delta_time: f32) {
+        let finished = match self.scene_manager.get_active_scene_mut() {
+            Some(scene) => scene.update_animations(delta_time),
+            None => return,
+        };
+        if finished.is_empty() {
+            return;
+        }
+
+        if let Some(game) = &mut self.game {
+            for event in &finished {
+                game.on_animation_finished(&mut self.scene_manager, event);
+            }
+        }
+        let scene_manager = &mut self.scene_manager;
+        let result = self.lua_scripting.with_engines(&mut self.physics_engine, &self.input_handler, |lua_scripting| {
+            lua_scripting.handle_animation_events(scene_manager, &finished)
+        });
+        if let Err(err) = result {
+            eprintln!("Error running animation callbacks: {}", err);
+        }
+    }
+
+    fn start_script_session(&mut self, active_scene_id: Uuid) {
+        if let Err(err) = self.lua_scripting.initializing_global_variables(&self.input_handler) {
+            eprintln!("Error initializing script globals: {}", err);
+        }
+        self.lua_scripting.initialize_bindings_physics_engine().unwrap();
+        self.lua_scripting.initialize_bindings_ecs().unwrap();
+        self.lua_scripting.initialize_bindings_input_handler().unwrap();
+
+        let scene_manager = &mut self.scene_manager;
+        let result = self.lua_scripting.with_engines(&mut self.physics_engine, &self.input_handler, |lua_scripting| {
+            lua_scripting.start_session(scene_manager, active_scene_id)
+        });
+        if let Err(err) = result {
+            eprintln!("Error starting scripts for scene {}: {}", active_scene_id, err);
+        }
+    }
+
+    // `on_destroy` callbacks may still use the physics bindings
+    fn end_script_session(&mut self) {
+        let scene_manager = &mut self.scene_manager;
+        let result = self.lua_scripting.with_engines(&mut self.physics_engine, &self.input_handler, |lua_scripting| {
+            lua_scripting.end_session(scene_manager);
+            Ok(())
+        });
+        if let Err(err) = result {
+            eprintln!("{}", err);
+        }
+    }
+
     pub fn stop(&mut self) {
         self.cleanup_and_reset();
     }
@@ -342,8 +595,11 @@ impl GameRuntime {
         // Stop all running systems
         self.running = false;
         self.state = RuntimeState::Stopped;
+        self.timestep.reset();
+        self.last_frame_time = None;
         
         // Cleanup engines
+        self.end_script_session();
         self.physics_engine.cleanup();
         self.render_engine.cleanup();
         self.audio_engine.cleanup();
@@ -365,6 +621,10 @@ impl GameRuntime {
         &self.scene_manager
     }
 
+    pub fn get_scene_manager_mut(&mut self) -> &mut SceneManager {
+        &mut self.scene_manager
+    }
+
     pub fn get_input_handler(&mut self) -> &mut InputHandler {
         &mut self.input_handler
     }
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+// GravitySource (optional component of an Entity)
+// └── Pulls (or pushes) the dynamic bodies around the entity that have gravity (`has_gravity`)
+// └── The pull is an acceleration, so light and heavy bodies fall the same:
+//     strength × falloff(distance) × the scene's `custom_gravity_strength`
+// └── Bodies are found with a circle query around the source, up to its max radius. Without one,
+//     the radius is where an inverse square pull of its strength becomes negligible.
+//     Bodies without a collider are in the default layer and pulled within the same radius.
+// An entity with the older `creates_gravity` attribute on and no component is a default source.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum GravityFalloff {
+    #[default]
+    InverseSquare, // strength / distance², bodies closer than 1 are pulled as at 1
+    Linear,        // strength at the center down to 0 at the radius
+    Constant,      // strength at any distance within the radius
+}
+
+impl GravityFalloff {
+    pub const ALL: [GravityFalloff; 3] = [GravityFalloff::InverseSquare, GravityFalloff::Linear, GravityFalloff::Constant];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GravityFalloff::InverseSquare => "inverse_square",
+            GravityFalloff::Linear => "linear",
+            GravityFalloff::Constant => "constant",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Self::ALL.into_iter().find(|falloff| falloff.as_str() == name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GravitySource {
+    #[serde(default = "default_strength")]
+    pub strength: f32,
+    #[serde(default)]
+    pub max_radius: f32,          // Distance past which bodies aren't pulled, 0 to derive it from the strength
+    #[serde(default)]
+    pub falloff: GravityFalloff,
+    #[serde(default)]
+    pub repel: bool,              // Pushes bodies away instead of pulling them
+    #[serde(default = "default_affected_layers")]
+    pub affected_layers: String,  // Names of the collision layers it pulls, or "all"
+}
+
+fn default_strength() -> f32 {
+    1000.0
+}
+
+fn default_affected_layers() -> String {
+    "all".to_string()
+}
+
+impl Default for GravitySource {
+    fn default() -> Self {
+        Self {
+            strength: default_strength(),
+            max_radius: 0.0,
+            falloff: GravityFalloff::default(),
+            repel: false,
+            affected_layers: default_affected_layers(),
+        }
+    }
+}
+
+/// Pull below which a source without a max radius stops reaching bodies
+pub const NEGLIGIBLE_ACCELERATION: f32 = 0.01;
+
+impl GravitySource {
+    /// Distance past which bodies aren't pulled: the max radius, or without one the distance
+    /// at which strength / distance² drops to NEGLIGIBLE_ACCELERATION
+    pub fn radius(&self) -> f32 {
+        if self.max_radius > 0.0 {
+            self.max_radius
+        } else {
+            (self.strength.abs() / NEGLIGIBLE_ACCELERATION).sqrt().max(1.0)
+        }
+    }
+
+    /// Acceleration towards the source of a body at `distance`, negative when it repels
+    pub fn acceleration(&self, distance: f32) -> f32 {
+        let radius = self.radius();
+        if distance > radius {
+            return 0.0;
+        }
+        let magnitude = match self.falloff {
+            GravityFalloff::InverseSquare => self.strength / distance.max(1.0).powi(2),
+            GravityFalloff::Linear => self.strength * (1.0 - distance / radius),
+            GravityFalloff::Constant => self.strength,
+        };
+        if self.repel { -magnitude } else { magnitude }
+    }
+}
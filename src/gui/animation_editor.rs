@@ -0,0 +1,245 @@
+use eframe::egui;
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::animation::{
+    format_frame_list, format_frame_rects, parse_frame_list, parse_frame_rects,
+    AnimationClip, FrameSource, LoopMode, SpriteAnimation,
+};
+
+/// Inspector section for an entity's sprite animation. Works on a copy of the
+/// animation; `show` returns true when the copy was changed.
+pub struct AnimationEditor {
+    // Entity the text below belongs to
+    entity_id: Option<Uuid>,
+    // Text of the frame lists and rectangles being typed, applied when the field loses focus
+    frames_text: HashMap<String, String>,
+    rects_text: Option<String>,
+    new_clip_name: String,
+    error_message: String,
+}
+
+impl Default for AnimationEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnimationEditor {
+    pub fn new() -> Self {
+        Self {
+            entity_id: None,
+            frames_text: HashMap::new(),
+            rects_text: None,
+            new_clip_name: String::new(),
+            error_message: String::new(),
+        }
+    }
+
+    /// Forgets the text being typed, e.g. when another entity is selected or after an undo
+    pub fn reset(&mut self) {
+        self.frames_text.clear();
+        self.rects_text = None;
+        self.error_message.clear();
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, entity_id: Uuid, animation: &mut Option<Box<SpriteAnimation>>, image_count: usize) -> bool {
+        if self.entity_id != Some(entity_id) {
+            self.entity_id = Some(entity_id);
+            self.reset();
+        }
+        let mut changed = false;
+
+        egui::CollapsingHeader::new("Animation")
+            .default_open(animation.is_some())
+            .show(ui, |ui| {
+                let Some(current) = animation.as_mut() else {
+                    if ui.button("Add Animation").clicked() {
+                        *animation = Some(Box::default());
+                        changed = true;
+                    }
+                    return;
+                };
+
+                changed |= self.show_source(ui, &mut current.source, image_count);
+                let frame_count = current.source.frame_count(image_count);
+                ui.label(format!("Frames: {}", frame_count));
+                ui.separator();
+
+                changed |= self.show_clips(ui, current, frame_count);
+
+                if !self.error_message.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.error_message);
+                }
+
+                ui.separator();
+                if ui.button("Remove Animation").clicked() {
+                    *animation = None;
+                    self.reset();
+                    changed = true;
+                }
+            });
+
+        changed
+    }
+
+    fn show_source(&mut self, ui: &mut egui::Ui, source: &mut FrameSource, image_count: usize) -> bool {
+        let mut changed = false;
+        let last_image = image_count.saturating_sub(1);
+
+        let source_name = match source {
+            FrameSource::Images => "Images",
+            FrameSource::Grid { .. } => "Grid",
+            FrameSource::Rects { .. } => "Rects",
+        };
+        egui::ComboBox::from_label("Frames from")
+            .selected_text(source_name)
+            .show_ui(ui, |ui| {
+                let options = [
+                    ("Images", FrameSource::Images),
+                    ("Grid", FrameSource::Grid { image: 0, columns: 1, rows: 1, count: None }),
+                    ("Rects", FrameSource::Rects { image: 0, rects: Vec::new() }),
+                ];
+                for (name, option) in options {
+                    if ui.selectable_label(source_name == name, name).clicked() && source_name != name {
+                        *source = option;
+                        self.rects_text = None;
+                        changed = true;
+                    }
+                }
+            });
+
+        match source {
+            FrameSource::Images => {
+                ui.label("Each image of the entity is one frame.");
+            }
+            FrameSource::Grid { image, columns, rows, count } => {
+                ui.horizontal(|ui| {
+                    ui.label("Image:");
+                    changed |= ui.add(egui::DragValue::new(image).range(0..=last_image)).changed();
+                    ui.label("Columns:");
+                    changed |= ui.add(egui::DragValue::new(columns).range(1..=256)).changed();
+                    ui.label("Rows:");
+                    changed |= ui.add(egui::DragValue::new(rows).range(1..=256)).changed();
+                });
+                ui.horizontal(|ui| {
+                    let mut limited = count.is_some();
+                    if ui.checkbox(&mut limited, "Frame count").changed() {
+                        *count = limited.then_some(*columns * *rows);
+                        changed = true;
+                    }
+                    if let Some(count) = count {
+                        changed |= ui.add(egui::DragValue::new(count).range(1..=*columns * *rows)).changed();
+                    }
+                });
+            }
+            FrameSource::Rects { image, rects } => {
+                ui.horizontal(|ui| {
+                    ui.label("Image:");
+                    changed |= ui.add(egui::DragValue::new(image).range(0..=last_image)).changed();
+                });
+                ui.label("Rectangles (x y width height, ...):");
+                let text = self.rects_text.get_or_insert_with(|| format_frame_rects(rects));
+                let response = ui.text_edit_multiline(text);
+                if response.lost_focus() {
+                    match parse_frame_rects(text) {
+                        Ok(parsed) => {
+                            changed |= *rects != parsed;
+                            *rects = parsed;
+                            self.rects_text = None;
+                            self.error_message.clear();
+                        }
+                        Err(err) => self.error_message = err,
+                    }
+                }
+            }
+        }
+
+        changed
+    }
+
+    fn show_clips(&mut self, ui: &mut egui::Ui, animation: &mut SpriteAnimation, frame_count: usize) -> bool {
+        let mut changed = false;
+        let mut removed = None;
+
+        for (name, clip) in animation.clips.iter_mut() {
+            ui.horizontal(|ui| {
+                let mut autoplay = animation.autoplay.as_deref() == Some(name.as_str());
+                if ui.checkbox(&mut autoplay, "").on_hover_text("Play when the game starts").changed() {
+                    animation.autoplay = autoplay.then(|| name.clone());
+                    changed = true;
+                }
+                ui.strong(name);
+                if ui.small_button("❌").clicked() {
+                    removed = Some(name.clone());
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Duration:");
+                changed |= ui.add(egui::DragValue::new(&mut clip.frame_duration).range(0.01..=10.0).speed(0.01).suffix(" s")).changed();
+
+                egui::ComboBox::from_id_salt(("loop_mode", name.as_str()))
+                    .selected_text(clip.loop_mode.as_str())
+                    .show_ui(ui, |ui| {
+                        for mode in LoopMode::ALL {
+                            changed |= ui.selectable_value(&mut clip.loop_mode, mode, mode.as_str()).changed();
+                        }
+                    });
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Frames:");
+                let text = self.frames_text.entry(name.clone()).or_insert_with(|| format_frame_list(&clip.frames));
+                if ui.text_edit_singleline(text).lost_focus() {
+                    match parse_frame_list(text) {
+                        Ok(frames) if frames.is_empty() => self.error_message = format!("Clip '{}' needs frames", name),
+                        Ok(frames) => {
+                            if let Some(frame) = frames.iter().find(|frame| **frame >= frame_count) {
+                                self.error_message = format!("Clip '{}' uses frame {}, there are {}", name, frame, frame_count);
+                            } else {
+                                self.error_message.clear();
+                            }
+                            changed |= clip.frames != frames;
+                            clip.frames = frames;
+                            self.frames_text.remove(name);
+                        }
+                        Err(err) => self.error_message = err,
+                    }
+                }
+            });
+            ui.add_space(4.0);
+        }
+
+        if let Some(name) = removed {
+            animation.clips.shift_remove(&name);
+            if animation.autoplay.as_ref() == Some(&name) {
+                animation.autoplay = None;
+            }
+            self.frames_text.remove(&name);
+            changed = true;
+        }
+
+        // New clips start with every frame of the source
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_clip_name);
+            if ui.button("Add Clip").clicked() {
+                let name = self.new_clip_name.trim().to_string();
+                let clip = AnimationClip::new((0..frame_count.max(1)).collect(), 0.1);
+                if animation.clips.contains_key(&name) {
+                    self.error_message = format!("Clip '{}' already exists", name);
+                } else {
+                    match animation.add_clip(&name, clip) {
+                        Ok(()) => {
+                            self.new_clip_name.clear();
+                            self.error_message.clear();
+                            changed = true;
+                        }
+                        Err(err) => self.error_message = err,
+                    }
+                }
+            }
+        });
+
+        changed
+    }
+}
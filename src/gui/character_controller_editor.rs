@@ -0,0 +1,43 @@
+use eframe::egui;
+use uuid::Uuid;
+use crate::character_controller::CharacterController;
+
+/// Inspector section for an entity's character controller. Works on a copy of the controller;
+/// returns true when the copy was changed.
+pub fn show_character_controller_editor(ui: &mut egui::Ui, entity_id: Uuid, controller: &mut Option<CharacterController>) -> bool {
+    let mut changed = false;
+
+    egui::CollapsingHeader::new("Character Controller")
+        .id_salt(("character_controller", entity_id))
+        .default_open(controller.is_some())
+        .show(ui, |ui| {
+            let Some(current) = controller.as_mut() else {
+                ui.label("Moves the entity with move_and_slide, its body becomes kinematic");
+                if ui.button("Add Character Controller").clicked() {
+                    *controller = Some(CharacterController::default());
+                    changed = true;
+                }
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                ui.label("Max slope:");
+                changed |= ui.add(egui::DragValue::new(&mut current.max_slope).range(0.0..=90.0).speed(0.5).suffix("°")).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Step height:");
+                changed |= ui.add(egui::DragValue::new(&mut current.step_height).range(0.0..=f32::MAX).speed(0.5)).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Snap to ground:");
+                changed |= ui.add(egui::DragValue::new(&mut current.snap_to_ground).range(0.0..=f32::MAX).speed(0.5)).changed();
+            });
+
+            if ui.button("Remove Character Controller").clicked() {
+                *controller = None;
+                changed = true;
+            }
+        });
+
+    changed
+}
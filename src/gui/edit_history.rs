@@ -0,0 +1,772 @@
+use crate::ecs::{Attribute, AttributeValue, Entity, Prefab, Scene, SceneManager, Transform};
+use crate::animation::SpriteAnimation;
+use crate::text::Text;
+use crate::tilemap::Tilemap;
+use crate::joint::Joint;
+use crate::character_controller::CharacterController;
+use crate::gravity_source::GravitySource;
+use crate::physics_settings::PhysicsSettings;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+// Editor history
+// └── Every edit of the scene manager made in the editor is an EditCommand
+//      └── Commands hold what is needed to apply and revert them,
+//          e.g. a deleted entity keeps a copy of the entity and its position
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AssetKind {
+    Image,
+    Sound,
+    Script,
+}
+
+#[derive(Clone, Debug)]
+pub enum EditCommand {
+    CreateScene { scene_id: Uuid, scene: Scene, index: usize },
+    DeleteScene { scene_id: Uuid, scene: Scene, index: usize },
+    RenameScene { scene_id: Uuid, old_name: String, new_name: String },
+    CreateEntity { scene_id: Uuid, entity: Entity, index: usize },
+    // The entity first, then its descendants, each with its position in the scene
+    DeleteEntity { scene_id: Uuid, entities: Vec<(usize, Entity)> },
+    RenameEntity { scene_id: Uuid, entity_id: Uuid, old_name: String, new_name: String },
+    SetParent { scene_id: Uuid, entity_id: Uuid, old_parent: Option<Uuid>, new_parent: Option<Uuid> },
+    CreateAttribute { scene_id: Uuid, entity_id: Uuid, attribute: Attribute, index: usize },
+    ModifyAttribute { scene_id: Uuid, entity_id: Uuid, attribute_id: Uuid, old_value: AttributeValue, new_value: AttributeValue },
+    DeleteAttribute { scene_id: Uuid, entity_id: Uuid, attribute: Attribute, index: usize },
+    // Attaching a script replaces the entity's script, if it had one
+    AttachAsset { scene_id: Uuid, entity_id: Uuid, kind: AssetKind, path: PathBuf, replaced: Option<PathBuf> },
+    DetachAsset { scene_id: Uuid, entity_id: Uuid, kind: AssetKind, path: PathBuf, index: usize },
+    // Creating a prefab, applying overrides to it or reverting an instance
+    PrefabChange { description: String, before: Box<PrefabSnapshot>, after: Box<PrefabSnapshot> },
+    // Adding, changing or removing an entity's sprite animation
+    SetAnimation { scene_id: Uuid, entity_id: Uuid, old: Option<Box<SpriteAnimation>>, new: Option<Box<SpriteAnimation>> },
+    // Adding, changing or removing an entity's text
+    SetText { scene_id: Uuid, entity_id: Uuid, old: Option<Box<Text>>, new: Option<Box<Text>> },
+    // Adding, painting or removing an entity's tilemap
+    SetTilemap { scene_id: Uuid, entity_id: Uuid, old: Option<Box<Tilemap>>, new: Option<Box<Tilemap>> },
+    // Adding, changing or removing joints of an entity
+    SetJoints { scene_id: Uuid, entity_id: Uuid, old: Vec<Joint>, new: Vec<Joint> },
+    SetCharacterController { scene_id: Uuid, entity_id: Uuid, old: Option<CharacterController>, new: Option<CharacterController> },
+    SetGravitySource { scene_id: Uuid, entity_id: Uuid, old: Option<GravitySource>, new: Option<GravitySource> },
+    // Moving, turning or scaling an entity from the transform section of the inspector
+    SetTransform { scene_id: Uuid, entity_id: Uuid, old: Transform, new: Transform },
+    // Choosing the camera the game is rendered through
+    SetDefaultCamera { scene_id: Uuid, old_camera: Option<Uuid>, new_camera: Uuid },
+    SetPhysicsSettings { scene_id: Uuid, old: PhysicsSettings, new: PhysicsSettings },
+}
+
+/// A prefab and the entities linked to it, taken before and after a prefab action
+#[derive(Clone, Debug)]
+pub struct PrefabSnapshot {
+    pub prefab_id: Uuid,
+    pub prefab: Option<Prefab>,
+    pub entities: Vec<(Uuid, Entity)>, // (scene id, entity)
+}
+
+impl PrefabSnapshot {
+    /// Captures the prefab, its instances and the given entity, which may not be an instance yet
+    pub fn capture(scene_manager: &SceneManager, prefab_id: Uuid, entity: Option<(Uuid, Uuid)>) -> Self {
+        let mut ids = scene_manager.instances_of(prefab_id);
+        if let Some(entity) = entity.filter(|entity| !ids.contains(entity)) {
+            ids.push(entity);
+        }
+        let entities = ids
+            .into_iter()
+            .filter_map(|(scene_id, entity_id)| Some((scene_id, entity_in(scene_manager, scene_id, entity_id).ok()?.clone())))
+            .collect();
+        Self {
+            prefab_id,
+            prefab: scene_manager.get_prefab(prefab_id).cloned(),
+            entities,
+        }
+    }
+
+    fn restore(&self, scene_manager: &mut SceneManager) -> Result<(), String> {
+        match &self.prefab {
+            Some(prefab) => {
+                scene_manager.prefabs.insert(self.prefab_id, prefab.clone());
+            }
+            None => {
+                scene_manager.prefabs.shift_remove(&self.prefab_id);
+            }
+        }
+        for (scene_id, entity) in &self.entities {
+            *entity_mut(scene_manager, *scene_id, entity.id)? = entity.clone();
+        }
+        Ok(())
+    }
+}
+
+impl EditCommand {
+    // === Constructors that read the current state ===
+
+    pub fn create_scene(scene_manager: &SceneManager, scene_id: Uuid) -> Result<Self, String> {
+        let index = scene_manager.scenes.get_index_of(&scene_id).ok_or("Scene not found")?;
+        Ok(EditCommand::CreateScene { scene_id, scene: scene_manager.scenes[index].clone(), index })
+    }
+
+    pub fn delete_scene(scene_manager: &SceneManager, scene_id: Uuid) -> Result<Self, String> {
+        let index = scene_manager.scenes.get_index_of(&scene_id).ok_or("Scene not found")?;
+        Ok(EditCommand::DeleteScene { scene_id, scene: scene_manager.scenes[index].clone(), index })
+    }
+
+    pub fn rename_scene(scene_manager: &SceneManager, scene_id: Uuid, new_name: &str) -> Result<Self, String> {
+        let scene = scene_manager.get_scene(scene_id).ok_or("Scene not found")?;
+        Ok(EditCommand::RenameScene { scene_id, old_name: scene.name.clone(), new_name: new_name.to_string() })
+    }
+
+    pub fn create_entity(scene_manager: &SceneManager, scene_id: Uuid, entity_id: Uuid) -> Result<Self, String> {
+        let scene = scene_manager.get_scene(scene_id).ok_or("Scene not found")?;
+        let index = scene.entities.get_index_of(&entity_id).ok_or("Entity not found")?;
+        Ok(EditCommand::CreateEntity { scene_id, entity: scene.entities[index].clone(), index })
+    }
+
+    pub fn delete_entity(scene_manager: &SceneManager, scene_id: Uuid, entity_id: Uuid) -> Result<Self, String> {
+        let scene = scene_manager.get_scene(scene_id).ok_or("Scene not found")?;
+        if !scene.entities.contains_key(&entity_id) {
+            return Err("Entity not found".to_string());
+        }
+        let entities = std::iter::once(entity_id)
+            .chain(scene.descendants_of(entity_id))
+            .filter_map(|id| {
+                let index = scene.entities.get_index_of(&id)?;
+                Some((index, scene.entities[index].clone()))
+            })
+            .collect();
+        Ok(EditCommand::DeleteEntity { scene_id, entities })
+    }
+
+    pub fn rename_entity(scene_manager: &SceneManager, scene_id: Uuid, entity_id: Uuid, new_name: &str) -> Result<Self, String> {
+        let entity = entity_in(scene_manager, scene_id, entity_id)?;
+        Ok(EditCommand::RenameEntity { scene_id, entity_id, old_name: entity.name.clone(), new_name: new_name.to_string() })
+    }
+
+    pub fn set_parent(scene_manager: &SceneManager, scene_id: Uuid, entity_id: Uuid, new_parent: Option<Uuid>) -> Result<Self, String> {
+        let entity = entity_in(scene_manager, scene_id, entity_id)?;
+        Ok(EditCommand::SetParent { scene_id, entity_id, old_parent: entity.parent, new_parent })
+    }
+
+    pub fn set_default_camera(scene_manager: &SceneManager, scene_id: Uuid, camera_id: Uuid) -> Result<Self, String> {
+        let scene = scene_manager.get_scene(scene_id).ok_or("Scene not found")?;
+        Ok(EditCommand::SetDefaultCamera { scene_id, old_camera: scene.default_camera, new_camera: camera_id })
+    }
+
+    pub fn set_animation(entity: &Entity, scene_id: Uuid, new: Option<Box<SpriteAnimation>>) -> Self {
+        EditCommand::SetAnimation {
+            scene_id,
+            entity_id: entity.id,
+            old: entity.animation.clone(),
+            new,
+        }
+    }
+
+    pub fn set_text(entity: &Entity, scene_id: Uuid, new: Option<Box<Text>>) -> Self {
+        EditCommand::SetText {
+            scene_id,
+            entity_id: entity.id,
+            old: entity.text.clone(),
+            new,
+        }
+    }
+
+    pub fn set_tilemap(entity: &Entity, scene_id: Uuid, new: Option<Box<Tilemap>>) -> Self {
+        EditCommand::SetTilemap {
+            scene_id,
+            entity_id: entity.id,
+            old: entity.tilemap.clone(),
+            new,
+        }
+    }
+
+    pub fn set_joints(entity: &Entity, scene_id: Uuid, new: Vec<Joint>) -> Self {
+        EditCommand::SetJoints {
+            scene_id,
+            entity_id: entity.id,
+            old: entity.joints.clone(),
+            new,
+        }
+    }
+
+    pub fn set_character_controller(entity: &Entity, scene_id: Uuid, new: Option<CharacterController>) -> Self {
+        EditCommand::SetCharacterController {
+            scene_id,
+            entity_id: entity.id,
+            old: entity.character_controller.clone(),
+            new,
+        }
+    }
+
+    pub fn set_gravity_source(entity: &Entity, scene_id: Uuid, new: Option<GravitySource>) -> Self {
+        EditCommand::SetGravitySource {
+            scene_id,
+            entity_id: entity.id,
+            old: entity.gravity_source.clone(),
+            new,
+        }
+    }
+
+    pub fn set_transform(entity: &Entity, scene_id: Uuid, new: Transform) -> Self {
+        EditCommand::SetTransform { scene_id, entity_id: entity.id, old: entity.local_transform(), new }
+    }
+
+    pub fn set_physics_settings(scene: &Scene, scene_id: Uuid, new: PhysicsSettings) -> Self {
+        EditCommand::SetPhysicsSettings { scene_id, old: scene.physics.clone(), new }
+    }
+
+    pub fn create_attribute(entity: &Entity, scene_id: Uuid, attribute_id: Uuid) -> Result<Self, String> {
+        let index = entity.attributes.get_index_of(&attribute_id).ok_or("Attribute not found")?;
+        Ok(EditCommand::CreateAttribute { scene_id, entity_id: entity.id, attribute: entity.attributes[index].clone(), index })
+    }
+
+    pub fn modify_attribute(entity: &Entity, scene_id: Uuid, attribute_id: Uuid, new_value: AttributeValue) -> Result<Self, String> {
+        let attribute = entity.get_attribute(attribute_id)?;
+        Ok(EditCommand::ModifyAttribute {
+            scene_id,
+            entity_id: entity.id,
+            attribute_id,
+            old_value: attribute.value.clone(),
+            new_value,
+        })
+    }
+
+    pub fn delete_attribute(entity: &Entity, scene_id: Uuid, attribute_id: Uuid) -> Result<Self, String> {
+        let index = entity.attributes.get_index_of(&attribute_id).ok_or("Attribute not found")?;
+        Ok(EditCommand::DeleteAttribute { scene_id, entity_id: entity.id, attribute: entity.attributes[index].clone(), index })
+    }
+
+    pub fn attach_asset(scene_manager: &SceneManager, scene_id: Uuid, entity_id: Uuid, kind: AssetKind, path: PathBuf) -> Result<Self, String> {
+        let entity = entity_in(scene_manager, scene_id, entity_id)?;
+        let replaced = if kind == AssetKind::Script { entity.script.clone() } else { None };
+        Ok(EditCommand::AttachAsset { scene_id, entity_id, kind, path, replaced })
+    }
+
+    pub fn detach_asset(scene_manager: &SceneManager, scene_id: Uuid, entity_id: Uuid, kind: AssetKind, path: PathBuf) -> Result<Self, String> {
+        let entity = entity_in(scene_manager, scene_id, entity_id)?;
+        let index = match kind {
+            AssetKind::Image => entity.images.iter().position(|p| *p == path),
+            AssetKind::Sound => entity.sounds.iter().position(|p| *p == path),
+            AssetKind::Script => (entity.script.as_ref() == Some(&path)).then_some(0),
+        }
+        .ok_or("Asset is not attached to the entity")?;
+        Ok(EditCommand::DetachAsset { scene_id, entity_id, kind, path, index })
+    }
+
+    // Short description for the Edit menu
+    pub fn description(&self) -> String {
+        match self {
+            EditCommand::CreateScene { scene, .. } => format!("Create scene '{}'", scene.name),
+            EditCommand::DeleteScene { scene, .. } => format!("Delete scene '{}'", scene.name),
+            EditCommand::RenameScene { new_name, .. } => format!("Rename scene to '{}'", new_name),
+            EditCommand::CreateEntity { entity, .. } => format!("Create entity '{}'", entity.name),
+            EditCommand::DeleteEntity { entities, .. } => match entities.first() {
+                Some((_, entity)) => format!("Delete entity '{}'", entity.name),
+                None => "Delete entity".to_string(),
+            },
+            EditCommand::RenameEntity { new_name, .. } => format!("Rename entity to '{}'", new_name),
+            EditCommand::SetParent { new_parent: Some(_), .. } => "Move entity".to_string(),
+            EditCommand::SetParent { new_parent: None, .. } => "Move entity to top level".to_string(),
+            EditCommand::CreateAttribute { attribute, .. } => format!("Create attribute '{}'", attribute.name),
+            EditCommand::ModifyAttribute { new_value, .. } => format!("Set attribute to {}", new_value),
+            EditCommand::DeleteAttribute { attribute, .. } => format!("Delete attribute '{}'", attribute.name),
+            EditCommand::AttachAsset { path, .. } => format!("Attach {}", file_name(path)),
+            EditCommand::DetachAsset { path, .. } => format!("Detach {}", file_name(path)),
+            EditCommand::PrefabChange { description, .. } => description.clone(),
+            EditCommand::SetAnimation { old: None, .. } => "Add animation".to_string(),
+            EditCommand::SetAnimation { new: None, .. } => "Remove animation".to_string(),
+            EditCommand::SetAnimation { .. } => "Edit animation".to_string(),
+            EditCommand::SetText { old: None, .. } => "Add text".to_string(),
+            EditCommand::SetText { new: None, .. } => "Remove text".to_string(),
+            EditCommand::SetText { .. } => "Edit text".to_string(),
+            EditCommand::SetTilemap { old: None, .. } => "Add tilemap".to_string(),
+            EditCommand::SetTilemap { new: None, .. } => "Remove tilemap".to_string(),
+            EditCommand::SetTilemap { .. } => "Edit tilemap".to_string(),
+            EditCommand::SetJoints { old, new, .. } if new.len() > old.len() => "Add joint".to_string(),
+            EditCommand::SetJoints { old, new, .. } if new.len() < old.len() => "Remove joint".to_string(),
+            EditCommand::SetJoints { .. } => "Edit joint".to_string(),
+            EditCommand::SetCharacterController { old: None, .. } => "Add character controller".to_string(),
+            EditCommand::SetCharacterController { new: None, .. } => "Remove character controller".to_string(),
+            EditCommand::SetCharacterController { .. } => "Edit character controller".to_string(),
+            EditCommand::SetGravitySource { old: None, .. } => "Add gravity source".to_string(),
+            EditCommand::SetGravitySource { new: None, .. } => "Remove gravity source".to_string(),
+            EditCommand::SetGravitySource { .. } => "Edit gravity source".to_string(),
+            EditCommand::SetTransform { .. } => "Edit transform".to_string(),
+            EditCommand::SetDefaultCamera { .. } => "Set active camera".to_string(),
+            EditCommand::SetPhysicsSettings { .. } => "Edit physics settings".to_string(),
+        }
+    }
+
+    // === Applying and reverting ===
+
+    pub fn apply(&self, scene_manager: &mut SceneManager) -> Result<(), String> {
+        match self {
+            EditCommand::CreateScene { scene_id, scene, index } => insert_scene(scene_manager, *scene_id, scene, *index),
+            EditCommand::DeleteScene { scene_id, .. } => remove_scene(scene_manager, *scene_id),
+            EditCommand::RenameScene { scene_id, new_name, .. } => {
+                scene_mut(scene_manager, *scene_id)?.modify_scene(new_name)
+            }
+            EditCommand::CreateEntity { scene_id, entity, index } => insert_entity(scene_manager, *scene_id, entity, *index),
+            EditCommand::DeleteEntity { scene_id, entities } => match entities.first() {
+                Some((_, entity)) => remove_entity(scene_manager, *scene_id, entity.id),
+                None => Ok(()),
+            },
+            EditCommand::RenameEntity { scene_id, entity_id, new_name, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.change_entity_name(new_name)
+            }
+            EditCommand::SetParent { scene_id, entity_id, new_parent, .. } => {
+                scene_mut(scene_manager, *scene_id)?.set_parent(*entity_id, *new_parent)
+            }
+            EditCommand::CreateAttribute { scene_id, entity_id, attribute, index } => {
+                insert_attribute(entity_mut(scene_manager, *scene_id, *entity_id)?, attribute, *index)
+            }
+            EditCommand::ModifyAttribute { scene_id, entity_id, attribute_id, new_value, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?
+                    .modify_attribute(*attribute_id, None, None, Some(new_value.clone()))
+            }
+            EditCommand::DeleteAttribute { scene_id, entity_id, attribute, .. } => {
+                remove_attribute(entity_mut(scene_manager, *scene_id, *entity_id)?, attribute.id)
+            }
+            EditCommand::AttachAsset { scene_id, entity_id, kind, path, .. } => {
+                let entity = entity_mut(scene_manager, *scene_id, *entity_id)?;
+                match kind {
+                    AssetKind::Image => entity.add_image(path.clone()),
+                    AssetKind::Sound => entity.add_sound(path.clone()),
+                    AssetKind::Script => {
+                        entity.script = Some(path.clone());
+                        Ok(())
+                    }
+                }
+            }
+            EditCommand::DetachAsset { scene_id, entity_id, kind, path, .. } => {
+                let entity = entity_mut(scene_manager, *scene_id, *entity_id)?;
+                match kind {
+                    AssetKind::Image => entity.remove_image(path),
+                    AssetKind::Sound => entity.remove_sound(path),
+                    AssetKind::Script => entity.remove_script(),
+                }
+            }
+            EditCommand::PrefabChange { after, .. } => after.restore(scene_manager),
+            EditCommand::SetAnimation { scene_id, entity_id, new, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.animation = new.clone();
+                Ok(())
+            }
+            EditCommand::SetText { scene_id, entity_id, new, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.text = new.clone();
+                Ok(())
+            }
+            EditCommand::SetTilemap { scene_id, entity_id, new, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.tilemap = new.clone();
+                Ok(())
+            }
+            EditCommand::SetJoints { scene_id, entity_id, new, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.joints = new.clone();
+                Ok(())
+            }
+            EditCommand::SetCharacterController { scene_id, entity_id, new, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.character_controller = new.clone();
+                Ok(())
+            }
+            EditCommand::SetGravitySource { scene_id, entity_id, new, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.gravity_source = new.clone();
+                Ok(())
+            }
+            EditCommand::SetTransform { scene_id, entity_id, new, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.set_local_transform(new)
+            }
+            EditCommand::SetDefaultCamera { scene_id, new_camera, .. } => {
+                scene_mut(scene_manager, *scene_id)?.set_default_camera(*new_camera)
+            }
+            EditCommand::SetPhysicsSettings { scene_id, new, .. } => {
+                scene_mut(scene_manager, *scene_id)?.physics = new.clone();
+                Ok(())
+            }
+        }
+    }
+
+    pub fn revert(&self, scene_manager: &mut SceneManager) -> Result<(), String> {
+        match self {
+            EditCommand::CreateScene { scene_id, .. } => remove_scene(scene_manager, *scene_id),
+            EditCommand::DeleteScene { scene_id, scene, index } => insert_scene(scene_manager, *scene_id, scene, *index),
+            EditCommand::RenameScene { scene_id, old_name, .. } => {
+                scene_mut(scene_manager, *scene_id)?.modify_scene(old_name)
+            }
+            EditCommand::CreateEntity { scene_id, entity, .. } => remove_entity(scene_manager, *scene_id, entity.id),
+            EditCommand::DeleteEntity { scene_id, entities } => {
+                // Lowest index first, so every entity lands back at its old position
+                let mut entities: Vec<&(usize, Entity)> = entities.iter().collect();
+                entities.sort_by_key(|(index, _)| *index);
+                for (index, entity) in entities {
+                    insert_entity(scene_manager, *scene_id, entity, *index)?;
+                }
+                Ok(())
+            }
+            EditCommand::RenameEntity { scene_id, entity_id, old_name, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.change_entity_name(old_name)
+            }
+            EditCommand::SetParent { scene_id, entity_id, old_parent, .. } => {
+                scene_mut(scene_manager, *scene_id)?.set_parent(*entity_id, *old_parent)
+            }
+            EditCommand::CreateAttribute { scene_id, entity_id, attribute, .. } => {
+                remove_attribute(entity_mut(scene_manager, *scene_id, *entity_id)?, attribute.id)
+            }
+            EditCommand::ModifyAttribute { scene_id, entity_id, attribute_id, old_value, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?
+                    .modify_attribute(*attribute_id, None, None, Some(old_value.clone()))
+            }
+            EditCommand::DeleteAttribute { scene_id, entity_id, attribute, index } => {
+                insert_attribute(entity_mut(scene_manager, *scene_id, *entity_id)?, attribute, *index)
+            }
+            EditCommand::AttachAsset { scene_id, entity_id, kind, path, replaced } => {
+                let entity = entity_mut(scene_manager, *scene_id, *entity_id)?;
+                match kind {
+                    AssetKind::Image => entity.remove_image(path),
+                    AssetKind::Sound => entity.remove_sound(path),
+                    AssetKind::Script => {
+                        entity.script = replaced.clone();
+                        Ok(())
+                    }
+                }
+            }
+            EditCommand::DetachAsset { scene_id, entity_id, kind, path, index } => {
+                let entity = entity_mut(scene_manager, *scene_id, *entity_id)?;
+                match kind {
+                    AssetKind::Image => {
+                        entity.images.insert((*index).min(entity.images.len()), path.clone());
+                        Ok(())
+                    }
+                    AssetKind::Sound => {
+                        entity.sounds.insert((*index).min(entity.sounds.len()), path.clone());
+                        Ok(())
+                    }
+                    AssetKind::Script => {
+                        entity.script = Some(path.clone());
+                        Ok(())
+                    }
+                }
+            }
+            EditCommand::PrefabChange { before, .. } => before.restore(scene_manager),
+            EditCommand::SetAnimation { scene_id, entity_id, old, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.animation = old.clone();
+                Ok(())
+            }
+            EditCommand::SetText { scene_id, entity_id, old, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.text = old.clone();
+                Ok(())
+            }
+            EditCommand::SetTilemap { scene_id, entity_id, old, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.tilemap = old.clone();
+                Ok(())
+            }
+            EditCommand::SetJoints { scene_id, entity_id, old, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.joints = old.clone();
+                Ok(())
+            }
+            EditCommand::SetCharacterController { scene_id, entity_id, old, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.character_controller = old.clone();
+                Ok(())
+            }
+            EditCommand::SetGravitySource { scene_id, entity_id, old, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.gravity_source = old.clone();
+                Ok(())
+            }
+            EditCommand::SetTransform { scene_id, entity_id, old, .. } => {
+                entity_mut(scene_manager, *scene_id, *entity_id)?.set_local_transform(old)
+            }
+            EditCommand::SetDefaultCamera { scene_id, old_camera, .. } => {
+                scene_mut(scene_manager, *scene_id)?.default_camera = *old_camera;
+                Ok(())
+            }
+            EditCommand::SetPhysicsSettings { scene_id, old, .. } => {
+                scene_mut(scene_manager, *scene_id)?.physics = old.clone();
+                Ok(())
+            }
+        }
+    }
+
+    // Folds a later edit into this one when both set the same attribute
+    fn merge(&mut self, next: &EditCommand) -> bool {
+        match (self, next) {
+            (
+                EditCommand::ModifyAttribute { scene_id, entity_id, attribute_id, new_value, .. },
+                EditCommand::ModifyAttribute { scene_id: next_scene, entity_id: next_entity, attribute_id: next_attribute, new_value: next_value, .. },
+            ) if scene_id == next_scene && entity_id == next_entity && attribute_id == next_attribute => {
+                *new_value = next_value.clone();
+                true
+            }
+            // Dragging a value of an animation changes it on every frame
+            (
+                EditCommand::SetAnimation { scene_id, entity_id, old: Some(_), new: new @ Some(_) },
+                EditCommand::SetAnimation { scene_id: next_scene, entity_id: next_entity, new: next_new @ Some(_), .. },
+            ) if scene_id == next_scene && entity_id == next_entity => {
+                *new = next_new.clone();
+                true
+            }
+            // Typing the text or dragging its size changes it on every frame
+            (
+                EditCommand::SetText { scene_id, entity_id, old: Some(_), new: new @ Some(_) },
+                EditCommand::SetText { scene_id: next_scene, entity_id: next_entity, new: next_new @ Some(_), .. },
+            ) if scene_id == next_scene && entity_id == next_entity => {
+                *new = next_new.clone();
+                true
+            }
+            // A brush stroke paints tiles on every frame
+            (
+                EditCommand::SetTilemap { scene_id, entity_id, old: Some(_), new: new @ Some(_) },
+                EditCommand::SetTilemap { scene_id: next_scene, entity_id: next_entity, new: next_new @ Some(_), .. },
+            ) if scene_id == next_scene && entity_id == next_entity => {
+                *new = next_new.clone();
+                true
+            }
+            // Dragging an anchor or a limit of a joint changes it on every frame
+            (
+                EditCommand::SetJoints { scene_id, entity_id, old, new },
+                EditCommand::SetJoints { scene_id: next_scene, entity_id: next_entity, new: next_new, .. },
+            ) if scene_id == next_scene && entity_id == next_entity && old.len() == new.len() && new.len() == next_new.len() => {
+                *new = next_new.clone();
+                true
+            }
+            // Dragging a value of the character controller changes it on every frame
+            (
+                EditCommand::SetCharacterController { scene_id, entity_id, old: Some(_), new: new @ Some(_) },
+                EditCommand::SetCharacterController { scene_id: next_scene, entity_id: next_entity, new: next_new @ Some(_), .. },
+            ) if scene_id == next_scene && entity_id == next_entity => {
+                *new = next_new.clone();
+                true
+            }
+            // Dragging the strength or the radius of a gravity source changes it on every frame
+            (
+                EditCommand::SetGravitySource { scene_id, entity_id, old: Some(_), new: new @ Some(_) },
+                EditCommand::SetGravitySource { scene_id: next_scene, entity_id: next_entity, new: next_new @ Some(_), .. },
+            ) if scene_id == next_scene && entity_id == next_entity => {
+                *new = next_new.clone();
+                true
+            }
+            // Dragging a position, rotation or scale changes the transform on every frame
+            (
+                EditCommand::SetTransform { scene_id, entity_id, new, .. },
+                EditCommand::SetTransform { scene_id: next_scene, entity_id: next_entity, new: next_new, .. },
+            ) if scene_id == next_scene && entity_id == next_entity => {
+                *new = *next_new;
+                true
+            }
+            // Dragging the gravity or the time step changes the settings on every frame
+            (
+                EditCommand::SetPhysicsSettings { scene_id, new, .. },
+                EditCommand::SetPhysicsSettings { scene_id: next_scene, new: next_new, .. },
+            ) if scene_id == next_scene => {
+                *new = next_new.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn file_name(path: &std::path::Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+fn scene_mut(scene_manager: &mut SceneManager, scene_id: Uuid) -> Result<&mut Scene, String> {
+    scene_manager.get_scene_mut(scene_id).ok_or_else(|| "Scene not found".to_string())
+}
+
+fn entity_in(scene_manager: &SceneManager, scene_id: Uuid, entity_id: Uuid) -> Result<&Entity, String> {
+    scene_manager.get_scene(scene_id).ok_or("Scene not found")?.get_entity(entity_id)
+}
+
+fn entity_mut(scene_manager: &mut SceneManager, scene_id: Uuid, entity_id: Uuid) -> Result<&mut Entity, String> {
+    scene_mut(scene_manager, scene_id)?.get_entity_mut(entity_id)
+}
+
+fn insert_scene(scene_manager: &mut SceneManager, scene_id: Uuid, scene: &Scene, index: usize) -> Result<(), String> {
+    if scene_manager.scenes.contains_key(&scene_id) {
+        return Err("Scene already exists".to_string());
+    }
+    let index = index.min(scene_manager.scenes.len());
+    scene_manager.scenes.shift_insert(index, scene_id, scene.clone());
+    Ok(())
+}
+
+fn remove_scene(scene_manager: &mut SceneManager, scene_id: Uuid) -> Result<(), String> {
+    match scene_manager.delete_scene(scene_id)? {
+        true => Ok(()),
+        false => Err("Scene not found".to_string()),
+    }
+}
+
+fn insert_entity(scene_manager: &mut SceneManager, scene_id: Uuid, entity: &Entity, index: usize) -> Result<(), String> {
+    let scene = scene_mut(scene_manager, scene_id)?;
+    if scene.entities.contains_key(&entity.id) {
+        return Err("Entity already exists".to_string());
+    }
+    let index = index.min(scene.entities.len());
+    scene.entities.shift_insert(index, entity.id, entity.clone());
+    Ok(())
+}
+
+fn remove_entity(scene_manager: &mut SceneManager, scene_id: Uuid, entity_id: Uuid) -> Result<(), String> {
+    match scene_mut(scene_manager, scene_id)?.delete_entity(entity_id)? {
+        true => Ok(()),
+        false => Err("Entity not found".to_string()),
+    }
+}
+
+fn insert_attribute(entity: &mut Entity, attribute: &Attribute, index: usize) -> Result<(), String> {
+    if entity.attributes.contains_key(&attribute.id) {
+        return Err(format!("Attribute '{}' already exists", attribute.name));
+    }
+    let index = index.min(entity.attributes.len());
+    entity.attributes.shift_insert(index, attribute.id, attribute.clone());
+    Ok(())
+}
+
+fn remove_attribute(entity: &mut Entity, attribute_id: Uuid) -> Result<(), String> {
+    match entity.delete_attribute(attribute_id)? {
+        true => Ok(()),
+        false => Err("Attribute not found".to_string()),
+    }
+}
+
+struct HistoryEntry {
+    command: EditCommand,
+    recorded_at: Instant,
+    mergeable: bool, // False for redone edits, so they stay separate from newer ones
+}
+
+/// Undo and redo stacks of the editor's edits.
+pub struct EditHistory {
+    undo_stack: VecDeque<HistoryEntry>,
+    redo_stack: Vec<EditCommand>,
+    max_depth: usize,
+    // Edits of the same attribute closer together than this are undone as one
+    merge_window: Duration,
+    // Changes on every undo and redo, so views caching values can refresh
+    revision: u64,
+}
+
+impl EditHistory {
+    pub const DEFAULT_MAX_DEPTH: usize = 100;
+
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            max_depth,
+            merge_window: Duration::from_millis(750),
+            revision: 0,
+        }
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+        while self.undo_stack.len() > max_depth {
+            self.undo_stack.pop_front();
+        }
+        self.redo_stack.truncate(max_depth);
+    }
+
+    pub fn set_merge_window(&mut self, merge_window: Duration) {
+        self.merge_window = merge_window;
+    }
+
+    /// Applies the command and records it.
+    pub fn execute(&mut self, command: EditCommand, scene_manager: &mut SceneManager) -> Result<(), String> {
+        command.apply(scene_manager)?;
+        self.record(command);
+        Ok(())
+    }
+
+    /// Records a command for an edit that was already made.
+    pub fn record(&mut self, command: EditCommand) {
+        self.redo_stack.clear();
+        if self.max_depth == 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        if let Some(last) = self.undo_stack.back_mut() {
+            if last.mergeable
+                && now.duration_since(last.recorded_at) <= self.merge_window
+                && last.command.merge(&command)
+            {
+                last.recorded_at = now;
+                return;
+            }
+        }
+
+        self.undo_stack.push_back(HistoryEntry { command, recorded_at: now, mergeable: true });
+        if self.undo_stack.len() > self.max_depth {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    /// Reverts the last edit. A command that no longer applies to the scenes is dropped.
+    pub fn undo(&mut self, scene_manager: &mut SceneManager) -> Result<(), String> {
+        let entry = self.undo_stack.pop_back().ok_or("Nothing to undo")?;
+        if let Err(e) = entry.command.revert(scene_manager) {
+            // Kept, so the edit isn't lost when the scene can't take it right now
+            self.undo_stack.push_back(entry);
+            return Err(e);
+        }
+        self.redo_stack.push(entry.command);
+        self.revision += 1;
+        Ok(())
+    }
+
+    /// Applies the last undone edit again.
+    pub fn redo(&mut self, scene_manager: &mut SceneManager) -> Result<(), String> {
+        let command = self.redo_stack.pop().ok_or("Nothing to redo")?;
+        if let Err(e) = command.apply(scene_manager) {
+            self.redo_stack.push(command);
+            return Err(e);
+        }
+        self.undo_stack.push_back(HistoryEntry { command, recorded_at: Instant::now(), mergeable: false });
+        if self.undo_stack.len() > self.max_depth {
+            self.undo_stack.pop_front();
+        }
+        self.revision += 1;
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn undo_description(&self) -> Option<String> {
+        self.undo_stack.back().map(|entry| entry.command.description())
+    }
+
+    pub fn redo_description(&self) -> Option<String> {
+        self.redo_stack.last().map(EditCommand::description)
+    }
+
+    pub fn undo_len(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.revision += 1;
+    }
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_DEPTH)
+    }
+}
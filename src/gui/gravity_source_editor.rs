@@ -0,0 +1,74 @@
+use eframe::egui;
+use uuid::Uuid;
+use crate::gravity_source::{GravityFalloff, GravitySource};
+use crate::physics_engine::{collision_layer_mask, format_collision_layers};
+
+/// Inspector section for an entity's gravity source. Works on a copy of the source;
+/// returns true when the copy was changed. `collision_layers` are the project's layers.
+pub fn show_gravity_source_editor(ui: &mut egui::Ui, entity_id: Uuid, source: &mut Option<GravitySource>, collision_layers: &[String]) -> bool {
+    let mut changed = false;
+
+    egui::CollapsingHeader::new("Gravity Source")
+        .id_salt(("gravity_source", entity_id))
+        .default_open(source.is_some())
+        .show(ui, |ui| {
+            let Some(current) = source.as_mut() else {
+                ui.label("Pulls the bodies around the entity that have gravity");
+                if ui.button("Add Gravity Source").clicked() {
+                    *source = Some(GravitySource::default());
+                    changed = true;
+                }
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                ui.label("Strength:");
+                changed |= ui.add(egui::DragValue::new(&mut current.strength).range(0.0..=f32::MAX).speed(10.0)).changed();
+                changed |= ui.checkbox(&mut current.repel, "Repel").changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Max radius:");
+                changed |= ui.add(egui::DragValue::new(&mut current.max_radius).range(0.0..=f32::MAX).speed(1.0)).changed();
+                if current.max_radius == 0.0 {
+                    ui.label(format!("(from strength: {:.0})", current.radius()));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Falloff:");
+                egui::ComboBox::from_id_salt(("gravity_falloff", entity_id))
+                    .selected_text(current.falloff.as_str())
+                    .show_ui(ui, |ui| {
+                        for falloff in GravityFalloff::ALL {
+                            changed |= ui.selectable_value(&mut current.falloff, falloff, falloff.as_str()).changed();
+                        }
+                    });
+            });
+
+            ui.label("Pulls layers");
+            match collision_layer_mask(collision_layers, &current.affected_layers) {
+                Ok(mask) => {
+                    let mut new_mask = mask;
+                    for (index, name) in collision_layers.iter().enumerate() {
+                        let mut checked = mask & (1 << index) != 0;
+                        if ui.checkbox(&mut checked, name).changed() {
+                            new_mask ^= 1 << index;
+                        }
+                    }
+                    if new_mask != mask {
+                        current.affected_layers = format_collision_layers(collision_layers, new_mask);
+                        changed = true;
+                    }
+                }
+                Err(_) => {
+                    ui.colored_label(egui::Color32::RED, "Unknown layer name, see Project > Collision Layers");
+                }
+            }
+
+            if ui.button("Remove Gravity Source").clicked() {
+                *source = None;
+                changed = true;
+            }
+        });
+
+    changed
+}
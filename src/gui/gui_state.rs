@@ -1,5 +1,7 @@
 use crate::ecs::SceneManager;
 use crate::project_manager::ProjectMetadata;
+use crate::gui::edit_history::EditHistory;
+use crate::gui::tilemap_editor::TileBrush;
 use std::path::PathBuf;
 use uuid::Uuid;
 use std::sync::{Arc, Mutex};
@@ -40,7 +42,10 @@ pub struct GuiState {
     pub build_result: Arc<Mutex<Option<Result<(), String>>>>,
     pub is_building: Arc<Mutex<bool>>,
     pub show_build_project_popup: bool,
+    pub show_collision_layers_window: bool,
 
+    pub edit_history: EditHistory,     // Undo/redo of edits to scene_manager
+    pub tile_brush: TileBrush,         // How the viewport paints the selected tilemap
 }
 
 impl GuiState {
@@ -66,6 +71,10 @@ impl GuiState {
             build_result: Arc::new(Mutex::new(None)),
             is_building: Arc::new(Mutex::new(false)),
             show_build_project_popup: false,
+            show_collision_layers_window: false,
+
+            edit_history: EditHistory::default(),
+            tile_brush: TileBrush::default(),
         }
     }
 }
\ No newline at end of file
@@ -1,6 +1,19 @@
 use eframe::egui;
 use crate::gui::gui_state::{GuiState, SelectedItem};
-use crate::ecs::{AttributeValue, AttributeType, Entity};
+use crate::ecs::{AttributeValue, AttributeType, BodyType, ColliderShape, Entity, Transform};
+use crate::gui::edit_history::{EditCommand, EditHistory};
+use crate::gui::animation_editor::AnimationEditor;
+use crate::gui::text_editor::show_text_editor;
+use crate::gui::joint_editor::show_joint_editor;
+use crate::gui::character_controller_editor::show_character_controller_editor;
+use crate::gui::gravity_source_editor::show_gravity_source_editor;
+use crate::gui::transform_editor::show_transform_editor;
+use crate::gui::physics_settings_editor::show_physics_settings_editor;
+use crate::gui::tilemap_editor::{TileBrush, TilemapEditor};
+use crate::physics_engine::{
+    collision_layer_mask, default_collision_layers, format_collider_points, format_collision_layers,
+    trace_sprite_outline, DEFAULT_COLLISION_LAYER,
+};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
@@ -23,6 +36,10 @@ pub struct Inspector {
     data_updated: bool,
     audio_engine: AudioEngine,
     delete_mode: bool,
+    // Edit history revision the editing states were filled at
+    history_revision: u64,
+    animation_editor: AnimationEditor,
+    tilemap_editor: TilemapEditor,
 }
 
 impl Inspector {
@@ -37,10 +54,20 @@ impl Inspector {
             data_updated: false,
             audio_engine: AudioEngine::new(),
             delete_mode: false,
+            history_revision: 0,
+            animation_editor: AnimationEditor::new(),
+            tilemap_editor: TilemapEditor::new(),
         }
     }
 
     pub fn show(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, gui_state: &mut GuiState) {
+        // Values shown in the text fields are stale after an undo or redo
+        if self.history_revision != gui_state.edit_history.revision() {
+            self.history_revision = gui_state.edit_history.revision();
+            self.editing_states.clear();
+            self.animation_editor.reset();
+        }
+
         match &gui_state.selected_item {
             SelectedItem::Entity(scene_id, entity_id) => {
                 let scene_id = *scene_id;
@@ -68,7 +95,10 @@ impl Inspector {
                     }
                 }
             }
-            SelectedItem::Scene(scene_id) => self.show_scene_details(ui, *scene_id, gui_state),
+            SelectedItem::Scene(scene_id) => {
+                let scene_id = *scene_id;
+                self.show_scene_details(ui, scene_id, gui_state)
+            }
             SelectedItem::File(file_path) => self.show_file_details(ui, file_path),
             SelectedItem::Asset(scene_id, entity_id, asset_path) => {
                 self.show_file_details(ui, asset_path);
@@ -80,14 +110,23 @@ impl Inspector {
     }
 
     // Display scene information
-    fn show_scene_details(&self, ui: &mut egui::Ui, scene_id: Uuid, gui_state: &GuiState) {
-        if let Some(scene_manager) = &gui_state.scene_manager {
-            if let Some(scene) = scene_manager.get_scene(scene_id) {
+    fn show_scene_details(&mut self, ui: &mut egui::Ui, scene_id: Uuid, gui_state: &mut GuiState) {
+        let edit_history = &mut gui_state.edit_history;
+        if let Some(scene_manager) = &mut gui_state.scene_manager {
+            if let Some(scene) = scene_manager.get_scene_mut(scene_id) {
                 ui.label("Scene Details");
                 ui.separator();
                 ui.label(format!("Name: {}", scene.name));
                 ui.label(format!("ID: {}", scene_id));
                 ui.label(format!("Number of Entities: {}", scene.entities.len()));
+
+                ui.separator();
+                let mut settings = scene.physics.clone();
+                if show_physics_settings_editor(ui, scene_id, &mut settings) {
+                    edit_history.record(EditCommand::set_physics_settings(scene, scene_id, settings.clone()));
+                    scene.physics = settings;
+                    self.data_updated = true;
+                }
             } else {
                 ui.label("Scene not found.");
             }
@@ -266,8 +305,24 @@ impl Inspector {
         entity_id: Uuid,
         gui_state: &mut GuiState,
     ) {
+        let edit_history = &mut gui_state.edit_history;
         if let Some(scene_manager) = &mut gui_state.scene_manager {
+            let prefab_names: HashMap<Uuid, String> = scene_manager.prefabs.iter()
+                .map(|(id, prefab)| (*id, prefab.name.clone()))
+                .collect();
             if let Some(scene) = scene_manager.get_scene_mut(scene_id) {
+                // x, y and z of a child entity are relative to its parent
+                let parent = scene.parent_of(entity_id).and_then(|parent_id| {
+                    let parent_name = scene.get_entity(parent_id).ok()?.name.clone();
+                    let world = scene.world_transform(entity_id).ok()?;
+                    Some((parent_name, world))
+                });
+                // Entities the joints of this one can connect to
+                let joint_targets: Vec<(Uuid, String)> = scene.entities.values()
+                    .filter(|other| other.id != entity_id)
+                    .map(|other| (other.id, other.name.clone()))
+                    .collect();
+
                 if let Ok(entity) = scene.get_entity_mut(entity_id) {
                     ui.label(format!("{}", entity.name));
                     ui.separator();
@@ -275,11 +330,42 @@ impl Inspector {
                     ui.separator();
                     ui.label(format!("Scene ID: {}", scene_id));
                     ui.separator();
+                    if let Some((parent_name, world)) = parent {
+                        ui.label(format!("Parent: {}", parent_name));
+                        ui.label(format!("World position: {:.2}, {:.2}, {:.2}", world.position.0, world.position.1, world.z));
+                        ui.separator();
+                    }
+                    if let Some(link) = &entity.prefab {
+                        let prefab_name = prefab_names.get(&link.prefab_id).map_or("missing", |name| name.as_str());
+                        if link.overrides.is_empty() {
+                            ui.label(format!("Prefab: {}", prefab_name));
+                        } else {
+                            ui.label(format!("Prefab: {} (overrides: {})", prefab_name, link.overrides.join(", ")));
+                        }
+                        ui.separator();
+                    }
+
+                    self.show_transform_tools(ui, entity, scene_id, edit_history);
 
+                    // The transform attributes are edited in the section above
                     for (&attribute_id, attribute) in &entity.attributes.clone() {
-                        self.display_attribute(ui, attribute_id, &attribute.name, &attribute.value, entity);
+                        if is_transform_attribute(&attribute.name, &attribute.value) {
+                            continue;
+                        }
+                        self.display_attribute(ui, attribute_id, &attribute.name, &attribute.value, entity, scene_id, edit_history);
                     }
 
+                    self.show_collider_tools(ui, entity, scene_id, edit_history, &gui_state.project_path);
+                    let collision_layers = gui_state.project_metadata.as_ref()
+                        .map_or_else(default_collision_layers, |metadata| metadata.collision_layers.clone());
+                    self.show_collision_layer_tools(ui, entity, scene_id, edit_history, &collision_layers);
+                    self.show_animation_tools(ui, entity, scene_id, edit_history);
+                    self.show_text_tools(ui, entity, scene_id, edit_history, &gui_state.project_path);
+                    self.show_tilemap_tools(ui, entity, scene_id, edit_history, &mut gui_state.tile_brush);
+                    self.show_joint_tools(ui, entity, scene_id, edit_history, &joint_targets);
+                    self.show_character_controller_tools(ui, entity, scene_id, edit_history);
+                    self.show_gravity_source_tools(ui, entity, scene_id, edit_history, &collision_layers);
+
                     // Buttons in same row with even spacing
                     ui.horizontal(|ui| {
                         let available_width = ui.available_width();
@@ -302,7 +388,7 @@ impl Inspector {
                     });
 
                     if self.show_metadata_popup {
-                        self.show_metadata_popup(ctx, ui, entity);
+                        self.show_metadata_popup(ctx, ui, entity, scene_id, edit_history);
                     }
                 } else {
                     ui.label("Entity not found.");
@@ -332,9 +418,202 @@ impl Inspector {
     }
 
 
+    /// Fill `collider_points` with the outline of the entity's sprite, for polygon and polyline colliders
+    fn show_collider_tools(
+        &mut self,
+        ui: &mut egui::Ui,
+        entity: &mut Entity,
+        scene_id: Uuid,
+        edit_history: &mut EditHistory,
+        project_path: &Path,
+    ) {
+        let shape = match entity.get_attribute_by_name("collider_shape").map(|attr| attr.value.clone()) {
+            Ok(AttributeValue::String(name)) => ColliderShape::from_name(&name),
+            _ => None,
+        };
+        if !matches!(shape, Some(ColliderShape::ConvexPolygon | ColliderShape::Polyline)) {
+            return;
+        }
+        let Ok(points_attribute_id) = entity.get_attribute_by_name("collider_points").map(|attr| attr.id) else {
+            return;
+        };
+        let Ok(image_path) = entity.get_image(0).cloned() else {
+            return;
+        };
+
+        if ui.button("Trace collider from sprite").clicked() {
+            let full_path = if image_path.is_absolute() { image_path } else { project_path.join(image_path) };
+            match trace_sprite_outline(&full_path) {
+                Some(mut outline) => {
+                    // A polyline is open, repeat the first point to close the outline
+                    if shape == Some(ColliderShape::Polyline) {
+                        outline.push(outline[0]);
+                    }
+                    let points = format_collider_points(&outline);
+                    self.set_attribute_value(entity, scene_id, points_attribute_id, AttributeValue::String(points), edit_history);
+                    self.editing_states.remove(&points_attribute_id);
+                }
+                None => println!("Could not trace an outline from {}", full_path.display()),
+            }
+        }
+    }
+
+    /// Pick the layer of a physical entity and the layers it collides with
+    fn show_collision_layer_tools(
+        &mut self,
+        ui: &mut egui::Ui,
+        entity: &mut Entity,
+        scene_id: Uuid,
+        edit_history: &mut EditHistory,
+        collision_layers: &[String],
+    ) {
+        let attribute = |name: &str| match entity.get_attribute_by_name(name) {
+            Ok(attribute) => match &attribute.value {
+                AttributeValue::String(value) => Some((attribute.id, value.clone())),
+                _ => None,
+            },
+            Err(_) => None,
+        };
+        let (Some((layer_id, layer)), Some((mask_id, mask))) = (attribute("collision_layer"), attribute("collision_mask")) else {
+            return;
+        };
+        // Empty values fall back to the defaults, like the physics engine does
+        let layer_bits = collision_layer_mask(collision_layers, if layer.is_empty() { DEFAULT_COLLISION_LAYER } else { &layer });
+        let mask_bits = collision_layer_mask(collision_layers, if mask.is_empty() { "all" } else { &mask });
+
+        egui::CollapsingHeader::new("Collision Layers")
+            .id_salt(("collision_layers", entity.id))
+            .show(ui, |ui| {
+                let (Ok(layer_bits), Ok(mask_bits)) = (layer_bits, mask_bits) else {
+                    ui.colored_label(egui::Color32::RED, "Unknown layer name, see Project > Collision Layers");
+                    return;
+                };
+
+                ui.label("Layer");
+                let mut new_layer = None;
+                for (index, name) in collision_layers.iter().enumerate() {
+                    if ui.radio(layer_bits & (1 << index) != 0, name).clicked() {
+                        new_layer = Some(name.clone());
+                    }
+                }
+
+                ui.label("Collides with");
+                let mut new_mask = mask_bits;
+                for (index, name) in collision_layers.iter().enumerate() {
+                    let mut checked = mask_bits & (1 << index) != 0;
+                    if ui.checkbox(&mut checked, name).changed() {
+                        new_mask ^= 1 << index;
+                    }
+                }
+
+                if let Some(name) = new_layer {
+                    self.set_attribute_value(entity, scene_id, layer_id, AttributeValue::String(name), edit_history);
+                    self.editing_states.remove(&layer_id);
+                }
+                if new_mask != mask_bits {
+                    let names = format_collision_layers(collision_layers, new_mask);
+                    self.set_attribute_value(entity, scene_id, mask_id, AttributeValue::String(names), edit_history);
+                    self.editing_states.remove(&mask_id);
+                }
+            });
+    }
+
+    /// Edit the entity's sprite animation, each change is one edit history entry
+    fn show_animation_tools(&mut self, ui: &mut egui::Ui, entity: &mut Entity, scene_id: Uuid, edit_history: &mut EditHistory) {
+        let mut animation = entity.animation.clone();
+        if self.animation_editor.show(ui, entity.id, &mut animation, entity.images.len()) {
+            edit_history.record(EditCommand::set_animation(entity, scene_id, animation.clone()));
+            entity.animation = animation;
+            self.data_updated = true;
+        }
+    }
+
+    /// Edit the entity's text, each change is one edit history entry
+    fn show_text_tools(&mut self, ui: &mut egui::Ui, entity: &mut Entity, scene_id: Uuid, edit_history: &mut EditHistory, project_path: &Path) {
+        let mut text = entity.text.clone();
+        if show_text_editor(ui, &mut text, project_path) {
+            edit_history.record(EditCommand::set_text(entity, scene_id, text.clone()));
+            entity.text = text;
+            self.data_updated = true;
+        }
+    }
+
+    /// Add, edit and remove the entity's joints to other entities
+    fn show_joint_tools(&mut self, ui: &mut egui::Ui, entity: &mut Entity, scene_id: Uuid, edit_history: &mut EditHistory, others: &[(Uuid, String)]) {
+        let mut joints = entity.joints.clone();
+        if show_joint_editor(ui, entity.id, &mut joints, others) {
+            edit_history.record(EditCommand::set_joints(entity, scene_id, joints.clone()));
+            entity.joints = joints;
+            self.data_updated = true;
+        }
+    }
+
+    /// Add, edit and remove the entity's character controller
+    fn show_character_controller_tools(&mut self, ui: &mut egui::Ui, entity: &mut Entity, scene_id: Uuid, edit_history: &mut EditHistory) {
+        let mut controller = entity.character_controller.clone();
+        if show_character_controller_editor(ui, entity.id, &mut controller) {
+            edit_history.record(EditCommand::set_character_controller(entity, scene_id, controller.clone()));
+            entity.character_controller = controller;
+            self.data_updated = true;
+        }
+    }
+
+    /// Add, edit and remove the entity's gravity source
+    fn show_gravity_source_tools(&mut self, ui: &mut egui::Ui, entity: &mut Entity, scene_id: Uuid, edit_history: &mut EditHistory, collision_layers: &[String]) {
+        let mut source = entity.gravity_source.clone();
+        if show_gravity_source_editor(ui, entity.id, &mut source, collision_layers) {
+            edit_history.record(EditCommand::set_gravity_source(entity, scene_id, source.clone()));
+            entity.gravity_source = source;
+            self.data_updated = true;
+        }
+    }
+
+    /// Edit the entity's position, z, rotation and scale as one transform
+    fn show_transform_tools(&mut self, ui: &mut egui::Ui, entity: &mut Entity, scene_id: Uuid, edit_history: &mut EditHistory) {
+        let mut transform = entity.local_transform();
+        if show_transform_editor(ui, entity.id, &mut transform) {
+            edit_history.record(EditCommand::set_transform(entity, scene_id, transform));
+            let old = entity.local_transform();
+            if let Err(err) = entity.set_local_transform(&transform) {
+                println!("Failed to set transform: {}", err);
+                return;
+            }
+            // Edits on a prefab instance stop following the prefab
+            if transform.rotation != old.rotation {
+                entity.mark_override("rotation");
+            }
+            if transform.scale != old.scale {
+                entity.mark_override("scale");
+            }
+            for attribute in entity.attributes.values() {
+                if Transform::ATTRIBUTES.contains(&attribute.name.as_str()) {
+                    self.editing_states.remove(&attribute.id);
+                }
+            }
+            self.data_updated = true;
+        }
+    }
+
+    /// Edit the entity's tilemap and pick the brush the viewport paints it with
+    fn show_tilemap_tools(&mut self, ui: &mut egui::Ui, entity: &mut Entity, scene_id: Uuid, edit_history: &mut EditHistory, brush: &mut TileBrush) {
+        let mut tilemap = entity.tilemap.clone();
+        if self.tilemap_editor.show(ui, &mut tilemap, &entity.images, brush) {
+            edit_history.record(EditCommand::set_tilemap(entity, scene_id, tilemap.clone()));
+            entity.tilemap = tilemap;
+            self.data_updated = true;
+        }
+    }
+
     /// Add metadata popup, type must be in Entity's attribute types
     // TODO: handle Vector2
-    fn show_metadata_popup(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, entity: &mut Entity) {
+    fn show_metadata_popup(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        entity: &mut Entity,
+        scene_id: Uuid,
+        edit_history: &mut EditHistory,
+    ) {
         egui::Window::new("Add Attribute")
             .collapsible(false)
             .resizable(false)
@@ -403,6 +682,10 @@ impl Inspector {
                             match entity.create_attribute(&full_name, attr_type, value) {
                                 Ok(attribute_id) => {
                                     println!("Created {} with ID: {}", full_name, attribute_id);
+                                    if let Ok(command) = EditCommand::create_attribute(entity, scene_id, attribute_id) {
+                                        edit_history.record(command);
+                                    }
+                                    entity.mark_override(&full_name);
                                     self.data_updated = true;
                                     self.show_metadata_popup = false;
                                     self.metadata_new_name.clear();
@@ -439,6 +722,8 @@ impl Inspector {
         attribute_name: &str,
         attribute_value: &AttributeValue,
         entity: &mut Entity,
+        scene_id: Uuid,
+        edit_history: &mut EditHistory,
     ) {
         let temp_value = self
             .editing_states
@@ -451,9 +736,19 @@ impl Inspector {
             // Only show delete button in delete mode
             if self.delete_mode {
                 if ui.small_button("❌").clicked() {
-                    entity.delete_attribute(attribute_id);
+                    let command = EditCommand::delete_attribute(entity, scene_id, attribute_id);
+                    match entity.delete_attribute(attribute_id) {
+                        Ok(true) => {
+                            if let Ok(command) = command {
+                                edit_history.record(command);
+                            }
+                            entity.mark_override(attribute_name);
+                            self.data_updated = true;
+                        }
+                        Ok(false) => {}
+                        Err(err) => println!("Failed to delete attribute: {}", err),
+                    }
                     self.editing_states.remove(&attribute_id);
-                    self.data_updated = true;
                     return;
                 }
             }
@@ -496,9 +791,41 @@ impl Inspector {
                     AttributeValue::Boolean(_) => {
                         let mut value = temp_value.parse::<bool>().unwrap_or(false);
                         if ui.checkbox(&mut value, "").changed() {
-                            entity.modify_attribute(attribute_id, None, None, Some(AttributeValue::Boolean(value)));
+                            self.set_attribute_value(entity, scene_id, attribute_id, AttributeValue::Boolean(value), edit_history);
                             self.editing_states.insert(attribute_id, value.to_string());
-                            self.data_updated = true;
+                        }
+                    }
+                    AttributeValue::String(current) if attribute_name == "collider_shape" => {
+                        let mut shape = ColliderShape::from_name(current).unwrap_or_default();
+                        egui::ComboBox::from_id_salt(attribute_id)
+                            .width(input_width)
+                            .selected_text(shape.as_str())
+                            .show_ui(ui, |ui| {
+                                for option in ColliderShape::ALL {
+                                    ui.selectable_value(&mut shape, option, option.as_str());
+                                }
+                            });
+                        if ColliderShape::from_name(current) != Some(shape) {
+                            self.set_attribute_value(entity, scene_id, attribute_id, AttributeValue::String(shape.as_str().to_string()), edit_history);
+                            self.editing_states.insert(attribute_id, shape.as_str().to_string());
+                        }
+                    }
+                    // Empty follows is_movable
+                    AttributeValue::String(current) if attribute_name == "body_type" => {
+                        let mut body_type = BodyType::from_name(current);
+                        egui::ComboBox::from_id_salt(attribute_id)
+                            .width(input_width)
+                            .selected_text(body_type.map_or("is_movable", |body_type| body_type.as_str()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut body_type, None, "is_movable");
+                                for option in BodyType::ALL {
+                                    ui.selectable_value(&mut body_type, Some(option), option.as_str());
+                                }
+                            });
+                        if BodyType::from_name(current) != body_type {
+                            let name = body_type.map_or("", |body_type| body_type.as_str()).to_string();
+                            self.set_attribute_value(entity, scene_id, attribute_id, AttributeValue::String(name.clone()), edit_history);
+                            self.editing_states.insert(attribute_id, name);
                         }
                     }
                     _ => {
@@ -509,9 +836,10 @@ impl Inspector {
 
                         if response.lost_focus() {
                             if let Some(new_value) = self.parse_attribute_value(&temp_value, attribute_value) {
-                                entity.modify_attribute(attribute_id, None, None, Some(new_value));
+                                if new_value != *attribute_value {
+                                    self.set_attribute_value(entity, scene_id, attribute_id, new_value, edit_history);
+                                }
                                 self.editing_states.remove(&attribute_id);
-                                self.data_updated = true;
                             } else {
                                 self.editing_states.remove(&attribute_id);
                             }
@@ -523,6 +851,32 @@ impl Inspector {
         ui.separator();
     }
 
+    /// Change an attribute's value and record the change in the edit history
+    fn set_attribute_value(
+        &mut self,
+        entity: &mut Entity,
+        scene_id: Uuid,
+        attribute_id: Uuid,
+        value: AttributeValue,
+        edit_history: &mut EditHistory,
+    ) {
+        let command = EditCommand::modify_attribute(entity, scene_id, attribute_id, value.clone());
+        match entity.modify_attribute(attribute_id, None, None, Some(value)) {
+            Ok(()) => {
+                if let Ok(command) = command {
+                    edit_history.record(command);
+                }
+                // Edits on a prefab instance stop following the prefab
+                if let Ok(attribute) = entity.get_attribute(attribute_id) {
+                    let name = attribute.name.clone();
+                    entity.mark_override(&name);
+                }
+                self.data_updated = true;
+            }
+            Err(err) => println!("Failed to modify attribute: {}", err),
+        }
+    }
+
     /// Validate for new attribute name
     fn is_valid_identifier(&self, name: &str) -> bool {
         !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
@@ -546,4 +900,12 @@ impl Inspector {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// Transform attributes of the type `Entity::local_transform` reads, which the transform section edits
+fn is_transform_attribute(name: &str, value: &AttributeValue) -> bool {
+    matches!(
+        (name, value),
+        ("x" | "y" | "z" | "rotation", AttributeValue::Float(_)) | ("scale", AttributeValue::Vector2(_, _))
+    )
+}
@@ -0,0 +1,135 @@
+use eframe::egui;
+use uuid::Uuid;
+use crate::joint::{Joint, JointKind, JointMotor};
+
+/// Inspector section for an entity's joints. Works on a copy of the joints; returns true
+/// when the copy was changed. `others` are the entities of the scene a joint can connect to.
+pub fn show_joint_editor(ui: &mut egui::Ui, entity_id: Uuid, joints: &mut Vec<Joint>, others: &[(Uuid, String)]) -> bool {
+    let mut changed = false;
+
+    egui::CollapsingHeader::new("Joints")
+        .id_salt(("joints", entity_id))
+        .default_open(!joints.is_empty())
+        .show(ui, |ui| {
+            let mut removed = None;
+            for (index, joint) in joints.iter_mut().enumerate() {
+                ui.push_id(joint.id, |ui| {
+                    changed |= show_joint(ui, joint, others);
+                    if ui.button("Remove Joint").clicked() {
+                        removed = Some(index);
+                    }
+                });
+                ui.separator();
+            }
+            if let Some(index) = removed {
+                joints.remove(index);
+                changed = true;
+            }
+
+            ui.add_enabled_ui(!others.is_empty(), |ui| {
+                if ui.button("Add Joint").clicked() {
+                    joints.push(Joint::new(JointKind::Revolute, others[0].0));
+                    changed = true;
+                }
+            });
+        });
+
+    changed
+}
+
+fn show_joint(ui: &mut egui::Ui, joint: &mut Joint, others: &[(Uuid, String)]) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("joint_kind")
+            .selected_text(joint.kind.as_str())
+            .show_ui(ui, |ui| {
+                for kind in JointKind::ALL {
+                    changed |= ui.selectable_value(&mut joint.kind, kind, kind.as_str()).changed();
+                }
+            });
+        ui.label("to");
+        // The other entity may have been deleted, the joint is then ignored
+        let other_name = others.iter()
+            .find(|(id, _)| *id == joint.other)
+            .map_or("(missing)".to_string(), |(_, name)| name.clone());
+        egui::ComboBox::from_id_salt("joint_other")
+            .selected_text(other_name)
+            .show_ui(ui, |ui| {
+                for (id, name) in others {
+                    changed |= ui.selectable_value(&mut joint.other, *id, name).changed();
+                }
+            });
+    });
+
+    changed |= drag_pair(ui, "Anchor:", &mut joint.anchor);
+    changed |= drag_pair(ui, "Other anchor:", &mut joint.other_anchor);
+
+    if joint.kind == JointKind::Prismatic {
+        changed |= drag_pair(ui, "Axis:", &mut joint.axis);
+    }
+
+    if joint.kind.has_limits() {
+        ui.horizontal(|ui| {
+            let mut limited = joint.limits.is_some();
+            let label = if joint.kind == JointKind::Revolute { "Limits (rad)" } else { "Limits" };
+            if ui.checkbox(&mut limited, label).changed() {
+                joint.limits = limited.then_some((-1.0, 1.0));
+                changed = true;
+            }
+            if let Some((min, max)) = joint.limits.as_mut() {
+                changed |= ui.add(egui::DragValue::new(min).speed(0.01)).changed();
+                changed |= ui.add(egui::DragValue::new(max).speed(0.01)).changed();
+                if *min > *max {
+                    std::mem::swap(min, max);
+                }
+            }
+        });
+    }
+
+    if joint.kind.has_motor() {
+        ui.horizontal(|ui| {
+            let mut driven = joint.motor.is_some();
+            if ui.checkbox(&mut driven, "Motor").changed() {
+                joint.motor = driven.then(JointMotor::default);
+                changed = true;
+            }
+            if let Some(motor) = joint.motor.as_mut() {
+                ui.label("Speed:");
+                changed |= ui.add(egui::DragValue::new(&mut motor.speed).speed(0.05)).changed();
+                ui.label("Max force:");
+                changed |= ui.add(egui::DragValue::new(&mut motor.max_force).range(0.0..=f32::MAX).speed(10.0)).changed();
+            }
+        });
+    }
+
+    if joint.kind.has_length() {
+        ui.horizontal(|ui| {
+            ui.label(if joint.kind == JointKind::Spring { "Rest length:" } else { "Length:" });
+            changed |= ui.add(egui::DragValue::new(&mut joint.length).range(0.01..=f32::MAX).speed(0.5)).changed();
+        });
+    }
+
+    if joint.kind == JointKind::Spring {
+        ui.horizontal(|ui| {
+            ui.label("Stiffness:");
+            changed |= ui.add(egui::DragValue::new(&mut joint.stiffness).range(0.0..=f32::MAX).speed(0.5)).changed();
+            ui.label("Damping:");
+            changed |= ui.add(egui::DragValue::new(&mut joint.damping).range(0.0..=f32::MAX).speed(0.05)).changed();
+        });
+    }
+
+    changed |= ui.checkbox(&mut joint.collide_connected, "Collide with the other entity").changed();
+
+    changed
+}
+
+fn drag_pair(ui: &mut egui::Ui, label: &str, value: &mut (f32, f32)) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let x = ui.add(egui::DragValue::new(&mut value.0).speed(0.5).prefix("x: ")).changed();
+        let y = ui.add(egui::DragValue::new(&mut value.1).speed(0.5).prefix("y: ")).changed();
+        x || y
+    })
+    .inner
+}
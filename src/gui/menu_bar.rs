@@ -68,4 +68,9 @@ impl MenuBar {
         });
     }
 
+    // Keyboard shortcuts of the menus, active while editing
+    pub fn handle_shortcuts(&mut self, ctx: &egui::Context, gui_state: &mut GuiState) {
+        self.edit_menu.handle_shortcuts(ctx, gui_state);
+    }
+
 }
\ No newline at end of file
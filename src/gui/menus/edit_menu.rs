@@ -1,5 +1,6 @@
 use eframe::egui;
 use crate::gui::gui_state::GuiState;
+use crate::gui::scene_hierarchy::utils;
 
 pub struct EditMenu;
 
@@ -9,8 +10,78 @@ impl EditMenu {
     }
 
     pub fn show(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, gui_state: &mut GuiState) {
-        ui.button("Undo");
-        ui.button("Redo");
+        let undo_shortcut = ctx.format_shortcut(&Self::undo_shortcut());
+        let redo_shortcut = ctx.format_shortcut(&Self::redo_shortcut());
+
+        let undo_label = match gui_state.edit_history.undo_description() {
+            Some(description) => format!("Undo {}", description),
+            None => "Undo".to_string(),
+        };
+        let undo_button = egui::Button::new(undo_label).shortcut_text(undo_shortcut);
+        if ui.add_enabled(gui_state.edit_history.can_undo(), undo_button).clicked() {
+            Self::undo(gui_state);
+            ui.close_menu();
+        }
+
+        let redo_label = match gui_state.edit_history.redo_description() {
+            Some(description) => format!("Redo {}", description),
+            None => "Redo".to_string(),
+        };
+        let redo_button = egui::Button::new(redo_label).shortcut_text(redo_shortcut);
+        if ui.add_enabled(gui_state.edit_history.can_redo(), redo_button).clicked() {
+            Self::redo(gui_state);
+            ui.close_menu();
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("History depth:");
+            let mut depth = gui_state.edit_history.max_depth();
+            if ui.add(egui::DragValue::new(&mut depth).range(1..=10_000)).changed() {
+                gui_state.edit_history.set_max_depth(depth);
+            }
+        });
+    }
+
+    fn undo_shortcut() -> egui::KeyboardShortcut {
+        egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z)
+    }
+
+    fn redo_shortcut() -> egui::KeyboardShortcut {
+        egui::KeyboardShortcut::new(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z)
+    }
+
+    /// Ctrl+Z / Ctrl+Shift+Z, left to text fields while one is being edited
+    pub fn handle_shortcuts(&mut self, ctx: &egui::Context, gui_state: &mut GuiState) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+
+        // Check redo first, the undo shortcut would also match it
+        if ctx.input_mut(|input| input.consume_shortcut(&Self::redo_shortcut())) {
+            Self::redo(gui_state);
+        } else if ctx.input_mut(|input| input.consume_shortcut(&Self::undo_shortcut())) {
+            Self::undo(gui_state);
+        }
+    }
+
+    fn undo(gui_state: &mut GuiState) {
+        if let Some(scene_manager) = &mut gui_state.scene_manager {
+            match gui_state.edit_history.undo(scene_manager) {
+                Ok(()) => utils::save_project(gui_state),
+                Err(err) => println!("Cannot undo: {}", err),
+            }
+        }
+    }
+
+    fn redo(gui_state: &mut GuiState) {
+        if let Some(scene_manager) = &mut gui_state.scene_manager {
+            match gui_state.edit_history.redo(scene_manager) {
+                Ok(()) => utils::save_project(gui_state),
+                Err(err) => println!("Cannot redo: {}", err),
+            }
+        }
     }
 
 }
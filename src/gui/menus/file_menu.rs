@@ -88,6 +88,7 @@ impl FileMenu {
 
                                             gui_state.project_metadata = Some(metadata);
                                             gui_state.scene_manager = Some(scene_manager);
+                                            gui_state.edit_history.clear();
 
                                             gui_state.show_new_project_popup = false;
                                             self.error_message.clear();
@@ -149,6 +150,7 @@ impl FileMenu {
 
                                     gui_state.project_metadata = Some(metadata);
                                     gui_state.scene_manager = Some(scene_manager);
+                                    gui_state.edit_history.clear();
 
                                     gui_state.show_open_project_popup = false;
                                     self.temp_project_path.clear();
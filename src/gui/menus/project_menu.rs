@@ -3,6 +3,7 @@ use crate::gui::gui_state::GuiState;
 use crate::logger::LOGGER;
 use std::sync::{Arc};
 use crate::project_manager::ProjectManager;
+use crate::physics_engine::MAX_COLLISION_LAYERS;
 
 pub struct ProjectMenu;
 
@@ -40,12 +41,68 @@ impl ProjectMenu {
             gui_state.show_build_project_popup = true;
         });
 
+        ui.add_enabled(gui_state.project_metadata.is_some(), egui::Button::new("Collision Layers")).clicked().then(|| {
+            gui_state.show_collision_layers_window = true;
+            ui.close_menu();
+        });
+
     }
 
     pub fn show_active_popup(&mut self, ctx: &egui::Context, gui_state: &mut GuiState) {
         if gui_state.show_build_project_popup {
             self.render_build_project_popup(ctx, gui_state);
         }
+        if gui_state.show_collision_layers_window {
+            self.render_collision_layers_window(ctx, gui_state);
+        }
+    }
+
+    // Name, add and remove the collision layers entities refer to in collision_layer and collision_mask
+    fn render_collision_layers_window(&self, ctx: &egui::Context, gui_state: &mut GuiState) {
+        let Some(metadata) = gui_state.project_metadata.as_mut() else {
+            gui_state.show_collision_layers_window = false;
+            return;
+        };
+
+        let mut open = true;
+        let mut changed = false;
+        egui::Window::new("Collision Layers")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let mut removed = None;
+                for (index, name) in metadata.collision_layers.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{:2}", index + 1));
+                        changed |= ui.text_edit_singleline(name).lost_focus();
+                        // The first layer is the default one of every entity
+                        if index > 0 && ui.small_button("🗑").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = removed {
+                    metadata.collision_layers.remove(index);
+                    changed = true;
+                }
+
+                ui.separator();
+                let can_add = metadata.collision_layers.len() < MAX_COLLISION_LAYERS;
+                if ui.add_enabled(can_add, egui::Button::new("Add Layer")).clicked() {
+                    metadata.collision_layers.push(format!("layer_{}", metadata.collision_layers.len() + 1));
+                    changed = true;
+                }
+                ui.label("Layers are matched by name, rename them in scenes too.");
+            });
+
+        if changed {
+            match ProjectManager::save_project(&gui_state.project_path, metadata) {
+                Ok(_) => LOGGER.info("Collision layers saved"),
+                Err(err) => LOGGER.error(format!("Failed to save collision layers: {}", err)),
+            }
+        }
+        gui_state.show_collision_layers_window = open;
     }
 
     fn render_build_project_popup(&self, ctx: &egui::Context, gui_state: &mut GuiState) {
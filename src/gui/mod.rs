@@ -4,3 +4,12 @@ pub mod menu_bar;
 pub mod gui_state;
 pub mod file_system;
 pub mod inspector;
+pub mod animation_editor;
+pub mod text_editor;
+pub mod tilemap_editor;
+pub mod joint_editor;
+pub mod character_controller_editor;
+pub mod gravity_source_editor;
+pub mod transform_editor;
+pub mod physics_settings_editor;
+pub mod edit_history;
@@ -0,0 +1,58 @@
+use eframe::egui;
+use uuid::Uuid;
+use crate::physics_settings::PhysicsSettings;
+
+/// Inspector section for a scene's physics settings. Works on a copy of the settings;
+/// returns true when the copy was changed.
+pub fn show_physics_settings_editor(ui: &mut egui::Ui, scene_id: Uuid, settings: &mut PhysicsSettings) -> bool {
+    let mut changed = false;
+
+    egui::CollapsingHeader::new("Physics")
+        .id_salt(("physics_settings", scene_id))
+        .default_open(true)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Gravity:");
+                changed |= ui.add(egui::DragValue::new(&mut settings.gravity.0).speed(0.5).prefix("x: ")).changed();
+                changed |= ui.add(egui::DragValue::new(&mut settings.gravity.1).speed(0.5).prefix("y: ")).changed();
+            });
+            ui.horizontal(|ui| {
+                // Edited as a rate, the step is its inverse
+                ui.label("Steps per second:");
+                let mut rate = (1.0 / settings.time_step).round();
+                if ui.add(egui::DragValue::new(&mut rate).range(1.0..=1000.0).speed(1.0).suffix(" Hz")).changed() {
+                    settings.time_step = 1.0 / rate;
+                    changed = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Solver iterations:");
+                changed |= ui.add(egui::DragValue::new(&mut settings.solver_iterations).range(1..=64)).changed();
+            });
+            changed |= ui.checkbox(&mut settings.ccd, "Continuous collision detection").changed();
+
+            ui.label("Sleeping:");
+            ui.horizontal(|ui| {
+                ui.label("Linear threshold:");
+                changed |= ui.add(egui::DragValue::new(&mut settings.sleep_linear_threshold).range(0.0..=f32::MAX).speed(0.01)).changed();
+                ui.label("Angular threshold:");
+                changed |= ui.add(egui::DragValue::new(&mut settings.sleep_angular_threshold).range(0.0..=f32::MAX).speed(0.01)).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Time until sleep:");
+                changed |= ui.add(egui::DragValue::new(&mut settings.time_until_sleep).range(0.0..=f32::MAX).speed(0.05).suffix(" s")).changed();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Gravity source strength:");
+                changed |= ui.add(egui::DragValue::new(&mut settings.custom_gravity_strength).speed(0.1)).changed();
+            });
+
+            if ui.button("Reset to Defaults").clicked() && *settings != PhysicsSettings::default() {
+                *settings = PhysicsSettings::default();
+                changed = true;
+            }
+        });
+
+    changed
+}
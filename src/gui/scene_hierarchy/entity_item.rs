@@ -1,78 +1,126 @@
-use crate::gui::scene_hierarchy::{SceneHierarchy, resource_item::ResourceItem};
+use crate::gui::scene_hierarchy::{SceneHierarchy, resource_item::ResourceItem, utils};
+use crate::gui::edit_history::EditCommand;
 use crate::gui::gui_state::{GuiState, ScenePanelSelectedItem, SelectedItem};
-use egui::{Context, Ui};
+use egui::Ui;
 use uuid::Uuid;
-use indexmap::IndexMap;
+use crate::ecs::{Entity, Scene};
 
 pub struct EntityItem;
 
+/// Drag and drop payload of an entity row, dropped on another entity to re-parent it
+#[derive(Clone, Copy)]
+pub struct DraggedEntity {
+    pub scene_id: Uuid,
+    pub entity_id: Uuid,
+}
+
 impl EntityItem {
     pub fn show_entities(
         ui: &mut Ui,
-        ctx: &Context,
         hierarchy: &mut SceneHierarchy,
         gui_state: &mut GuiState,
         scene_id: &Uuid,
-        entities: &IndexMap<Uuid, crate::ecs::Entity>,
+        scene: &Scene,
     ) {
-        let mut sorted_entities: Vec<(&Uuid, &crate::ecs::Entity)> = entities.iter().collect();
-        sorted_entities.sort_by(|(_, entity_a), (_, entity_b)| {
-            entity_a.name.to_lowercase().cmp(&entity_b.name.to_lowercase())
-        });
+        // While searching, matches are listed flat so children of collapsed parents show up too
+        if !hierarchy.search_query.is_empty() {
+            let query = hierarchy.search_query.to_lowercase();
+            let mut matches: Vec<&Entity> = scene.entities.values()
+                .filter(|entity| entity.name.to_lowercase().contains(&query))
+                .collect();
+            matches.sort_by_key(|entity| entity.name.to_lowercase());
+            for entity in matches {
+                Self::show_entity_node(ui, hierarchy, gui_state, scene_id, scene, entity, false);
+            }
+            return;
+        }
 
-        for (entity_id, entity) in sorted_entities {
-            if !hierarchy.search_query.is_empty()
-                && !entity.name.to_lowercase().contains(&hierarchy.search_query.to_lowercase()) {
-                continue;
+        for entity_id in Self::sorted_by_name(scene, scene.root_entities()) {
+            if let Ok(entity) = scene.get_entity(entity_id) {
+                Self::show_entity_node(ui, hierarchy, gui_state, scene_id, scene, entity, true);
             }
+        }
+    }
 
-            let header_id = ui.make_persistent_id(entity_id);
+    fn sorted_by_name(scene: &Scene, mut ids: Vec<Uuid>) -> Vec<Uuid> {
+        ids.sort_by_cached_key(|id| scene.get_entity(*id).map(|entity| entity.name.to_lowercase()).unwrap_or_default());
+        ids
+    }
+
+    // An entity row, with its children nested below it when `with_children` is set
+    fn show_entity_node(
+        ui: &mut Ui,
+        hierarchy: &mut SceneHierarchy,
+        gui_state: &mut GuiState,
+        scene_id: &Uuid,
+        scene: &Scene,
+        entity: &Entity,
+        with_children: bool,
+    ) {
+        let entity_id = &entity.id;
+        let children = if with_children { Self::sorted_by_name(scene, scene.children_of(entity.id)) } else { Vec::new() };
+        let header_id = ui.make_persistent_id(entity_id);
+        let ctx = ui.ctx().clone();
 
-            // Show as collapsable if has images or sounds, otherwise show as label
-            if !entity.images.is_empty() || !entity.sounds.is_empty() {
-                egui::collapsing_header::CollapsingState::load_with_default_open(ctx, header_id, true)
-                    .show_header(ui, |ui| {
-                        EntityItem::tree_item_entity(ui, scene_id, entity_id, &entity.name, hierarchy, gui_state);
-                    })
-                    .body(|ui| {
-                        if !entity.images.is_empty() {
-                            for path in &entity.images {
-                                let filename = path.file_name()
-                                    .unwrap_or_default()
-                                    .to_string_lossy()
-                                    .to_string();
-                                ui.horizontal(|ui| {
-                                    ui.label(format!("🔆 {}", filename));
-                                });
-                            }
+        // Show as collapsable if has children, images or sounds, otherwise show as label
+        if !children.is_empty() || !entity.images.is_empty() || !entity.sounds.is_empty() {
+            egui::collapsing_header::CollapsingState::load_with_default_open(&ctx, header_id, true)
+                .show_header(ui, |ui| {
+                    EntityItem::tree_item_entity(ui, scene_id, entity_id, &entity.name, hierarchy, gui_state);
+                })
+                .body(|ui| {
+                    for child_id in children {
+                        if let Ok(child) = scene.get_entity(child_id) {
+                            Self::show_entity_node(ui, hierarchy, gui_state, scene_id, scene, child, true);
                         }
-                        if !entity.sounds.is_empty() {
-                            for path in &entity.sounds {
-                                let filename = path.file_name()
-                                    .unwrap_or_default()
-                                    .to_string_lossy()
-                                    .to_string();
-                                ui.horizontal(|ui| {
-                                    ui.label(format!("🎵 {}", filename));
-                                });
-                            }
+                    }
+                    if !entity.images.is_empty() {
+                        for path in &entity.images {
+                            let filename = path.file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .to_string();
+                            ui.horizontal(|ui| {
+                                ui.label(format!("🔆 {}", filename));
+                            });
                         }
-                        if entity.script.is_some() {
-                                let filename = entity.script.clone().unwrap().file_name()
-                                    .unwrap_or_default()
-                                    .to_string_lossy()
-                                    .to_string();
-                                ui.horizontal(|ui| {
-                                    ui.label(format!("🎵 {}", filename));
-                                });
+                    }
+                    if !entity.sounds.is_empty() {
+                        for path in &entity.sounds {
+                            let filename = path.file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .to_string();
+                            ui.horizontal(|ui| {
+                                ui.label(format!("🎵 {}", filename));
+                            });
                         }
-                    });
-            } else {
-                ui.horizontal(|ui| {
-                    EntityItem::tree_item_entity(ui, scene_id, entity_id, &entity.name, hierarchy, gui_state);
+                    }
+                    if entity.script.is_some() {
+                            let filename = entity.script.clone().unwrap().file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .to_string();
+                            ui.horizontal(|ui| {
+                                ui.label(format!("🎵 {}", filename));
+                            });
+                    }
                 });
-            }
+        } else {
+            ui.horizontal(|ui| {
+                EntityItem::tree_item_entity(ui, scene_id, entity_id, &entity.name, hierarchy, gui_state);
+            });
+        }
+    }
+
+    /// Re-parent the dragged entity, `None` moves it to the top level
+    pub fn drop_entity(gui_state: &mut GuiState, dragged: DraggedEntity, scene_id: Uuid, new_parent: Option<Uuid>) {
+        if dragged.scene_id != scene_id || Some(dragged.entity_id) == new_parent {
+            return;
         }
+        utils::apply_edit(gui_state, |scene_manager| {
+            EditCommand::set_parent(scene_manager, scene_id, dragged.entity_id, new_parent)
+        });
     }
 
     pub fn tree_item_entity(
@@ -95,12 +143,22 @@ impl EntityItem {
             entity_name
         };
 
-        let response = ui.selectable_label(selected, format!("🖼 {}", display_name));
+        let response = ui.selectable_label(selected, format!("🖼 {}", display_name))
+            .interact(egui::Sense::drag());
         if response.clicked() {
             gui_state.selected_item = SelectedItem::Entity(*scene_id, *entity_id);
             gui_state.scene_panel_selected_item = ScenePanelSelectedItem::Entity(*scene_id, *entity_id);
         }
 
+        // Drag an entity onto another one to make it a child
+        response.dnd_set_drag_payload(DraggedEntity { scene_id: *scene_id, entity_id: *entity_id });
+        if response.dnd_hover_payload::<DraggedEntity>().is_some_and(|dragged| dragged.entity_id != *entity_id) {
+            ui.painter().rect_stroke(response.rect, 2.0, ui.visuals().selection.stroke);
+        }
+        if let Some(dragged) = response.dnd_release_payload::<DraggedEntity>() {
+            Self::drop_entity(gui_state, *dragged, *scene_id, Some(*entity_id));
+        }
+
         response.context_menu(|ui| {
             if ui.button("Attach Asset").clicked() {
                 hierarchy.popup_manager.resource_selection = Some((*scene_id, *entity_id));
@@ -112,21 +170,77 @@ impl EntityItem {
                 hierarchy.popup_manager.manage_assets_popup_active = true;
                 ui.close_menu();
             }
+            let has_parent = gui_state.scene_manager.as_ref()
+                .and_then(|scene_manager| scene_manager.get_scene(*scene_id))
+                .and_then(|scene| scene.parent_of(*entity_id))
+                .is_some();
+            if has_parent && ui.button("Move to Top Level").clicked() {
+                let dragged = DraggedEntity { scene_id: *scene_id, entity_id: *entity_id };
+                Self::drop_entity(gui_state, dragged, *scene_id, None);
+                ui.close_menu();
+            }
+            let camera_state = gui_state.scene_manager.as_ref()
+                .and_then(|scene_manager| scene_manager.get_scene(*scene_id))
+                .and_then(|scene| Some((scene.get_entity(*entity_id).ok()?.is_camera(), scene.default_camera == Some(*entity_id))));
+            if let Some((true, is_active)) = camera_state {
+                if ui.add_enabled(!is_active, egui::Button::new("Set as Active Camera")).clicked() {
+                    utils::apply_edit(gui_state, |scene_manager| {
+                        EditCommand::set_default_camera(scene_manager, *scene_id, *entity_id)
+                    });
+                    ui.close_menu();
+                }
+            }
+            Self::prefab_menu(ui, gui_state, *scene_id, *entity_id, entity_name);
             if ui.button("Rename").clicked() {
                 hierarchy.popup_manager.entity_rename_entity = Some((*scene_id, *entity_id));
                 hierarchy.popup_manager.rename_input = entity_name.to_string();
                 ui.close_menu();
             }
             if ui.button("Delete").clicked() {
-                gui_state
-                    .scene_manager
-                    .as_mut()
-                    .unwrap()
-                    .get_scene_mut(*scene_id)
-                    .unwrap()
-                    .delete_entity(*entity_id);
+                utils::apply_edit(gui_state, |scene_manager| {
+                    EditCommand::delete_entity(scene_manager, *scene_id, *entity_id)
+                });
                 ui.close_menu();
             }
         });
     }
+
+    // Create a prefab from an entity, or push/discard the overrides of an instance
+    fn prefab_menu(ui: &mut Ui, gui_state: &mut GuiState, scene_id: Uuid, entity_id: Uuid, entity_name: &str) {
+        let link = gui_state.scene_manager.as_ref()
+            .and_then(|scene_manager| scene_manager.get_scene(scene_id))
+            .and_then(|scene| scene.get_entity(entity_id).ok())
+            .and_then(|entity| entity.prefab.clone());
+
+        match link {
+            None => {
+                if ui.button("Create Prefab").clicked() {
+                    let description = format!("Create prefab '{}'", entity_name);
+                    utils::apply_prefab_change(gui_state, scene_id, entity_id, description, |scene_manager| {
+                        scene_manager.create_prefab(scene_id, entity_id, entity_name)
+                    });
+                    ui.close_menu();
+                }
+            }
+            Some(link) => {
+                // Every instance follows the prefab, including those in scenes that aren't loaded yet
+                if ui.add_enabled(!link.overrides.is_empty(), egui::Button::new("Apply Overrides to Prefab")).clicked()
+                    && utils::load_all_scenes(gui_state)
+                {
+                    let description = format!("Apply overrides of '{}'", entity_name);
+                    utils::apply_prefab_change(gui_state, scene_id, entity_id, description, |scene_manager| {
+                        scene_manager.apply_overrides_to_prefab(scene_id, entity_id).map(|_| link.prefab_id)
+                    });
+                    ui.close_menu();
+                }
+                if ui.button("Revert Instance").clicked() {
+                    let description = format!("Revert '{}' to prefab", entity_name);
+                    utils::apply_prefab_change(gui_state, scene_id, entity_id, description, |scene_manager| {
+                        scene_manager.revert_prefab_instance(scene_id, entity_id).map(|_| link.prefab_id)
+                    });
+                    ui.close_menu();
+                }
+            }
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use crate::ecs::{PhysicsProperties, SceneManager};
+use crate::gui::edit_history::{AssetKind, EditCommand, EditHistory};
 use crate::gui::gui_state::{GuiState, ScenePanelSelectedItem, SelectedItem};
 use crate::gui::scene_hierarchy::predefined_entities::PREDEFINED_ENTITIES;
 use crate::gui::scene_hierarchy::utils;
@@ -109,20 +110,15 @@ impl PopupManager {
     fn rename_item(&mut self, scene_id: Uuid, entity_id: Option<Uuid>, gui_state: &mut GuiState) {
         if let Some(scene_manager) = gui_state.scene_manager.as_mut() {
             let new_name = self.rename_input.trim().to_string();
-            if let Some(entity_id) = entity_id {
+            let command = match entity_id {
                 // Rename entity
-                if let Some(scene) = scene_manager.get_scene_mut(scene_id) {
-                    if let Ok(entity) = scene.get_entity_mut(entity_id) {
-                        entity.name = new_name;
-                        println!("Renamed entity to: {}", entity.name);
-                    }
-                }
-            } else {
+                Some(entity_id) => EditCommand::rename_entity(scene_manager, scene_id, entity_id, &new_name),
                 // Rename scene
-                if let Some(scene) = scene_manager.get_scene_mut(scene_id) {
-                    scene.name = new_name;
-                    println!("Renamed scene to: {}", scene.name);
-                }
+                None => EditCommand::rename_scene(scene_manager, scene_id, &new_name),
+            };
+            match command.and_then(|command| gui_state.edit_history.execute(command, scene_manager)) {
+                Ok(()) => println!("Renamed to: {}", new_name),
+                Err(err) => println!("Failed to rename: {}", err),
             }
             utils::save_project(gui_state);
         }
@@ -151,6 +147,7 @@ impl PopupManager {
                         ("Entity", "Entity"),
                         ("Camera", "Camera"),
                         ("Physics", "Physics"),
+                        ("Tilemap", "Tilemap"),
                     ];
 
                     for (type_name, label) in all_item_types {
@@ -208,6 +205,7 @@ impl PopupManager {
             "Entity" => self.create_new_entity("Entity".to_string(), gui_state, "Empty"),
             "Camera" => self.create_new_entity("Camera".to_string(), gui_state, "Camera"),
             "Physics" => self.create_new_entity("Physics".to_string(), gui_state, "Physics"),
+            "Tilemap" => self.create_new_entity("Tilemap".to_string(), gui_state, "Tilemap"),
             other => {
                 if PREDEFINED_ENTITIES
                     .iter()
@@ -255,6 +253,9 @@ impl PopupManager {
                 gui_state.selected_item = SelectedItem::Scene(new_scene_id);
 
                 println!("Created new scene '{}' with ID: {:?}", name, new_scene_id);
+                if let Ok(command) = EditCommand::create_scene(scene_manager, new_scene_id) {
+                    gui_state.edit_history.record(command);
+                }
 
                 // Save the project
                 utils::save_project(gui_state);
@@ -305,6 +306,7 @@ impl PopupManager {
         let new_entity_id = match predefined_type {
             "Empty" => scene.create_entity(name),
             "Camera" => scene.create_camera(name),
+            "Tilemap" => scene.create_tilemap(name),
             "Physics" => {
                 match scene.create_entity(name) {
                     Ok(entity_id) => {
@@ -333,6 +335,9 @@ impl PopupManager {
                     "Created new entity '{}' with type '{}' and ID: {:?}",
                     name, entity_type, entity_id
                 );
+                if let Ok(command) = EditCommand::create_entity(scene_manager, scene_id, entity_id) {
+                    gui_state.edit_history.record(command);
+                }
 
                 // Save the project
                 utils::save_project(gui_state);
@@ -356,24 +361,31 @@ impl PopupManager {
         // Render manage assets popup
         if self.manage_assets_popup_active {
             if let Some(scene_manager) = &mut gui_state.scene_manager {
-                self.show_manage_assets_popup(ctx, scene_manager);
+                if self.show_manage_assets_popup(ctx, scene_manager, &mut gui_state.edit_history) {
+                    utils::save_project(gui_state);
+                }
             }
         }
 
         // Render resource selection popup
         if self.resource_selection.is_some() {
             if let Some(scene_manager) = &mut gui_state.scene_manager {
-                self.show_resource_selection_popup(ctx, scene_manager, &gui_state.project_path);
+                if self.show_resource_selection_popup(ctx, scene_manager, &mut gui_state.edit_history, &gui_state.project_path) {
+                    utils::save_project(gui_state);
+                }
             }
         }
     }
 
+    /// Returns true if an asset was attached
     pub fn show_resource_selection_popup(
         &mut self,
         ctx: &egui::Context,
         scene_manager: &mut SceneManager,
+        edit_history: &mut EditHistory,
         project_path: &Path,
-    ) {
+    ) -> bool {
+        let mut attached = false;
         if let Some((scene_id, entity_id)) = self.resource_selection {
             egui::Window::new("Attach Resource")
                 .open(&mut self.resource_selection_popup_active)
@@ -418,27 +430,22 @@ impl PopupManager {
                                 for resource_path in &self.available_resources {
                                     if let Some(filename) = resource_path.file_name() {
                                         if ui.button(filename.to_string_lossy().to_string()).clicked() {
-                                            if let Some(scene) = scene_manager.get_scene_mut(scene_id) {
-                                                if let Ok(entity) = scene.get_entity_mut(entity_id) {
-                                                    match self.selected_resource_type.as_str() {
-                                                        "Images" => {
-                                                            // Check if image is already attached
-                                                            if !entity.images.contains(resource_path) {
-                                                                entity.images.push(resource_path.clone());
-                                                            }
-                                                        },
-                                                        "Sounds" => {
-                                                            // Check if sound is already attached
-                                                            if !entity.sounds.contains(resource_path) {
-                                                                entity.sounds.push(resource_path.clone());
-                                                            }
-                                                        },
-                                                        "Scripts" => {
-                                                            if entity.script.as_ref() != Some(&resource_path) {
-                                                                entity.script = Some(resource_path.clone());
-                                                            }
-                                                        },
-                                                        _ => {}
+                                            if let Some(scene) = scene_manager.get_scene(scene_id) {
+                                                if let Ok(entity) = scene.get_entity(entity_id) {
+                                                    // Skip assets that are already attached
+                                                    let kind = match self.selected_resource_type.as_str() {
+                                                        "Images" if !entity.images.contains(resource_path) => Some(AssetKind::Image),
+                                                        "Sounds" if !entity.sounds.contains(resource_path) => Some(AssetKind::Sound),
+                                                        "Scripts" if entity.script.as_ref() != Some(resource_path) => Some(AssetKind::Script),
+                                                        _ => None,
+                                                    };
+                                                    if let Some(kind) = kind {
+                                                        let result = EditCommand::attach_asset(scene_manager, scene_id, entity_id, kind, resource_path.clone())
+                                                            .and_then(|command| edit_history.execute(command, scene_manager));
+                                                        match result {
+                                                            Ok(()) => attached = true,
+                                                            Err(err) => eprintln!("Failed to attach asset: {}", err),
+                                                        }
                                                     }
                                                 }
                                             }
@@ -454,13 +461,17 @@ impl PopupManager {
                 self.resource_selection = None;
             }
         }
+        attached
     }
 
+    /// Returns true if an asset was detached
     pub fn show_manage_assets_popup(
         &mut self,
         ctx: &egui::Context,
         scene_manager: &mut SceneManager,
-    ) {
+        edit_history: &mut EditHistory,
+    ) -> bool {
+        let mut to_detach = None;
         if let Some((scene_id, entity_id)) = self.manage_assets_entity {
             egui::Window::new("Manage Assets")
                 .open(&mut self.manage_assets_popup_active)
@@ -468,26 +479,21 @@ impl PopupManager {
                 .resizable(false)
                 .order(egui::Order::Foreground)
                 .show(ctx, |ui| {
-                    if let Some(scene) = scene_manager.get_scene_mut(scene_id) {
-                        if let Ok(entity) = scene.get_entity_mut(entity_id) {
+                    if let Some(scene) = scene_manager.get_scene(scene_id) {
+                        if let Ok(entity) = scene.get_entity(entity_id) {
                             // Images section as collapsing header
                             if !entity.images.is_empty() {
                                 egui::CollapsingHeader::new("Images")
                                     .default_open(true)
                                     .show(ui, |ui| {
-                                        let mut images_to_remove = Vec::new();
-                                        for (i, path) in entity.images.iter().enumerate() {
+                                        for path in entity.images.iter() {
                                             ui.horizontal(|ui| {
                                                 ui.label(path.file_name().unwrap_or_default().to_string_lossy().to_string());
                                                 if ui.button("Remove").clicked() {
-                                                    images_to_remove.push(i);
+                                                    to_detach = Some((AssetKind::Image, path.clone()));
                                                 }
                                             });
                                         }
-                                        // Remove images outside the loop
-                                        for &i in images_to_remove.iter().rev() {
-                                            entity.images.remove(i);
-                                        }
                                     });
                             }
 
@@ -496,31 +502,26 @@ impl PopupManager {
                                 egui::CollapsingHeader::new("Sounds")
                                     .default_open(true)
                                     .show(ui, |ui| {
-                                        let mut sounds_to_remove = Vec::new();
-                                        for (i, path) in entity.sounds.iter().enumerate() {
+                                        for path in entity.sounds.iter() {
                                             ui.horizontal(|ui| {
                                                 ui.label(path.file_name().unwrap_or_default().to_string_lossy().to_string());
                                                 if ui.button("Remove").clicked() {
-                                                    sounds_to_remove.push(i);
+                                                    to_detach = Some((AssetKind::Sound, path.clone()));
                                                 }
                                             });
                                         }
-                                        // Remove sounds outside the loop
-                                        for &i in sounds_to_remove.iter().rev() {
-                                            entity.sounds.remove(i);
-                                        }
                                     });
                             }
 
                             // Script section as collapsing header
-                            if entity.script.is_some() {
+                            if let Some(script) = &entity.script {
                                 egui::CollapsingHeader::new("Script")
                                     .default_open(true)
                                     .show(ui, |ui| {
                                         ui.horizontal(|ui| {
-                                            ui.label(entity.script.clone().unwrap().file_name().unwrap_or_default().to_string_lossy().to_string());
+                                            ui.label(script.file_name().unwrap_or_default().to_string_lossy().to_string());
                                             if ui.button("Remove").clicked() {
-                                                entity.script = None;
+                                                to_detach = Some((AssetKind::Script, script.clone()));
                                             }
                                         });
                                     });
@@ -528,7 +529,18 @@ impl PopupManager {
                         }
                     }
                 });
+
+            // Remove the asset outside the window
+            if let Some((kind, path)) = to_detach {
+                let result = EditCommand::detach_asset(scene_manager, scene_id, entity_id, kind, path)
+                    .and_then(|command| edit_history.execute(command, scene_manager));
+                match result {
+                    Ok(()) => return true,
+                    Err(err) => eprintln!("Failed to detach asset: {}", err),
+                }
+            }
         }
+        false
     }
 
 }
@@ -6,25 +6,12 @@ pub struct EntityDefinition {
 }
 
 pub const PREDEFINED_ENTITIES: &[EntityDefinition] = &[
-    EntityDefinition {
-        name: "Camera",
-        attributes: &[
-            ("transform_position_x", AttributeType::Float, AttributeValue::Float(0.0)),
-            ("transform_position_y", AttributeType::Float, AttributeValue::Float(0.0)),
-            ("transform_position_z", AttributeType::Float, AttributeValue::Float(0.0)),
-            ("transform_rotation_x", AttributeType::Float, AttributeValue::Float(0.0)),
-            ("transform_rotation_y", AttributeType::Float, AttributeValue::Float(0.0)),
-            ("transform_rotation_z", AttributeType::Float, AttributeValue::Float(0.0)),
-            ("transform_scale_x", AttributeType::Float, AttributeValue::Float(1.0)),
-            ("transform_scale_y", AttributeType::Float, AttributeValue::Float(1.0)),
-            ("transform_scale_z", AttributeType::Float, AttributeValue::Float(1.0)),
-        ],
-    },
     EntityDefinition {
         name: "Physics",
         attributes: &[
-            ("position", AttributeType::Vector2, AttributeValue::Vector2(0.0, 0.0)),
             ("is_movable", AttributeType::Boolean, AttributeValue::Boolean(true)),
+            // An empty body type is dynamic or fixed, following is_movable
+            ("body_type", AttributeType::String, AttributeValue::String(String::new())),
             ("has_gravity", AttributeType::Boolean, AttributeValue::Boolean(true)),
             ("creates_gravity", AttributeType::Boolean, AttributeValue::Boolean(false)),
             ("has_collision", AttributeType::Boolean, AttributeValue::Boolean(true)),
@@ -32,6 +19,19 @@ pub const PREDEFINED_ENTITIES: &[EntityDefinition] = &[
             ("restitution", AttributeType::Float, AttributeValue::Float(0.0)),
             ("density", AttributeType::Float, AttributeValue::Float(1.0)),
             ("can_rotate", AttributeType::Boolean, AttributeValue::Boolean(true)),
+            // An empty shape is a box; a (0, 0) size fits the collider to the sprite
+            ("collider_shape", AttributeType::String, AttributeValue::String(String::new())),
+            ("collider_size", AttributeType::Vector2, AttributeValue::Vector2(0.0, 0.0)),
+            ("collider_offset", AttributeType::Vector2, AttributeValue::Vector2(0.0, 0.0)),
+            ("collider_points", AttributeType::String, AttributeValue::String(String::new())),
+            // Empty layers are the default layer, an empty mask collides with every layer
+            ("is_trigger", AttributeType::Boolean, AttributeValue::Boolean(false)),
+            ("collision_layer", AttributeType::String, AttributeValue::String(String::new())),
+            ("collision_mask", AttributeType::String, AttributeValue::String(String::new())),
+            // Kept in sync with the body while playing
+            ("rotation", AttributeType::Float, AttributeValue::Float(0.0)),
+            ("velocity", AttributeType::Vector2, AttributeValue::Vector2(0.0, 0.0)),
+            ("angular_velocity", AttributeType::Float, AttributeValue::Float(0.0)),
         ],
     },
 ];
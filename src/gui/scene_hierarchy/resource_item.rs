@@ -3,7 +3,8 @@ use crate::gui::gui_state::{GuiState, ScenePanelSelectedItem, SelectedItem};
 use egui::Ui;
 use uuid::Uuid;
 use std::path::PathBuf;
-use super::utils::get_icon_for_file;
+use super::utils::{self, get_icon_for_file};
+use crate::gui::edit_history::{AssetKind, EditCommand};
 use crate::ecs::Entity;
 
 pub struct ResourceItem;
@@ -40,15 +41,9 @@ impl ResourceItem {
             let path_clone = path.clone();
             response.context_menu(|ui| {
                 if ui.button("Remove").clicked() {
-                    if let Some(scene_manager) = &mut gui_state.scene_manager {
-                        if let Some(scene) = scene_manager.get_scene_mut(scene_id) {
-                            if let Ok(entity) = scene.get_entity_mut(entity_id) {
-                                if let Err(e) = entity.remove_image(&path_clone) {
-                                    eprintln!("Failed to remove image: {}", e);
-                                }
-                            }
-                        }
-                    }
+                    utils::apply_edit(gui_state, |scene_manager| {
+                        EditCommand::detach_asset(scene_manager, scene_id, entity_id, AssetKind::Image, path_clone.clone())
+                    });
                     ui.close_menu();
                 }
             });
@@ -77,15 +72,9 @@ impl ResourceItem {
             let path_clone = path.clone();
             response.context_menu(|ui| {
                 if ui.button("Remove").clicked() {
-                    if let Some(scene_manager) = &mut gui_state.scene_manager {
-                        if let Some(scene) = scene_manager.get_scene_mut(scene_id) {
-                            if let Ok(entity) = scene.get_entity_mut(entity_id) {
-                                if let Err(e) = entity.remove_sound(&path_clone) {
-                                    eprintln!("Failed to remove sound: {}", e);
-                                }
-                            }
-                        }
-                    }
+                    utils::apply_edit(gui_state, |scene_manager| {
+                        EditCommand::detach_asset(scene_manager, scene_id, entity_id, AssetKind::Sound, path_clone.clone())
+                    });
                     ui.close_menu();
                 }
             });
@@ -114,15 +103,9 @@ impl ResourceItem {
 
                 response.context_menu(|ui| {
                     if ui.button("Remove").clicked() {
-                        if let Some(scene_manager) = &mut gui_state.scene_manager {
-                            if let Some(scene) = scene_manager.get_scene_mut(scene_id) {
-                                if let Ok(entity) = scene.get_entity_mut(entity_id) {
-                                    if let Err(e) = entity.remove_script() {
-                                        eprintln!("Failed to remove script: {}", e);
-                                    }
-                                }
-                            }
-                        }
+                        utils::apply_edit(gui_state, |scene_manager| {
+                            EditCommand::detach_asset(scene_manager, scene_id, entity_id, AssetKind::Script, script_path.clone())
+                        });
                         ui.close_menu();
                     }
                 });
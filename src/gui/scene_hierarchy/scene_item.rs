@@ -1,4 +1,5 @@
-use crate::gui::scene_hierarchy::{SceneHierarchy, entity_item::EntityItem};
+use crate::gui::scene_hierarchy::{SceneHierarchy, entity_item::{DraggedEntity, EntityItem}, utils};
+use crate::gui::edit_history::EditCommand;
 use crate::gui::gui_state::{GuiState, SelectedItem, ScenePanelSelectedItem};
 use egui::{Context, Ui};
 use uuid::Uuid;
@@ -13,8 +14,8 @@ impl SceneItem {
         hierarchy: &mut SceneHierarchy,
         gui_state: &mut GuiState,
     ) {
-        let scenes = if let Some(scene_manager) = &gui_state.scene_manager {
-            scene_manager.scenes.clone()
+        let (scenes, unloaded_scenes) = if let Some(scene_manager) = &gui_state.scene_manager {
+            (scene_manager.scenes.clone(), scene_manager.unloaded_scenes.clone())
         } else {
             egui::Frame {
                 inner_margin: egui::Margin { left: 4.0, right: 0.0, top: 0.0, bottom: 0.0 },
@@ -29,23 +30,61 @@ impl SceneItem {
             return;
         };
 
-        let mut sorted_scenes: Vec<(&Uuid, &Scene)> = scenes.iter().collect();
-        sorted_scenes.sort_by(|(_, scene_a), (_, scene_b)| {
-            scene_a.name.to_lowercase().cmp(&scene_b.name.to_lowercase())
+        // Scenes that aren't loaded yet are listed by name and read when they are opened
+        let mut sorted_scenes: Vec<(&Uuid, &str, Option<&Scene>)> = scenes.iter()
+            .map(|(scene_id, scene)| (scene_id, scene.name.as_str(), Some(scene)))
+            .chain(unloaded_scenes.iter().map(|(scene_id, name)| (scene_id, name.as_str(), None)))
+            .collect();
+        sorted_scenes.sort_by(|(_, name_a, _), (_, name_b, _)| {
+            name_a.to_lowercase().cmp(&name_b.to_lowercase())
         });
 
-        for (scene_id, scene) in sorted_scenes {
+        for (scene_id, scene_name, scene) in sorted_scenes {
+            let Some(scene) = scene else {
+                SceneItem::tree_item_unloaded_scene(ui, scene_id, scene_name, gui_state);
+                continue;
+            };
             let header_id = ui.make_persistent_id(scene_id);
             egui::collapsing_header::CollapsingState::load_with_default_open(ctx, header_id, true)
                 .show_header(ui, |ui| {
-                    SceneItem::tree_item_scene(ui, scene_id, &scene.name, hierarchy, gui_state);
+                    SceneItem::tree_item_scene(ui, scene_id, scene_name, hierarchy, gui_state);
                 })
                 .body(|ui| {
-                    EntityItem::show_entities(ui, ctx, hierarchy, gui_state, scene_id, &scene.entities);
+                    EntityItem::show_entities(ui, hierarchy, gui_state, scene_id, scene);
                 });
         }
     }
 
+    fn tree_item_unloaded_scene(ui: &mut Ui, scene_id: &Uuid, scene_name: &str, gui_state: &mut GuiState) {
+        let response = ui.selectable_label(false, format!("{} (not loaded)", scene_name));
+        if response.clicked() && utils::load_scene(gui_state, *scene_id) {
+            gui_state.selected_item = SelectedItem::Scene(*scene_id);
+            gui_state.scene_panel_selected_item = ScenePanelSelectedItem::Scene(*scene_id);
+        }
+        response.context_menu(|ui| {
+            if ui.button("Load").clicked() {
+                utils::load_scene(gui_state, *scene_id);
+                ui.close_menu();
+            }
+            if ui.button("Set Active").clicked() {
+                Self::set_active_scene(gui_state, *scene_id);
+                ui.close_menu();
+            }
+        });
+    }
+
+    // Reads the scene first if it isn't loaded yet
+    fn set_active_scene(gui_state: &mut GuiState, scene_id: Uuid) {
+        if !utils::load_scene(gui_state, scene_id) {
+            return;
+        }
+        if let Some(scene_manager) = &mut gui_state.scene_manager {
+            if let Err(err) = scene_manager.set_active_scene(scene_id) {
+                eprintln!("Cannot set the active scene: {}", err);
+            }
+        }
+    }
+
     fn tree_item_scene(
         ui: &mut Ui,
         scene_id: &Uuid,
@@ -65,21 +104,64 @@ impl SceneItem {
                 gui_state.scene_panel_selected_item = ScenePanelSelectedItem::Scene(*scene_id);
             }
 
+            // Dropping an entity on its scene moves it to the top level
+            if response.dnd_hover_payload::<DraggedEntity>().is_some_and(|dragged| dragged.scene_id == *scene_id) {
+                ui.painter().rect_stroke(response.rect, 2.0, ui.visuals().selection.stroke);
+            }
+            if let Some(dragged) = response.dnd_release_payload::<DraggedEntity>() {
+                EntityItem::drop_entity(gui_state, *dragged, *scene_id, None);
+            }
+
             response.context_menu(|ui| {
                 if ui.button("Rename").clicked() {
                     hierarchy.popup_manager.start_rename_scene(*scene_id, scene_name.to_string());
                     ui.close_menu();
                 }
                 if ui.button("Delete").clicked() {
-                    gui_state.scene_manager.as_mut().unwrap().delete_scene(*scene_id);
+                    utils::apply_edit(gui_state, |scene_manager| {
+                        EditCommand::delete_scene(scene_manager, *scene_id)
+                    });
                     ui.close_menu();
                 }
+                Self::add_prefab_menu(ui, gui_state, *scene_id);
                 if ui.button("Set Active").clicked() {
-                    if let Some(scene_manager) = &mut gui_state.scene_manager {
-                        let _ = scene_manager.set_active_scene(*scene_id);
-                    }
+                    Self::set_active_scene(gui_state, *scene_id);
+                    ui.close_menu();
                 }
             });
         });
     }
+
+    // Instantiates a prefab at the scene origin
+    fn add_prefab_menu(ui: &mut Ui, gui_state: &mut GuiState, scene_id: Uuid) {
+        let prefabs: Vec<(Uuid, String)> = match &gui_state.scene_manager {
+            Some(scene_manager) => scene_manager.list_prefabs().into_iter()
+                .map(|(id, name)| (id, name.to_string()))
+                .collect(),
+            None => return,
+        };
+        if prefabs.is_empty() {
+            return;
+        }
+
+        ui.menu_button("Add Prefab", |ui| {
+            for (prefab_id, name) in prefabs {
+                if !ui.button(&name).clicked() {
+                    continue;
+                }
+                if let Some(scene_manager) = &mut gui_state.scene_manager {
+                    match scene_manager.instantiate_prefab(scene_id, prefab_id, 0.0, 0.0) {
+                        Ok(entity_id) => {
+                            if let Ok(command) = EditCommand::create_entity(scene_manager, scene_id, entity_id) {
+                                gui_state.edit_history.record(command);
+                            }
+                            utils::save_project(gui_state);
+                        }
+                        Err(err) => eprintln!("Cannot add prefab '{}': {}", name, err),
+                    }
+                }
+                ui.close_menu();
+            }
+        });
+    }
 }
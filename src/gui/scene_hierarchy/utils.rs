@@ -1,7 +1,9 @@
 use crate::gui::gui_state::GuiState;
 use crate::project_manager::ProjectManager;
 use std::path::{Path, PathBuf};
-use crate::ecs::Entity;
+use crate::ecs::{Entity, SceneManager};
+use crate::gui::edit_history::{EditCommand, PrefabSnapshot};
+use uuid::Uuid;
 
 pub fn save_project(gui_state: &GuiState) {
     if let (Some(scene_manager), Some(project_metadata)) = (
@@ -21,6 +23,77 @@ pub fn save_project(gui_state: &GuiState) {
     }
 }
 
+/// Reads a scene of the project that isn't loaded yet, returns whether it is loaded now
+pub fn load_scene(gui_state: &mut GuiState, scene_id: Uuid) -> bool {
+    let Some(scene_manager) = &mut gui_state.scene_manager else {
+        return false;
+    };
+    match ProjectManager::load_scene(&gui_state.project_path, scene_manager, scene_id) {
+        Ok(()) => true,
+        Err(err) => {
+            eprintln!("Error loading scene: {}", err);
+            false
+        }
+    }
+}
+
+/// Reads every scene that isn't loaded yet, for changes that touch all of them
+pub fn load_all_scenes(gui_state: &mut GuiState) -> bool {
+    let Some(scene_manager) = &mut gui_state.scene_manager else {
+        return false;
+    };
+    match ProjectManager::load_all_scenes(&gui_state.project_path, scene_manager) {
+        Ok(()) => true,
+        Err(err) => {
+            eprintln!("Error loading scenes: {}", err);
+            false
+        }
+    }
+}
+
+/// Builds an edit command from the current scenes, runs it through the edit history and saves
+pub fn apply_edit<F>(gui_state: &mut GuiState, build: F)
+where
+    F: FnOnce(&SceneManager) -> Result<EditCommand, String>,
+{
+    let result = match &mut gui_state.scene_manager {
+        Some(scene_manager) => build(scene_manager)
+            .and_then(|command| gui_state.edit_history.execute(command, scene_manager)),
+        None => Err("No project loaded".to_string()),
+    };
+    match result {
+        Ok(()) => save_project(gui_state),
+        Err(err) => eprintln!("Edit failed: {}", err),
+    }
+}
+
+/// Runs a prefab action on an entity, records it as one undoable step and saves.
+/// The action returns the id of the prefab it touched.
+pub fn apply_prefab_change<F>(gui_state: &mut GuiState, scene_id: Uuid, entity_id: Uuid, description: String, action: F)
+where
+    F: FnOnce(&mut SceneManager) -> Result<Uuid, String>,
+{
+    let Some(scene_manager) = &mut gui_state.scene_manager else {
+        return;
+    };
+    let linked_prefab = scene_manager.get_scene(scene_id)
+        .and_then(|scene| scene.get_entity(entity_id).ok())
+        .and_then(|entity| entity.prefab.as_ref())
+        .map(|link| link.prefab_id)
+        .unwrap_or_else(Uuid::nil);
+    let mut before = PrefabSnapshot::capture(scene_manager, linked_prefab, Some((scene_id, entity_id)));
+
+    match action(scene_manager) {
+        Ok(prefab_id) => {
+            before.prefab_id = prefab_id;
+            let after = PrefabSnapshot::capture(scene_manager, prefab_id, Some((scene_id, entity_id)));
+            gui_state.edit_history.record(EditCommand::PrefabChange { description, before: Box::new(before), after: Box::new(after) });
+            save_project(gui_state);
+        }
+        Err(err) => eprintln!("Prefab action failed: {}", err),
+    }
+}
+
 pub fn is_valid_identifier(name: &str) -> bool {
     !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
 }
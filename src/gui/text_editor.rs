@@ -0,0 +1,115 @@
+use eframe::egui;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::text::{Text, TextAlign, TextOutline, TextSpace};
+
+/// Inspector section for an entity's text. Works on a copy of the text; returns true
+/// when the copy was changed. Fonts are picked from the project's assets/fonts folder.
+pub fn show_text_editor(ui: &mut egui::Ui, text: &mut Option<Box<Text>>, project_path: &Path) -> bool {
+    let mut changed = false;
+
+    egui::CollapsingHeader::new("Text")
+        .default_open(text.is_some())
+        .show(ui, |ui| {
+            let Some(current) = text.as_mut() else {
+                if ui.button("Add Text").clicked() {
+                    *text = Some(Box::new(Text::new("Text")));
+                    changed = true;
+                }
+                return;
+            };
+
+            changed |= ui.text_edit_multiline(&mut current.text).changed();
+
+            ui.horizontal(|ui| {
+                ui.label("Font:");
+                let font_name = current.font.as_deref()
+                    .and_then(Path::file_name)
+                    .map_or("Default".to_string(), |name| name.to_string_lossy().to_string());
+                egui::ComboBox::from_id_salt("text_font")
+                    .selected_text(font_name)
+                    .show_ui(ui, |ui| {
+                        changed |= ui.selectable_value(&mut current.font, None, "Default").changed();
+                        for font in project_fonts(project_path) {
+                            let name = font.file_name().unwrap_or_default().to_string_lossy().to_string();
+                            changed |= ui.selectable_value(&mut current.font, Some(font), name).changed();
+                        }
+                    });
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Size:");
+                changed |= ui.add(egui::DragValue::new(&mut current.size).range(1.0..=512.0).speed(0.5)).changed();
+                ui.label("Color:");
+                changed |= color_edit(ui, &mut current.color);
+            });
+
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Align")
+                    .selected_text(current.align.as_str())
+                    .show_ui(ui, |ui| {
+                        for align in TextAlign::ALL {
+                            changed |= ui.selectable_value(&mut current.align, align, align.as_str()).changed();
+                        }
+                    });
+                egui::ComboBox::from_label("Space")
+                    .selected_text(current.space.as_str())
+                    .show_ui(ui, |ui| {
+                        for space in TextSpace::ALL {
+                            changed |= ui.selectable_value(&mut current.space, space, space.as_str()).changed();
+                        }
+                    })
+                    .response
+                    .on_hover_text("world: placed in the world\nscreen: pixels from the top left of the camera's view");
+            });
+
+            ui.horizontal(|ui| {
+                let mut outlined = current.outline.is_some();
+                if ui.checkbox(&mut outlined, "Outline").changed() {
+                    current.outline = outlined.then_some(TextOutline { width: 2.0, color: [0, 0, 0, 255] });
+                    changed = true;
+                }
+                if let Some(outline) = current.outline.as_mut() {
+                    changed |= ui.add(egui::DragValue::new(&mut outline.width).range(0.0..=32.0).speed(0.1)).changed();
+                    changed |= color_edit(ui, &mut outline.color);
+                }
+            });
+
+            ui.separator();
+            if ui.button("Remove Text").clicked() {
+                *text = None;
+                changed = true;
+            }
+        });
+
+    changed
+}
+
+fn color_edit(ui: &mut egui::Ui, color: &mut [u8; 4]) -> bool {
+    let [r, g, b, a] = *color;
+    let mut edited = egui::Color32::from_rgba_unmultiplied(r, g, b, a);
+    if ui.color_edit_button_srgba(&mut edited).changed() {
+        *color = edited.to_srgba_unmultiplied();
+        true
+    } else {
+        false
+    }
+}
+
+// Font files in the project's assets/fonts folder, sorted by name
+fn project_fonts(project_path: &Path) -> Vec<PathBuf> {
+    let mut fonts: Vec<PathBuf> = fs::read_dir(project_path.join("assets/fonts"))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("ttf") || ext.eq_ignore_ascii_case("otf"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    fonts.sort();
+    fonts
+}
@@ -0,0 +1,241 @@
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use crate::tilemap::Tilemap;
+
+/// Tool the editor viewport uses on the selected tilemap
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileTool {
+    Paint,
+    Erase,
+    Fill,
+    Rect,
+}
+
+impl TileTool {
+    pub const ALL: [TileTool; 4] = [TileTool::Paint, TileTool::Erase, TileTool::Fill, TileTool::Rect];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TileTool::Paint => "Paint",
+            TileTool::Erase => "Erase",
+            TileTool::Fill => "Fill",
+            TileTool::Rect => "Rectangle",
+        }
+    }
+}
+
+/// Tool, tile and layer chosen in the inspector, used when clicking on a tilemap in the viewport
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TileBrush {
+    pub tool: Option<TileTool>, // None leaves clicks in the viewport alone
+    pub tile: u32,
+    pub layer: usize,
+    pub rect_start: Option<(i32, i32)>, // Cell the rectangle tool was pressed on
+}
+
+impl TileBrush {
+    /// Uses the tool on a cell, returning whether a tile changed.
+    /// The rectangle tool fills from `rect_start` to the cell.
+    pub fn apply(&self, tilemap: &mut Tilemap, cell: (i32, i32)) -> bool {
+        let (x, y) = cell;
+        match self.tool {
+            Some(TileTool::Paint) => tilemap.set(self.layer, x, y, Some(self.tile)),
+            Some(TileTool::Erase) => tilemap.set(self.layer, x, y, None),
+            Some(TileTool::Fill) => tilemap.fill(self.layer, x, y, Some(self.tile)),
+            Some(TileTool::Rect) => tilemap.fill_rect(self.layer, self.rect_start.unwrap_or(cell), cell, Some(self.tile)),
+            None => false,
+        }
+    }
+}
+
+/// Inspector section for an entity's tilemap. Works on a copy of the tilemap; `show`
+/// returns true when the copy was changed. Painting itself happens in the viewport.
+pub struct TilemapEditor {
+    // Tileset image shown as the tile palette
+    palette: Option<(PathBuf, egui::TextureHandle)>,
+    new_layer_name: String,
+}
+
+impl Default for TilemapEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TilemapEditor {
+    pub fn new() -> Self {
+        Self {
+            palette: None,
+            new_layer_name: String::new(),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, tilemap: &mut Option<Box<Tilemap>>, images: &[PathBuf], brush: &mut TileBrush) -> bool {
+        let mut changed = false;
+
+        egui::CollapsingHeader::new("Tilemap")
+            .default_open(tilemap.is_some())
+            .show(ui, |ui| {
+                let Some(current) = tilemap.as_mut() else {
+                    if ui.button("Add Tilemap").clicked() {
+                        *tilemap = Tilemap::new(20, 15, (32.0, 32.0)).ok().map(Box::new);
+                        changed = true;
+                    }
+                    return;
+                };
+
+                changed |= Self::show_grid(ui, current, images.len());
+                ui.separator();
+                changed |= self.show_layers(ui, current, brush);
+                ui.separator();
+                self.show_brush(ui, current, images, brush);
+
+                ui.separator();
+                if ui.button("Remove Tilemap").clicked() {
+                    *tilemap = None;
+                    brush.tool = None;
+                    changed = true;
+                }
+            });
+
+        changed
+    }
+
+    fn show_grid(ui: &mut egui::Ui, tilemap: &mut Tilemap, image_count: usize) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            let (mut width, mut height) = (tilemap.width, tilemap.height);
+            ui.label("Size:");
+            let resized = ui.add(egui::DragValue::new(&mut width).range(1..=1024)).changed()
+                | ui.add(egui::DragValue::new(&mut height).range(1..=1024)).changed();
+            if resized {
+                match tilemap.resize(width, height) {
+                    Ok(()) => changed = true,
+                    Err(e) => eprintln!("Error resizing tilemap: {}", e),
+                }
+            }
+            ui.label("tiles");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Tile size:");
+            changed |= ui.add(egui::DragValue::new(&mut tilemap.tile_size.0).range(1.0..=1024.0)).changed();
+            changed |= ui.add(egui::DragValue::new(&mut tilemap.tile_size.1).range(1.0..=1024.0)).changed();
+        });
+        ui.horizontal(|ui| {
+            let tileset = &mut tilemap.tileset;
+            ui.label("Tileset image:");
+            changed |= ui.add(egui::DragValue::new(&mut tileset.image).range(0..=image_count.saturating_sub(1))).changed();
+            ui.label("Columns:");
+            changed |= ui.add(egui::DragValue::new(&mut tileset.columns).range(1..=256)).changed();
+            ui.label("Rows:");
+            changed |= ui.add(egui::DragValue::new(&mut tileset.rows).range(1..=256)).changed();
+        });
+        if image_count == 0 {
+            ui.label("Attach the tileset image to the entity to draw its tiles.");
+        }
+
+        changed
+    }
+
+    fn show_layers(&mut self, ui: &mut egui::Ui, tilemap: &mut Tilemap, brush: &mut TileBrush) -> bool {
+        let mut changed = false;
+        let mut removed = None;
+        let layer_count = tilemap.layers.len();
+
+        ui.label("Layers (painted layer selected):");
+        for (index, layer) in tilemap.layers.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut brush.layer, index, "");
+                changed |= ui.add(egui::TextEdit::singleline(&mut layer.name).desired_width(100.0)).changed();
+                changed |= ui.checkbox(&mut layer.visible, "Visible").changed();
+                changed |= ui.checkbox(&mut layer.collision, "Collision").on_hover_text("Its tiles are solid in the game").changed();
+                if layer_count > 1 && ui.small_button("❌").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+
+        if let Some(index) = removed {
+            tilemap.layers.remove(index);
+            brush.layer = brush.layer.min(tilemap.layers.len() - 1);
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_layer_name);
+            if ui.button("Add Layer").clicked() {
+                let name = match self.new_layer_name.trim() {
+                    "" => format!("layer {}", tilemap.layers.len() + 1),
+                    name => name.to_string(),
+                };
+                match tilemap.add_layer(&name) {
+                    Ok(()) => {
+                        brush.layer = tilemap.layers.len() - 1;
+                        self.new_layer_name.clear();
+                        changed = true;
+                    }
+                    Err(e) => eprintln!("Error adding layer: {}", e),
+                }
+            }
+        });
+
+        changed
+    }
+
+    fn show_brush(&mut self, ui: &mut egui::Ui, tilemap: &Tilemap, images: &[PathBuf], brush: &mut TileBrush) {
+        ui.horizontal(|ui| {
+            ui.label("Tool:");
+            ui.selectable_value(&mut brush.tool, None, "None")
+                .on_hover_text("Clicks in the viewport don't change the tilemap");
+            for tool in TileTool::ALL {
+                ui.selectable_value(&mut brush.tool, Some(tool), tool.as_str());
+            }
+        });
+
+        let tile_count = tilemap.tileset.tile_count();
+        brush.tile = brush.tile.min(tile_count - 1);
+        let texture = images.get(tilemap.tileset.image).and_then(|path| self.palette_texture(ui.ctx(), path));
+        let Some(texture) = texture else {
+            ui.horizontal(|ui| {
+                ui.label("Tile:");
+                ui.add(egui::DragValue::new(&mut brush.tile).range(0..=tile_count - 1));
+            });
+            return;
+        };
+
+        // Tiles of the tileset, in rows like in the image
+        ui.label("Tile:");
+        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(2.0, 2.0);
+            for row in 0..tilemap.tileset.rows.max(1) {
+                ui.horizontal(|ui| {
+                    for column in 0..tilemap.tileset.columns.max(1) {
+                        let tile = row * tilemap.tileset.columns.max(1) + column;
+                        let ((u0, v0), (u1, v1)) = tilemap.tileset.uv_rect(tile);
+                        let image = egui::Image::new((texture, egui::vec2(28.0, 28.0)))
+                            .uv(egui::Rect::from_min_max(egui::pos2(u0, v0), egui::pos2(u1, v1)));
+                        let button = egui::ImageButton::new(image).selected(brush.tile == tile);
+                        if ui.add(button).on_hover_text(format!("Tile {}", tile)).clicked() {
+                            brush.tile = tile;
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    // Texture of the tileset image, loaded again when the tileset changes
+    fn palette_texture(&mut self, ctx: &egui::Context, path: &Path) -> Option<egui::TextureId> {
+        if self.palette.as_ref().map(|(palette_path, _)| palette_path.as_path()) != Some(path) {
+            let image = image::open(path).ok()?.to_rgba8();
+            let color_image = egui::ColorImage::from_rgba_unmultiplied(
+                [image.width() as usize, image.height() as usize],
+                image.as_raw(),
+            );
+            let texture = ctx.load_texture("tileset_palette", color_image, egui::TextureOptions::NEAREST);
+            self.palette = Some((path.to_path_buf(), texture));
+        }
+        self.palette.as_ref().map(|(_, texture)| texture.id())
+    }
+}
@@ -0,0 +1,39 @@
+use eframe::egui;
+use uuid::Uuid;
+use crate::ecs::Transform;
+
+/// Inspector section for an entity's transform, relative to its parent. Works on a copy
+/// of the transform; returns true when the copy was changed. The rotation is shown in degrees.
+pub fn show_transform_editor(ui: &mut egui::Ui, entity_id: Uuid, transform: &mut Transform) -> bool {
+    let mut changed = false;
+
+    egui::CollapsingHeader::new("Transform")
+        .id_salt(("transform", entity_id))
+        .default_open(true)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Position:");
+                changed |= ui.add(egui::DragValue::new(&mut transform.position.0).speed(1.0).prefix("x: ")).changed();
+                changed |= ui.add(egui::DragValue::new(&mut transform.position.1).speed(1.0).prefix("y: ")).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Z:");
+                changed |= ui.add(egui::DragValue::new(&mut transform.z).speed(0.1)).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Rotation:");
+                let mut degrees = transform.rotation.to_degrees();
+                if ui.add(egui::DragValue::new(&mut degrees).speed(1.0).suffix("°")).changed() {
+                    transform.rotation = degrees.to_radians();
+                    changed = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Scale:");
+                changed |= ui.add(egui::DragValue::new(&mut transform.scale.0).speed(0.01).prefix("x: ")).changed();
+                changed |= ui.add(egui::DragValue::new(&mut transform.scale.1).speed(0.01).prefix("y: ")).changed();
+            });
+        });
+
+    changed
+}
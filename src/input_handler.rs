@@ -1,5 +1,6 @@
 use egui::{Key, PointerButton};
 use std::collections::HashSet;
+use std::ops::Range;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum InputContext {
@@ -46,19 +47,8 @@ impl InputHandler {
         // Store modifiers state
         self.modifiers = input.modifiers;
 
-        // Track which keys were just pressed this frame
-        let old_keys = self.keys_pressed.clone();
-        
         // Update key states
-        self.keys_pressed.clear();
-        self.keys_just_pressed.clear();
-        
-        input.keys_down.iter().for_each(|key| {
-            self.keys_pressed.insert(*key);
-            if !old_keys.contains(key) {
-                self.keys_just_pressed.insert(*key);
-            }
-        });
+        self.set_keys_down(input.keys_down.iter().copied());
 
         // Update mouse position
         self.prev_mouse_pos = self.mouse_pos;
@@ -80,6 +70,28 @@ impl InputHandler {
         self.scroll_delta = input.raw_scroll_delta;
     }
 
+    // Replaces the keys held down, keys that weren't held before are just pressed this frame
+    pub fn set_keys_down(&mut self, keys: impl IntoIterator<Item = Key>) {
+        let old_keys = std::mem::take(&mut self.keys_pressed);
+        self.keys_just_pressed.clear();
+
+        for key in keys {
+            self.keys_pressed.insert(key);
+            if !old_keys.contains(&key) {
+                self.keys_just_pressed.insert(key);
+            }
+        }
+    }
+
+    pub fn set_mouse_pos(&mut self, pos: egui::Pos2) {
+        self.prev_mouse_pos = self.mouse_pos;
+        self.mouse_pos = pos;
+    }
+
+    pub fn set_mouse_buttons(&mut self, buttons: &[PointerButton]) {
+        self.mouse_buttons = buttons.to_vec();
+    }
+
     pub fn is_key_pressed(&self, key: Key) -> bool {
         self.keys_pressed.contains(&key)
     }
@@ -141,3 +153,33 @@ impl InputHandler {
         all_inputs
     }
 }
+
+/// Keys held down on given frames, to drive a game without a window (see `GameRuntime::run_frames`)
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedInput {
+    holds: Vec<(Key, Range<u32>)>,
+}
+
+impl ScriptedInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Holds the key down from the first frame of the range until before its end
+    pub fn hold(mut self, key: Key, frames: Range<u32>) -> Self {
+        self.holds.push((key, frames));
+        self
+    }
+
+    // Presses the key for a single frame
+    pub fn tap(self, key: Key, frame: u32) -> Self {
+        self.hold(key, frame..frame + 1)
+    }
+
+    pub fn keys_at(&self, frame: u32) -> Vec<Key> {
+        self.holds.iter()
+            .filter(|(_, frames)| frames.contains(&frame))
+            .map(|(key, _)| *key)
+            .collect()
+    }
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Joint (component of an Entity, which can have several)
+// └── Connects the entity to `other`, an entity of the same scene, at an anchor point on each of them
+// └── Both entities need a physics body; the joint is created with them and removed with either one
+// Anchors and the prismatic axis are in the local space of their entity, unrotated and relative to its position.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum JointKind {
+    #[default]
+    Fixed,     // The anchors stay together and the entities keep their relative rotation
+    Revolute,  // The anchors stay together, the entities turn freely around them
+    Prismatic, // The other entity slides along `axis` and can't turn
+    Rope,      // The anchors are at most `length` apart
+    Distance,  // The anchors are exactly `length` apart
+    Spring,    // The anchors are pulled towards `length` apart
+}
+
+impl JointKind {
+    pub const ALL: [JointKind; 6] = [
+        JointKind::Fixed,
+        JointKind::Revolute,
+        JointKind::Prismatic,
+        JointKind::Rope,
+        JointKind::Distance,
+        JointKind::Spring,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JointKind::Fixed => "fixed",
+            JointKind::Revolute => "revolute",
+            JointKind::Prismatic => "prismatic",
+            JointKind::Rope => "rope",
+            JointKind::Distance => "distance",
+            JointKind::Spring => "spring",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        JointKind::ALL.into_iter().find(|kind| kind.as_str() == name.trim().to_lowercase())
+    }
+
+    /// Revolute limits are angles in radians, prismatic ones distances along the axis
+    pub fn has_limits(&self) -> bool {
+        matches!(self, JointKind::Revolute | JointKind::Prismatic)
+    }
+
+    pub fn has_motor(&self) -> bool {
+        matches!(self, JointKind::Revolute | JointKind::Prismatic)
+    }
+
+    pub fn has_length(&self) -> bool {
+        matches!(self, JointKind::Rope | JointKind::Distance | JointKind::Spring)
+    }
+}
+
+/// Drives a revolute joint at `speed` radians per second, or a prismatic one at `speed` along its axis,
+/// pushing with at most `max_force`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct JointMotor {
+    pub speed: f32,
+    pub max_force: f32,
+}
+
+impl Default for JointMotor {
+    fn default() -> Self {
+        Self { speed: 1.0, max_force: 1000.0 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Joint {
+    pub id: Uuid,
+    pub kind: JointKind,
+    pub other: Uuid,
+    #[serde(default)]
+    pub anchor: (f32, f32),
+    #[serde(default)]
+    pub other_anchor: (f32, f32),
+    #[serde(default = "default_axis")]
+    pub axis: (f32, f32),         // Prismatic
+    #[serde(default)]
+    pub limits: Option<(f32, f32)>, // Revolute and prismatic, min and max
+    #[serde(default)]
+    pub motor: Option<JointMotor>,  // Revolute and prismatic
+    #[serde(default)]
+    pub length: f32,              // Rope, distance and spring
+    #[serde(default)]
+    pub stiffness: f32,           // Spring
+    #[serde(default)]
+    pub damping: f32,             // Spring
+    #[serde(default)]
+    pub collide_connected: bool,  // Whether the two entities still collide with each other
+}
+
+fn default_axis() -> (f32, f32) {
+    (1.0, 0.0)
+}
+
+impl Joint {
+    pub fn new(kind: JointKind, other: Uuid) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            other,
+            anchor: (0.0, 0.0),
+            other_anchor: (0.0, 0.0),
+            axis: default_axis(),
+            limits: None,
+            motor: None,
+            length: 100.0,
+            stiffness: 50.0,
+            damping: 1.0,
+            collide_connected: false,
+        }
+    }
+}
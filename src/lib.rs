@@ -1,10 +1,19 @@
 pub mod input_handler;
 pub mod engine_gui;
 pub mod render_engine;
+pub mod camera;
 pub mod audio_engine;
 pub mod physics_engine;
 pub mod project_manager;
 pub mod ecs;
+pub mod animation;
+pub mod text;
+pub mod tilemap;
+pub mod joint;
+pub mod character_controller;
+pub mod gravity_source;
+pub mod physics_settings;
+pub mod scene_format;
 pub mod game_runtime;
 pub mod gui;
 pub mod script_interpreter;
@@ -1,18 +1,27 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
-use mlua::{Lua, Value as LuaValue, Result as LuaResult, Function as LuaFunction, Table as LuaTable};
-use crate::ecs::SceneManager;
+use std::rc::Rc;
+use indexmap::IndexMap;
+use mlua::{Lua, Value as LuaValue, Result as LuaResult, Table as LuaTable, IntoLuaMulti};
+use mlua::{AnyUserData, MetaMethod, UserDataFields, UserDataMethods};
+use crate::ecs::{Entity, Scene, SceneManager};
 use crate::ecs::AttributeType;
 use crate::ecs::AttributeValue;
 use serde_json::Value as JsonValue;
 use uuid::Uuid;
 use std::fs;
 use mlua::{LuaSerdeExt, UserData};
-use crate::physics_engine::PhysicsEngine;
+use crate::physics_engine::{CollisionEvent, PhysicsEngine, QueryFilter, QueryHit, QueryShape};
+use crate::animation::{AnimationFinished, SpriteAnimation};
+use crate::camera;
+use crate::render_engine::parse_color;
+use crate::text::Text;
+use crate::joint::{Joint, JointKind, JointMotor};
+use crate::physics_settings::PhysicsSettings;
 use rapier2d::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use egui::Key;
 use crate::gui::scene_hierarchy::predefined_entities::PREDEFINED_ENTITIES;
-use crate::project_manager::ProjectManager;
 use crate::input_handler::InputHandler;
 
 use serde::{Serialize, Deserialize};
@@ -22,10 +31,593 @@ pub struct ScriptState {
     pub state: HashMap<String, JsonValue>,
 }
 
+// Registry key under which the SceneManager is reachable while scripts are running.
+// It is only set inside `LuaScripting::with_scene_access`, so entity handles stored by a
+// script between callbacks can't touch the scene after it has been dropped or replaced.
+const SCENE_MANAGER_KEY: &str = "scene_manager";
+
+/// Runs `f` with the SceneManager currently exposed to scripts.
+fn with_scene_manager<R>(lua: &Lua, f: impl FnOnce(&mut SceneManager) -> LuaResult<R>) -> LuaResult<R> {
+    let scene_manager: Option<AnyUserData> = lua.named_registry_value(SCENE_MANAGER_KEY)?;
+    let scene_manager = scene_manager
+        .ok_or_else(|| mlua::Error::external("The scene can only be accessed while scripts are running"))?;
+    scene_manager.borrow_mut_scoped::<SceneManager, _>(f)?
+}
+
+// Registry keys of the physics engine and the input handler, lent to Lua by
+// `LuaScripting::with_engines` the same way as the SceneManager.
+const PHYSICS_ENGINE_KEY: &str = "physics_engine";
+const INPUT_HANDLER_KEY: &str = "input_handler";
+
+// Registry key of the project folder, set by `LuaScripting::set_project_path`
+const PROJECT_PATH_KEY: &str = "project_path";
+
+/// Runs `f` with the PhysicsEngine currently exposed to scripts.
+fn with_physics_engine<R>(lua: &Lua, f: impl FnOnce(&mut PhysicsEngine) -> LuaResult<R>) -> LuaResult<R> {
+    let physics_engine: Option<AnyUserData> = lua.named_registry_value(PHYSICS_ENGINE_KEY)?;
+    let physics_engine = physics_engine
+        .ok_or_else(|| mlua::Error::external("The physics engine can only be accessed while scripts are running"))?;
+    physics_engine.borrow_mut_scoped::<PhysicsEngine, _>(f)?
+}
+
+/// Runs `f` with the InputHandler currently exposed to scripts.
+fn with_input_handler<R>(lua: &Lua, f: impl FnOnce(&InputHandler) -> LuaResult<R>) -> LuaResult<R> {
+    let input_handler: Option<AnyUserData> = lua.named_registry_value(INPUT_HANDLER_KEY)?;
+    let input_handler = input_handler
+        .ok_or_else(|| mlua::Error::external("The input can only be read while scripts are running"))?;
+    input_handler.borrow_scoped::<InputHandler, _>(f)?
+}
+
+/// Resolves a path given by a script relative to the project folder.
+fn project_relative_path(lua: &Lua, path: &str) -> LuaResult<PathBuf> {
+    let path = PathBuf::from(path);
+    let project_path: Option<String> = lua.named_registry_value(PROJECT_PATH_KEY)?;
+    Ok(match project_path {
+        Some(project_path) if path.is_relative() => PathBuf::from(project_path).join(path),
+        _ => path,
+    })
+}
+
+/// Accepts either an entity handle or an entity UUID string.
+fn entity_uuid_from_lua(value: &LuaValue) -> LuaResult<Uuid> {
+    match value {
+        LuaValue::UserData(ud) => Ok(ud.borrow::<EntityRef>()?.entity_id),
+        LuaValue::String(s) => {
+            let entity_id = s.to_str()?;
+            Uuid::parse_str(&entity_id)
+                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", &*entity_id, e)))
+        }
+        other => Err(mlua::Error::external(format!("Expected an entity, got {}", other.type_name()))),
+    }
+}
+
+fn attribute_value_to_lua(lua: &Lua, value: &AttributeValue) -> LuaResult<LuaValue> {
+    match value {
+        AttributeValue::Integer(i) => Ok(LuaValue::Integer(*i as i64)),
+        AttributeValue::Float(f) => Ok(LuaValue::Number(*f as f64)),
+        AttributeValue::String(s) => Ok(LuaValue::String(lua.create_string(s)?)),
+        AttributeValue::Boolean(b) => Ok(LuaValue::Boolean(*b)),
+        AttributeValue::Vector2(x, y) => Ok(LuaValue::Table(vector2_to_lua(lua, (*x, *y))?)),
+    }
+}
+
+fn vector2_from_lua(value: &LuaValue) -> Option<(f32, f32)> {
+    let LuaValue::Table(table) = value else {
+        return None;
+    };
+    let x = table.get::<Option<f32>>("x").ok().flatten().or_else(|| table.get::<Option<f32>>(1).ok().flatten())?;
+    let y = table.get::<Option<f32>>("y").ok().flatten().or_else(|| table.get::<Option<f32>>(2).ok().flatten())?;
+    Some((x, y))
+}
+
+fn vector2_to_lua(lua: &Lua, (x, y): (f32, f32)) -> LuaResult<LuaTable> {
+    let table = lua.create_table()?;
+    table.set("x", x)?;
+    table.set("y", y)?;
+    Ok(table)
+}
+
+/// Reads the optional filter of a physics query:
+/// `{ layers = "player, enemy" or a mask, exclude = entity or { entities }, sensors = true }`.
+fn query_filter_from_lua(physics_engine: &PhysicsEngine, filter: Option<LuaTable>) -> LuaResult<QueryFilter> {
+    let mut query_filter = QueryFilter::default();
+    let Some(filter) = filter else {
+        return Ok(query_filter);
+    };
+    match filter.get::<LuaValue>("layers")? {
+        LuaValue::Nil => {}
+        LuaValue::String(names) => {
+            query_filter.layers = physics_engine.layer_mask(&names.to_str()?).map_err(mlua::Error::external)?;
+        }
+        LuaValue::Integer(mask) => {
+            query_filter.layers = u32::try_from(mask)
+                .map_err(|_| mlua::Error::external(format!("Invalid layer mask {}", mask)))?;
+        }
+        other => return Err(mlua::Error::external(format!("Expected layer names or a mask, got {}", other.type_name()))),
+    }
+    match filter.get::<LuaValue>("exclude")? {
+        LuaValue::Nil => {}
+        LuaValue::Table(entities) => {
+            for entity in entities.sequence_values::<LuaValue>() {
+                query_filter.exclude.push(entity_uuid_from_lua(&entity?)?);
+            }
+        }
+        entity => query_filter.exclude.push(entity_uuid_from_lua(&entity)?),
+    }
+    query_filter.include_sensors = filter.get::<Option<bool>>("sensors")?.unwrap_or(false);
+    Ok(query_filter)
+}
+
+/// Reads the shape of `shape_cast`: `{ radius = r }` or `{ width = w, height = h }`
+fn query_shape_from_lua(shape: &LuaTable) -> LuaResult<QueryShape> {
+    if let Some(radius) = shape.get::<Option<f32>>("radius")? {
+        return Ok(QueryShape::Circle { radius });
+    }
+    match (shape.get::<Option<f32>>("width")?, shape.get::<Option<f32>>("height")?) {
+        (Some(width), Some(height)) => Ok(QueryShape::Box { width, height }),
+        _ => Err(mlua::Error::external("A shape needs a radius, or a width and a height")),
+    }
+}
+
+fn query_hit_to_lua(lua: &Lua, scene_id: Uuid, hit: &QueryHit) -> LuaResult<LuaTable> {
+    let table = lua.create_table()?;
+    table.set("entity", EntityRef::new(scene_id, hit.entity))?;
+    table.set("point", vector2_to_lua(lua, hit.point)?)?;
+    table.set("normal", vector2_to_lua(lua, hit.normal)?)?;
+    table.set("distance", hit.distance)?;
+    Ok(table)
+}
+
+/// Reads a joint of `kind` to `other` with its options:
+/// `{ anchor = {x, y}, other_anchor = {x, y}, axis = {x, y}, limits = {min, max}, motor = { speed = s, max_force = f },
+///    length = l, stiffness = k, damping = d, collide_connected = true }`, all optional.
+fn joint_from_lua(kind: &str, other: Uuid, options: Option<LuaTable>) -> LuaResult<Joint> {
+    let kind = JointKind::from_name(kind).ok_or_else(|| mlua::Error::external(format!("Unknown joint kind '{}'", kind)))?;
+    let mut joint = Joint::new(kind, other);
+    let Some(options) = options else {
+        return Ok(joint);
+    };
+
+    let pair = |name: &str| -> LuaResult<Option<(f32, f32)>> {
+        match options.get::<LuaValue>(name)? {
+            LuaValue::Nil => Ok(None),
+            value => vector2_from_lua(&value)
+                .map(Some)
+                .ok_or_else(|| mlua::Error::external(format!("Joint option '{}' must be a pair of numbers", name))),
+        }
+    };
+    joint.anchor = pair("anchor")?.unwrap_or(joint.anchor);
+    joint.other_anchor = pair("other_anchor")?.unwrap_or(joint.other_anchor);
+    joint.axis = pair("axis")?.unwrap_or(joint.axis);
+    joint.limits = pair("limits")?;
+    if let Some(motor) = options.get::<Option<LuaTable>>("motor")? {
+        let default = JointMotor::default();
+        joint.motor = Some(JointMotor {
+            speed: motor.get::<Option<f32>>("speed")?.unwrap_or(default.speed),
+            max_force: motor.get::<Option<f32>>("max_force")?.unwrap_or(default.max_force),
+        });
+    }
+    joint.length = options.get::<Option<f32>>("length")?.unwrap_or(joint.length);
+    joint.stiffness = options.get::<Option<f32>>("stiffness")?.unwrap_or(joint.stiffness);
+    joint.damping = options.get::<Option<f32>>("damping")?.unwrap_or(joint.damping);
+    joint.collide_connected = options.get::<Option<bool>>("collide_connected")?.unwrap_or(false);
+    Ok(joint)
+}
+
+fn physics_settings_to_lua(lua: &Lua, settings: &PhysicsSettings) -> LuaResult<LuaTable> {
+    let table = lua.create_table()?;
+    table.set("gravity", vector2_to_lua(lua, settings.gravity)?)?;
+    table.set("time_step", settings.time_step)?;
+    table.set("solver_iterations", settings.solver_iterations)?;
+    table.set("ccd", settings.ccd)?;
+    table.set("sleep_linear_threshold", settings.sleep_linear_threshold)?;
+    table.set("sleep_angular_threshold", settings.sleep_angular_threshold)?;
+    table.set("time_until_sleep", settings.time_until_sleep)?;
+    table.set("custom_gravity_strength", settings.custom_gravity_strength)?;
+    Ok(table)
+}
+
+/// `settings` with the fields given in `changes` replaced, the others are kept
+fn physics_settings_from_lua(settings: &PhysicsSettings, changes: &LuaTable) -> LuaResult<PhysicsSettings> {
+    let mut settings = settings.clone();
+    match changes.get::<LuaValue>("gravity")? {
+        LuaValue::Nil => {}
+        value => {
+            settings.gravity = vector2_from_lua(&value)
+                .ok_or_else(|| mlua::Error::external("Physics setting 'gravity' must be a pair of numbers"))?;
+        }
+    }
+    settings.time_step = changes.get::<Option<f32>>("time_step")?.unwrap_or(settings.time_step);
+    settings.solver_iterations = changes.get::<Option<usize>>("solver_iterations")?.unwrap_or(settings.solver_iterations);
+    settings.ccd = changes.get::<Option<bool>>("ccd")?.unwrap_or(settings.ccd);
+    settings.sleep_linear_threshold = changes.get::<Option<f32>>("sleep_linear_threshold")?.unwrap_or(settings.sleep_linear_threshold);
+    settings.sleep_angular_threshold = changes.get::<Option<f32>>("sleep_angular_threshold")?.unwrap_or(settings.sleep_angular_threshold);
+    settings.time_until_sleep = changes.get::<Option<f32>>("time_until_sleep")?.unwrap_or(settings.time_until_sleep);
+    settings.custom_gravity_strength = changes.get::<Option<f32>>("custom_gravity_strength")?.unwrap_or(settings.custom_gravity_strength);
+    Ok(settings)
+}
+
+fn joint_id_from_lua(joint_id: &str) -> LuaResult<Uuid> {
+    Uuid::parse_str(joint_id).map_err(|_| mlua::Error::external(format!("Invalid joint ID '{}'", joint_id)))
+}
+
+/// Id of the active scene, whose entities physics queries return
+fn active_scene_id(lua: &Lua) -> LuaResult<Uuid> {
+    with_scene_manager(lua, |scene_manager| {
+        scene_manager.active_scene.ok_or_else(|| mlua::Error::external("No active scene found"))
+    })
+}
+
+/// Converts a Lua value into an attribute value of the given type,
+/// failing instead of silently coercing to another type.
+fn attribute_value_from_lua(name: &str, data_type: &AttributeType, value: &LuaValue) -> LuaResult<AttributeValue> {
+    let converted = match (data_type, value) {
+        (AttributeType::Integer, LuaValue::Integer(i)) => i32::try_from(*i).ok().map(AttributeValue::Integer),
+        (AttributeType::Integer, LuaValue::Number(n)) if n.fract() == 0.0 && *n >= i32::MIN as f64 && *n <= i32::MAX as f64 => {
+            Some(AttributeValue::Integer(*n as i32))
+        }
+        (AttributeType::Float, LuaValue::Integer(i)) => Some(AttributeValue::Float(*i as f32)),
+        (AttributeType::Float, LuaValue::Number(n)) => Some(AttributeValue::Float(*n as f32)),
+        (AttributeType::String, LuaValue::String(s)) => Some(AttributeValue::String(s.to_str()?.to_string())),
+        (AttributeType::Boolean, LuaValue::Boolean(b)) => Some(AttributeValue::Boolean(*b)),
+        (AttributeType::Vector2, value) => vector2_from_lua(value).map(|(x, y)| AttributeValue::Vector2(x, y)),
+        _ => None,
+    };
+
+    converted.ok_or_else(|| {
+        mlua::Error::external(format!(
+            "Cannot assign {} to attribute '{}' of type {:?}",
+            value.type_name(),
+            name,
+            data_type
+        ))
+    })
+}
+
+/// Picks an attribute type for a value assigned to an attribute that doesn't exist yet.
+fn attribute_type_of_lua(value: &LuaValue) -> Option<AttributeType> {
+    match value {
+        LuaValue::Integer(_) => Some(AttributeType::Integer),
+        LuaValue::Number(_) => Some(AttributeType::Float),
+        LuaValue::String(_) => Some(AttributeType::String),
+        LuaValue::Boolean(_) => Some(AttributeType::Boolean),
+        LuaValue::Table(_) if vector2_from_lua(value).is_some() => Some(AttributeType::Vector2),
+        _ => None,
+    }
+}
+
+/// Handle to an entity given to Lua. It only stores ids; the entity itself is looked up
+/// in the scene on every access, so a handle to a removed entity fails with an error.
+///
+/// In Lua:
+/// - `entity.id`, `entity.scene_id` are read-only, `entity.name` can be assigned.
+/// - Any attribute can be read or assigned as a field, e.g. `self.x = 10`.
+/// - The transform is `x`, `y`, `z`, `rotation` (radians) and `scale` (`{x, y}`);
+///   `rotation` and `scale` read as 0 and `{x = 1, y = 1}` until they are assigned.
+/// - `entity:get(name)`, `entity:set(name, value)`, `entity:has(name)`.
+/// - `entity:add_image(path)`, `entity:set_script(path)`.
+/// - `entity:play_animation(clip)`, `entity:pause_animation()`, `entity:resume_animation()`,
+///   `entity:stop_animation()`, `entity:current_animation()` for entities with an animation.
+/// - `entity:set_text(text)` sets the text the entity shows, adding a text if it has none,
+///   `entity:get_text()` returns it or nil, `entity:set_text_color("#RRGGBB")`.
+/// - `camera:follow(entity)` makes a camera follow an entity, `camera:follow(nil)` stops it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntityRef {
+    pub scene_id: Uuid,
+    pub entity_id: Uuid,
+}
+
+impl EntityRef {
+    pub fn new(scene_id: Uuid, entity_id: Uuid) -> Self {
+        Self { scene_id, entity_id }
+    }
+
+    fn with_entity<R>(&self, lua: &Lua, f: impl FnOnce(&mut Entity) -> LuaResult<R>) -> LuaResult<R> {
+        with_scene_manager(lua, |scene_manager| {
+            let scene = scene_manager
+                .get_scene_mut(self.scene_id)
+                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", self.scene_id)))?;
+            let entity = scene
+                .get_entity_mut(self.entity_id)
+                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", self.entity_id, e)))?;
+            f(entity)
+        })
+    }
+
+    fn with_animation<R>(&self, lua: &Lua, f: impl FnOnce(&mut SpriteAnimation) -> LuaResult<R>) -> LuaResult<R> {
+        self.with_entity(lua, |entity| match entity.animation.as_mut() {
+            Some(animation) => f(animation),
+            None => Err(mlua::Error::external(format!("Entity '{}' has no animation", entity.name))),
+        })
+    }
+
+    fn get_attribute(&self, lua: &Lua, name: &str) -> LuaResult<LuaValue> {
+        self.with_entity(lua, |entity| match entity.get_attribute_by_name(name) {
+            Ok(attribute) => attribute_value_to_lua(lua, &attribute.value),
+            Err(_) => Ok(LuaValue::Nil),
+        })
+    }
+
+    // Existing attributes keep their type; a missing one is created only if `create` is set.
+    fn set_attribute(&self, lua: &Lua, name: &str, value: &LuaValue, create: bool) -> LuaResult<()> {
+        self.with_entity(lua, |entity| {
+            let existing = entity.get_attribute_by_name(name).map(|attribute| (attribute.id, attribute.data_type.clone()));
+            match existing {
+                Ok((attribute_id, data_type)) => {
+                    let new_value = attribute_value_from_lua(name, &data_type, value)?;
+                    entity
+                        .modify_attribute(attribute_id, None, None, Some(new_value))
+                        .map_err(mlua::Error::external)
+                }
+                Err(_) if create => {
+                    let data_type = attribute_type_of_lua(value).ok_or_else(|| {
+                        mlua::Error::external(format!("Cannot create attribute '{}' from {}", name, value.type_name()))
+                    })?;
+                    let new_value = attribute_value_from_lua(name, &data_type, value)?;
+                    entity.create_attribute(name, data_type, new_value).map_err(mlua::Error::external)?;
+                    Ok(())
+                }
+                Err(e) => Err(mlua::Error::external(format!("Entity '{}': {}", entity.name, e))),
+            }
+        })
+    }
+}
+
+impl UserData for EntityRef {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("get", |lua, this, name: String| this.get_attribute(lua, &name));
+
+        methods.add_method("set", |lua, this, (name, value): (String, LuaValue)| {
+            this.set_attribute(lua, &name, &value, true)
+        });
+
+        methods.add_method("has", |lua, this, name: String| {
+            this.with_entity(lua, |entity| Ok(entity.get_attribute_by_name(&name).is_ok()))
+        });
+
+        methods.add_method("add_image", |lua, this, path: String| {
+            this.with_entity(lua, |entity| {
+                entity.add_image(project_relative_path(lua, &path)?).map_err(mlua::Error::external)
+            })
+        });
+
+        methods.add_method("set_script", |lua, this, path: String| {
+            this.with_entity(lua, |entity| {
+                entity.set_script(project_relative_path(lua, &path)?).map_err(mlua::Error::external)
+            })
+        });
+
+        methods.add_method("play_animation", |lua, this, clip: String| {
+            this.with_animation(lua, |animation| animation.play(&clip).map_err(mlua::Error::external))
+        });
+
+        methods.add_method("pause_animation", |lua, this, ()| {
+            this.with_animation(lua, |animation| {
+                animation.pause();
+                Ok(())
+            })
+        });
+
+        methods.add_method("resume_animation", |lua, this, ()| {
+            this.with_animation(lua, |animation| {
+                animation.resume();
+                Ok(())
+            })
+        });
+
+        methods.add_method("stop_animation", |lua, this, ()| {
+            this.with_animation(lua, |animation| {
+                animation.stop();
+                Ok(())
+            })
+        });
+
+        methods.add_method("current_animation", |lua, this, ()| {
+            this.with_animation(lua, |animation| Ok(animation.current_clip().map(str::to_string)))
+        });
+
+        methods.add_method("set_text", |lua, this, value: LuaValue| {
+            let value = lua.coerce_string(value)?
+                .ok_or_else(|| mlua::Error::external("Text must be a string or a number"))?
+                .to_str()?
+                .to_string();
+            this.with_entity(lua, |entity| {
+                entity.text.get_or_insert_with(|| Box::new(Text::default())).text = value;
+                Ok(())
+            })
+        });
+
+        methods.add_method("get_text", |lua, this, ()| {
+            this.with_entity(lua, |entity| Ok(entity.text.as_ref().map(|text| text.text.clone())))
+        });
+
+        methods.add_method("set_text_color", |lua, this, color: String| {
+            let color = parse_color(&color).map_err(mlua::Error::external)?;
+            this.with_entity(lua, |entity| match entity.text.as_mut() {
+                Some(text) => {
+                    text.color = color;
+                    Ok(())
+                }
+                None => Err(mlua::Error::external(format!("Entity '{}' has no text", entity.name))),
+            })
+        });
+
+        methods.add_method("follow", |lua, this, target: LuaValue| {
+            let target = match target {
+                LuaValue::Nil => None,
+                target => Some(entity_uuid_from_lua(&target)?),
+            };
+            this.with_entity(lua, |entity| camera::set_follow_target(entity, target).map_err(mlua::Error::external))
+        });
+
+        methods.add_meta_method(MetaMethod::Index, |lua, this, key: String| match key.as_str() {
+            "id" => Ok(LuaValue::String(lua.create_string(this.entity_id.to_string())?)),
+            "scene_id" => Ok(LuaValue::String(lua.create_string(this.scene_id.to_string())?)),
+            "name" => this.with_entity(lua, |entity| Ok(LuaValue::String(lua.create_string(&entity.name)?))),
+            "rotation" => this.with_entity(lua, |entity| Ok(LuaValue::Number(entity.local_transform().rotation as f64))),
+            "scale" => this.with_entity(lua, |entity| Ok(LuaValue::Table(vector2_to_lua(lua, entity.local_transform().scale)?))),
+            _ => this.get_attribute(lua, &key),
+        });
+
+        methods.add_meta_method(MetaMethod::NewIndex, |lua, this, (key, value): (String, LuaValue)| match key.as_str() {
+            "id" | "scene_id" => Err(mlua::Error::external(format!("'{}' is read-only", key))),
+            "name" => {
+                let name = lua.coerce_string(value)?
+                    .ok_or_else(|| mlua::Error::external("Entity name must be a string"))?
+                    .to_str()?
+                    .to_string();
+                this.with_entity(lua, |entity| entity.change_entity_name(&name).map_err(mlua::Error::external))
+            }
+            // Entities without a rotation or scale attribute get one
+            "rotation" | "scale" => this.with_entity(lua, |entity| {
+                let mut transform = entity.local_transform();
+                if key == "rotation" {
+                    transform.rotation = lua.unpack::<f32>(value)?;
+                } else {
+                    transform.scale = vector2_from_lua(&value)
+                        .ok_or_else(|| mlua::Error::external("Scale must be a table {x, y}"))?;
+                }
+                entity.set_local_transform(&transform).map_err(mlua::Error::external)
+            }),
+            _ => this.set_attribute(lua, &key, &value, false),
+        });
+
+        methods.add_meta_method(MetaMethod::Eq, |_, this, other: AnyUserData| {
+            Ok(other.borrow::<EntityRef>().map(|other| *this == *other).unwrap_or(false))
+        });
+
+        methods.add_meta_method(MetaMethod::ToString, |_, this, ()| Ok(format!("Entity({})", this.entity_id)));
+    }
+}
+
+/// Handle to a scene given to Lua, available to scripts as `scene`.
+///
+/// In Lua:
+/// - `scene.id`, `scene.name`.
+/// - `scene:find(name)` returns the first entity with that name, or nil.
+/// - `scene:get(id)` returns the entity with that UUID, or nil.
+/// - `scene:entities()` returns a list of all entities.
+/// - `scene:add_entity(name)`, `scene:create_physical_entity(name, x, y, z)`, `scene:remove_entity(entity)`.
+/// - `scene:camera()` returns the camera the game is rendered through, `scene:set_camera(entity)` switches to another one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SceneRef {
+    pub scene_id: Uuid,
+}
+
+impl SceneRef {
+    pub fn new(scene_id: Uuid) -> Self {
+        Self { scene_id }
+    }
+
+    fn with_scene<R>(&self, lua: &Lua, f: impl FnOnce(&mut Scene) -> LuaResult<R>) -> LuaResult<R> {
+        with_scene_manager(lua, |scene_manager| {
+            let scene = scene_manager
+                .get_scene_mut(self.scene_id)
+                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", self.scene_id)))?;
+            f(scene)
+        })
+    }
+}
+
+impl UserData for SceneRef {
+    fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("id", |_, this| Ok(this.scene_id.to_string()));
+        fields.add_field_method_get("name", |lua, this| this.with_scene(lua, |scene| Ok(scene.name.clone())));
+    }
+
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("find", |lua, this, name: String| {
+            this.with_scene(lua, |scene| {
+                Ok(scene
+                    .entities
+                    .values()
+                    .find(|entity| entity.name == name)
+                    .map(|entity| EntityRef::new(this.scene_id, entity.id)))
+            })
+        });
+
+        methods.add_method("get", |lua, this, entity: LuaValue| {
+            let entity_id = entity_uuid_from_lua(&entity)?;
+            this.with_scene(lua, |scene| {
+                Ok(scene.entities.contains_key(&entity_id).then(|| EntityRef::new(this.scene_id, entity_id)))
+            })
+        });
+
+        methods.add_method("entities", |lua, this, ()| {
+            this.with_scene(lua, |scene| {
+                Ok(scene
+                    .entities
+                    .keys()
+                    .map(|entity_id| EntityRef::new(this.scene_id, *entity_id))
+                    .collect::<Vec<_>>())
+            })
+        });
+
+        methods.add_method("add_entity", |lua, this, name: String| {
+            this.with_scene(lua, |scene| {
+                let entity_id = scene
+                    .create_entity(&name)
+                    .map_err(|e| mlua::Error::external(format!("Failed to create entity '{}': {}", name, e)))?;
+                Ok(EntityRef::new(this.scene_id, entity_id))
+            })
+        });
+
+        // Uses the predefined Physics attributes, the body is placed at the entity's x and y
+        methods.add_method("create_physical_entity", |lua, this, (name, x, y, z): (String, f32, f32, f32)| {
+            this.with_scene(lua, |scene| {
+                let entity_id = scene
+                    .create_entity(&name)
+                    .map_err(|e| mlua::Error::external(format!("Failed to create physical entity '{}': {}", name, e)))?;
+                let entity = scene.get_entity_mut(entity_id).map_err(mlua::Error::external)?;
+
+                if let Some(predefined) = PREDEFINED_ENTITIES.iter().find(|e| e.name == "Physics") {
+                    for (attr_name, attr_type, attr_value) in predefined.attributes.iter() {
+                        let _ = entity.create_attribute(attr_name, attr_type.clone(), attr_value.clone());
+                    }
+                }
+                entity.set_position(x, y, z).map_err(mlua::Error::external)?;
+
+                Ok(EntityRef::new(this.scene_id, entity_id))
+            })
+        });
+
+        methods.add_method("remove_entity", |lua, this, entity: LuaValue| {
+            let entity_id = entity_uuid_from_lua(&entity)?;
+            this.with_scene(lua, |scene| scene.delete_entity(entity_id).map_err(mlua::Error::external))
+        });
+
+        methods.add_method("camera", |lua, this, ()| {
+            this.with_scene(lua, |scene| Ok(scene.default_camera.map(|camera_id| EntityRef::new(this.scene_id, camera_id))))
+        });
+
+        methods.add_method("set_camera", |lua, this, entity: LuaValue| {
+            let entity_id = entity_uuid_from_lua(&entity)?;
+            this.with_scene(lua, |scene| scene.set_default_camera(entity_id).map_err(mlua::Error::external))
+        });
+
+        methods.add_meta_method(MetaMethod::ToString, |_, this, ()| Ok(format!("Scene({})", this.scene_id)));
+    }
+}
+
+/// The running copy of an entity's script. Each instance has its own environment
+/// table, so script-level variables persist between frames without leaking into
+/// other entities that use the same script.
+struct ScriptInstance {
+    script_path: PathBuf,
+    env: LuaTable,
+    this: AnyUserData, // the EntityRef passed as `self` to the callbacks
+    enabled: bool,
+    faulted: bool,
+}
+
 pub struct LuaScripting {
     pub lua: Lua,
     accumulated_time: f32,
     script_state: ScriptState,
+    compiled_chunks: HashMap<PathBuf, Vec<u8>>,
+    instances: IndexMap<Uuid, ScriptInstance>,
+    pending_enable_changes: Rc<RefCell<Vec<(Uuid, bool)>>>,
+    session_active: bool,
 }
 
 impl LuaScripting {
@@ -34,31 +626,41 @@ impl LuaScripting {
             lua: Lua::new(),
             accumulated_time: 0.0,
             script_state: ScriptState::default(),
+            compiled_chunks: HashMap::new(),
+            instances: IndexMap::new(),
+            pending_enable_changes: Rc::new(RefCell::new(Vec::new())),
+            session_active: false,
         }
     }
 }
 
+impl Default for LuaScripting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // In Lua:
 // - `accumulated_time` is read-only.
 // - `script_state` is writable.
 // - `scene_manager` was previously accessible for both reading and writing. However, for safety reasons
 //   and to ensure that position updates happen within the physics engine, `scene_manager` is no longer
 //   directly accessible. Instead, it can be read and modified through specific bound functions.
+// - Each script instance gets `self` (an EntityRef) in its callbacks and `scene` (a SceneRef) in its environment.
+// - `raycast`, `raycast_all`, `overlap_circle`, `overlap_box`, `point_query` and `shape_cast` find entities
+//   by their colliders, in world coordinates, e.g. `raycast(self.x, self.y, 0, 1, 100, { exclude = self })`.
+// - `add_joint`, `remove_joint` and `set_joint_motor` connect entities, e.g. `add_joint(self, door, "revolute")`.
+// - `move_and_slide` and `is_grounded` move characters, e.g. `move_and_slide(self, speed * dt, fall_speed * dt)`.
+// - `get_physics_settings` and `set_physics_settings` change the scene's gravity, time step, etc., e.g. `set_physics_settings({ gravity = { x = 0, y = -50 } })`.
 impl LuaScripting {
 
     // This is for binding physics engine functions to Lua
-    pub fn initialize_bindings_physics_engine(&mut self, physics_engine: &mut PhysicsEngine, scene_manager: &mut SceneManager) -> Result<(), mlua::Error> {
-        let physics_engine_ref = physics_engine as *mut PhysicsEngine;
-        let scene_manager_ref = scene_manager as *const SceneManager;
-
+    // Functions taking an entity accept either an entity handle or its UUID string.
+    // They reach the physics engine lent by `with_engines`.
+    pub fn initialize_bindings_physics_engine(&mut self) -> Result<(), mlua::Error> {
         // Binding set_velocity
-        let set_velocity = self.lua.create_function(move |_, (entity_id, x, y): (String, f32, f32)| {
-            let physics_engine = unsafe { &mut *physics_engine_ref };
-
-            let uuid = Uuid::parse_str(&entity_id).map_err(|e| {
-                eprintln!("Invalid UUID '{}': {}", entity_id, e);
-                mlua::Error::external(format!("Invalid UUID '{}': {}", entity_id, e))
-            })?;
+        let set_velocity = self.lua.create_function(|lua, (entity, x, y): (LuaValue, f32, f32)| with_physics_engine(lua, |physics_engine| {
+            let uuid = entity_uuid_from_lua(&entity).inspect_err(|e| eprintln!("{}", e))?;
 
             if !physics_engine.has_rigid_body(&uuid) {
                 eprintln!("Entity '{}' not found in physics engine.", uuid);
@@ -74,17 +676,12 @@ impl LuaScripting {
             physics_engine.set_velocity(&uuid, velocity);
 
             Ok(())
-        })?;
+        }))?;
         self.lua.globals().set("set_velocity", set_velocity)?;
 
         // Binding apply_force
-        let apply_force = self.lua.create_function(move |_, (entity_id, x, y): (String, f32, f32)| {
-            let physics_engine = unsafe { &mut *physics_engine_ref };
-
-            let uuid = Uuid::parse_str(&entity_id).map_err(|e| {
-                eprintln!("Invalid UUID '{}': {}", entity_id, e);
-                mlua::Error::external(format!("Invalid UUID '{}': {}", entity_id, e))
-            })?;
+        let apply_force = self.lua.create_function(|lua, (entity, x, y): (LuaValue, f32, f32)| with_physics_engine(lua, |physics_engine| {
+            let uuid = entity_uuid_from_lua(&entity).inspect_err(|e| eprintln!("{}", e))?;
 
             if !physics_engine.has_rigid_body(&uuid) {
                 eprintln!("Entity '{}' not found in physics engine.", uuid);
@@ -100,17 +697,12 @@ impl LuaScripting {
             physics_engine.apply_force(&uuid, force);
 
             Ok(())
-        })?;
+        }))?;
         self.lua.globals().set("apply_force", apply_force)?;
 
         // Binding apply_impulse
-        let apply_impulse = self.lua.create_function(move |_, (entity_id, x, y): (String, f32, f32)| {
-            let physics_engine = unsafe { &mut *physics_engine_ref };
-
-            let uuid = Uuid::parse_str(&entity_id).map_err(|e| {
-                eprintln!("Invalid UUID '{}': {}", entity_id, e);
-                mlua::Error::external(format!("Invalid UUID '{}': {}", entity_id, e))
-            })?;
+        let apply_impulse = self.lua.create_function(|lua, (entity, x, y): (LuaValue, f32, f32)| with_physics_engine(lua, |physics_engine| {
+            let uuid = entity_uuid_from_lua(&entity).inspect_err(|e| eprintln!("{}", e))?;
 
             if !physics_engine.has_rigid_body(&uuid) {
                 eprintln!("Entity '{}' not found in physics engine.", uuid);
@@ -126,59 +718,219 @@ impl LuaScripting {
             physics_engine.apply_impulse(&uuid, impulse);
 
             Ok(())
-        })?;
+        }))?;
         self.lua.globals().set("apply_impulse", apply_impulse)?;
 
         // Binding add_entity (Rust) to add_entity_to_physics_engine (Lua)
-        let add_entity_to_physics_engine = self.lua.create_function(move |_, entity_id: String| {
-            let physics_engine = unsafe { &mut *physics_engine_ref };
-            let scene_manager = unsafe { &*scene_manager_ref };
-
-            let uuid = Uuid::parse_str(&entity_id).map_err(|e| {
-                mlua::Error::external(format!("Invalid UUID '{}': {}", entity_id, e))
-            })?;
-
-            if let Some(active_scene) = scene_manager.get_active_scene() {
-                if let Some(entity) = active_scene.entities.get(&uuid) {
-                    physics_engine.add_entity(entity);
-                    // println!("Entity '{}' added to physics engine.", uuid);
-                    return Ok(());
+        let add_entity_to_physics_engine = self.lua.create_function(|lua, entity: LuaValue| with_physics_engine(lua, |physics_engine| {
+            let uuid = entity_uuid_from_lua(&entity)?;
+
+            with_scene_manager(lua, |scene_manager| {
+                if let Some(active_scene) = scene_manager.get_active_scene() {
+                    if let Some(entity) = active_scene.entities.get(&uuid) {
+                        physics_engine.add_entity(entity);
+                        // println!("Entity '{}' added to physics engine.", uuid);
+                        return Ok(());
+                    }
                 }
-            }
 
-            Err(mlua::Error::external(format!(
-                "Entity '{}' not found in active scene",
-                uuid
-            )))
-        })?;
+                Err(mlua::Error::external(format!(
+                    "Entity '{}' not found in active scene",
+                    uuid
+                )))
+            })
+        }))?;
         self.lua.globals().set("add_entity_to_physics_engine", add_entity_to_physics_engine)?;
 
         // Binding remove_entity (Rust) to remove_entity_from_physics_engine (Lua)
-        let remove_entity_from_physics_engine = self.lua.create_function(move |_, entity_id: String| {
-            let physics_engine = unsafe { &mut *physics_engine_ref };
-
-            let uuid = Uuid::parse_str(&entity_id).map_err(|e| {
-                mlua::Error::external(format!("Invalid UUID '{}': {}", entity_id, e))
-            })?;
+        let remove_entity_from_physics_engine = self.lua.create_function(|lua, entity: LuaValue| with_physics_engine(lua, |physics_engine| {
+            let uuid = entity_uuid_from_lua(&entity)?;
 
             physics_engine.remove_entity(uuid);
             // println!("Entity '{}' removed from physics engine.", uuid);
             return Ok(());
-        })?;
+        }))?;
         self.lua.globals().set("remove_entity_from_physics_engine", remove_entity_from_physics_engine)?;
 
+        // Binding spawn_prefab, instantiates a prefab by name in the active scene and adds it to the physics engine
+        let spawn_prefab = self.lua.create_function(|lua, (name, x, y): (String, f32, f32)| with_physics_engine(lua, |physics_engine| {
+            with_scene_manager(lua, |scene_manager| {
+                let scene_id = scene_manager
+                    .active_scene
+                    .ok_or_else(|| mlua::Error::external("No active scene found"))?;
+                let prefab_id = scene_manager
+                    .get_prefab_by_name(&name)
+                    .map(|prefab| prefab.id)
+                    .ok_or_else(|| mlua::Error::external(format!("Prefab '{}' not found", name)))?;
+                let entity_id = scene_manager
+                    .instantiate_prefab(scene_id, prefab_id, x, y)
+                    .map_err(|e| mlua::Error::external(format!("Failed to spawn prefab '{}': {}", name, e)))?;
+
+                let entity = scene_manager
+                    .get_scene(scene_id)
+                    .and_then(|scene| scene.get_entity(entity_id).ok())
+                    .ok_or_else(|| mlua::Error::external(format!("Entity '{}' not found in active scene", entity_id)))?;
+                physics_engine.add_entity(entity);
+                Ok(EntityRef::new(scene_id, entity_id))
+            })
+        }))?;
+        self.lua.globals().set("spawn_prefab", spawn_prefab)?;
+
+        // Scene queries. Hits are tables { entity, point = {x, y}, normal = {x, y}, distance },
+        // the last argument is an optional filter, see `query_filter_from_lua`.
+        let raycast = self.lua.create_function(|lua, (x, y, dx, dy, max_distance, filter): (f32, f32, f32, f32, f32, Option<LuaTable>)| with_physics_engine(lua, |physics_engine| {
+            let filter = query_filter_from_lua(physics_engine, filter)?;
+            let scene_id = active_scene_id(lua)?;
+            physics_engine.raycast((x, y), (dx, dy), max_distance, &filter)
+                .map(|hit| query_hit_to_lua(lua, scene_id, &hit))
+                .transpose()
+        }))?;
+        self.lua.globals().set("raycast", raycast)?;
+
+        let raycast_all = self.lua.create_function(|lua, (x, y, dx, dy, max_distance, filter): (f32, f32, f32, f32, f32, Option<LuaTable>)| with_physics_engine(lua, |physics_engine| {
+            let filter = query_filter_from_lua(physics_engine, filter)?;
+            let scene_id = active_scene_id(lua)?;
+            physics_engine.raycast_all((x, y), (dx, dy), max_distance, &filter).iter()
+                .map(|hit| query_hit_to_lua(lua, scene_id, hit))
+                .collect::<LuaResult<Vec<_>>>()
+        }))?;
+        self.lua.globals().set("raycast_all", raycast_all)?;
+
+        let overlap_circle = self.lua.create_function(|lua, (x, y, radius, filter): (f32, f32, f32, Option<LuaTable>)| with_physics_engine(lua, |physics_engine| {
+            let filter = query_filter_from_lua(physics_engine, filter)?;
+            let scene_id = active_scene_id(lua)?;
+            Ok(physics_engine.overlap_circle((x, y), radius, &filter).into_iter()
+                .map(|entity_id| EntityRef::new(scene_id, entity_id))
+                .collect::<Vec<_>>())
+        }))?;
+        self.lua.globals().set("overlap_circle", overlap_circle)?;
+
+        // The box is centered on x, y and turned by rotation radians, which may be nil
+        let overlap_box = self.lua.create_function(
+            |lua, (x, y, width, height, rotation, filter): (f32, f32, f32, f32, Option<f32>, Option<LuaTable>)| with_physics_engine(lua, |physics_engine| {
+                let filter = query_filter_from_lua(physics_engine, filter)?;
+                let scene_id = active_scene_id(lua)?;
+                Ok(physics_engine.overlap_box((x, y), (width, height), rotation.unwrap_or(0.0), &filter).into_iter()
+                    .map(|entity_id| EntityRef::new(scene_id, entity_id))
+                    .collect::<Vec<_>>())
+            }),
+        )?;
+        self.lua.globals().set("overlap_box", overlap_box)?;
+
+        let point_query = self.lua.create_function(|lua, (x, y, filter): (f32, f32, Option<LuaTable>)| with_physics_engine(lua, |physics_engine| {
+            let filter = query_filter_from_lua(physics_engine, filter)?;
+            let scene_id = active_scene_id(lua)?;
+            Ok(physics_engine.point_query((x, y), &filter).into_iter()
+                .map(|entity_id| EntityRef::new(scene_id, entity_id))
+                .collect::<Vec<_>>())
+        }))?;
+        self.lua.globals().set("point_query", point_query)?;
+
+        // The shape is { radius = r } or { width = w, height = h }, see `query_shape_from_lua`
+        let shape_cast = self.lua.create_function(
+            |lua, (shape, x, y, dx, dy, max_distance, filter): (LuaTable, f32, f32, f32, f32, f32, Option<LuaTable>)| with_physics_engine(lua, |physics_engine| {
+                let shape = query_shape_from_lua(&shape)?;
+                let filter = query_filter_from_lua(physics_engine, filter)?;
+                let scene_id = active_scene_id(lua)?;
+                physics_engine.shape_cast(shape, (x, y), (dx, dy), max_distance, &filter)
+                    .map(|hit| query_hit_to_lua(lua, scene_id, &hit))
+                    .transpose()
+            }),
+        )?;
+        self.lua.globals().set("shape_cast", shape_cast)?;
+
+        // Joints, saved with the entity so they are also there when the scene is loaded again.
+        // add_joint(entity, other, kind, options?) returns the joint's ID, see `joint_from_lua` for the options.
+        let add_joint = self.lua.create_function(|lua, (entity, other, kind, options): (LuaValue, LuaValue, String, Option<LuaTable>)| with_physics_engine(lua, |physics_engine| {
+            let entity_id = entity_uuid_from_lua(&entity)?;
+            let joint = joint_from_lua(&kind, entity_uuid_from_lua(&other)?, options)?;
+            physics_engine.add_joint(entity_id, &joint).map_err(mlua::Error::external)?;
+
+            with_scene_manager(lua, |scene_manager| {
+                let entity = scene_manager
+                    .get_active_scene_mut()
+                    .and_then(|scene| scene.get_entity_mut(entity_id).ok())
+                    .ok_or_else(|| mlua::Error::external(format!("Entity '{}' not found in active scene", entity_id)))?;
+                entity.joints.push(joint.clone());
+                Ok(joint.id.to_string())
+            })
+        }))?;
+        self.lua.globals().set("add_joint", add_joint)?;
+
+        // remove_joint(joint_id) returns whether there was such a joint
+        let remove_joint = self.lua.create_function(|lua, joint_id: String| with_physics_engine(lua, |physics_engine| {
+            let joint_id = joint_id_from_lua(&joint_id)?;
+            let removed = physics_engine.remove_joint(joint_id);
+            with_scene_manager(lua, |scene_manager| {
+                if let Some(scene) = scene_manager.get_active_scene_mut() {
+                    for entity in scene.entities.values_mut() {
+                        entity.joints.retain(|joint| joint.id != joint_id);
+                    }
+                }
+                Ok(removed)
+            })
+        }))?;
+        self.lua.globals().set("remove_joint", remove_joint)?;
+
+        // set_joint_motor(joint_id, speed, max_force) drives a revolute or prismatic joint,
+        // set_joint_motor(joint_id, nil) stops its motor
+        let set_joint_motor = self.lua.create_function(|lua, (joint_id, speed, max_force): (String, Option<f32>, Option<f32>)| with_physics_engine(lua, |physics_engine| {
+            let joint_id = joint_id_from_lua(&joint_id)?;
+            let motor = speed.map(|speed| JointMotor {
+                speed,
+                max_force: max_force.unwrap_or(JointMotor::default().max_force),
+            });
+            physics_engine.set_joint_motor(joint_id, motor).map_err(mlua::Error::external)
+        }))?;
+        self.lua.globals().set("set_joint_motor", set_joint_motor)?;
+
+        // move_and_slide(entity, dx, dy) moves an entity with a character controller, sliding along walls.
+        // Returns { x, y, grounded }: how far it moved and whether it stands on the ground.
+        let move_and_slide = self.lua.create_function(|lua, (entity, dx, dy): (LuaValue, f32, f32)| with_physics_engine(lua, |physics_engine| {
+            let entity_id = entity_uuid_from_lua(&entity)?;
+            let movement = physics_engine.move_and_slide(entity_id, (dx, dy)).map_err(mlua::Error::external)?;
+            let table = vector2_to_lua(lua, movement.translation)?;
+            table.set("grounded", movement.grounded)?;
+            Ok(table)
+        }))?;
+        self.lua.globals().set("move_and_slide", move_and_slide)?;
+
+        // is_grounded(entity) tells whether a character stood on the ground after its last move
+        let is_grounded = self.lua.create_function(|lua, entity: LuaValue| with_physics_engine(lua, |physics_engine| {
+            Ok(physics_engine.is_grounded(&entity_uuid_from_lua(&entity)?))
+        }))?;
+        self.lua.globals().set("is_grounded", is_grounded)?;
+
+        // Physics settings of the running scene, see `physics_settings_to_lua` for the fields.
+        // set_physics_settings(changes) only changes the fields given, e.g. { ccd = true }.
+        let get_physics_settings = self.lua.create_function(|lua, ()| with_physics_engine(lua, |physics_engine| {
+            physics_settings_to_lua(lua, physics_engine.settings())
+        }))?;
+        self.lua.globals().set("get_physics_settings", get_physics_settings)?;
+
+        let set_physics_settings = self.lua.create_function(|lua, changes: LuaTable| with_physics_engine(lua, |physics_engine| {
+            let settings = physics_settings_from_lua(physics_engine.settings(), &changes)?;
+            physics_engine.apply_settings(&settings).map_err(mlua::Error::external)?;
+
+            // Kept with the scene, like joints
+            with_scene_manager(lua, |scene_manager| {
+                let scene = scene_manager
+                    .get_active_scene_mut()
+                    .ok_or_else(|| mlua::Error::external("No active scene found"))?;
+                scene.physics = settings;
+                Ok(())
+            })
+        }))?;
+        self.lua.globals().set("set_physics_settings", set_physics_settings)?;
+
         println!("Lua physics engine bindings initialized successfully.");
         Ok(())
     }
 
-    pub fn initialize_bindings_input_handler(&mut self, input_handler: &mut InputHandler) -> Result<(), mlua::Error> {
-
-        let input_handler_ref = input_handler as *mut InputHandler;
-
+    // Input bindings, they reach the input handler lent by `with_engines`
+    pub fn initialize_bindings_input_handler(&mut self) -> Result<(), mlua::Error> {
         // Binding is_key_just_pressed
-        let is_key_just_pressed = self.lua.create_function(move |_, key: String| {
-            let input_handler = unsafe { &*input_handler_ref };
-
+        let is_key_just_pressed = self.lua.create_function(|lua, key: String| with_input_handler(lua, |input_handler| {
             // Convert the key string to a Key enum
             let parsed_key = Key::from_name(&key).ok_or_else(|| {
                 let error_message = format!("Invalid key '{}'", key);
@@ -190,7 +942,7 @@ impl LuaScripting {
             let just_pressed = input_handler.is_key_just_pressed(parsed_key);
 
             Ok(just_pressed)
-        })?;
+        }))?;
         self.lua.globals().set("is_key_just_pressed", is_key_just_pressed)?;
 
         println!("Lua input handler bindings initialized successfully.");
@@ -198,519 +950,482 @@ impl LuaScripting {
 
     }
 
-    // This is for binding ECS functions to Lua
-    pub fn initialize_bindings_ecs(&mut self, scene_manager: &mut SceneManager) -> Result<(), mlua::Error> {
-
-        let scene_manager_ref = scene_manager as *mut SceneManager;
-
-        // Binding add_entity
-        let add_entity = self.lua.create_function(move |_, (scene_id, entity_name): (String, String)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
-
-            let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
-
-            let entity_id = scene.create_entity(&entity_name)
-                .map_err(|e| mlua::Error::external(format!("Failed to create entity '{}': {}", entity_name, e)))?;
-
-            match scene.get_entity(entity_id) {
-                Ok(entity) => {
-                    println!("Entity '{}' created in scene '{}':", entity_id, scene_uuid);
-                    for (attr_name, attr) in &entity.attributes {
-                        println!(
-                            "  Attribute: {} -> {:?} (Type: {:?})",
-                            attr_name, attr.value, attr.data_type
-                        );
-                    }
-                }
-                Err(e) => {
-                    println!(
-                        "Entity '{}' created but failed to retrieve attributes for debug: {}",
-                        entity_id, e
-                    );
-                }
-            }
-
-            // println!("Entity '{}' created in scene '{}'", entity_id, scene_uuid);
-            Ok(entity_id.to_string())
+    // This is for binding ECS functions to Lua. Entities and scenes are reached through the
+    // `self` and `scene` handles (see EntityRef and SceneRef); only lookups by id live here.
+    pub fn initialize_bindings_ecs(&mut self) -> Result<(), mlua::Error> {
+
+        // Binding get_entity, for scripts that still hold entity UUID strings
+        let get_entity = self.lua.create_function(|lua, entity: LuaValue| {
+            let entity_id = entity_uuid_from_lua(&entity)?;
+            with_scene_manager(lua, |scene_manager| {
+                let scene = scene_manager
+                    .get_active_scene()
+                    .ok_or_else(|| mlua::Error::external("No active scene found"))?;
+                Ok(scene.entities.contains_key(&entity_id).then(|| EntityRef::new(scene.id, entity_id)))
+            })
         })?;
-        self.lua.globals().set("add_entity", add_entity)?;
-
-        // Binding delete_entity
-        let remove_entity = self.lua.create_function(move |_, (scene_id, entity_id): (String, String)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
-
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
-
-            let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
-
-            let success = scene.delete_entity(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Failed to delete entity '{}': {}", entity_uuid, e)))?;
-
-            if success {
-                println!("Entity '{}' deleted from scene '{}'", entity_uuid, scene_uuid);
-            } else {
-                println!("Entity '{}' not found in scene '{}'", entity_uuid, scene_uuid);
-            }
-
-            Ok(success)
-        })?;
-        self.lua.globals().set("remove_entity", remove_entity)?;
-
-        // This uses create_entity, and add attributes inside the Lua, due to somehow create_physical_entity not working
-        let create_physical_entity = self.lua.create_function(move |_, (scene_id, name, x, y, z): (String, String, f32, f32, f32)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
-
-            let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
-
-            let entity_id = scene.create_entity(&name)
-                .map_err(|e| mlua::Error::external(format!("Failed to create physical entity '{}': {}", name, e)))?;
-
-
-            // Assign default attributes based on predefined entities
-            if let Ok(entity) = scene.get_entity_mut(entity_id) {
-                if let Some(predefined) = PREDEFINED_ENTITIES.iter().find(|e| e.name == "Physics") {
-                    for (attr_name, attr_type, attr_value) in predefined.attributes.iter() {
-                        // Create attributes for the entity
-                        let _ = entity.create_attribute(attr_name, attr_type.clone(), attr_value.clone());
-                    }
-                }
-            } else {
-                return Err(mlua::Error::external(format!(
-                    "Failed to retrieve entity with ID '{}'",
-                    entity_id
-                )));
-            }
-
-            // println!("Physical entity '{}' created in scene '{}'", entity_id, scene_uuid);
-            Ok(entity_id.to_string())
-        })?;
-
-        self.lua.globals().set("create_physical_entity", create_physical_entity)?;
-
-        // Update Single Entity Attribute Binding
-        let set_x = self.lua.create_function(move |_, (scene_id, entity_id, value): (String, String, f32)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
-
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
-
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
-
-            let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
-
-            let entity = scene.get_entity_mut(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e)))?;
-
-            entity.set_x(value)
-                .map_err(|e| mlua::Error::external(format!("Failed to set x: {}", e)))?;
-
-            // println!(
-            //     "Set x to '{}' for entity '{}' in scene '{}'",
-            //     value, entity_uuid, scene_uuid
-            // );
+        self.lua.globals().set("get_entity", get_entity)?;
+
+        // Set Script Enabled Binding
+        // The change is queued and applied at the start of the next script frame,
+        // since the instance being toggled may be the one currently running.
+        let pending_enable_changes = Rc::clone(&self.pending_enable_changes);
+        let set_script_enabled = self.lua.create_function(move |_, (entity, enabled): (LuaValue, bool)| {
+            let entity_uuid = entity_uuid_from_lua(&entity)?;
+            pending_enable_changes.borrow_mut().push((entity_uuid, enabled));
             Ok(())
         })?;
-        self.lua.globals().set("set_x", set_x)?;
-
-        let set_y = self.lua.create_function(move |_, (scene_id, entity_id, value): (String, String, f32)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
-
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
-
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
-
-            let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
-
-            let entity = scene.get_entity_mut(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e)))?;
+        self.lua.globals().set("set_script_enabled", set_script_enabled)?;
 
-            entity.set_y(value)
-                .map_err(|e| mlua::Error::external(format!("Failed to set y: {}", e)))?;
-
-            // println!(
-            //     "Set y to '{}' for entity '{}' in scene '{}'",
-            //     value, entity_uuid, scene_uuid
-            // );
-            Ok(())
-        })?;
-        self.lua.globals().set("set_y", set_y)?;
-
-        let set_z = self.lua.create_function(move |_, (scene_id, entity_id, value): (String, String, f32)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
-
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
+        println!("ECS bindings initialized successfully.");
+        Ok(())
+    }
 
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
 
-            let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
 
-            let entity = scene.get_entity_mut(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e)))?;
+    pub fn load_script_state(&self) -> Result<(), mlua::Error> {
+        // Serialize ScriptState as Lua userdata
+        let globals = self.lua.globals();
+        globals.set("script_state", self.lua.to_value(&self.script_state)?)?;
+        Ok(())
+    }
 
-            entity.set_z(value)
-                .map_err(|e| mlua::Error::external(format!("Failed to set z: {}", e)))?;
+    pub fn update_script_state(&mut self) -> Result<(), String> {
+        // Update the script_state from Lua
+        let globals = self.lua.globals();
+        let script_state_lua: LuaValue = globals
+            .get("script_state")
+            .map_err(|e| format!("Error accessing Lua script_state: {}", e))?;
 
-            // println!(
-            //     "Set z to '{}' for entity '{}' in scene '{}'",
-            //     value, entity_uuid, scene_uuid
-            // );
-            Ok(())
+        let script_state_json = self.lua.from_value(script_state_lua).map_err(|e| {
+            eprintln!("Error converting Lua script_state to JSON: {:?}", e);
+            format!("Failed to convert Lua script_state to JSON: {}", e)
         })?;
-        self.lua.globals().set("set_z", set_z)?;
 
-        let set_position = self.lua.create_function(move |_, (scene_id, entity_id, x, y): (String, String, f32, f32)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
-
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
-
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
+        // Deserialize JSON into ScriptState
+        self.script_state = serde_json::from_value(script_state_json)
+            .map_err(|e| format!("Error deserializing JSON script_state: {}", e))?;
 
-            let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
+        Ok(())
+    }
 
-            let entity = scene.get_entity_mut(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e)))?;
+    /// Compile every script attached to an entity of the scene into bytecode.
+    /// Scripts are only read and parsed once per play session; later instances
+    /// reuse the cached chunk. Already cached scripts are skipped.
+    pub fn compile_scripts(&mut self, scene_manager: &SceneManager, scene_id: Uuid) -> Result<(), String> {
+        let scene = scene_manager
+            .get_scene(scene_id)
+            .ok_or_else(|| "Active scene not found.".to_string())?;
 
-            entity.set_position(x, y, 0.0)
-                .map_err(|e| mlua::Error::external(format!("Failed to set position: {}", e)))?;
+        let mut errors = Vec::new();
+        for entity in scene.entities.values() {
+            if let Some(script_path) = &entity.script {
+                if let Err(e) = self.compile_script(script_path) {
+                    errors.push(e);
+                }
+            }
+        }
 
-            // println!(
-            //     "Set position to '({}, {}, 0.0)' for entity '{}' in scene '{}'",
-            //     x, y, entity_uuid, scene_uuid
-            // );
+        if errors.is_empty() {
             Ok(())
-        })?;
-        self.lua.globals().set("set_position", set_position)?;
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
 
+    fn compile_script(&mut self, script_path: &PathBuf) -> Result<(), String> {
+        if self.compiled_chunks.contains_key(script_path) {
+            return Ok(());
+        }
 
-        // Add Image Binding
-        let add_image = self.lua.create_function(move |_, (entity_id, image_path): (String, String)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
+        let script_content = fs::read_to_string(script_path)
+            .map_err(|e| format!("Error reading script file {:?}: {}", script_path, e))?;
 
-            let scene = scene_manager
-                .get_active_scene_mut()
-                .ok_or_else(|| mlua::Error::external("No active scene found"))?;
+        let bytecode = self
+            .lua
+            .load(&script_content)
+            .set_name(format!("@{}", script_path.display()))
+            .into_function()
+            .map_err(|e| format!("Error compiling script {:?}: {}", script_path, e))?
+            .dump(false);
 
-            let entity = scene.get_entity_mut(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e)))?;
+        self.compiled_chunks.insert(script_path.clone(), bytecode);
+        Ok(())
+    }
 
-            let full_image_path = PathBuf::from(ProjectManager::get_project_path().unwrap().to_string()).join(&image_path);
-            entity.add_image(full_image_path)
-                .map_err(|e| mlua::Error::external(format!("Failed to add image to entity '{}': {}", entity_uuid, e)))?;
+    /// Create the script instance of an entity: a fresh environment table that
+    /// falls back to the globals, in which the compiled chunk runs once.
+    /// `init(self)` and then `on_enable(self)` are called if the script defines them.
+    fn create_instance(&mut self, scene_id: Uuid, entity_id: Uuid, script_path: &PathBuf) -> Result<(), String> {
+        self.compile_script(script_path)?;
+        let bytecode = &self.compiled_chunks[script_path];
+
+        let build = || -> LuaResult<(LuaTable, AnyUserData)> {
+            let env = self.lua.create_table()?;
+            let metatable = self.lua.create_table()?;
+            metatable.set("__index", self.lua.globals())?;
+            env.set_metatable(Some(metatable));
+            env.set("scene", SceneRef::new(scene_id))?;
+
+            let this = self.lua.create_userdata(EntityRef::new(scene_id, entity_id))?;
+
+            self.lua
+                .load(bytecode.as_slice())
+                .set_name(format!("@{}", script_path.display()))
+                .set_environment(env.clone())
+                .exec()?;
+            Ok((env, this))
+        };
+
+        let (env, this) = build()
+            .map_err(|e| format!("Error executing script for entity {}: {}", entity_id, e))?;
+
+        self.instances.insert(entity_id, ScriptInstance {
+            script_path: script_path.clone(),
+            env,
+            this,
+            enabled: true,
+            faulted: false,
+        });
+
+        self.call_callback(entity_id, "init", ())?;
+        self.call_callback(entity_id, "on_enable", ())
+    }
 
-            // println!("Image added to entity '{}'", entity_uuid);
-            Ok(())
-        })?;
-        self.lua.globals().set("add_image", add_image)?;
+    /// Call `name(self, args...)` on an instance if its script defines it.
+    /// An error marks the instance as faulted so that it is not called again.
+    fn call_callback(&mut self, entity_id: Uuid, name: &str, args: impl IntoLuaMulti) -> Result<(), String> {
+        let Some(instance) = self.instances.get_mut(&entity_id) else {
+            return Ok(());
+        };
+        if instance.faulted {
+            return Ok(());
+        }
 
-        // Set Script Binding
-        let set_script = self.lua.create_function(move |_, (entity_id, script_path): (String, String)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
+        let callback = match instance.env.raw_get::<LuaValue>(name) {
+            Ok(LuaValue::Function(callback)) => callback,
+            _ => return Ok(()),
+        };
 
-            let scene = scene_manager
-                .get_active_scene_mut()
-                .ok_or_else(|| mlua::Error::external("No active scene found"))?;
+        let mut call_args = args.into_lua_multi(&self.lua).map_err(|e| e.to_string())?;
+        call_args.push_front(LuaValue::UserData(instance.this.clone()));
 
-            let entity = scene.get_entity_mut(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e)))?;
+        callback.call::<()>(call_args).map_err(|e| {
+            instance.faulted = true;
+            format!("Error executing {}() in script {:?} for entity {}: {}", name, instance.script_path, entity_id, e)
+        })
+    }
 
-            let full_script_path = PathBuf::from(ProjectManager::get_project_path().unwrap().to_string()).join(&script_path);
-            entity.set_script(full_script_path)
-                .map_err(|e| mlua::Error::external(format!("Failed to set script for entity '{}': {}", entity_uuid, e)))?;
+    /// Run `f` with the SceneManager reachable from Lua. The scene is lent to Lua through a
+    /// scoped userdata, which is invalidated as soon as `f` returns.
+    fn with_scene_access(
+        &mut self,
+        scene_manager: &mut SceneManager,
+        f: impl FnOnce(&mut Self) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let lua = self.lua.clone();
+        let result = lua.scope(|scope| {
+            let scene_manager = scope.create_any_userdata_ref_mut(scene_manager)?;
+            lua.set_named_registry_value(SCENE_MANAGER_KEY, scene_manager)?;
+            Ok(f(self))
+        });
+        let _ = lua.unset_named_registry_value(SCENE_MANAGER_KEY);
+
+        result.map_err(|e| format!("Error giving scripts access to the scene: {}", e))?
+    }
 
-            // println!("Script set for entity '{}'", entity_uuid);
-            Ok(())
-        })?;
-        self.lua.globals().set("set_script", set_script)?;
+    /// Folder that paths given by scripts, e.g. to `add_image`, are relative to
+    pub fn set_project_path(&self, project_path: &Path) -> Result<(), String> {
+        self.lua.set_named_registry_value(PROJECT_PATH_KEY, project_path.to_string_lossy().to_string())
+            .map_err(|e| format!("Error setting the project path for scripts: {}", e))
+    }
 
+    /// Run `f` with the physics engine and the input handler reachable from the physics and
+    /// input bindings. Like the scene in `with_scene_access`, they are lent to Lua through
+    /// scoped userdata, so scripts can't reach them once `f` returns.
+    pub fn with_engines<R>(
+        &mut self,
+        physics_engine: &mut PhysicsEngine,
+        input_handler: &InputHandler,
+        f: impl FnOnce(&mut Self) -> Result<R, String>,
+    ) -> Result<R, String> {
+        let lua = self.lua.clone();
+        let result = lua.scope(|scope| {
+            lua.set_named_registry_value(PHYSICS_ENGINE_KEY, scope.create_any_userdata_ref_mut(physics_engine)?)?;
+            lua.set_named_registry_value(INPUT_HANDLER_KEY, scope.create_any_userdata_ref(input_handler)?)?;
+            Ok(f(self))
+        });
+        let _ = lua.unset_named_registry_value(PHYSICS_ENGINE_KEY);
+        let _ = lua.unset_named_registry_value(INPUT_HANDLER_KEY);
+
+        result.map_err(|e| format!("Error giving scripts access to the engines: {}", e))?
+    }
 
-        let update_entity_attribute_bool = self.lua.create_function(move |_, (scene_id, entity_id, attr_name, value): (String, String, String, bool)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
+    /// Instantiate the scripts of every entity in the scene and call their `init`.
+    /// The bindings and global variables should be set up before this is called.
+    pub fn start_session(&mut self, scene_manager: &mut SceneManager, scene_id: Uuid) -> Result<(), String> {
+        self.session_active = true;
 
-            // Parse UUIDs
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
+        let compile_result = self.compile_scripts(scene_manager, scene_id);
+        let sync_result = self.with_scene_access(scene_manager, |this| this.sync_instances(scene_id));
+        compile_result.and(sync_result)
+    }
 
-            // Find the scene
-            let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
-
-            // Find the entity
-            let entity = scene.get_entity_mut(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e)))?;
-
-            // Find the attribute ID using the attribute name
-            let attr_id = entity
-                .get_attribute_by_name(&attr_name)
-                .map_err(|e| mlua::Error::external(format!("Attribute '{}' not found: {}", attr_name, e)))?
-                .id;
-
-            // Update the attribute value
-            scene
-                .update_entity_attribute(entity_uuid, attr_id, AttributeValue::Boolean(value))
-                .map_err(|e| mlua::Error::external(format!("Failed to update attribute '{}': {}", attr_name, e)))?;
-
-            println!(
-                "Boolean attribute '{}' updated to '{}' for entity '{}' in scene '{}'",
-                attr_name, value, entity_uuid, scene_uuid
-            );
-            Ok(())
-        })?;
-        self.lua.globals().set("update_entity_attribute_bool", update_entity_attribute_bool)?;
+    /// Call `on_destroy` on every instance and drop the Lua state, ready for the next session.
+    pub fn end_session(&mut self, scene_manager: &mut SceneManager) {
+        if !self.session_active && self.instances.is_empty() {
+            return;
+        }
 
+        let result = self.with_scene_access(scene_manager, |this| {
+            let entity_ids: Vec<Uuid> = this.instances.keys().copied().collect();
+            let errors: Vec<String> = entity_ids
+                .into_iter()
+                .filter_map(|entity_id| this.destroy_instance(entity_id).err())
+                .collect();
+            if errors.is_empty() { Ok(()) } else { Err(errors.join("\n")) }
+        });
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
 
+        self.instances.clear();
+        self.compiled_chunks.clear();
+        self.pending_enable_changes.borrow_mut().clear();
+        self.lua = Lua::new();
+        self.accumulated_time = 0.0;
+        self.session_active = false;
+    }
 
-        // Function to create a float attribute
-        let create_attribute_float = self.lua.create_function(move |_, (scene_id, entity_id, attr_name, value): (String, String, String, f32)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
+    pub fn is_session_active(&self) -> bool {
+        self.session_active
+    }
 
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
+    pub fn has_instance(&self, entity_id: &Uuid) -> bool {
+        self.instances.contains_key(entity_id)
+    }
 
-            let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
+    pub fn is_script_enabled(&self, entity_id: &Uuid) -> bool {
+        self.instances.get(entity_id).is_some_and(|instance| instance.enabled)
+    }
 
-            let entity = scene.get_entity_mut(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e)))?;
+    /// Enable or disable the script of an entity, calling `on_enable` / `on_disable`.
+    /// Disabled instances keep their state but no longer receive `update`.
+    pub fn set_script_enabled(&mut self, scene_manager: &mut SceneManager, entity_id: Uuid, enabled: bool) -> Result<(), String> {
+        self.with_scene_access(scene_manager, |this| this.apply_script_enabled(entity_id, enabled))
+    }
 
-            entity.create_attribute(&attr_name, AttributeType::Float, AttributeValue::Float(value))
-                .map_err(|e| mlua::Error::external(format!("Failed to create float attribute '{}': {}", attr_name, e)))?;
+    fn apply_script_enabled(&mut self, entity_id: Uuid, enabled: bool) -> Result<(), String> {
+        let instance = self
+            .instances
+            .get_mut(&entity_id)
+            .ok_or_else(|| format!("Entity {} has no script instance", entity_id))?;
 
-            // println!(
-            //     "Float attribute '{}' with value '{}' created for entity '{}' in scene '{}'",
-            //     attr_name, value, entity_uuid, scene_uuid
-            // );
-            Ok(())
-        })?;
-        self.lua.globals().set("create_attribute_float", create_attribute_float)?;
+        if instance.enabled == enabled {
+            return Ok(());
+        }
+        instance.enabled = enabled;
 
-        // Function to create a bool attribute
-        let create_attribute_bool = self.lua.create_function(move |_, (scene_id, entity_id, attr_name, value): (String, String, String, bool)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
+        if enabled {
+            self.call_callback(entity_id, "on_enable", ())
+        } else {
+            self.call_callback(entity_id, "on_disable", ())
+        }
+    }
 
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
+    fn destroy_instance(&mut self, entity_id: Uuid) -> Result<(), String> {
+        let mut result = Ok(());
+        if self.is_script_enabled(&entity_id) {
+            result = self.call_callback(entity_id, "on_disable", ());
+        }
+        let destroy_result = self.call_callback(entity_id, "on_destroy", ());
+        self.instances.shift_remove(&entity_id);
+        result.and(destroy_result)
+    }
 
+    /// Keep the instances in line with the scene: entities that gained a script
+    /// (or were spawned by a script) get a new instance, and instances whose
+    /// entity was removed, or whose script changed, are destroyed.
+    /// Must run inside `with_scene_access`.
+    fn sync_instances(&mut self, scene_id: Uuid) -> Result<(), String> {
+        let scripted: Vec<(Uuid, PathBuf)> = with_scene_manager(&self.lua, |scene_manager| {
             let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
-
-            let entity = scene.get_entity_mut(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e)))?;
+                .get_scene(scene_id)
+                .ok_or_else(|| mlua::Error::external("Active scene not found."))?;
+            Ok(scene
+                .entities
+                .iter()
+                .filter_map(|(id, entity)| entity.script.as_ref().map(|path| (*id, path.clone())))
+                .collect())
+        })
+        .map_err(|e| e.to_string())?;
+
+        let mut errors = Vec::new();
+
+        let stale: Vec<Uuid> = self
+            .instances
+            .iter()
+            .filter(|(id, instance)| !scripted.iter().any(|(entity_id, path)| entity_id == *id && *path == instance.script_path))
+            .map(|(id, _)| *id)
+            .collect();
+        for entity_id in stale {
+            if let Err(e) = self.destroy_instance(entity_id) {
+                errors.push(e);
+            }
+        }
 
-            entity.create_attribute(&attr_name, AttributeType::Boolean, AttributeValue::Boolean(value))
-                .map_err(|e| mlua::Error::external(format!("Failed to create boolean attribute '{}': {}", attr_name, e)))?;
+        for (entity_id, script_path) in scripted {
+            if !self.instances.contains_key(&entity_id) {
+                if let Err(e) = self.create_instance(scene_id, entity_id, &script_path) {
+                    errors.push(e);
+                }
+            }
+        }
 
-            // println!(
-            //     "Boolean attribute '{}' with value '{}' created for entity '{}' in scene '{}'",
-            //     attr_name, value, entity_uuid, scene_uuid
-            // );
+        if errors.is_empty() {
             Ok(())
-        })?;
-        self.lua.globals().set("create_attribute_bool", create_attribute_bool)?;
-
-        // Function to create a vector2 attribute
-        let create_attribute_vector2 = self.lua.create_function(move |_, (scene_id, entity_id, attr_name, x, y): (String, String, String, f32, f32)| {
-            let scene_manager = unsafe { &mut *scene_manager_ref };
-
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
-            let entity_uuid = Uuid::parse_str(&entity_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid entity UUID '{}': {}", entity_id, e)))?;
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
 
-            let scene = scene_manager
-                .get_scene_mut(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
+    /// Run one frame of scripts: apply pending enable/disable requests, create or
+    /// destroy instances for entities that changed, then call `update(self, dt)`
+    /// on every enabled instance.
+    pub fn run_scripts_for_scene(
+        &mut self,
+        scene_manager: &mut SceneManager,
+        active_scene_id: Uuid,
+        delta_time: f32,
+    ) -> Result<(), String> {
+        if !self.session_active {
+            self.start_session(scene_manager, active_scene_id)?;
+        }
 
-            let entity = scene.get_entity_mut(entity_uuid)
-                .map_err(|e| mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e)))?;
+        self.with_scene_access(scene_manager, |this| {
+            let mut errors = Vec::new();
 
-            entity.create_attribute(&attr_name, AttributeType::Vector2, AttributeValue::Vector2(x, y))
-                .map_err(|e| mlua::Error::external(format!("Failed to create vector2 attribute '{}': {}", attr_name, e)))?;
+            let pending: Vec<(Uuid, bool)> = this.pending_enable_changes.borrow_mut().drain(..).collect();
+            for (entity_id, enabled) in pending {
+                if let Err(e) = this.apply_script_enabled(entity_id, enabled) {
+                    errors.push(e);
+                }
+            }
 
-            // println!(
-            //     "Vector2 attribute '{}' with value '({}, {})' created for entity '{}' in scene '{}'",
-            //     attr_name, x, y, entity_uuid, scene_uuid
-            // );
-            Ok(())
-        })?;
-        self.lua.globals().set("create_attribute_vector2", create_attribute_vector2)?;
+            if let Err(e) = this.sync_instances(active_scene_id) {
+                errors.push(e);
+            }
 
+            let entity_ids: Vec<Uuid> = this
+                .instances
+                .iter()
+                .filter(|(_, instance)| instance.enabled)
+                .map(|(id, _)| *id)
+                .collect();
+            for entity_id in entity_ids {
+                if let Err(e) = this.call_callback(entity_id, "update", delta_time) {
+                    errors.push(e);
+                }
+            }
 
-        // TODO: list_entities_name_x_y is not working, need to be fixed
-        let list_entities_name_x_y = self.lua.create_function(move |lua, scene_id: String| {
-            let scene_manager = unsafe { &*scene_manager_ref };
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors.join("\n"))
+            }
+        })
+    }
 
-            let scene_uuid = Uuid::parse_str(&scene_id)
-                .map_err(|e| mlua::Error::external(format!("Invalid scene UUID '{}': {}", scene_id, e)))?;
 
-            let scene = scene_manager
-                .get_scene(scene_uuid)
-                .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", scene_uuid)))?;
+    /// Deliver collision events to the scripts of both entities involved:
+    /// `on_collision_enter(self, other, collision)` when a contact starts,
+    /// `on_collision_exit(self, other, collision)` when it stops. `collision` holds
+    /// `normal` (pointing away from `self`) and `impulse`.
+    /// Overlaps with a trigger call `on_trigger_enter(self, other)` and `on_trigger_exit(self, other)`
+    /// instead, on the trigger's script and on the other entity's.
+    pub fn handle_collision_events(&mut self, scene_manager: &mut SceneManager, events: &[CollisionEvent]) -> Result<(), String> {
+        let Some(scene_id) = scene_manager.active_scene else {
+            return Ok(());
+        };
 
-            let lua_table = lua.create_table()?;
+        self.with_scene_access(scene_manager, |this| {
+            let mut errors = Vec::new();
 
-            for (index, (entity_id, entity)) in scene.entities.iter().enumerate() {
-                let name = &entity.name;
+            for event in events {
+                let callback = match (event.is_sensor(), event.is_started()) {
+                    (false, true) => "on_collision_enter",
+                    (false, false) => "on_collision_exit",
+                    (true, true) => "on_trigger_enter",
+                    (true, false) => "on_trigger_exit",
+                };
+                let sides = [
+                    (event.entity1, event.entity2, event.normal),
+                    (event.entity2, event.entity1, (-event.normal.0, -event.normal.1)),
+                ];
+
+                for (entity_id, other_id, normal) in sides {
+                    if !this.is_script_enabled(&entity_id) {
+                        continue;
+                    }
 
-                let (x, y) = if let Ok(position_attr) = entity.get_attribute_by_name("position") {
-                    if let AttributeValue::Vector2(x, y) = position_attr.value {
-                        (x, y)
+                    let other = EntityRef::new(scene_id, other_id);
+                    let result = if event.is_sensor() {
+                        this.call_callback(entity_id, callback, other)
                     } else {
-                        (0.0, 0.0)
+                        match this.collision_table(normal, event.impulse) {
+                            Ok(collision) => this.call_callback(entity_id, callback, (other, collision)),
+                            Err(e) => {
+                                errors.push(e.to_string());
+                                continue;
+                            }
+                        }
+                    };
+                    if let Err(e) = result {
+                        errors.push(e);
                     }
-                } else {
-                    (0.0, 0.0)
-                };
-
-                // Create a table for each entity
-                let entity_data = lua.create_table()?;
-                entity_data.set("id", entity_id.to_string())?;
-                entity_data.set("name", name.to_string())?;
-                entity_data.set("x", x)?;
-                entity_data.set("y", y)?;
-
-                // println!("Entity '{}' list for scene '{}'", entity_id, name);
-
-                // Add the entity table to the main table
-                lua_table.set(index + 1, entity_data)?;
+                }
             }
 
-            Ok(lua_table)
-        })?;
-
-        self.lua.globals().set("list_entities_name_x_y", list_entities_name_x_y)?;
-
-
-
-
-
-        println!("ECS bindings initialized successfully.");
-        Ok(())
-    }
-
-
-
-    /// Load SceneManager into Lua global space
-    pub fn load_scene_manager(&mut self, scene_manager: &SceneManager) -> Result<(), mlua::Error> {
-        // Serialize SceneManager as Lua userdata
-        self.lua = Lua::new();
-        let globals = self.lua.globals();
-        globals.set("scene_manager", self.lua.to_value(scene_manager)?)?;
-        Ok(())
-    }
-
-    pub fn load_script_state(&self) -> Result<(), mlua::Error> {
-        // Serialize ScriptState as Lua userdata
-        let globals = self.lua.globals();
-        globals.set("script_state", self.lua.to_value(&self.script_state)?)?;
-        Ok(())
-    }
-
-    pub fn update_script_state(&mut self) -> Result<(), String> {
-        // Update the script_state from Lua
-        let globals = self.lua.globals();
-        let script_state_lua: LuaValue = globals
-            .get("script_state")
-            .map_err(|e| format!("Error accessing Lua script_state: {}", e))?;
-
-        let script_state_json = self.lua.from_value(script_state_lua).map_err(|e| {
-            eprintln!("Error converting Lua script_state to JSON: {:?}", e);
-            format!("Failed to convert Lua script_state to JSON: {}", e)
-        })?;
-
-        // Deserialize JSON into ScriptState
-        self.script_state = serde_json::from_value(script_state_json)
-            .map_err(|e| format!("Error deserializing JSON script_state: {}", e))?;
-
-        println!("Script state updated: {:?}", self.script_state);
-
-        Ok(())
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors.join("\n"))
+            }
+        })
     }
 
-    pub fn run_scripts_for_scene(
-        &mut self,
-        scene_manager: &mut SceneManager,
-        active_scene_id: Uuid,
-    ) -> Result<(), String> {
-        let active_scene = scene_manager
-            .get_scene_mut(active_scene_id)
-            .ok_or_else(|| "Active scene not found.".to_string())?;
-
-        for (entity_id, entity) in &mut active_scene.entities {
-            if let Some(script_path) = &entity.script {
-                // println!("Found script for entity {}: {:?}", entity_id, script_path);
-
-                let script_content = std::fs::read_to_string(script_path)
-                    .map_err(|e| format!("Error reading script file for entity {}: {}", entity_id, e))?;
-
-                self.lua
-                    .load(&script_content)
-                    .exec()
-                    .map_err(|e| format!("Error executing script for entity {}: {}", entity_id, e))?;
-
-                let update_function: LuaFunction = self
-                    .lua
-                    .globals()
-                    .get("update")
-                    .map_err(|e| format!("Error: Script for entity {} does not define update(): {}", entity_id, e))?;
+    /// Calls `on_animation_finished(self, clip)` on the scripts of entities whose clip finished playing
+    pub fn handle_animation_events(&mut self, scene_manager: &mut SceneManager, events: &[AnimationFinished]) -> Result<(), String> {
+        self.with_scene_access(scene_manager, |this| {
+            let mut errors = Vec::new();
+            for event in events {
+                if !this.is_script_enabled(&event.entity_id) {
+                    continue;
+                }
+                if let Err(e) = this.call_callback(event.entity_id, "on_animation_finished", event.clip.clone()) {
+                    errors.push(e);
+                }
+            }
 
-                update_function
-                    .call::<()>((active_scene_id.to_string(), entity_id.to_string()))
-                    .map_err(|e| format!("Error executing update() in script for entity {}: {}", entity_id, e))?;
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors.join("\n"))
             }
-        }
+        })
+    }
 
-        if let Err(e) = self.update_script_state() {
-            eprintln!("Error updating script state: {}", e);
-        } else {
-            println!("Script state updated successfully!");
-        }
+    fn collision_table(&self, normal: (f32, f32), impulse: f32) -> LuaResult<LuaTable> {
+        let normal_table = self.lua.create_table()?;
+        normal_table.set("x", normal.0)?;
+        normal_table.set("y", normal.1)?;
 
-        println!("Lua scripts executed successfully for scene {}", active_scene_id);
-        Ok(())
+        let collision = self.lua.create_table()?;
+        collision.set("normal", normal_table)?;
+        collision.set("impulse", impulse)?;
+        Ok(collision)
     }
 
-
     /// Convert JSON Value to Lua Value
     pub fn lua_to_json(&self, lua_value: LuaValue) -> Result<JsonValue, mlua::Error> {
         match lua_value {
@@ -829,67 +1544,10 @@ impl LuaScripting {
     }
 
 
-    pub fn run_entity_scripts(
-        &mut self,
-        scene_manager: &mut SceneManager,
-        active_scene_id: Uuid,
-    ) -> Result<(), String> {
-        let mut scripts_to_run = Vec::new();
-
-        if let Some(active_scene) = scene_manager.get_scene_mut(active_scene_id) {
-            for (entity_id, entity) in &active_scene.entities {
-                if let Some(script_path) = &entity.script {
-                    scripts_to_run.push((entity_id.clone(), script_path.clone()));
-                }
-            }
-        } else {
-            return Err("Active scene not found.".to_string());
-        }
-
-        for (entity_id, script_path) in scripts_to_run {
-            // println!("Found script for entity {}: {:?}", entity_id, script_path);
-
-            let script_content = std::fs::read_to_string(&script_path)
-                .map_err(|e| format!("Error reading script file for entity {}: {}", entity_id, e))?;
-
-            // println!("Executing script for entity {}...", entity_id);
-
-            // Load the Lua script
-            let lua_function = self.lua
-                .load(&script_content)
-                .into_function()
-                .map_err(|e| format!("Error loading script for entity {}: {}", entity_id, e))?;
-
-            self.execute_entity_script(lua_function, scene_manager, active_scene_id, entity_id)?;
-        }
-
-        Ok(())
-    }
-
-    fn execute_entity_script(
-        &self,
-        lua_function: LuaFunction,
-        scene_manager: &mut SceneManager,
-        scene_id: Uuid,
-        entity_id: Uuid,
-    ) -> Result<(), String> {
-        // Serialize the scene_manager to pass to Lua
-        let json_scene_manager =
-            serde_json::to_value(scene_manager).map_err(|e| format!("Serialization error: {}", e))?;
-        let lua_scene_manager = self
-            .json_to_lua(&json_scene_manager)
-            .map_err(|e| format!("Error converting JSON to Lua: {}", e))?;
-
-        // Call the Lua function, passing the scene_id and entity_id as arguments
-        lua_function
-            .call::<()>((lua_scene_manager, scene_id.to_string(), entity_id.to_string()))
-            .map_err(|e| format!("Error executing Lua script: {}", e))
-    }
-
-    pub fn initializing_global_variables(&mut self, input_handler: &InputHandler) {
-        self.update_global_time(0.0);
-        self.load_script_state();
-        self.bind_keys_pressed(input_handler).unwrap();
+    pub fn initializing_global_variables(&mut self, input_handler: &InputHandler) -> Result<(), String> {
+        self.update_global_time(0.0)?;
+        self.load_script_state().map_err(|e| format!("Error loading script state: {}", e))?;
+        self.bind_keys_pressed(input_handler).map_err(|e| format!("Error binding pressed keys: {}", e))
     }
 
     pub fn update_global_time(&mut self, delta_time: f32) -> Result<(), String> {
@@ -4,9 +4,18 @@ mod project_manager;
 use eframe::*;
 mod audio_engine;
 mod ecs;
+mod animation;
+mod text;
+mod tilemap;
+mod joint;
+mod character_controller;
+mod gravity_source;
+mod physics_settings;
+mod scene_format;
 mod input_handler;
 mod physics_engine;
 mod render_engine;
+mod camera;
 mod game_runtime;
 mod lua_scripting;
 mod logger;
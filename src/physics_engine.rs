@@ -1,12 +1,432 @@
 use rapier2d::prelude::*;
+use rapier2d::parry::query::ShapeCastOptions;
+use rapier2d::control::{CharacterAutostep, CharacterCollision, CharacterLength, KinematicCharacterController};
 use uuid::Uuid;
-use std::collections::HashMap;
-use crate::ecs::{Scene, Entity, AttributeValue};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::path::Path;
+use std::num::NonZeroUsize;
+use std::f32::consts::PI;
+use crate::ecs::{Scene, Entity, AttributeType, AttributeValue, BodyType, ColliderShape, Transform};
+use crate::tilemap::Tilemap;
+use crate::joint::{Joint, JointKind, JointMotor};
+use crate::character_controller::{CharacterController, CharacterMovement};
+use crate::physics_settings::PhysicsSettings;
+use crate::gravity_source::GravitySource;
 use image::GenericImageView;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollisionEventKind {
+    Started,
+    Stopped,
+    SensorStarted,
+    SensorStopped,
+}
+
+/// A contact between two entities, reported by `PhysicsEngine::step`.
+/// Entering and leaving a trigger (an `is_trigger` collider) are the sensor kinds.
+/// The normal points from `entity1` towards `entity2`; for stopped and sensor
+/// events there is no contact, so it is zero, as is the impulse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionEvent {
+    pub kind: CollisionEventKind,
+    pub entity1: Uuid,
+    pub entity2: Uuid,
+    pub normal: (f32, f32),
+    pub impulse: f32,
+}
+
+impl CollisionEvent {
+    pub fn is_started(&self) -> bool {
+        matches!(self.kind, CollisionEventKind::Started | CollisionEventKind::SensorStarted)
+    }
+
+    pub fn is_sensor(&self) -> bool {
+        matches!(self.kind, CollisionEventKind::SensorStarted | CollisionEventKind::SensorStopped)
+    }
+}
+
+/// A collider as reported by `PhysicsEngine::get_collider_data`, in world coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColliderData {
+    pub entity_id: Uuid,
+    pub position: (f32, f32), // Center of the collider
+    pub rotation: f32,        // In radians
+    pub size: (f32, f32),     // Width and height of the shape's bounding box, before rotation
+    pub shape: String,        // "Circle", "Rectangle", "Capsule", "ConvexPolygon" or "Polyline"
+    pub points: Vec<(f32, f32)>, // Outline of the shape, empty for circles
+    pub sensor: bool,         // A trigger, which doesn't collide
+}
+
+/// A joint as reported by `PhysicsEngine::get_joint_data`, with its anchors in world coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JointData {
+    pub joint_id: Uuid,
+    pub kind: JointKind,
+    pub entity1: Uuid, // The entity the joint belongs to
+    pub entity2: Uuid, // The entity it connects to
+    pub anchor1: (f32, f32),
+    pub anchor2: (f32, f32),
+}
+
+// Values of the attributes a body is synced with
+#[derive(Debug, Clone, Copy, Default)]
+struct AttributeSnapshot {
+    position: (f32, f32), // The x and y attributes
+    rotation: f32,
+    velocity: (f32, f32),
+    angular_velocity: f32,
+}
+
+impl AttributeSnapshot {
+    // Attributes the entity doesn't have keep their value in `fallback`
+    fn read(entity: &Entity, fallback: &AttributeSnapshot) -> Self {
+        Self {
+            position: entity.local_transform().position,
+            rotation: float_attribute(entity, "rotation").unwrap_or(fallback.rotation),
+            velocity: vector2_attribute(entity, "velocity").unwrap_or(fallback.velocity),
+            angular_velocity: float_attribute(entity, "angular_velocity").unwrap_or(fallback.angular_velocity),
+        }
+    }
+}
+
+// An attribute was edited by a script or the inspector when it differs both from the value the engine
+// read at the last step and from the one it wrote back (which the caller may not have applied)
+#[derive(Debug, Clone, Copy, Default)]
+struct SyncedAttributes {
+    read: AttributeSnapshot,
+    written: AttributeSnapshot,
+}
+
+impl SyncedAttributes {
+    fn edited<T: PartialEq + Copy>(&self, current: &AttributeSnapshot, field: fn(&AttributeSnapshot) -> T) -> Option<T> {
+        let value = field(current);
+        (value != field(&self.read) && value != field(&self.written)).then_some(value)
+    }
+}
+
+// A joint added to the engine, with the entities it connects
+struct JointEntry {
+    handle: ImpulseJointHandle,
+    kind: JointKind,
+    entity1: Uuid,
+    entity2: Uuid,
+}
+
+// An entity moved by `move_and_slide`
+struct CharacterEntry {
+    controller: CharacterController,
+    grounded: bool, // At the end of its last move
+}
+
+// Gap kept between characters and what they slide along, so they don't get stuck in it
+const CHARACTER_OFFSET: f32 = 0.5;
+
+/// Layer of colliders whose entity doesn't name one, the first layer of every project
+pub const DEFAULT_COLLISION_LAYER: &str = "default";
+
+// How quickly joint motors reach their speed, within their max force
+const JOINT_MOTOR_FACTOR: f32 = 100.0;
+
+/// Only the first 32 collision layers of a project can be used, a layer's bit is its index
+pub const MAX_COLLISION_LAYERS: usize = 32;
+
+pub fn default_collision_layers() -> Vec<String> {
+    vec![DEFAULT_COLLISION_LAYER.to_string()]
+}
+
+/// Mask of the layers named in `names`, separated by commas, e.g. "player, enemy".
+/// "all" stands for every layer and "none" for no layer.
+pub fn collision_layer_mask(layers: &[String], names: &str) -> Result<u32, String> {
+    let mut mask = 0;
+    for name in names.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        if name == "all" {
+            mask = u32::MAX;
+            continue;
+        }
+        if name == "none" {
+            continue;
+        }
+        match layers.iter().take(MAX_COLLISION_LAYERS).position(|layer| layer == name) {
+            Some(index) => mask |= 1 << index,
+            None => return Err(format!("Unknown collision layer '{}'", name)),
+        }
+    }
+    Ok(mask)
+}
+
+/// Names of the layers in a mask, the way `collision_layer_mask` reads them
+pub fn format_collision_layers(layers: &[String], mask: u32) -> String {
+    if mask == u32::MAX {
+        return "all".to_string();
+    }
+    if mask == 0 {
+        return "none".to_string();
+    }
+    layers.iter()
+        .take(MAX_COLLISION_LAYERS)
+        .enumerate()
+        .filter(|(index, _)| mask & (1 << index) != 0)
+        .map(|(_, name)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn float_attribute(entity: &Entity, name: &str) -> Option<f32> {
+    match entity.get_attribute_by_name(name).ok()?.value {
+        AttributeValue::Float(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn vector2_attribute(entity: &Entity, name: &str) -> Option<(f32, f32)> {
+    match entity.get_attribute_by_name(name).ok()?.value {
+        AttributeValue::Vector2(x, y) => Some((x, y)),
+        _ => None,
+    }
+}
+
+/// Which colliders a scene query (raycast, overlap, ...) can find.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryFilter {
+    pub layers: u32,         // Mask of the collision layers that can be hit, all of them by default
+    pub exclude: Vec<Uuid>,  // Entities that are never hit, e.g. the one casting the ray
+    pub include_sensors: bool,
+}
+
+impl Default for QueryFilter {
+    fn default() -> Self {
+        Self { layers: u32::MAX, exclude: Vec::new(), include_sensors: false }
+    }
+}
+
+/// A collider found by a raycast or a shape cast, in world coordinates.
+/// The normal points out of the hit collider; `distance` is how far the ray or shape went.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueryHit {
+    pub entity: Uuid,
+    pub point: (f32, f32),
+    pub normal: (f32, f32),
+    pub distance: f32,
+}
+
+/// A shape moved by `PhysicsEngine::shape_cast`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryShape {
+    Circle { radius: f32 },
+    Box { width: f32, height: f32 },
+}
+
+impl QueryShape {
+    fn to_shape(self) -> SharedShape {
+        match self {
+            QueryShape::Circle { radius } => SharedShape::ball(radius),
+            QueryShape::Box { width, height } => SharedShape::cuboid(width / 2.0, height / 2.0),
+        }
+    }
+}
+
+// Alpha value above which a sprite pixel counts as solid when tracing its outline
+const SPRITE_ALPHA_THRESHOLD: u8 = 128;
+
+// Tolerance in pixels when simplifying a traced outline
+const SPRITE_OUTLINE_TOLERANCE: f32 = 1.0;
+
+/// Parses collider points written as `x y, x y, ...` (a `collider_points` attribute).
+pub fn parse_collider_points(text: &str) -> Result<Vec<(f32, f32)>, String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|point| !point.is_empty())
+        .map(|point| {
+            let coords: Vec<&str> = point.split_whitespace().collect();
+            match coords.as_slice() {
+                [x, y] => match (x.parse::<f32>(), y.parse::<f32>()) {
+                    (Ok(x), Ok(y)) => Ok((x, y)),
+                    _ => Err(format!("Invalid collider point '{}'", point)),
+                },
+                _ => Err(format!("Collider point '{}' should be 'x y'", point)),
+            }
+        })
+        .collect()
+}
+
+/// Formats collider points the way `parse_collider_points` reads them.
+pub fn format_collider_points(points: &[(f32, f32)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("{} {}", x, y))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Traces the outline of the solid part of a sprite from its alpha channel.
+/// Returns the simplified outline, relative to the sprite's center.
+/// Only the first shape found scanning from the top is traced.
+pub fn trace_sprite_outline(image_path: &Path) -> Option<Vec<(f32, f32)>> {
+    let image = image::open(image_path).ok()?.to_rgba8();
+    let (width, height) = image.dimensions();
+
+    let solid = |x: i64, y: i64| -> bool {
+        x >= 0 && y >= 0 && x < width as i64 && y < height as i64
+            && image.get_pixel(x as u32, y as u32)[3] >= SPRITE_ALPHA_THRESHOLD
+    };
+
+    // The first solid pixel in raster order has empty space above it
+    let start = (0..height as i64)
+        .flat_map(|y| (0..width as i64).map(move |x| (x, y)))
+        .find(|&(x, y)| solid(x, y))?;
+
+    // Walk the pixel corners around the shape, keeping the solid pixels on the right
+    let mut outline = Vec::new();
+    let (mut x, mut y) = start;
+    let (mut dx, mut dy) = (1i64, 0i64);
+    let max_steps = 4 * (width as usize + 1) * (height as usize + 1);
+    for _ in 0..max_steps {
+        let (prev_dx, prev_dy) = (dx, dy);
+        x += dx;
+        y += dy;
+
+        let tl = solid(x - 1, y - 1);
+        let tr = solid(x, y - 1);
+        let bl = solid(x - 1, y);
+        let br = solid(x, y);
+
+        // Edges leaving this corner with a solid pixel on their right
+        let mut exits = Vec::with_capacity(2);
+        if br && !tr { exits.push((1, 0)); }
+        if bl && !br { exits.push((0, 1)); }
+        if tl && !bl { exits.push((-1, 0)); }
+        if tr && !tl { exits.push((0, -1)); }
+
+        (dx, dy) = match exits.as_slice() {
+            [only] => *only,
+            // On a diagonal saddle, turn right so the same pixel stays on our right
+            [_, _] => (-prev_dy, prev_dx),
+            _ => return None,
+        };
+
+        if (dx, dy) != (prev_dx, prev_dy) {
+            outline.push((x as f32, y as f32));
+        }
+        if (x, y) == start && (dx, dy) == (1, 0) {
+            break;
+        }
+    }
+
+    if outline.len() < 3 {
+        return None;
+    }
+
+    let outline = simplify_closed_outline(&outline, SPRITE_OUTLINE_TOLERANCE);
+    let (half_width, half_height) = (width as f32 / 2.0, height as f32 / 2.0);
+    Some(outline.iter().map(|(x, y)| (x - half_width, y - half_height)).collect())
+}
+
+// Ramer-Douglas-Peucker simplification of a closed outline
+fn simplify_closed_outline(points: &[(f32, f32)], tolerance: f32) -> Vec<(f32, f32)> {
+    fn simplify(points: &[(f32, f32)], tolerance: f32, keep: &mut Vec<bool>, offset: usize) {
+        if points.len() < 3 {
+            return;
+        }
+        let (first, last) = (points[0], points[points.len() - 1]);
+        let (lx, ly) = (last.0 - first.0, last.1 - first.1);
+        let length = (lx * lx + ly * ly).sqrt();
+
+        let distance = |p: &(f32, f32)| {
+            if length == 0.0 {
+                ((p.0 - first.0).powi(2) + (p.1 - first.1).powi(2)).sqrt()
+            } else {
+                ((p.0 - first.0) * ly - (p.1 - first.1) * lx).abs() / length
+            }
+        };
+
+        let (index, max_distance) = points[1..points.len() - 1]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i + 1, distance(p)))
+            .fold((0, 0.0), |best, current| if current.1 > best.1 { current } else { best });
+
+        if max_distance > tolerance {
+            keep[offset + index] = true;
+            simplify(&points[..=index], tolerance, keep, offset);
+            simplify(&points[index..], tolerance, keep, offset + index);
+        }
+    }
+
+    // Close the loop so the last segment back to the start is simplified as well
+    let mut closed = points.to_vec();
+    closed.push(points[0]);
+    let mut keep = vec![false; closed.len()];
+    keep[0] = true;
+
+    // Split at the point farthest from the start so both halves are well defined
+    let farthest = (1..points.len())
+        .max_by(|&a, &b| {
+            let da = (points[a].0 - points[0].0).powi(2) + (points[a].1 - points[0].1).powi(2);
+            let db = (points[b].0 - points[0].0).powi(2) + (points[b].1 - points[0].1).powi(2);
+            da.total_cmp(&db)
+        })
+        .unwrap_or(0);
+    keep[farthest] = true;
+    simplify(&closed[..=farthest], tolerance, &mut keep, 0);
+    simplify(&closed[farthest..], tolerance, &mut keep, farthest);
+
+    points
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(point, _)| *point)
+        .collect()
+}
+
+// Outline of a capsule around the segment a-b, each rounded end approximated by a few points
+fn capsule_outline(a: Point<Real>, b: Point<Real>, radius: Real) -> Vec<Point<Real>> {
+    const ARC_SEGMENTS: usize = 8;
+    let direction = (b - a).try_normalize(1.0e-6).unwrap_or(vector![0.0, 1.0]);
+    let base_angle = direction.y.atan2(direction.x);
+
+    let mut points = Vec::with_capacity(2 * (ARC_SEGMENTS + 1));
+    for (end, start_angle) in [(b, base_angle - std::f32::consts::FRAC_PI_2), (a, base_angle + std::f32::consts::FRAC_PI_2)] {
+        for i in 0..=ARC_SEGMENTS {
+            let angle = start_angle + std::f32::consts::PI * i as f32 / ARC_SEGMENTS as f32;
+            points.push(point![end.x + radius * angle.cos(), end.y + radius * angle.sin()]);
+        }
+    }
+    points
+}
+
+// Collects rapier's collision events during a step, they are turned into
+// CollisionEvents once the step is done and the contact data is available
+#[derive(Default)]
+struct CollisionEventCollector {
+    events: Mutex<Vec<rapier2d::geometry::CollisionEvent>>,
+}
+
+impl EventHandler for CollisionEventCollector {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        event: rapier2d::geometry::CollisionEvent,
+        _contact_pair: Option<&ContactPair>,
+    ) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        _dt: Real,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        _contact_pair: &ContactPair,
+        _total_force_magnitude: Real,
+    ) {
+    }
+}
+
 pub struct PhysicsEngine {
-    // Global gravity force applied to all dynamic bodies
-    gravity: Vector<Real>,
+    // Gravity, time step, solver and sleep settings, usually those of the loaded scene
+    settings: PhysicsSettings,
 
     // Controls physics simulation timing and accuracy
     integration_parameters: IntegrationParameters,
@@ -31,38 +451,61 @@ pub struct PhysicsEngine {
     // Stores and manages all colliders (shapes that define how objects collide)
     collider_set: ColliderSet,
 
-    // For future: Handles physical joints/constraints between bodies
+    // Handles physical joints/constraints between bodies, see `add_joint`
     impulse_joint_set: ImpulseJointSet,
 
     // For future: Handles more complex joint systems
     multibody_joint_set: MultibodyJointSet,
 
-    // For future: Handles continuous collision detection for fast-moving objects
+    // Keeps fast bodies from tunneling through thin colliders, for the bodies
+    // that have CCD enabled by `PhysicsSettings::ccd`
     ccd_solver: CCDSolver,
 
-    // For future: Handles spatial queries like raycasts and shape intersections
+    // Handles spatial queries like raycasts and shape intersections, see `raycast` and the other queries
     query_pipeline: QueryPipeline,
 
     // Maps our entity IDs to Rapier's physics handles
     entity_to_body: HashMap<Uuid, RigidBodyHandle>,
     entity_to_collider: HashMap<Uuid, ColliderHandle>,
 
-    time_step: f32,  // Physics update time step in seconds
+    // Reverse of entity_to_collider, kept until the step after a collider is removed
+    // so its stop events can still be mapped back to the entity
+    collider_to_entity: HashMap<ColliderHandle, Uuid>,
+
+    // Collision events from the last steps, until they are drained
+    collision_events: Vec<CollisionEvent>,
 
-    // Store position attribute IDs for quick updates
-    entity_position_attrs: HashMap<Uuid, Uuid>,
+    // Bodies that move their entity's x and y, all but tilemaps
+    transform_bodies: HashSet<Uuid>,
+
+    // Attribute values of each body at the last step, see `apply_attribute_edits`
+    synced_attributes: HashMap<Uuid, SyncedAttributes>,
+
+    // Body positions before the last step, to interpolate rendering between steps
+    previous_positions: HashMap<Uuid, (f32, f32)>,
+    // Same for the rotations of bodies that can rotate
+    previous_rotations: HashMap<Uuid, f32>,
+
+    // Names of the project's collision layers, see `collision_layer_mask`
+    collision_layers: Vec<String>,
+
+    // Joints by their ID
+    joints: HashMap<Uuid, JointEntry>,
+
+    // Entities with a character controller, see `move_and_slide`
+    characters: HashMap<Uuid, CharacterEntry>,
 }
 
 impl PhysicsEngine {
     pub fn new() -> Self {
-        Self {
-            // Default gravity points downward (-Y direction)
-            gravity: vector![0.0, 50.0],
+        // Default gravity points downward (+Y direction), physics runs at 60Hz
+        let settings = PhysicsSettings::default();
 
-            // Physics runs at 60Hz (60 updates per second)
+        Self {
             integration_parameters: IntegrationParameters {
-                dt: 1.0 / 60.0,
-                min_ccd_dt: 1.0 / 60.0 / 100.0,
+                dt: settings.time_step,
+                min_ccd_dt: settings.time_step / 100.0,
+                num_solver_iterations: NonZeroUsize::new(settings.solver_iterations).unwrap(),
                 contact_damping_ratio: 0.0,
                 contact_natural_frequency: 30.0,
                 joint_natural_frequency: 30.0,
@@ -82,19 +525,55 @@ impl PhysicsEngine {
             query_pipeline: QueryPipeline::new(),
             entity_to_body: HashMap::new(),
             entity_to_collider: HashMap::new(),
-            time_step: 1.0 / 60.0,  // Default 60Hz physics
-            entity_position_attrs: HashMap::new(),
+            collider_to_entity: HashMap::new(),
+            collision_events: Vec::new(),
+            transform_bodies: HashSet::new(),
+            synced_attributes: HashMap::new(),
+            previous_positions: HashMap::new(),
+            previous_rotations: HashMap::new(),
+            collision_layers: default_collision_layers(),
+            joints: HashMap::new(),
+            characters: HashMap::new(),
+            settings,
+        }
+    }
+
+    pub fn settings(&self) -> &PhysicsSettings {
+        &self.settings
+    }
+
+    /// Switches to new physics settings, also updating the CCD and sleep thresholds of
+    /// the existing bodies. The minimum CCD time step follows the time step.
+    pub fn apply_settings(&mut self, settings: &PhysicsSettings) -> Result<(), String> {
+        settings.validate()?;
+        self.integration_parameters.dt = settings.time_step;
+        self.integration_parameters.min_ccd_dt = settings.time_step / 100.0;
+        self.integration_parameters.num_solver_iterations = NonZeroUsize::new(settings.solver_iterations).unwrap();
+        for (_, rb) in self.rigid_body_set.iter_mut() {
+            if rb.is_dynamic() {
+                Self::apply_body_settings(rb, settings);
+            }
         }
+        self.settings = settings.clone();
+        Ok(())
+    }
+
+    fn apply_body_settings(rb: &mut RigidBody, settings: &PhysicsSettings) {
+        rb.enable_ccd(settings.ccd);
+        let activation = rb.activation_mut();
+        activation.normalized_linear_threshold = settings.sleep_linear_threshold;
+        activation.angular_threshold = settings.sleep_angular_threshold;
+        activation.time_until_sleep = settings.time_until_sleep;
     }
 
     // Time step control
     pub fn set_time_step(&mut self, time_step: f32) {
-        self.time_step = time_step;
+        self.settings.time_step = time_step;
         self.integration_parameters.dt = time_step;
     }
 
     pub fn get_time_step(&self) -> f32 {
-        self.time_step
+        self.settings.time_step
     }
 
     // CCD control
@@ -113,39 +592,158 @@ impl PhysicsEngine {
         self.integration_parameters.joint_natural_frequency = frequency;
     }
 
+    // Collision layers, set from the project settings before loading a scene
+    pub fn set_collision_layers(&mut self, layers: Vec<String>) {
+        self.collision_layers = layers;
+    }
+
+    pub fn collision_layers(&self) -> &[String] {
+        &self.collision_layers
+    }
+
+    pub fn layer_mask(&self, names: &str) -> Result<u32, String> {
+        collision_layer_mask(&self.collision_layers, names)
+    }
+
+    // Layers the entity's collider is in (`collision_layer`, the default layer if missing or empty)
+    // and layers it collides with (`collision_mask`, all if missing or empty). Two colliders only touch when
+    // each one is in a layer the other collides with.
+    fn interaction_groups(&self, entity: &Entity) -> InteractionGroups {
+        let mask = |attribute: &str, default: u32| match entity.get_attribute_by_name(attribute).map(|attr| &attr.value) {
+            Ok(AttributeValue::String(names)) if !names.trim().is_empty() => self.layer_mask(names).unwrap_or_else(|e| {
+                eprintln!("{} on entity '{}'", e, entity.name);
+                default
+            }),
+            _ => default,
+        };
+        InteractionGroups::new(
+            Group::from_bits_truncate(mask("collision_layer", 1)),
+            Group::from_bits_truncate(mask("collision_mask", u32::MAX)),
+        )
+    }
+
+    // Builds the collider described by the entity's collider attributes. Missing attributes
+    // give a box fitted to the sprite, the center is the sprite's center plus `collider_offset`.
     fn create_collider(&self, entity: &Entity, density: f32, friction: f32, restitution: f32) -> Collider {
-        // Get first image path from entity (assuming first image is the sprite)
-        let collider_builder = if let Ok(image_path) = entity.get_image(0) {
-            // Get image dimensions
-            if let Ok(img) = image::open(image_path) {
+        let shape = match entity.get_attribute_by_name("collider_shape").map(|attr| attr.value.clone()) {
+            Ok(AttributeValue::String(name)) => ColliderShape::from_name(&name).unwrap_or_else(|| {
+                eprintln!("Unknown collider shape '{}' on entity '{}', using a box", name, entity.name);
+                ColliderShape::Box
+            }),
+            _ => ColliderShape::Box,
+        };
+
+        let vector2_attribute = |name: &str| match entity.get_attribute_by_name(name).map(|attr| attr.value.clone()) {
+            Ok(AttributeValue::Vector2(x, y)) => Some((x, y)),
+            _ => None,
+        };
+
+        // Sprite size, the first image is the sprite
+        let image_path = entity.get_image(0).ok();
+        let sprite_size = image_path.as_ref()
+            .and_then(|path| image::open(path).ok())
+            .map(|img| {
                 let (width, height) = img.dimensions();
+                (width as f32, height as f32)
+            });
 
-                let offset = vector![width as f32 / 2.0, height as f32 / 2.0];
+        let (width, height) = match vector2_attribute("collider_size") {
+            Some((w, h)) if w > 0.0 && h > 0.0 => (w, h),
+            _ => sprite_size.unwrap_or((1.0, 1.0)),
+        };
+        let offset = vector2_attribute("collider_offset").unwrap_or((0.0, 0.0));
+        let center = match sprite_size {
+            Some((sprite_width, sprite_height)) => vector![sprite_width / 2.0 + offset.0, sprite_height / 2.0 + offset.1],
+            None => vector![offset.0, offset.1],
+        };
 
-                // If width and height are similar, use circle
-                if (width as f32 / height as f32).abs() > 0.9
-                   && (width as f32 / height as f32).abs() < 1.1 {
-                    ColliderBuilder::ball(width as f32 / 2.0).translation(offset)
+        let collider_builder = match shape {
+            ColliderShape::Box => ColliderBuilder::cuboid(width / 2.0, height / 2.0),
+            ColliderShape::Circle => ColliderBuilder::ball(width.min(height) / 2.0),
+            ColliderShape::Capsule => {
+                if height >= width {
+                    ColliderBuilder::capsule_y((height - width) / 2.0, width / 2.0)
                 } else {
-                    // Otherwise use box
-                    ColliderBuilder::cuboid(width as f32 / 2.0, height as f32 / 2.0).translation(offset)
+                    ColliderBuilder::capsule_x((width - height) / 2.0, height / 2.0)
                 }
-            } else {
-                ColliderBuilder::ball(0.5) // Default if can't load image
             }
-        } else {
-            ColliderBuilder::ball(0.5) // Default if no image
+            ColliderShape::ConvexPolygon | ColliderShape::Polyline => {
+                let points = self.collider_points(entity, image_path.map(|path| path.as_path()), (width, height), sprite_size);
+                let polygon = if shape == ColliderShape::ConvexPolygon {
+                    if points.len() >= 3 { ColliderBuilder::convex_hull(&points) } else { None }
+                } else if points.len() >= 2 {
+                    Some(ColliderBuilder::polyline(points, None))
+                } else {
+                    None
+                };
+                polygon.unwrap_or_else(|| {
+                    eprintln!("Entity '{}' has no usable collider points, using a box", entity.name);
+                    ColliderBuilder::cuboid(width / 2.0, height / 2.0)
+                })
+            }
         };
 
+        // Triggers report overlaps as sensor events instead of colliding
+        let is_trigger = matches!(
+            entity.get_attribute_by_name("is_trigger").map(|attr| &attr.value),
+            Ok(AttributeValue::Boolean(true))
+        );
+
         // Add physics properties
         collider_builder
+            .translation(center)
             .density(density)
             .friction(friction)
             .restitution(restitution)
+            .sensor(is_trigger)
+            .collision_groups(self.interaction_groups(entity))
+            .active_events(ActiveEvents::COLLISION_EVENTS)
+            .user_data(entity.id.as_u128())
             .build()
     }
 
+    // Points of a polygon or polyline collider, relative to the collider's center. They come from
+    // `collider_points`, or are traced from the sprite's alpha and scaled to `collider_size` when empty.
+    fn collider_points(&self, entity: &Entity, image_path: Option<&Path>, size: (f32, f32), sprite_size: Option<(f32, f32)>) -> Vec<Point<Real>> {
+        if let Ok(AttributeValue::String(text)) = entity.get_attribute_by_name("collider_points").map(|attr| attr.value.clone()) {
+            if !text.trim().is_empty() {
+                return match parse_collider_points(&text) {
+                    Ok(points) => points.into_iter().map(|(x, y)| point![x, y]).collect(),
+                    Err(e) => {
+                        eprintln!("Entity '{}': {}", entity.name, e);
+                        Vec::new()
+                    }
+                };
+            }
+        }
+
+        let Some(outline) = image_path.and_then(trace_sprite_outline) else {
+            return Vec::new();
+        };
+        let (scale_x, scale_y) = match sprite_size {
+            Some((sprite_width, sprite_height)) => (size.0 / sprite_width, size.1 / sprite_height),
+            None => (1.0, 1.0),
+        };
+        let mut points: Vec<Point<Real>> = outline.iter().map(|(x, y)| point![x * scale_x, y * scale_y]).collect();
+        // A traced outline is a closed loop
+        if let Some(first) = points.first().copied() {
+            points.push(first);
+        }
+        points
+    }
+
     pub fn add_entity(&mut self, entity: &Entity) {
+        self.add_entity_under(entity, None);
+        self.query_pipeline.update(&self.collider_set);
+    }
+
+    /// Adds an entity whose position is relative to a parent with the given world transform.
+    /// Bodies are simulated in world space.
+    pub fn add_entity_under(&mut self, entity: &Entity, parent_world: Option<&Transform>) {
+        if let Some(tilemap) = entity.tilemap.as_deref() {
+            self.add_tilemap(entity, tilemap, parent_world);
+            return;
+        }
 
         let required_attributes = ["has_gravity", "has_collision", "creates_gravity"];
         let should_skip = required_attributes.iter().all(|attr_name| entity.get_attribute_by_name(attr_name).is_err());
@@ -155,21 +753,19 @@ impl PhysicsEngine {
             return;
         }
 
-        // Store position attribute ID for quick updates
-        if let Ok(pos_attr) = entity.get_attribute_by_name("position") {
-            self.entity_position_attrs.insert(entity.id, pos_attr.id);
-        }
+        self.transform_bodies.insert(entity.id);
 
-        // Get physics properties from entity attributes
-        let position = if let Ok(pos_attr) = entity.get_attribute_by_name("position") {
-            if let AttributeValue::Vector2(x, y) = pos_attr.value {
-                vector![x, y]
-            } else {
-                vector![0.0, 0.0]
-            }
-        } else {
-            vector![0.0, 0.0]
+        // The body starts where the entity is, scale only applies to rendering
+        let local = entity.local_transform();
+        let world_position = match parent_world {
+            Some(parent_world) => Transform::new()
+                .with_position(local.position.0, local.position.1)
+                .combine(parent_world)
+                .position,
+            None => local.position,
         };
+        let position = vector![world_position.0, world_position.1];
+        let rotation = local.rotation + parent_world.map_or(0.0, |parent| parent.rotation);
 
         let is_movable = entity.get_attribute_by_name("is_movable")
             .and_then(|attr| if let AttributeValue::Boolean(v) = attr.value { Ok(v) } else { Err("Attribute value is not a boolean".to_string()) })
@@ -199,21 +795,56 @@ impl PhysicsEngine {
             .and_then(|attr| if let AttributeValue::Boolean(v) = attr.value { Ok(v) } else { Err("Attribute value is not a boolean".to_string()) })
             .unwrap_or(false);
 
+        let snapshot = AttributeSnapshot::read(entity, &AttributeSnapshot::default());
+        let (velocity, angular_velocity) = (snapshot.velocity, snapshot.angular_velocity);
+        self.synced_attributes.insert(entity.id, SyncedAttributes { read: snapshot, written: snapshot });
+
+        // Characters are moved by `move_and_slide`, which needs a kinematic body
+        let body_type = match (&entity.character_controller, entity.get_attribute_by_name("body_type").map(|attr| &attr.value)) {
+            (Some(_), _) => BodyType::KinematicPosition,
+            (None, Ok(AttributeValue::String(name))) if !name.trim().is_empty() => BodyType::from_name(name).unwrap_or_else(|| {
+                eprintln!("Unknown body type '{}' on entity '{}', using is_movable", name, entity.name);
+                if is_movable { BodyType::Dynamic } else { BodyType::Fixed }
+            }),
+            _ if is_movable => BodyType::Dynamic,
+            _ => BodyType::Fixed,
+        };
+        if let Some(controller) = &entity.character_controller {
+            self.characters.insert(entity.id, CharacterEntry { controller: controller.clone(), grounded: false });
+        }
+
         // Create rigid body
-        let rigid_body = if is_movable {
-            let mut rb = RigidBodyBuilder::dynamic()
-                .translation(position)
-                .gravity_scale(if affected_by_gravity { 1.0 } else { 0.0 });
+        let rigid_body = match body_type {
+            BodyType::Dynamic => {
+                let mut rb = RigidBodyBuilder::dynamic()
+                    .translation(position)
+                    .rotation(rotation)
+                    .linvel(vector![velocity.0, velocity.1])
+                    .angvel(angular_velocity)
+                    .gravity_scale(if affected_by_gravity { 1.0 } else { 0.0 });
+
+                if !can_rotate {
+                    rb = rb.lock_rotations();
+                }
 
-            if !can_rotate {
-                rb = rb.lock_rotations();
+                let mut rb = rb.build();
+                Self::apply_body_settings(&mut rb, &self.settings);
+                rb
             }
-
-            rb.build()
-        } else {
-            RigidBodyBuilder::fixed()
+            BodyType::KinematicVelocity => RigidBodyBuilder::kinematic_velocity_based()
+                .translation(position)
+                .rotation(rotation)
+                .linvel(vector![velocity.0, velocity.1])
+                .angvel(angular_velocity)
+                .build(),
+            BodyType::KinematicPosition => RigidBodyBuilder::kinematic_position_based()
+                .translation(position)
+                .rotation(rotation)
+                .build(),
+            BodyType::Fixed => RigidBodyBuilder::fixed()
                 .translation(position)
-                .build()
+                .rotation(rotation)
+                .build(),
         };
 
         let rb_handle = self.rigid_body_set.insert(rigid_body);
@@ -224,13 +855,61 @@ impl PhysicsEngine {
             let collider_handle = self.collider_set
                 .insert_with_parent(collider, rb_handle, &mut self.rigid_body_set);
             self.entity_to_collider.insert(entity.id, collider_handle);
+            self.collider_to_entity.insert(collider_handle, entity.id);
         }
 
         self.entity_to_body.insert(entity.id, rb_handle);
     }
 
+    // A tilemap is a fixed body with one collider, made of a rectangle per group of solid tiles
+    fn add_tilemap(&mut self, entity: &Entity, tilemap: &Tilemap, parent_world: Option<&Transform>) {
+        let rects = tilemap.solid_rects();
+        if rects.is_empty() {
+            return;
+        }
+        let transform = match parent_world {
+            Some(parent_world) => entity.local_transform().combine(parent_world),
+            None => entity.local_transform(),
+        };
+        let tile_size = (tilemap.tile_size.0 * transform.scale.0, tilemap.tile_size.1 * transform.scale.1);
+        let shapes: Vec<(Isometry<Real>, SharedShape)> = rects.iter()
+            .map(|&(x, y, width, height)| {
+                let (half_width, half_height) = (width as f32 * tile_size.0 / 2.0, height as f32 * tile_size.1 / 2.0);
+                let center = vector![x as f32 * tile_size.0 + half_width, y as f32 * tile_size.1 + half_height];
+                (Isometry::translation(center.x, center.y), SharedShape::cuboid(half_width, half_height))
+            })
+            .collect();
+
+        let friction = match entity.get_attribute_by_name("friction").map(|attr| &attr.value) {
+            Ok(AttributeValue::Float(friction)) => *friction,
+            _ => 0.5,
+        };
+        let rigid_body = RigidBodyBuilder::fixed()
+            .translation(vector![transform.position.0, transform.position.1])
+            .rotation(transform.rotation)
+            .build();
+        let collider = ColliderBuilder::compound(shapes)
+            .friction(friction)
+            .collision_groups(self.interaction_groups(entity))
+            .active_events(ActiveEvents::COLLISION_EVENTS)
+            .user_data(entity.id.as_u128())
+            .build();
+
+        let rb_handle = self.rigid_body_set.insert(rigid_body);
+        let collider_handle = self.collider_set.insert_with_parent(collider, rb_handle, &mut self.rigid_body_set);
+        self.entity_to_collider.insert(entity.id, collider_handle);
+        self.collider_to_entity.insert(collider_handle, entity.id);
+        self.entity_to_body.insert(entity.id, rb_handle);
+    }
+
     pub fn remove_entity(&mut self, entity_id: Uuid) {
-        self.entity_position_attrs.remove(&entity_id);
+        // Rapier removes the joints attached to the body with it
+        self.joints.retain(|_, joint| joint.entity1 != entity_id && joint.entity2 != entity_id);
+        self.transform_bodies.remove(&entity_id);
+        self.synced_attributes.remove(&entity_id);
+        self.characters.remove(&entity_id);
+        self.previous_positions.remove(&entity_id);
+        self.previous_rotations.remove(&entity_id);
         if let Some(rb_handle) = self.entity_to_body.remove(&entity_id) {
             self.rigid_body_set.remove(
                 rb_handle,
@@ -252,50 +931,163 @@ impl PhysicsEngine {
         }
     }
 
-    pub fn step(&mut self, scene: &mut Scene) -> Vec<(Uuid, Uuid, AttributeValue)> {
-        // Process custom gravity fields
-        for (_, entity1) in &scene.entities {
-            if let Ok(creates_gravity) = entity1.get_attribute_by_name("creates_gravity") {
-                if let AttributeValue::Boolean(true) = creates_gravity.value {
-                    let pos1 = if let Ok(pos) = entity1.get_attribute_by_name("position") {
-                        if let AttributeValue::Vector2(x, y) = pos.value {
-                            vector![x, y]
-                        } else {
-                            continue;
-                        }
-                    } else {
-                        continue;
-                    };
-
-                    // Apply gravity to other entities
-                    for (_, entity2) in &scene.entities {
-                        if entity1.id == entity2.id {
-                            continue;
-                        }
-
-                        if let Ok(affected_by_gravity) = entity2.get_attribute_by_name("has_gravity") {
-                            if let AttributeValue::Boolean(true) = affected_by_gravity.value {
-                                if let Some(rb_handle) = self.entity_to_body.get(&entity2.id) {
-                                    if let Some(rb) = self.rigid_body_set.get_mut(*rb_handle) {
-                                        let pos2 = rb.translation();
-                                        let direction = pos1 - pos2;
-                                        let distance = direction.norm();
-                                        if distance > 0.0 {
-                                            let force = direction * (1.0 / (distance * distance));
-                                            rb.add_force(force * 10.0, true);  // Scale force as needed
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+    /// Removes the bodies of entities that are no longer in the scene,
+    /// e.g. children deleted together with their parent
+    pub fn remove_missing_entities(&mut self, scene: &Scene) {
+        let missing: Vec<Uuid> = self.entity_to_body.keys()
+            .filter(|entity_id| !scene.entities.contains_key(*entity_id))
+            .copied()
+            .collect();
+        for entity_id in missing {
+            self.remove_entity(entity_id);
+        }
+    }
+
+    /// Connects the body of `entity_id` to the body of `joint.other`, replacing the joint with the same ID.
+    /// Both entities must have been added before.
+    pub fn add_joint(&mut self, entity_id: Uuid, joint: &Joint) -> Result<(), String> {
+        let body1 = *self.entity_to_body.get(&entity_id)
+            .ok_or_else(|| format!("Entity '{}' has no physics body for joint {}", entity_id, joint.id))?;
+        let body2 = *self.entity_to_body.get(&joint.other)
+            .ok_or_else(|| format!("Entity '{}' has no physics body for joint {}", joint.other, joint.id))?;
+        if body1 == body2 {
+            return Err(format!("Joint {} connects an entity to itself", joint.id));
+        }
+        if joint.kind.has_length() && joint.length <= 0.0 {
+            return Err(format!("Joint {} needs a length greater than 0", joint.id));
+        }
+
+        self.remove_joint(joint.id);
+        let handle = self.impulse_joint_set.insert(body1, body2, Self::generic_joint(joint), true);
+        self.joints.insert(joint.id, JointEntry { handle, kind: joint.kind, entity1: entity_id, entity2: joint.other });
+        Ok(())
+    }
+
+    /// Returns whether there was a joint with this ID
+    pub fn remove_joint(&mut self, joint_id: Uuid) -> bool {
+        match self.joints.remove(&joint_id) {
+            Some(entry) => {
+                self.impulse_joint_set.remove(entry.handle, true);
+                true
             }
+            None => false,
         }
+    }
+
+    pub fn has_joint(&self, joint_id: &Uuid) -> bool {
+        self.joints.contains_key(joint_id)
+    }
+
+    /// Starts, changes or (with None) stops the motor of a revolute or prismatic joint
+    pub fn set_joint_motor(&mut self, joint_id: Uuid, motor: Option<JointMotor>) -> Result<(), String> {
+        let entry = self.joints.get(&joint_id).ok_or_else(|| format!("Joint {} not found", joint_id))?;
+        if !entry.kind.has_motor() {
+            return Err(format!("A {} joint has no motor", entry.kind.as_str()));
+        }
+        let axis = if entry.kind == JointKind::Revolute { JointAxis::AngX } else { JointAxis::LinX };
+        let joint = self.impulse_joint_set.get_mut(entry.handle)
+            .ok_or_else(|| format!("Joint {} not found", joint_id))?;
+        let motor = motor.unwrap_or(JointMotor { speed: 0.0, max_force: 0.0 });
+        joint.data.set_motor_velocity(axis, motor.speed, JOINT_MOTOR_FACTOR);
+        joint.data.set_motor_max_force(axis, motor.max_force);
+
+        // Sleeping bodies wouldn't feel the new motor
+        for body in [joint.body1, joint.body2] {
+            if let Some(body) = self.rigid_body_set.get_mut(body) {
+                body.wake_up(true);
+            }
+        }
+        Ok(())
+    }
+
+    // Rapier's description of a joint, anchors are in the local space of each body
+    fn generic_joint(joint: &Joint) -> GenericJoint {
+        let anchor1 = point![joint.anchor.0, joint.anchor.1];
+        let anchor2 = point![joint.other_anchor.0, joint.other_anchor.1];
+        let mut generic: GenericJoint = match joint.kind {
+            JointKind::Fixed => FixedJointBuilder::new()
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            JointKind::Revolute => {
+                let mut builder = RevoluteJointBuilder::new().local_anchor1(anchor1).local_anchor2(anchor2);
+                if let Some((min, max)) = joint.limits {
+                    builder = builder.limits([min, max]);
+                }
+                if let Some(motor) = joint.motor {
+                    builder = builder.motor_velocity(motor.speed, JOINT_MOTOR_FACTOR).motor_max_force(motor.max_force);
+                }
+                builder.into()
+            }
+            JointKind::Prismatic => {
+                let axis = UnitVector::try_new(vector![joint.axis.0, joint.axis.1], 1.0e-6).unwrap_or(Vector::x_axis());
+                let mut builder = PrismaticJointBuilder::new(axis).local_anchor1(anchor1).local_anchor2(anchor2);
+                if let Some((min, max)) = joint.limits {
+                    builder = builder.limits([min, max]);
+                }
+                if let Some(motor) = joint.motor {
+                    builder = builder.motor_velocity(motor.speed, JOINT_MOTOR_FACTOR).motor_max_force(motor.max_force);
+                }
+                builder.into()
+            }
+            JointKind::Rope => RopeJointBuilder::new(joint.length)
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            // A rope that can't get shorter either
+            JointKind::Distance => {
+                let mut generic: GenericJoint = RopeJointBuilder::new(joint.length)
+                    .local_anchor1(anchor1)
+                    .local_anchor2(anchor2)
+                    .into();
+                generic.set_limits(JointAxis::LinX, [joint.length, joint.length]);
+                generic
+            }
+            JointKind::Spring => SpringJointBuilder::new(joint.length, joint.stiffness, joint.damping)
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+        };
+        generic.set_contacts_enabled(joint.collide_connected);
+        generic
+    }
+
+    /// The joints with their anchors in world coordinates, for the debug overlay
+    pub fn get_joint_data(&self) -> Vec<JointData> {
+        self.joints.iter()
+            .filter_map(|(joint_id, entry)| {
+                let joint = self.impulse_joint_set.get(entry.handle)?;
+                let body1 = self.rigid_body_set.get(joint.body1)?;
+                let body2 = self.rigid_body_set.get(joint.body2)?;
+                let anchor1 = body1.position() * joint.data.local_anchor1();
+                let anchor2 = body2.position() * joint.data.local_anchor2();
+                Some(JointData {
+                    joint_id: *joint_id,
+                    kind: entry.kind,
+                    entity1: entry.entity1,
+                    entity2: entry.entity2,
+                    anchor1: (anchor1.x, anchor1.y),
+                    anchor2: (anchor2.x, anchor2.y),
+                })
+            })
+            .collect()
+    }
+
+    pub fn step(&mut self, scene: &mut Scene) -> Vec<(Uuid, Uuid, AttributeValue)> {
+        // Attributes edited since the last step move the bodies
+        self.apply_attribute_edits(scene);
+
+        // Gravity sources pull the bodies around them
+        self.apply_gravity_sources(scene);
+
+        // Remember where the bodies were, for interpolated_positions and interpolated_rotations
+        self.previous_positions = self.body_positions();
+        self.previous_rotations = self.body_rotations();
 
         // Run physics simulation
+        let event_collector = CollisionEventCollector::default();
         self.physics_pipeline.step(
-            &self.gravity,
+            &vector![self.settings.gravity.0, self.settings.gravity.1],
             &self.integration_parameters,
             &mut self.island_manager,
             &mut self.broad_phase,
@@ -307,42 +1099,71 @@ impl PhysicsEngine {
             &mut self.ccd_solver,
             Some(&mut self.query_pipeline),
             &(),
-            &(),
+            &event_collector,
         );
 
-        // Update positions using stored attribute IDs
+        let raw_events = event_collector.events.into_inner().unwrap();
+        self.record_collision_events(raw_events);
+
+        // Write the bodies back to the entities' transform attributes
         let mut updates = Vec::new();
 
+        // Bodies are in world space, the attributes of child entities are relative to their parent
+        let body_positions = self.body_positions();
+        let body_rotations = self.body_rotations();
+        let world_transforms = scene.world_transforms_with(&body_positions, &body_rotations);
+        let mut missing_rotations = Vec::new();
+
         for (entity_id, rb_handle) in &self.entity_to_body {
-            if let Some(rb) = self.rigid_body_set.get(*rb_handle) {
-                if let Some(pos_attr_id) = self.entity_position_attrs.get(entity_id) {
-                    let position = rb.translation();
-                    // println!("position: {:?}", position);
-                    updates.push((
-                        *entity_id,
-                        *pos_attr_id,
-                        AttributeValue::Vector2(position.x, position.y)
-                    ));
-
-                    // Also update the entity's x and y, these are used to render in the view
-                    if let Some(entity) = scene.entities.get(entity_id) {
-                        if let Ok(x_attr) = entity.get_attribute_by_name("x") {
-                            updates.push((
-                                *entity_id,
-                                x_attr.id,
-                                AttributeValue::Float(position.x),
-                            ));
-                        }
-
-                        if let Ok(y_attr) = entity.get_attribute_by_name("y") {
-                            updates.push((
-                                *entity_id,
-                                y_attr.id,
-                                AttributeValue::Float(position.y),
-                            ));
-                        }
-                    }
+            let (Some(rb), Some(entity)) = (self.rigid_body_set.get(*rb_handle), scene.entities.get(entity_id)) else {
+                continue;
+            };
+            let synced = self.synced_attributes.entry(*entity_id).or_default();
+            let parent_world = scene.parent_of(*entity_id).and_then(|parent| world_transforms.get(&parent));
+            let local = |world: Transform| match parent_world {
+                Some(parent_world) => world.relative_to(parent_world),
+                None => world,
+            };
+
+            if self.transform_bodies.contains(entity_id) {
+                let (x, y) = local(Transform::new().with_position(rb.translation().x, rb.translation().y)).position;
+                if let Ok(x_attr) = entity.get_attribute_by_name("x") {
+                    updates.push((*entity_id, x_attr.id, AttributeValue::Float(x)));
+                }
+                if let Ok(y_attr) = entity.get_attribute_by_name("y") {
+                    updates.push((*entity_id, y_attr.id, AttributeValue::Float(y)));
+                }
+                synced.written.position = (x, y);
+            }
+
+            // Only bodies that can rotate turn, the others keep their entity's rotation
+            if body_rotations.contains_key(entity_id) {
+                let rotation = local(Transform::new().with_rotation(rb.rotation().angle())).rotation;
+                match entity.get_attribute_by_name("rotation") {
+                    Ok(rotation_attr) => updates.push((*entity_id, rotation_attr.id, AttributeValue::Float(rotation))),
+                    Err(_) if rotation != 0.0 => missing_rotations.push((*entity_id, rotation)),
+                    Err(_) => {}
+                }
+                synced.written.rotation = rotation;
+            }
+
+            // Velocities are in world space, also for child entities
+            if let Ok(velocity_attr) = entity.get_attribute_by_name("velocity") {
+                let velocity = (rb.linvel().x, rb.linvel().y);
+                updates.push((*entity_id, velocity_attr.id, AttributeValue::Vector2(velocity.0, velocity.1)));
+                synced.written.velocity = velocity;
+            }
+            if let Ok(angular_velocity_attr) = entity.get_attribute_by_name("angular_velocity") {
+                updates.push((*entity_id, angular_velocity_attr.id, AttributeValue::Float(rb.angvel())));
+                synced.written.angular_velocity = rb.angvel();
+            }
+        }
 
+        // Like Entity::set_local_transform, the rotation attribute is created once an entity turns
+        for (entity_id, rotation) in missing_rotations {
+            if let Some(entity) = scene.entities.get_mut(&entity_id) {
+                if let Err(err) = entity.create_attribute("rotation", AttributeType::Float, AttributeValue::Float(rotation)) {
+                    eprintln!("Failed to add rotation to entity {}: {}", entity_id, err);
                 }
             }
         }
@@ -350,10 +1171,238 @@ impl PhysicsEngine {
         updates
     }
 
+    /// Moves, turns and sets the velocity of the bodies whose `x` and `y`, `rotation`,
+    /// `velocity` or `angular_velocity` attribute changed since the last step, e.g. set by a script.
+    fn apply_attribute_edits(&mut self, scene: &Scene) {
+        let world_transforms = scene.world_transforms_with(&self.body_positions(), &self.body_rotations());
+
+        for (entity_id, synced) in self.synced_attributes.iter_mut() {
+            let Some(entity) = scene.entities.get(entity_id) else {
+                continue;
+            };
+            let Some(rb) = self.entity_to_body.get(entity_id).and_then(|rb_handle| self.rigid_body_set.get_mut(*rb_handle)) else {
+                continue;
+            };
+
+            let current = AttributeSnapshot::read(entity, &synced.read);
+
+            let position = synced.edited(&current, |snapshot| snapshot.position)
+                .filter(|_| self.transform_bodies.contains(entity_id));
+            let rotation = synced.edited(&current, |snapshot| snapshot.rotation);
+            if position.is_some() || rotation.is_some() {
+                let (x, y) = position.unwrap_or(current.position);
+                let local = Transform::new().with_position(x, y).with_rotation(current.rotation);
+                let world = match scene.parent_of(*entity_id).and_then(|parent| world_transforms.get(&parent)) {
+                    Some(parent_world) => local.combine(parent_world),
+                    None => local,
+                };
+                if position.is_some() {
+                    rb.set_translation(vector![world.position.0, world.position.1], true);
+                }
+                if rotation.is_some() {
+                    rb.set_rotation(Rotation::new(world.rotation), true);
+                }
+            }
+
+            if let Some(velocity) = synced.edited(&current, |snapshot| snapshot.velocity) {
+                rb.set_linvel(vector![velocity.0, velocity.1], true);
+            }
+            if let Some(angular_velocity) = synced.edited(&current, |snapshot| snapshot.angular_velocity) {
+                rb.set_angvel(angular_velocity, true);
+            }
+            synced.read = current;
+        }
+    }
+
+    // Positions of the bodies that move their entity, see `transform_bodies`
+    fn body_positions(&self) -> HashMap<Uuid, (f32, f32)> {
+        self.transform_bodies.iter()
+            .filter_map(|entity_id| {
+                let rb = self.rigid_body_set.get(*self.entity_to_body.get(entity_id)?)?;
+                Some((*entity_id, (rb.translation().x, rb.translation().y)))
+            })
+            .collect()
+    }
+
+    // Rotations of the dynamic bodies whose rotation isn't locked, the others keep their entity's rotation
+    fn body_rotations(&self) -> HashMap<Uuid, f32> {
+        self.entity_to_body.iter()
+            .filter_map(|(entity_id, rb_handle)| {
+                let rb = self.rigid_body_set.get(*rb_handle)?;
+                let can_rotate = !rb.is_fixed() && !rb.locked_axes().contains(LockedAxes::ROTATION_LOCKED);
+                can_rotate.then(|| (*entity_id, rb.rotation().angle()))
+            })
+            .collect()
+    }
+
+    /// Body positions blended between the previous and the last step, `alpha` being
+    /// how far the renderer is into the next step (0 is the previous step, 1 the last one).
+    /// Bodies added since the last step are at their current position.
+    pub fn interpolated_positions(&self, alpha: f32) -> HashMap<Uuid, (f32, f32)> {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let mut positions = self.body_positions();
+        for (entity_id, (x, y)) in positions.iter_mut() {
+            if let Some(&(prev_x, prev_y)) = self.previous_positions.get(entity_id) {
+                *x = prev_x + (*x - prev_x) * alpha;
+                *y = prev_y + (*y - prev_y) * alpha;
+            }
+        }
+        positions
+    }
+
+    /// Rotations of the bodies that can rotate, blended like `interpolated_positions`
+    /// the short way around
+    pub fn interpolated_rotations(&self, alpha: f32) -> HashMap<Uuid, f32> {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let mut rotations = self.body_rotations();
+        for (entity_id, angle) in rotations.iter_mut() {
+            if let Some(&previous) = self.previous_rotations.get(entity_id) {
+                let delta = (*angle - previous + PI).rem_euclid(2.0 * PI) - PI;
+                *angle = previous + delta * alpha;
+            }
+        }
+        rotations
+    }
+
+    fn record_collision_events(&mut self, raw_events: Vec<rapier2d::geometry::CollisionEvent>) {
+        for raw_event in raw_events {
+            let (handle1, handle2) = (raw_event.collider1(), raw_event.collider2());
+            let (Some(&entity1), Some(&entity2)) = (
+                self.collider_to_entity.get(&handle1),
+                self.collider_to_entity.get(&handle2),
+            ) else {
+                continue;
+            };
+
+            let kind = match (raw_event.started(), raw_event.sensor()) {
+                (true, false) => CollisionEventKind::Started,
+                (false, false) => CollisionEventKind::Stopped,
+                (true, true) => CollisionEventKind::SensorStarted,
+                (false, true) => CollisionEventKind::SensorStopped,
+            };
+
+            // Take the normal from the manifold with the most contact points, and the total impulse of the pair
+            let mut normal = (0.0, 0.0);
+            let mut impulse = 0.0;
+            if kind == CollisionEventKind::Started {
+                if let Some(pair) = self.narrow_phase.contact_pair(handle1, handle2) {
+                    let flip = pair.collider1 != handle1;
+                    if let Some(manifold) = pair.manifolds.iter().max_by_key(|m| m.points.len()) {
+                        let n = if flip { -manifold.data.normal } else { manifold.data.normal };
+                        normal = (n.x, n.y);
+                    }
+                    impulse = pair
+                        .manifolds
+                        .iter()
+                        .flat_map(|m| m.points.iter())
+                        .map(|point| point.data.impulse)
+                        .sum();
+                }
+            }
+
+            self.collision_events.push(CollisionEvent { kind, entity1, entity2, normal, impulse });
+        }
+
+        // Forget colliders that have been removed, now that their stop events are out
+        let collider_set = &self.collider_set;
+        self.collider_to_entity.retain(|handle, _| collider_set.contains(*handle));
+    }
+
+    // Changes the velocity of the bodies pulled by gravity sources by one step of their pull.
+    // Each source finds the bodies within its radius with a circle query instead of going through all of them.
+    fn apply_gravity_sources(&mut self, scene: &Scene) {
+        let sources: Vec<(&Entity, GravitySource)> = scene.entities.values()
+            .filter_map(|entity| match (&entity.gravity_source, entity.get_attribute_by_name("creates_gravity").map(|attr| &attr.value)) {
+                (Some(source), _) => Some((entity, source.clone())),
+                (None, Ok(AttributeValue::Boolean(true))) => Some((entity, GravitySource::default())),
+                _ => None,
+            })
+            .collect();
+        if sources.is_empty() {
+            return;
+        }
+        // The query only finds colliders, bodies without one are checked by distance
+        let colliderless: Vec<(Uuid, RigidBodyHandle)> = self.entity_to_body.iter()
+            .filter(|(entity_id, _)| !self.entity_to_collider.contains_key(entity_id))
+            .map(|(entity_id, rb_handle)| (*entity_id, *rb_handle))
+            .collect();
+
+        let step_strength = self.settings.custom_gravity_strength * self.integration_parameters.dt;
+        for (entity, source) in sources {
+            // The source is at its body's position, or at its world position when it has none
+            let center = match self.entity_to_body.get(&entity.id).and_then(|rb| self.rigid_body_set.get(*rb)) {
+                Some(rb) => (rb.translation().x, rb.translation().y),
+                None => match scene.world_transform(entity.id) {
+                    Ok(world) => world.position,
+                    Err(_) => continue,
+                },
+            };
+            let layers = match self.layer_mask(&source.affected_layers) {
+                Ok(layers) => layers,
+                Err(err) => {
+                    eprintln!("{} on entity '{}'", err, entity.name);
+                    continue;
+                }
+            };
+
+            let radius = source.radius();
+            let filter = QueryFilter { layers, exclude: vec![entity.id], include_sensors: true };
+            let mut pulled: Vec<RigidBodyHandle> = self.overlap_circle(center, radius, &filter).iter()
+                .filter_map(|entity_id| self.entity_to_body.get(entity_id).copied())
+                .collect();
+            // Bodies without a collider are in the default layer
+            if layers & 1 != 0 {
+                pulled.extend(colliderless.iter()
+                    .filter(|(entity_id, _)| *entity_id != entity.id)
+                    .filter(|(_, rb_handle)| self.rigid_body_set.get(*rb_handle).is_some_and(|rb| {
+                        (vector![center.0, center.1] - rb.translation()).norm() <= radius
+                    }))
+                    .map(|(_, rb_handle)| *rb_handle));
+            }
+
+            for rb_handle in pulled {
+                let Some(rb) = self.rigid_body_set.get_mut(rb_handle) else {
+                    continue;
+                };
+                // Only dynamic bodies with gravity are pulled
+                if !rb.is_dynamic() || rb.gravity_scale() == 0.0 {
+                    continue;
+                }
+                let direction = vector![center.0, center.1] - rb.translation();
+                let distance = direction.norm();
+                let acceleration = source.acceleration(distance);
+                if distance > 0.0 && acceleration != 0.0 {
+                    let velocity = rb.linvel() + direction / distance * acceleration * step_strength;
+                    rb.set_linvel(velocity, true);
+                }
+            }
+        }
+    }
+
+    // Take the collision events collected since the last call
+    pub fn drain_collision_events(&mut self) -> Vec<CollisionEvent> {
+        std::mem::take(&mut self.collision_events)
+    }
+
     pub fn load_scene(&mut self, scene: &Scene) {
+        if let Err(err) = self.apply_settings(&scene.physics) {
+            eprintln!("Invalid physics settings in scene '{}': {}", scene.name, err);
+        }
+        let world_transforms = scene.world_transforms();
+        for (_, entity) in &scene.entities {
+            let parent_world = scene.parent_of(entity.id).and_then(|parent| world_transforms.get(&parent));
+            self.add_entity_under(entity, parent_world);
+        }
+        // Joints once every body exists, those to deleted entities went with them
         for (_, entity) in &scene.entities {
-            self.add_entity(entity);
+            for joint in entity.joints.iter().filter(|joint| scene.entities.contains_key(&joint.other)) {
+                if let Err(err) = self.add_joint(entity.id, joint) {
+                    eprintln!("Failed to add joint: {}", err);
+                }
+            }
         }
+        // Queries can be made before the first step
+        self.query_pipeline.update(&self.collider_set);
     }
 
     // We should also add cleanup for scene switching
@@ -361,6 +1410,14 @@ impl PhysicsEngine {
         // Clear entity mappings
         self.entity_to_body.clear();
         self.entity_to_collider.clear();
+        self.collider_to_entity.clear();
+        self.collision_events.clear();
+        self.joints.clear();
+        self.characters.clear();
+        self.synced_attributes.clear();
+        self.transform_bodies.clear();
+        self.previous_positions.clear();
+        self.previous_rotations.clear();
 
         // Remove all physics objects
         self.rigid_body_set = RigidBodySet::new();
@@ -436,33 +1493,145 @@ impl PhysicsEngine {
         colliding
     }
 
-    // Get all colliders and gives a shape for rendering
-    // Returns:
-    // - (f32, f32): The world coordinate of the collider (x, y).
-    // - (f32, f32): The size of the collider in world coordinate (width, height).
-    // - String: The shape of the collider (e.g., "Circle", "Rectangle").
-    pub fn get_collider_data(&self) -> Vec<((f32, f32), (f32, f32), String)> {
+    // Scene queries, against the colliders as they were after the last step or scene load
+
+    /// First collider hit by a ray from `origin` along `direction`, within `max_distance`
+    pub fn raycast(&self, origin: (f32, f32), direction: (f32, f32), max_distance: f32, filter: &QueryFilter) -> Option<QueryHit> {
+        let ray = Self::ray(origin, direction)?;
+        let (handle, intersection) = self.with_query_filter(filter, |query_filter| {
+            self.query_pipeline.cast_ray_and_get_normal(&self.rigid_body_set, &self.collider_set, &ray, max_distance, true, query_filter)
+        })?;
+        self.ray_hit(&ray, handle, intersection)
+    }
+
+    /// Every collider hit by the ray, nearest first
+    pub fn raycast_all(&self, origin: (f32, f32), direction: (f32, f32), max_distance: f32, filter: &QueryFilter) -> Vec<QueryHit> {
+        let Some(ray) = Self::ray(origin, direction) else {
+            return Vec::new();
+        };
+        let mut hits = Vec::new();
+        self.with_query_filter(filter, |query_filter| {
+            self.query_pipeline.intersections_with_ray(&self.rigid_body_set, &self.collider_set, &ray, max_distance, true, query_filter, |handle, intersection| {
+                hits.extend(self.ray_hit(&ray, handle, intersection));
+                true
+            });
+        });
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    /// Entities whose collider overlaps a circle
+    pub fn overlap_circle(&self, center: (f32, f32), radius: f32, filter: &QueryFilter) -> Vec<Uuid> {
+        self.overlap_shape(&Isometry::translation(center.0, center.1), &Ball::new(radius), filter)
+    }
+
+    /// Entities whose collider overlaps a box of the given size, turned by `rotation` radians about its center
+    pub fn overlap_box(&self, center: (f32, f32), size: (f32, f32), rotation: f32, filter: &QueryFilter) -> Vec<Uuid> {
+        let isometry = Isometry::new(vector![center.0, center.1], rotation);
+        self.overlap_shape(&isometry, &Cuboid::new(vector![size.0 / 2.0, size.1 / 2.0]), filter)
+    }
+
+    /// Entities whose collider contains a point, e.g. to pick what is under the mouse
+    pub fn point_query(&self, point: (f32, f32), filter: &QueryFilter) -> Vec<Uuid> {
+        let mut entities = Vec::new();
+        self.with_query_filter(filter, |query_filter| {
+            self.query_pipeline.intersections_with_point(&self.rigid_body_set, &self.collider_set, &point![point.0, point.1], query_filter, |handle| {
+                entities.extend(self.collider_to_entity.get(&handle));
+                true
+            });
+        });
+        entities
+    }
+
+    /// Moves a shape from `origin` along `direction` and returns the first collider it touches.
+    /// The hit point is on that collider; `distance` is how far the shape's center went.
+    pub fn shape_cast(&self, shape: QueryShape, origin: (f32, f32), direction: (f32, f32), max_distance: f32, filter: &QueryFilter) -> Option<QueryHit> {
+        let ray = Self::ray(origin, direction)?;
+        let options = ShapeCastOptions {
+            max_time_of_impact: max_distance,
+            target_distance: 0.0,
+            stop_at_penetration: true,
+            compute_impact_geometry_on_penetration: true,
+        };
+        let shape = shape.to_shape();
+        let (handle, hit) = self.with_query_filter(filter, |query_filter| {
+            self.query_pipeline.cast_shape(
+                &self.rigid_body_set,
+                &self.collider_set,
+                &Isometry::translation(origin.0, origin.1),
+                &ray.dir,
+                shape.as_ref(),
+                options,
+                query_filter,
+            )
+        })?;
+        Some(QueryHit {
+            entity: *self.collider_to_entity.get(&handle)?,
+            point: (hit.witness1.x, hit.witness1.y),
+            normal: (hit.normal1.x, hit.normal1.y),
+            distance: hit.time_of_impact,
+        })
+    }
+
+    fn overlap_shape(&self, isometry: &Isometry<Real>, shape: &dyn Shape, filter: &QueryFilter) -> Vec<Uuid> {
+        let mut entities = Vec::new();
+        self.with_query_filter(filter, |query_filter| {
+            self.query_pipeline.intersections_with_shape(&self.rigid_body_set, &self.collider_set, isometry, shape, query_filter, |handle| {
+                entities.extend(self.collider_to_entity.get(&handle));
+                true
+            });
+        });
+        entities
+    }
+
+    // A ray with a unit direction, so times of impact are distances. None for a zero direction.
+    fn ray(origin: (f32, f32), direction: (f32, f32)) -> Option<Ray> {
+        let direction = vector![direction.0, direction.1];
+        let length = direction.norm();
+        (length > 0.0).then(|| Ray::new(point![origin.0, origin.1], direction / length))
+    }
+
+    fn ray_hit(&self, ray: &Ray, handle: ColliderHandle, intersection: RayIntersection) -> Option<QueryHit> {
+        let point = ray.point_at(intersection.time_of_impact);
+        Some(QueryHit {
+            entity: *self.collider_to_entity.get(&handle)?,
+            point: (point.x, point.y),
+            normal: (intersection.normal.x, intersection.normal.y),
+            distance: intersection.time_of_impact,
+        })
+    }
+
+    // Runs a query with rapier's filter for `filter`. Colliders only hit queries whose layers
+    // share a bit with their memberships.
+    fn with_query_filter<R>(&self, filter: &QueryFilter, query: impl FnOnce(rapier2d::pipeline::QueryFilter) -> R) -> R {
+        let not_excluded = |_: ColliderHandle, collider: &Collider| !filter.exclude.contains(&Uuid::from_u128(collider.user_data));
+        let mut query_filter = rapier2d::pipeline::QueryFilter::new()
+            .groups(InteractionGroups::new(Group::ALL, Group::from_bits_truncate(filter.layers)))
+            .predicate(&not_excluded);
+        if !filter.include_sensors {
+            query_filter = query_filter.exclude_sensors();
+        }
+        query(query_filter)
+    }
+
+    // Get all colliders and gives a shape for rendering, see `ColliderData`
+    pub fn get_collider_data(&self) -> Vec<ColliderData> {
         let mut colliders = Vec::new();
 
         for (entity_id, collider_handle) in &self.entity_to_collider {
-            if let Some(collider) = self.collider_set.get(*collider_handle) {
-                if let Some(rb_handle) = self.entity_to_body.get(entity_id) {
-                    if let Some(rb) = self.rigid_body_set.get(*rb_handle) {
-                        let position = (collider.translation().x, collider.translation().y);
-
-                        if let Some(ball) = collider.shape().as_ball() {
-                            colliders.push((position, (ball.radius * 2.0, ball.radius * 2.0), "Circle".to_string()));
-                        } else if let Some(cuboid) = collider.shape().as_cuboid() {
-                            colliders.push((
-                                position,
-                                (
-                                    cuboid.half_extents.x * 2.0,
-                                    cuboid.half_extents.y * 2.0,
-                                ),
-                                "Rectangle".to_string(),
-                            ));
-                        }
-                    }
+            let Some(collider) = self.collider_set.get(*collider_handle) else {
+                continue;
+            };
+            // Each part of a compound collider (a tilemap) is drawn on its own
+            let parts: Vec<(Isometry<Real>, &dyn Shape)> = match collider.shape().as_compound() {
+                Some(compound) => compound.shapes().iter()
+                    .map(|(part_isometry, part)| (collider.position() * part_isometry, part.as_ref()))
+                    .collect(),
+                None => vec![(*collider.position(), collider.shape())],
+            };
+            for (isometry, shape) in parts {
+                if let Some(data) = Self::shape_data(*entity_id, &isometry, shape) {
+                    colliders.push(ColliderData { sensor: collider.is_sensor(), ..data });
                 }
             }
         }
@@ -470,6 +1639,40 @@ impl PhysicsEngine {
         colliders
     }
 
+    // Outline of a collider shape placed at `isometry`, None for shapes that aren't drawn
+    fn shape_data(entity_id: Uuid, isometry: &Isometry<Real>, shape: &dyn Shape) -> Option<ColliderData> {
+        let to_world = |p: Point<Real>| {
+            let world = isometry * p;
+            (world.x, world.y)
+        };
+
+        let (shape_name, local_points): (&str, Vec<Point<Real>>) = if shape.as_ball().is_some() {
+            ("Circle", Vec::new())
+        } else if let Some(cuboid) = shape.as_cuboid() {
+            let (hx, hy) = (cuboid.half_extents.x, cuboid.half_extents.y);
+            ("Rectangle", vec![point![-hx, -hy], point![hx, -hy], point![hx, hy], point![-hx, hy]])
+        } else if let Some(capsule) = shape.as_capsule() {
+            ("Capsule", capsule_outline(capsule.segment.a, capsule.segment.b, capsule.radius))
+        } else if let Some(polygon) = shape.as_convex_polygon() {
+            ("ConvexPolygon", polygon.points().to_vec())
+        } else if let Some(polyline) = shape.as_polyline() {
+            ("Polyline", polyline.vertices().to_vec())
+        } else {
+            return None;
+        };
+
+        let aabb = shape.compute_local_aabb();
+        Some(ColliderData {
+            entity_id,
+            position: (isometry.translation.x, isometry.translation.y),
+            rotation: isometry.rotation.angle(),
+            size: (aabb.maxs.x - aabb.mins.x, aabb.maxs.y - aabb.mins.y),
+            shape: shape_name.to_string(),
+            points: local_points.into_iter().map(to_world).collect(),
+            sensor: false,
+        })
+    }
+
     // Angular motion
     pub fn get_angular_velocity(&self, entity_id: &Uuid) -> Option<Real> {
         self.entity_to_body.get(entity_id)
@@ -493,6 +1696,105 @@ impl PhysicsEngine {
         }
     }
 
+    /// Moves an entity with a character controller by `translation`, stopping at what it hits and
+    /// sliding along it. The body gets there at the next step; moves made before it add up.
+    pub fn move_and_slide(&mut self, entity_id: Uuid, translation: (f32, f32)) -> Result<CharacterMovement, String> {
+        let character = self.characters.get(&entity_id)
+            .ok_or_else(|| format!("Entity '{}' has no character controller", entity_id))?;
+        let rb_handle = *self.entity_to_body.get(&entity_id)
+            .ok_or_else(|| format!("Entity '{}' not found in physics engine", entity_id))?;
+        let collider = self.entity_to_collider.get(&entity_id)
+            .and_then(|collider_handle| self.collider_set.get(*collider_handle))
+            .ok_or_else(|| format!("Entity '{}' has no collider", entity_id))?;
+
+        let controller = &character.controller;
+        let max_slope = controller.max_slope.to_radians();
+        let kinematic_controller = KinematicCharacterController {
+            up: -Vector::y_axis(), // +y points down
+            offset: CharacterLength::Absolute(CHARACTER_OFFSET),
+            autostep: (controller.step_height > 0.0).then(|| CharacterAutostep {
+                max_height: CharacterLength::Absolute(controller.step_height),
+                include_dynamic_bodies: false,
+                ..Default::default()
+            }),
+            max_slope_climb_angle: max_slope,
+            min_slope_slide_angle: max_slope,
+            snap_to_ground: (controller.snap_to_ground > 0.0).then_some(CharacterLength::Absolute(controller.snap_to_ground)),
+            ..Default::default()
+        };
+
+        // From where the previous moves since the last step left it
+        let start = *self.rigid_body_set[rb_handle].next_position();
+        let collider_position = start * collider.position_wrt_parent().copied().unwrap_or_default();
+        let filter = rapier2d::pipeline::QueryFilter::new()
+            .exclude_rigid_body(rb_handle)
+            .exclude_sensors()
+            .groups(collider.collision_groups());
+        let move_shape = |from: &Isometry<Real>, desired: Vector<Real>, collisions: &mut Vec<CharacterCollision>| {
+            kinematic_controller.move_shape(
+                self.integration_parameters.dt,
+                &self.rigid_body_set,
+                &self.collider_set,
+                &self.query_pipeline,
+                collider.shape(),
+                from,
+                desired,
+                filter,
+                |collision| collisions.push(collision),
+            )
+        };
+        let mut collisions = Vec::new();
+        let mut movement = move_shape(&collider_position, vector![translation.0, translation.1], &mut collisions);
+
+        // In 2D rapier lets characters slide up any slope they walk into. When it climbed one steeper than
+        // the max slope, it stops where it hit it and only keeps the vertical part of the rest of the move.
+        let up = -Vector::y_axis();
+        let climbed = up.dot(&movement.translation) > up.dot(&vector![translation.0, translation.1]) + 1.0e-3;
+        let too_steep = |collision: &&CharacterCollision| {
+            let normal = *collision.hit.normal1; // From the surface towards the character
+            up.dot(&normal) > 0.0 && up.angle(&normal) > max_slope + 1.0e-3
+        };
+        if let Some(wall) = collisions.iter().find(too_steep).filter(|_| climbed) {
+            let applied = wall.translation_applied;
+            let vertical = *up * up.dot(&wall.translation_remaining);
+            let from = Isometry::translation(applied.x, applied.y) * collider_position;
+            let rest = move_shape(&from, vertical, &mut Vec::new());
+            movement.translation = applied + rest.translation;
+            movement.grounded = rest.grounded;
+        }
+
+        self.rigid_body_set[rb_handle].set_next_kinematic_translation(start.translation.vector + movement.translation);
+        if let Some(character) = self.characters.get_mut(&entity_id) {
+            character.grounded = movement.grounded;
+        }
+        Ok(CharacterMovement {
+            translation: (movement.translation.x, movement.translation.y),
+            grounded: movement.grounded,
+        })
+    }
+
+    /// Whether a character stood on the ground at the end of its last `move_and_slide`
+    pub fn is_grounded(&self, entity_id: &Uuid) -> bool {
+        self.characters.get(entity_id).is_some_and(|character| character.grounded)
+    }
+
+    pub fn body_type(&self, entity_id: &Uuid) -> Option<BodyType> {
+        let rb = self.rigid_body_set.get(*self.entity_to_body.get(entity_id)?)?;
+        Some(match rb.body_type() {
+            RigidBodyType::Fixed => BodyType::Fixed,
+            RigidBodyType::Dynamic => BodyType::Dynamic,
+            RigidBodyType::KinematicPositionBased => BodyType::KinematicPosition,
+            RigidBodyType::KinematicVelocityBased => BodyType::KinematicVelocity,
+        })
+    }
+
+    /// Whether the entity's body fell asleep, see the sleep thresholds of `PhysicsSettings`
+    pub fn is_sleeping(&self, entity_id: &Uuid) -> bool {
+        self.entity_to_body.get(entity_id)
+            .and_then(|rb_handle| self.rigid_body_set.get(*rb_handle))
+            .is_some_and(|rb| rb.is_sleeping())
+    }
+
     // Movement status
     pub fn is_moving(&self, entity_id: &Uuid) -> bool {
         if let Some(vel) = self.get_velocity(entity_id) {
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+// PhysicsSettings (one per Scene, saved with it)
+// └── Applied by `PhysicsEngine::load_scene`, and at runtime by `PhysicsEngine::apply_settings`
+// └── Every field has a default, so scenes saved before the settings existed still load
+// Gravity is in world units per second squared, +y points down.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct PhysicsSettings {
+    pub gravity: (f32, f32),
+    pub time_step: f32,               // Seconds simulated by each physics step
+    pub solver_iterations: usize,     // More iterations make stacks and joints stiffer, at a cost
+    pub ccd: bool,                    // Continuous collision detection, so fast bodies don't pass through thin ones
+    pub sleep_linear_threshold: f32,  // Speed under which a dynamic body may fall asleep
+    pub sleep_angular_threshold: f32, // Same for the angular speed, in radians per second
+    pub time_until_sleep: f32,        // Seconds a body must stay under both thresholds before sleeping
+    pub custom_gravity_strength: f32, // Multiplier of the pull of gravity sources, see `GravitySource`
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            gravity: (0.0, 50.0),
+            time_step: 1.0 / 60.0,
+            solver_iterations: 4,
+            ccd: false,
+            sleep_linear_threshold: 0.4,
+            sleep_angular_threshold: 0.5,
+            time_until_sleep: 2.0,
+            custom_gravity_strength: 10.0,
+        }
+    }
+}
+
+impl PhysicsSettings {
+    /// Checks the values the physics engine can't run with
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.time_step > 0.0 && self.time_step.is_finite()) {
+            return Err(format!("Time step must be positive, got {}", self.time_step));
+        }
+        if self.solver_iterations == 0 {
+            return Err("Solver iterations must be at least 1".to_string());
+        }
+        if !(self.gravity.0.is_finite() && self.gravity.1.is_finite()) {
+            return Err("Gravity must be finite".to_string());
+        }
+        if self.sleep_linear_threshold < 0.0 || self.sleep_angular_threshold < 0.0 || self.time_until_sleep < 0.0 {
+            return Err("Sleep thresholds cannot be negative".to_string());
+        }
+        Ok(())
+    }
+}
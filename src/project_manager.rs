@@ -1,11 +1,15 @@
 // Required imports for file operations, serialization, and project management
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use uuid::Uuid;
-use crate::ecs::SceneManager;
+use crate::ecs::{Entity, Prefab, Scene, SceneManager};
+use crate::physics_engine::default_collision_layers;
+use crate::scene_format::{self, FORMAT_VERSION};
+use indexmap::IndexMap;
 use crate::logger::LOGGER;
 use std::io::{BufRead, BufReader};
 use strip_ansi_escapes::strip;
@@ -22,6 +26,23 @@ pub struct ProjectMetadata {
     pub project_path: String,      // Absolute path to project directory
     pub default_scene: String,     // Default scene file name
     pub active_scene_id: Option<Uuid>, // Currently active scene's UUID
+    #[serde(default = "default_collision_layers")]
+    pub collision_layers: Vec<String>, // Names of the collision layers, see physics_engine::collision_layer_mask
+}
+
+// scenes/index.json, the scenes of the project in order and the file each one is saved in
+#[derive(Serialize, Deserialize, Debug)]
+struct SceneIndex {
+    format_version: u32,
+    active_scene: Option<Uuid>,
+    scenes: Vec<SceneIndexEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SceneIndexEntry {
+    id: Uuid,     // Key of the scene in SceneManager::scenes
+    name: String,
+    file: String, // In the scenes folder
 }
 
 // Add a new struct to represent project loading result
@@ -37,6 +58,13 @@ pub struct ProjectManager;
 impl ProjectManager {
     // Add constant definition
     const PROJECT_FILE_NAME: &'static str = "project.epm";
+    const PREFAB_FOLDER: &'static str = "assets/prefabs";
+    const PREFAB_EXTENSION: &'static str = "prefab";
+    const SCENE_FOLDER: &'static str = "scenes";
+    const SCENE_INDEX_FILE: &'static str = "index.json";
+    const SCENE_EXTENSION: &'static str = "scene";
+    const SHARED_ENTITIES_FILE: &'static str = "shared_entities.json";
+    const LEGACY_SCENE_FILE: &'static str = "scene_manager.json";
 
     pub fn set_project_path(path: String) {
         let mut project_path_lock = PROJECT_PATH.write().unwrap();
@@ -64,6 +92,7 @@ impl ProjectManager {
             project_path: project_path.to_str().unwrap().to_string(),
             default_scene: "main.scene".to_string(),
             active_scene_id: None,
+            collision_layers: default_collision_layers(),
         };
 
         Self::set_project_path(metadata.project_path.clone());
@@ -91,6 +120,7 @@ impl ProjectManager {
             "assets/sounds",    // For audio assets
             "assets/fonts",     // For font files
             "assets/scripts",   // For game scripts
+            "assets/prefabs",   // For prefab templates
             "scenes",           // For scene data files
             "src",             // For Rust source files
         ];
@@ -169,7 +199,7 @@ fn main() -> eframe::Result<()> {{
     let mut camera_height = 600.0;
 
     ProjectManager::set_project_path(project_path.to_string_lossy().to_string());
-    let scene_manager = match ProjectManager::load_scene_hierarchy(&project_path) {{
+    let scene_manager = match ProjectManager::load_scene_hierarchy_lazily(&project_path) {{
         Ok(manager) => manager,
         Err(e) => {{
             println!("Failed to load scene hierarchy: {{}}", e);
@@ -196,6 +226,9 @@ fn main() -> eframe::Result<()> {{
     }};
 
     game_runtime.set_scene_manager(scene_manager.clone());
+    if let Err(e) = game_runtime.set_project_path(&project_path) {{
+        println!("{{}}", e);
+    }}
     game_runtime.run();
 
     eframe::run_native(
@@ -280,6 +313,15 @@ my_game_engine = {{ path = "../path/to/engine" }}
         Ok(metadata)
     }
 
+    // Collision layers of the project, read without updating project.epm like load_project does.
+    // A missing or unreadable file gives the default layers.
+    pub fn load_collision_layers(project_path: &Path) -> Vec<String> {
+        fs::read_to_string(project_path.join(Self::PROJECT_FILE_NAME))
+            .ok()
+            .and_then(|json| serde_json::from_str::<ProjectMetadata>(&json).ok())
+            .map_or_else(default_collision_layers, |metadata| metadata.collision_layers)
+    }
+
     // Saves project metadata to project.json
     pub fn save_project(project_path: &Path, metadata: &ProjectMetadata) -> Result<(), String> {
         let file_path = project_path.join(Self::PROJECT_FILE_NAME);
@@ -360,6 +402,10 @@ my_game_engine = {{ path = "../path/to/engine" }}
                 .map_err(|e| format!("Failed to copy assets: {}", e))?;
         }
 
+        // Copy the project settings, the game reads its collision layers from them
+        fs::copy(project_path.join(Self::PROJECT_FILE_NAME), target_dir.join(Self::PROJECT_FILE_NAME))
+            .map_err(|e| format!("Failed to copy project settings: {}", e))?;
+
         // Copy scenes to target directory
         let scenes_dir = project_path.join("scenes");
         if scenes_dir.exists() {
@@ -446,14 +492,61 @@ my_game_engine = {{ path = "../path/to/engine" }}
             .into_owned())
     }
 
-    // Saves the current scene hierarchy to scene_manager.json
+    // Saves the scene hierarchy as scenes/index.json, one scenes/<name>.scene per scene and
+    // scenes/shared_entities.json. Files whose content didn't change are left untouched.
     pub fn save_scene_hierarchy(project_path: &Path, scene_manager: &SceneManager) -> Result<(), String> {
-        let scene_file = project_path.join("scenes").join("scene_manager.json");
-        let json = serde_json::to_string_pretty(&scene_manager)
-            .map_err(|e| format!("Failed to serialize scene hierarchy: {}", e))?;
-        
-        fs::write(&scene_file, json)
-            .map_err(|e| format!("Failed to write scene hierarchy: {}", e))?;
+        let scene_dir = project_path.join(Self::SCENE_FOLDER);
+        fs::create_dir_all(&scene_dir)
+            .map_err(|e| format!("Failed to create scene folder: {}", e))?;
+
+        let entries = Self::scene_index_entries(&scene_dir, scene_manager)?;
+        for entry in &entries {
+            // Unloaded scenes keep their file as it is
+            let Some(scene) = scene_manager.scenes.get(&entry.id) else {
+                continue;
+            };
+            let mut scene = scene.clone();
+            for entity in scene.entities.values_mut() {
+                Self::relative_entity_asset_paths(entity);
+            }
+            let json = serde_json::to_value(&scene)
+                .map_err(|e| format!("Failed to serialize scene '{}': {}", scene.name, e))?;
+            let mut document = scene_format::scene_file_document(entry.id, json);
+            scene_format::key_attributes_by_name(&mut document)
+                .map_err(|e| format!("Failed to serialize scene '{}': {}", scene.name, e))?;
+            let mut json = scene_format::take_scene(&mut document, entry.id).unwrap_or_default();
+            scene_format::set_format_version(&mut json);
+            Self::write_scene_file(&scene_dir.join(&entry.file), &json)?;
+        }
+
+        let mut shared_entities = scene_manager.shared_entities.clone();
+        for entity in shared_entities.values_mut() {
+            Self::relative_entity_asset_paths(entity);
+        }
+        let mut json = serde_json::json!({ "shared_entities": shared_entities });
+        scene_format::key_attributes_by_name(&mut json)
+            .map_err(|e| format!("Failed to serialize shared entities: {}", e))?;
+        scene_format::set_format_version(&mut json);
+        Self::write_scene_file(&scene_dir.join(Self::SHARED_ENTITIES_FILE), &json)?;
+
+        let index = SceneIndex {
+            format_version: FORMAT_VERSION,
+            active_scene: scene_manager.active_scene,
+            scenes: entries,
+        };
+        let json = serde_json::to_value(&index)
+            .map_err(|e| format!("Failed to serialize scene index: {}", e))?;
+        Self::write_scene_file(&scene_dir.join(Self::SCENE_INDEX_FILE), &json)?;
+
+        let saved: Vec<PathBuf> = index.scenes.iter().map(|entry| scene_dir.join(&entry.file)).collect();
+        for stale in Self::scene_files(&scene_dir)?.into_iter().filter(|path| !saved.contains(path)) {
+            Self::back_up_older_file(&stale)?;
+            fs::remove_file(&stale)
+                .map_err(|e| format!("Failed to remove scene file {}: {}", stale.display(), e))?;
+        }
+        Self::retire_legacy_scene_file(&scene_dir)?;
+
+        Self::save_prefabs(project_path, scene_manager)?;
 
         // Update project metadata with active scene
         if let Ok(mut metadata) = Self::load_project(project_path) {
@@ -464,22 +557,315 @@ my_game_engine = {{ path = "../path/to/engine" }}
         Ok(())
     }
 
-    // Loads the scene hierarchy from scene_manager.json
+    // The index of the scenes in the order of the scene manager. Unloaded scenes keep their file
+    // and go back after the scene they followed in the previous index.
+    fn scene_index_entries(scene_dir: &Path, scene_manager: &SceneManager) -> Result<Vec<SceneIndexEntry>, String> {
+        let mut previous_order = Vec::new();
+        let mut unloaded = Vec::new();
+        if !scene_manager.unloaded_scenes.is_empty() {
+            let index = Self::read_scene_index(scene_dir)?
+                .ok_or("The scene index is missing, the unloaded scenes can't be saved")?;
+            previous_order = index.scenes.iter().map(|entry| entry.id).collect();
+            unloaded = index.scenes.into_iter()
+                .filter(|entry| scene_manager.unloaded_scenes.contains_key(&entry.id))
+                .collect();
+        }
+        if let Some(name) = scene_manager.unloaded_scenes.iter()
+            .find(|(id, _)| !unloaded.iter().any(|entry| entry.id == **id))
+            .map(|(_, name)| name)
+        {
+            return Err(format!("Scene '{}' is missing from the scene index", name));
+        }
+
+        let mut used_files: HashSet<String> = unloaded.iter().map(|entry| entry.file.to_lowercase()).collect();
+        let mut entries: Vec<SceneIndexEntry> = scene_manager.scenes.iter()
+            .map(|(id, scene)| SceneIndexEntry {
+                id: *id,
+                name: scene.name.clone(),
+                file: Self::scene_file_name(&scene.name, &mut used_files),
+            })
+            .collect();
+        for entry in unloaded {
+            let position = previous_order.iter().position(|id| *id == entry.id).unwrap_or(0);
+            let after = previous_order[..position].iter().rev()
+                .find_map(|id| entries.iter().position(|other| other.id == *id));
+            entries.insert(after.map_or(0, |after| after + 1), entry);
+        }
+        Ok(entries)
+    }
+
+    // A file name from the scene's name that no other scene uses, "Level 1" is saved as Level_1.scene
+    fn scene_file_name(name: &str, used_files: &mut HashSet<String>) -> String {
+        Self::unique_file_name(name, "scene", Self::SCENE_EXTENSION, used_files)
+    }
+
+    // A file name that is valid on every system and not in `used_files` yet: characters other than
+    // letters, digits, '-' and '_' become '_' and names Windows reserves for devices get a '_'.
+    // Compared without case, some file systems don't tell Level_1 and level_1 apart.
+    fn unique_file_name(name: &str, default_stem: &str, extension: &str, used_files: &mut HashSet<String>) -> String {
+        const RESERVED: [&str; 22] = [
+            "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+            "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+        ];
+        let mut stem: String = name.trim().chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        if stem.is_empty() {
+            stem = default_stem.to_string();
+        } else if RESERVED.contains(&stem.to_uppercase().as_str()) {
+            stem.push('_');
+        }
+
+        let mut file = format!("{}.{}", stem, extension);
+        let mut count = 2;
+        while !used_files.insert(file.to_lowercase()) {
+            file = format!("{}_{}.{}", stem, count, extension);
+            count += 1;
+        }
+        file
+    }
+
+    // Pretty JSON ending in a newline, only written when it differs from the file on disk
+    fn write_scene_file(path: &Path, json: &serde_json::Value) -> Result<(), String> {
+        let mut contents = serde_json::to_string_pretty(json)
+            .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+        contents.push('\n');
+        if fs::read_to_string(path).is_ok_and(|existing| existing == contents) {
+            return Ok(());
+        }
+        Self::back_up_older_file(path)?;
+        fs::write(path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // A file of an older format is copied to e.g. menu.scene.v2.bak before it is replaced or removed,
+    // an older engine can't read what replaces it. An existing backup is never replaced.
+    fn back_up_older_file(path: &Path) -> Result<(), String> {
+        let Ok(json) = fs::read_to_string(path) else {
+            return Ok(());
+        };
+        let Ok(Ok(version)) = serde_json::from_str(&json).map(|json| scene_format::format_version(&json)) else {
+            return Ok(());
+        };
+        if version >= FORMAT_VERSION {
+            return Ok(());
+        }
+
+        let mut backup_file = path.as_os_str().to_owned();
+        backup_file.push(format!(".v{}.bak", version));
+        let backup_file = PathBuf::from(backup_file);
+        if !backup_file.exists() {
+            fs::copy(path, &backup_file)
+                .map_err(|e| format!("Failed to back up {} to {}: {}", path.display(), backup_file.display(), e))?;
+            println!("Backed up the format {} file {} to {}", version, path.display(), backup_file.display());
+        }
+        Ok(())
+    }
+
+    fn scene_files(scene_dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let entries = fs::read_dir(scene_dir)
+            .map_err(|e| format!("Failed to read scene folder: {}", e))?;
+        Ok(entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(Self::SCENE_EXTENSION))
+            .collect())
+    }
+
+    // Loads the scene hierarchy with all of its scenes
     pub fn load_scene_hierarchy(project_path: &Path) -> Result<SceneManager, String> {
-        let scene_file = project_path.join("scenes").join("scene_manager.json");
+        let mut scene_manager = Self::load_scene_hierarchy_lazily(project_path)?;
+        Self::load_all_scenes(project_path, &mut scene_manager)?;
+        Ok(scene_manager)
+    }
+
+    // Reads the scenes of a lazily loaded project that aren't loaded yet
+    pub fn load_all_scenes(project_path: &Path, scene_manager: &mut SceneManager) -> Result<(), String> {
+        let unloaded: Vec<Uuid> = scene_manager.unloaded_scenes.keys().copied().collect();
+        for scene_id in unloaded {
+            Self::load_scene(project_path, scene_manager, scene_id)?;
+        }
+        Ok(())
+    }
+
+    // Loads the scene hierarchy with only the active scene, or the first one if none is active.
+    // The other scenes are listed in unloaded_scenes until load_scene reads them.
+    pub fn load_scene_hierarchy_lazily(project_path: &Path) -> Result<SceneManager, String> {
+        let scene_dir = project_path.join(Self::SCENE_FOLDER);
+        let Some(index) = Self::read_scene_index(&scene_dir)? else {
+            return Self::load_legacy_scene_hierarchy(project_path);
+        };
+
+        let mut scene_manager = SceneManager::new();
+        scene_manager.shared_entities = Self::read_shared_entities(project_path)?;
+        scene_manager.unloaded_scenes = index.scenes.iter()
+            .map(|entry| (entry.id, entry.name.clone()))
+            .collect();
+
+        let active_scene = index.active_scene.filter(|id| scene_manager.unloaded_scenes.contains_key(id));
+        if let Some(first_scene) = active_scene.or(index.scenes.first().map(|entry| entry.id)) {
+            Self::load_scene(project_path, &mut scene_manager, first_scene)?;
+        }
+        scene_manager.active_scene = active_scene;
+
+        Self::attach_prefabs(project_path, &mut scene_manager)?;
+        Ok(scene_manager)
+    }
+
+    // Reads one of the unloaded scenes, it takes the place it has in the index among the loaded ones
+    pub fn load_scene(project_path: &Path, scene_manager: &mut SceneManager, scene_id: Uuid) -> Result<(), String> {
+        if scene_manager.is_scene_loaded(scene_id) {
+            return Ok(());
+        }
+        let index = Self::read_scene_index(&project_path.join(Self::SCENE_FOLDER))?
+            .ok_or("The project has no scene index")?;
+        let position = index.scenes.iter().position(|entry| entry.id == scene_id)
+            .ok_or_else(|| format!("Scene {} is not in the scene index", scene_id))?;
+        let scene = Self::read_scene_file(project_path, &index.scenes[position])?;
+
+        let loaded_before = index.scenes[..position].iter()
+            .filter(|entry| scene_manager.is_scene_loaded(entry.id))
+            .count();
+        scene_manager.scenes.shift_insert(loaded_before, scene_id, scene);
+        scene_manager.unloaded_scenes.shift_remove(&scene_id);
+        Ok(())
+    }
+
+    fn read_scene_index(scene_dir: &Path) -> Result<Option<SceneIndex>, String> {
+        let index_file = scene_dir.join(Self::SCENE_INDEX_FILE);
+        if !index_file.exists() {
+            return Ok(None);
+        }
+        let mut json = Self::read_json_file(&index_file)?;
+        scene_format::migrate(&mut json)
+            .map_err(|e| format!("Failed to load scene index {}: {}", index_file.display(), e))?;
+        serde_json::from_value(json)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse scene index {}: {}", index_file.display(), e))
+    }
+
+    fn read_scene_file(project_path: &Path, entry: &SceneIndexEntry) -> Result<Scene, String> {
+        let scene_file = project_path.join(Self::SCENE_FOLDER).join(&entry.file);
+        let json = Self::read_json_file(&scene_file)?;
+        let mut document = scene_format::scene_file_document(entry.id, json);
+        let version = Self::migrate_scene_document(&mut document, &scene_file)?;
+        let json = scene_format::take_scene(&mut document, entry.id).unwrap_or_default();
+        let mut scene: Scene = serde_json::from_value(json)
+            .map_err(|e| format!("Failed to parse scene {} (format {}): {}", scene_file.display(), version, e))?;
+
+        for entity in scene.entities.values_mut() {
+            Self::update_entity_asset_paths(entity, project_path);
+        }
+        Ok(scene)
+    }
+
+    fn read_shared_entities(project_path: &Path) -> Result<IndexMap<Uuid, Entity>, String> {
+        let shared_file = project_path.join(Self::SCENE_FOLDER).join(Self::SHARED_ENTITIES_FILE);
+        if !shared_file.exists() {
+            return Ok(IndexMap::new());
+        }
+        let mut document = Self::read_json_file(&shared_file)?;
+        let version = Self::migrate_scene_document(&mut document, &shared_file)?;
+        let json = document.get_mut("shared_entities").map(serde_json::Value::take).unwrap_or_default();
+        let mut shared_entities: IndexMap<Uuid, Entity> = if json.is_null() {
+            IndexMap::new()
+        } else {
+            serde_json::from_value(json)
+                .map_err(|e| format!("Failed to parse shared entities {} (format {}): {}", shared_file.display(), version, e))?
+        };
+
+        for entity in shared_entities.values_mut() {
+            Self::update_entity_asset_paths(entity, project_path);
+        }
+        Ok(shared_entities)
+    }
+
+    // Scenes saved up to format 2, all of them in scenes/scene_manager.json
+    fn load_legacy_scene_hierarchy(project_path: &Path) -> Result<SceneManager, String> {
+        let scene_file = project_path.join(Self::SCENE_FOLDER).join(Self::LEGACY_SCENE_FILE);
         
         // Return new scene manager if file doesn't exist
         if !scene_file.exists() {
             return Ok(SceneManager::new());
         }
 
-        let json = fs::read_to_string(&scene_file)
-            .map_err(|e| format!("Failed to read scene hierarchy: {}", e))?;
-            
-        let mut scene_manager: SceneManager = serde_json::from_str(&json)
-            .map_err(|e| format!("Failed to parse scene hierarchy: {}", e))?;
+        let mut json = Self::read_json_file(&scene_file)?;
+        let version = Self::migrate_scene_document(&mut json, &scene_file)?;
+        let mut scene_manager: SceneManager = serde_json::from_value(json)
+            .map_err(|e| format!("Failed to parse scene hierarchy (format {}): {}", version, e))?;
 
         // ======== update resource paths in entities ========
+        for (_, scene) in scene_manager.scenes.iter_mut() {
+            for (_, entity) in scene.entities.iter_mut() {
+                Self::update_entity_asset_paths(entity, project_path);
+            }
+        }
+        for entity in scene_manager.shared_entities.values_mut() {
+            Self::update_entity_asset_paths(entity, project_path);
+        }
+
+        Self::attach_prefabs(project_path, &mut scene_manager)?;
+        Ok(scene_manager)
+    }
+
+    fn read_json_file(path: &Path) -> Result<serde_json::Value, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    // Older files are brought up to the current format before they are deserialized.
+    // Returns the version the file had.
+    fn migrate_scene_document(document: &mut serde_json::Value, path: &Path) -> Result<u32, String> {
+        let version = scene_format::migrate(document)
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?;
+        if version < FORMAT_VERSION {
+            println!("Migrated {} from format {} to {}", path.display(), version, FORMAT_VERSION);
+        }
+        scene_format::key_attributes_by_id(document);
+        Ok(version)
+    }
+
+    fn attach_prefabs(project_path: &Path, scene_manager: &mut SceneManager) -> Result<(), String> {
+        scene_manager.prefabs = Self::load_prefabs(project_path)?;
+        for prefab in scene_manager.prefabs.values_mut() {
+            Self::update_entity_asset_paths(&mut prefab.entity, project_path);
+        }
+        Ok(())
+    }
+
+    // Once the scenes are saved in their own files, the scene_manager.json of an older engine is
+    // renamed to e.g. scene_manager.v1.json.bak. An existing backup is never replaced.
+    fn retire_legacy_scene_file(scene_dir: &Path) -> Result<(), String> {
+        let scene_file = scene_dir.join(Self::LEGACY_SCENE_FILE);
+        let Ok(json) = fs::read_to_string(&scene_file) else {
+            return Ok(());
+        };
+        let Ok(Ok(version)) = serde_json::from_str(&json).map(|json| scene_format::format_version(&json)) else {
+            return Ok(());
+        };
+
+        let backup_file = scene_dir.join(format!("scene_manager.v{}.json.bak", version));
+        if !backup_file.exists() {
+            fs::rename(&scene_file, &backup_file)
+                .map_err(|e| format!("Failed to back up scene hierarchy to {}: {}", backup_file.display(), e))?;
+            println!("Backed up the format {} scene hierarchy to {}", version, backup_file.display());
+        }
+        Ok(())
+    }
+
+    // Points the entity's assets into the project folder, which may have moved since it was saved
+    fn update_entity_asset_paths(entity: &mut Entity, project_path: &Path) {
+        Self::map_entity_asset_paths(entity, |relative_path| format!("{}/{}", project_path.display(), relative_path));
+    }
+
+    // Saved asset paths are relative to the project, the same on every machine
+    fn relative_entity_asset_paths(entity: &mut Entity) {
+        Self::map_entity_asset_paths(entity, str::to_string);
+    }
+
+    // Rewrites the entity's paths into the assets folders, `map` gets the path from 'assets/{type}' on
+    fn map_entity_asset_paths(entity: &mut Entity, map: impl Fn(&str) -> String) {
         let asset_paths = [
             ("images", "assets/images"),
             ("sounds", "assets/sounds"),
@@ -487,47 +873,105 @@ my_game_engine = {{ path = "../path/to/engine" }}
             ("scripts", "assets/scripts"),
         ];
 
-        // Helper function to update asset paths
-        fn update_asset_path(original_path: &str, project_path: &Path, asset_type: &str) -> String {
-            if let Some(pos) = original_path.rfind(&format!("/{}", asset_type)) {
-                // Extract the relative asset path after 'assets/{type}'
-                let relative_path = &original_path[pos..];
-                format!("{}/{}", project_path.display(), &relative_path[1..])
+        // Helper function to update asset paths, both absolute and project relative ones
+        let update_asset_path = |path: &mut PathBuf, asset_type: &str| {
+            let original_path = path.to_str().unwrap_or("");
+            let relative_path = if original_path.starts_with(&format!("{}/", asset_type)) {
+                Some(original_path)
             } else {
-                original_path.to_string()
+                // Extract the relative asset path after 'assets/{type}'
+                original_path.rfind(&format!("/{}", asset_type)).map(|pos| &original_path[pos + 1..])
+            };
+            if let Some(relative_path) = relative_path {
+                *path = PathBuf::from(map(relative_path));
             }
+        };
+
+        // Update images
+        for image in entity.images.iter_mut() {
+            update_asset_path(image, asset_paths[0].1);
         }
 
-        // Update paths
-        for (_, scene) in scene_manager.scenes.iter_mut() {
-            for (_, entity) in scene.entities.iter_mut() {
-                // Update images
-                for image in entity.images.iter_mut() {
-                    let updated_path = update_asset_path(image.to_str().unwrap_or(""), project_path, asset_paths[0].1);
-                    *image = PathBuf::from(updated_path);
-                }
+        // Update sounds
+        for sound in entity.sounds.iter_mut() {
+            update_asset_path(sound, asset_paths[1].1);
+        }
 
-                // Update sounds
-                for sound in entity.sounds.iter_mut() {
-                    let updated_path = update_asset_path(sound.to_str().unwrap_or(""), project_path, asset_paths[1].1);
-                    *sound = PathBuf::from(updated_path);
-                }
+        // Update font
+        if let Some(font) = entity.text.as_mut().and_then(|text| text.font.as_mut()) {
+            update_asset_path(font, asset_paths[2].1);
+        }
 
-                // Update fonts
-                // for font in entity.fonts.iter_mut() {
-                //     let updated_path = update_asset_path(font.to_str().unwrap_or(""), project_path, asset_paths[1].1);
-                //     *font = PathBuf::from(updated_path);
-                // }
+        // Update script
+        if let Some(script) = entity.script.as_mut() {
+            update_asset_path(script, asset_paths[3].1);
+        }
+    }
 
-                // Update script
-                if let Some(script) = entity.script.as_mut() {
-                    let updated_path = update_asset_path(script.to_str().unwrap_or(""), project_path, asset_paths[3].1);
-                    *script = PathBuf::from(updated_path);
-                }
-            }
+    // Saves every prefab to assets/prefabs/<name>.prefab, removing files of deleted prefabs
+    pub fn save_prefabs(project_path: &Path, scene_manager: &SceneManager) -> Result<(), String> {
+        let prefab_dir = project_path.join(Self::PREFAB_FOLDER);
+        if scene_manager.prefabs.is_empty() && !prefab_dir.exists() {
+            return Ok(());
+        }
+        fs::create_dir_all(&prefab_dir)
+            .map_err(|e| format!("Failed to create prefab folder: {}", e))?;
+
+        let mut saved = Vec::new();
+        let mut used_files = HashSet::new();
+        for prefab in scene_manager.prefabs.values() {
+            let file = Self::unique_file_name(&prefab.name, "prefab", Self::PREFAB_EXTENSION, &mut used_files);
+            let prefab_file = prefab_dir.join(file);
+            let mut prefab = prefab.clone();
+            Self::relative_entity_asset_paths(&mut prefab.entity);
+            let json = serde_json::to_value(&prefab)
+                .map_err(|e| format!("Failed to serialize prefab '{}': {}", prefab.name, e))?;
+            let mut document = scene_format::prefab_file_document(json);
+            scene_format::key_attributes_by_name(&mut document)
+                .map_err(|e| format!("Failed to serialize prefab '{}': {}", prefab.name, e))?;
+            let mut json = scene_format::take_prefab(&mut document).unwrap_or_default();
+            scene_format::set_format_version(&mut json);
+            Self::write_scene_file(&prefab_file, &json)?;
+            saved.push(prefab_file);
         }
 
-        Ok(scene_manager)
+        for stale in Self::prefab_files(&prefab_dir)?.into_iter().filter(|path| !saved.contains(path)) {
+            Self::back_up_older_file(&stale)?;
+            fs::remove_file(&stale)
+                .map_err(|e| format!("Failed to remove prefab file {}: {}", stale.display(), e))?;
+        }
+        Ok(())
+    }
+
+    // Loads all prefabs from assets/prefabs, a project without the folder has none
+    pub fn load_prefabs(project_path: &Path) -> Result<IndexMap<Uuid, Prefab>, String> {
+        let prefab_dir = project_path.join(Self::PREFAB_FOLDER);
+        let mut prefabs = IndexMap::new();
+        if !prefab_dir.exists() {
+            return Ok(prefabs);
+        }
+
+        let mut files = Self::prefab_files(&prefab_dir)?;
+        files.sort();
+        for prefab_file in files {
+            let json = Self::read_json_file(&prefab_file)?;
+            let mut document = scene_format::prefab_file_document(json);
+            let version = Self::migrate_scene_document(&mut document, &prefab_file)?;
+            let json = scene_format::take_prefab(&mut document).unwrap_or_default();
+            let prefab: Prefab = serde_json::from_value(json)
+                .map_err(|e| format!("Failed to parse prefab {} (format {}): {}", prefab_file.display(), version, e))?;
+            prefabs.insert(prefab.id, prefab);
+        }
+        Ok(prefabs)
+    }
+
+    fn prefab_files(prefab_dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let entries = fs::read_dir(prefab_dir)
+            .map_err(|e| format!("Failed to read prefab folder: {}", e))?;
+        Ok(entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(Self::PREFAB_EXTENSION))
+            .collect())
     }
 
     // Loads both project metadata and scene hierarchy
@@ -545,8 +989,8 @@ my_game_engine = {{ path = "../path/to/engine" }}
         // Load and update project metadata (this will update and save the new path)
         let metadata = Self::load_project(project_path)?;
         
-        // Load scene manager
-        let scene_manager = Self::load_scene_hierarchy(project_path)?;
+        // Load scene manager, the other scenes are read when the editor opens them
+        let scene_manager = Self::load_scene_hierarchy_lazily(project_path)?;
 
         Self::set_project_path(metadata.project_path.clone());
 
@@ -597,10 +1041,10 @@ my_game_engine = {{ path = "../path/to/engine" }}
             }
         }
 
-        // Finally check scene manager
-        let scene_file = project_path.join("scenes").join("scene_manager.json");
-        if !scene_file.exists() {
-            return Err("Scene manager file is missing".to_string());
+        // Finally check the scene index, or the scene_manager.json of an older engine
+        let scene_dir = project_path.join(Self::SCENE_FOLDER);
+        if !scene_dir.join(Self::SCENE_INDEX_FILE).exists() && !scene_dir.join(Self::LEGACY_SCENE_FILE).exists() {
+            return Err("Scene index file is missing".to_string());
         }
 
         Ok(())
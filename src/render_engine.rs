@@ -2,13 +2,177 @@ use std::path::{Path, PathBuf};
 use image::GenericImageView;
 use std::collections::HashMap;
 use uuid::Uuid;
-use crate::ecs::{AttributeValue, Scene};
+use crate::ecs::{AttributeValue, Entity, Scene};
+use crate::animation::{parse_frame_rects, FrameRect, FrameRegion};
+pub use crate::ecs::Transform;
+pub use crate::animation::Animation;
+use crate::physics_engine::{ColliderData, JointData};
+use crate::camera::SceneCamera;
+use crate::text::{Text, TextAlign, TextOutline, TextSpace};
+use crate::tilemap::Tilemap;
 use sha2::{Sha256, Digest};
+use std::fs;
+use std::time::{Duration, Instant, SystemTime};
+
+// Texture coordinates (min, max) of a region of a texture
+pub type UvRect = ((f32, f32), (f32, f32));
+
+/// A sprite or a text to draw, in screen coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderItem {
+    pub texture_id: Uuid,
+    pub position: (f32, f32), // Top left corner before rotating
+    pub size: (f32, f32),
+    pub z: f32,
+    pub uv: UvRect, // Source rect of the sprite in the texture
+    pub rotation: f32, // In radians, about the pivot
+    pub pivot: (f32, f32), // Offset from the position
+    pub flip: (bool, bool), // Horizontal, vertical
+    pub tint: [u8; 4], // RGBA, the opacity is part of the alpha
+    pub text: Option<Box<TextDraw>>, // Drawn instead of a texture, at the position
+}
+
+/// Text of a render item. Its size is only known once egui lays it out, so the item's size is empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextDraw {
+    pub text: String,
+    pub font: Option<PathBuf>,
+    pub size: f32, // Line height on screen
+    pub align: TextAlign,
+    pub outline: Option<TextOutline>, // Width on screen
+}
+
+impl RenderItem {
+    /// Corners of the rotated sprite: top left, top right, bottom right, bottom left
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let pivot = (self.position.0 + self.pivot.0, self.position.1 + self.pivot.1);
+        [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(fx, fy)| {
+            let x = fx * self.size.0 - self.pivot.0;
+            let y = fy * self.size.1 - self.pivot.1;
+            (pivot.0 + x * cos - y * sin, pivot.1 + x * sin + y * cos)
+        })
+    }
+
+    /// Axis aligned (min, max) bounds of the rotated sprite
+    pub fn bounds(&self) -> ((f32, f32), (f32, f32)) {
+        self.corners().iter().fold(
+            ((f32::INFINITY, f32::INFINITY), (f32::NEG_INFINITY, f32::NEG_INFINITY)),
+            |(min, max), &(x, y)| ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y))),
+        )
+    }
+
+    /// Textured quad of the sprite, moved by `offset` (e.g. the top left corner of the viewport)
+    pub fn mesh(&self, texture: egui::TextureId, offset: egui::Vec2) -> egui::Mesh {
+        let ((u0, v0), (u1, v1)) = self.uv;
+        let (u0, u1) = if self.flip.0 { (u1, u0) } else { (u0, u1) };
+        let (v0, v1) = if self.flip.1 { (v1, v0) } else { (v0, v1) };
+        let uvs = [(u0, v0), (u1, v0), (u1, v1), (u0, v1)];
+        let color = egui::Color32::from_rgba_unmultiplied(self.tint[0], self.tint[1], self.tint[2], self.tint[3]);
+
+        let mut mesh = egui::Mesh::with_texture(texture);
+        for ((x, y), (u, v)) in self.corners().into_iter().zip(uvs) {
+            mesh.vertices.push(egui::epaint::Vertex {
+                pos: egui::pos2(x, y) + offset,
+                uv: egui::pos2(u, v),
+                color,
+            });
+        }
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(0, 2, 3);
+        mesh
+    }
+}
+
+/// How an entity's sprite is drawn, from its optional attributes:
+/// `pivot` (Vector2, fraction of the size, defaults to the top left corner where the entity is),
+/// `flip_x` and `flip_y` (Boolean), `tint` (String, "#RRGGBB" or "#RRGGBBAA"),
+/// `opacity` (Float, 0 to 1) and `source_rect` (String, "x y width height" in pixels)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteStyle {
+    pub pivot: (f32, f32),
+    pub flip: (bool, bool),
+    pub tint: [u8; 4],
+    pub opacity: f32,
+    pub source_rect: Option<FrameRect>,
+}
+
+impl Default for SpriteStyle {
+    fn default() -> Self {
+        Self {
+            pivot: (0.0, 0.0),
+            flip: (false, false),
+            tint: [255, 255, 255, 255],
+            opacity: 1.0,
+            source_rect: None,
+        }
+    }
+}
+
+impl SpriteStyle {
+    pub fn from_entity(entity: &Entity) -> Self {
+        let attribute = |name: &str| entity.get_attribute_by_name(name).ok().map(|attr| &attr.value);
+        let flag = |name: &str| matches!(attribute(name), Some(AttributeValue::Boolean(true)));
+        let default = Self::default();
+
+        Self {
+            pivot: match attribute("pivot") {
+                Some(AttributeValue::Vector2(x, y)) => (*x, *y),
+                _ => default.pivot,
+            },
+            flip: (flag("flip_x"), flag("flip_y")),
+            tint: match attribute("tint") {
+                Some(AttributeValue::String(color)) => parse_color(color).unwrap_or(default.tint),
+                _ => default.tint,
+            },
+            opacity: match attribute("opacity") {
+                Some(AttributeValue::Float(opacity)) => opacity.clamp(0.0, 1.0),
+                _ => default.opacity,
+            },
+            source_rect: match attribute("source_rect") {
+                Some(AttributeValue::String(rect)) => parse_frame_rects(rect).ok()
+                    .and_then(|rects| rects.first().copied()),
+                _ => None,
+            },
+        }
+    }
+
+    // RGBA color to draw with, the opacity multiplied into the alpha
+    fn color(&self) -> [u8; 4] {
+        let [r, g, b, a] = self.tint;
+        [r, g, b, (a as f32 * self.opacity).round() as u8]
+    }
+}
+
+/// Parses "#RRGGBB" or "#RRGGBBAA" (the # is optional)
+pub fn parse_color(text: &str) -> Result<[u8; 4], String> {
+    let hex = text.trim().trim_start_matches('#');
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(format!("Invalid color '{}', expected #RRGGBB or #RRGGBBAA", text));
+    }
+    let mut color = [255; 4];
+    for (i, channel) in color.iter_mut().enumerate().take(hex.len() / 2) {
+        *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| format!("Invalid color '{}', expected #RRGGBB or #RRGGBBAA", text))?;
+    }
+    Ok(color)
+}
+
+/// Formats a color as "#RRGGBB", or "#RRGGBBAA" when it isn't opaque
+pub fn format_color(color: [u8; 4]) -> String {
+    let [r, g, b, a] = color;
+    if a == 255 {
+        format!("#{:02X}{:02X}{:02X}", r, g, b)
+    } else {
+        format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a)
+    }
+}
 
 #[derive(Clone)]
 pub struct Camera {
-    pub position: (f32, f32),
+    pub position: (f32, f32), // World position at the top left corner of the screen
     pub zoom: f32,
+    pub rotation: f32, // In radians, the view turns the other way
 }
 
 impl Camera {
@@ -16,6 +180,7 @@ impl Camera {
         Self {
             position: (0.0, 0.0),
             zoom: 1.0,
+            rotation: 0.0,
         }
     }
 
@@ -29,15 +194,27 @@ impl Camera {
     }
 
     pub fn world_to_screen(&self, world_pos: (f32, f32)) -> (f32, f32) {
+        let offset = (world_pos.0 - self.position.0, world_pos.1 - self.position.1);
+        let (sin, cos) = (-self.rotation).sin_cos();
+        (
+            (offset.0 * cos - offset.1 * sin) * self.zoom,
+            (offset.0 * sin + offset.1 * cos) * self.zoom,
+        )
+    }
+
+    pub fn screen_to_world(&self, screen_pos: (f32, f32)) -> (f32, f32) {
+        let scaled = (screen_pos.0 / self.zoom, screen_pos.1 / self.zoom);
+        let (sin, cos) = self.rotation.sin_cos();
         (
-            (world_pos.0 - self.position.0) * self.zoom,
-            (world_pos.1 - self.position.1) * self.zoom,
+            self.position.0 + scaled.0 * cos - scaled.1 * sin,
+            self.position.1 + scaled.0 * sin + scaled.1 * cos,
         )
     }
 
     pub fn reset(&mut self) {
         self.position = (0.0, 0.0);
         self.zoom = 1.0;
+        self.rotation = 0.0;
     }
 }
 
@@ -48,120 +225,34 @@ pub struct TextureInfo {
     pub aspect_ratio: f32,
 }
 
-// Animation support with enhanced controls
-#[derive(Debug)]
-pub struct Animation {
-    frames: Vec<TextureInfo>,
-    frame_duration: f32,
-    current_frame: usize,
-    elapsed_time: f32,
-    is_playing: bool,
-    is_looping: bool,
-    playback_speed: f32,
+/// Bytes used by textures, as reported by `RenderEngine::get_memory_usage`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryUsage {
+    pub cpu_bytes: usize, // Decoded pixels in the texture cache
+    pub gpu_bytes: usize, // Textures uploaded for drawing
 }
 
-impl Animation {
-    pub fn new(frames: Vec<TextureInfo>, frame_duration: f32) -> Self {
-        Self {
-            frames,
-            frame_duration,
-            current_frame: 0,
-            elapsed_time: 0.0,
-            is_playing: true,
-            is_looping: true,
-            playback_speed: 1.0,
-        }
-    }
-
-    pub fn update(&mut self, delta_time: f32) {
-        if !self.is_playing || self.frames.is_empty() {
-            return;
-        }
-
-        self.elapsed_time += delta_time * self.playback_speed;
-
-        if self.elapsed_time >= self.frame_duration {
-            let next_frame = self.current_frame + 1;
-
-            if next_frame >= self.frames.len() {
-                if self.is_looping {
-                    self.current_frame = 0;
-                } else {
-                    self.is_playing = false;
-                    self.current_frame = self.frames.len() - 1; // Stay on last frame
-                }
-            } else {
-                self.current_frame = next_frame;
-            }
-
-            self.elapsed_time = 0.0;
-        }
-    }
-
-    // Control methods
-    pub fn play(&mut self) {
-        self.is_playing = true;
-    }
-
-    pub fn pause(&mut self) {
-        self.is_playing = false;
-    }
-
-    pub fn stop(&mut self) {
-        self.is_playing = false;
-        self.current_frame = 0;
-        self.elapsed_time = 0.0;
-    }
-
-    pub fn set_looping(&mut self, looping: bool) {
-        self.is_looping = looping;
-    }
-
-    pub fn set_speed(&mut self, speed: f32) {
-        self.playback_speed = speed.max(0.0); // Prevent negative speed
-    }
-
-    pub fn set_frame(&mut self, frame: usize) {
-        if frame < self.frames.len() {
-            self.current_frame = frame;
-            self.elapsed_time = 0.0;
-        }
-    }
-
-    // State queries
-    pub fn is_playing(&self) -> bool {
-        self.is_playing
-    }
-
-    pub fn is_finished(&self) -> bool {
-        !self.is_looping && self.current_frame == self.frames.len() - 1
-    }
-
-    pub fn get_current_frame(&self) -> Option<&TextureInfo> {
-        self.frames.get(self.current_frame)
-    }
-
-    pub fn get_frame_count(&self) -> usize {
-        self.frames.len()
-    }
-
-    pub fn get_current_frame_index(&self) -> usize {
-        self.current_frame
-    }
-
-    pub fn get_progress(&self) -> f32 {
-        if self.frames.is_empty() {
-            return 0.0;
-        }
-        self.current_frame as f32 / (self.frames.len() - 1) as f32
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.cpu_bytes + self.gpu_bytes
     }
 }
 
+// How often loaded image files are checked for changes
+const TEXTURE_CHANGE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Clone)]
 pub struct RenderEngine {
     viewport_size: (f32, f32),
     last_frame_time: std::time::Instant,
     pub texture_cache: HashMap<Uuid, TextureInfo>,
+    // Textures uploaded to the GPU on first draw, kept until the texture is unloaded
+    gpu_textures: HashMap<Uuid, egui::TextureHandle>,
+    // File each loaded texture came from and its modification time, to reload changed files
+    texture_sources: HashMap<Uuid, (PathBuf, Option<SystemTime>)>,
+    last_change_check: Instant,
+    // Fonts read for text, None if the file couldn't be loaded
+    font_cache: HashMap<PathBuf, Option<Vec<u8>>>,
     pub camera: Camera,
 }
 
@@ -190,10 +281,68 @@ impl RenderEngine {
 
         let texture = self.load_texture_from_path(path)?;
         self.texture_cache.insert(texture_id, texture);
+        self.texture_sources.insert(texture_id, (path.to_path_buf(), Self::file_modified(path)));
         
         Ok(texture_id)
     }
 
+    fn file_modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// Id of the GPU texture to draw a cached texture with. It is uploaded the first time
+    /// and reused after that, until the texture is unloaded.
+    pub fn gpu_texture(&mut self, ctx: &egui::Context, texture_id: &Uuid) -> Option<egui::TextureId> {
+        if let Some(handle) = self.gpu_textures.get(texture_id) {
+            return Some(handle.id());
+        }
+
+        let texture_info = self.texture_cache.get(texture_id)?;
+        let handle = ctx.load_texture(
+            format!("texture_{}", texture_id),
+            Self::color_image(texture_info),
+            Default::default(),
+        );
+        let id = handle.id();
+        self.gpu_textures.insert(*texture_id, handle);
+        Some(id)
+    }
+
+    fn color_image(texture_info: &TextureInfo) -> egui::ColorImage {
+        egui::ColorImage::from_rgba_unmultiplied(
+            [texture_info.dimensions.0 as usize, texture_info.dimensions.1 as usize],
+            &texture_info.data,
+        )
+    }
+
+    /// Reloads textures whose file changed on disk since it was loaded, updating their
+    /// GPU textures in place. Returns the ids of the reloaded textures.
+    pub fn reload_changed_textures(&mut self) -> Vec<Uuid> {
+        let changed: Vec<(Uuid, PathBuf)> = self.texture_sources
+            .iter()
+            .filter(|(_, (path, modified))| Self::file_modified(path) != *modified)
+            .map(|(texture_id, (path, _))| (*texture_id, path.clone()))
+            .collect();
+
+        let mut reloaded = Vec::new();
+        for (texture_id, path) in changed {
+            // A file that can't be read right now keeps its old texture until it changes again
+            self.texture_sources.insert(texture_id, (path.clone(), Self::file_modified(&path)));
+            match self.load_texture_from_path(&path) {
+                Ok(texture) => {
+                    if let Some(handle) = self.gpu_textures.get_mut(&texture_id) {
+                        handle.set(Self::color_image(&texture), Default::default());
+                    }
+                    self.texture_cache.insert(texture_id, texture);
+                    println!("Reloaded texture: {}", path.to_string_lossy());
+                    reloaded.push(texture_id);
+                }
+                Err(err) => eprintln!("Failed to reload texture: {}", err),
+            }
+        }
+        reloaded
+    }
+
     // Get texture data using path
     pub fn get_texture(&self, path: &Path) -> Option<(&Vec<u8>, (u32, u32))> {
         let texture_id = Self::path_to_uuid(path);
@@ -218,115 +367,380 @@ impl RenderEngine {
     }
 
     // Modified render method to use z coordinate for ordering
-    pub fn render(&mut self, scene: &Scene) -> Vec<(Uuid, (f32, f32), (f32, f32), f32)> {
+    pub fn render(&mut self, scene: &Scene) -> Vec<RenderItem> {
+        self.render_interpolated(scene, &HashMap::new(), &HashMap::new())
+    }
+
+    // Same as render, but entities in `positions` and `rotations` are drawn there instead of at their
+    // world position and rotation, used to draw physics bodies between two fixed steps
+    pub fn render_interpolated(
+        &mut self,
+        scene: &Scene,
+        positions: &HashMap<Uuid, (f32, f32)>,
+        rotations: &HashMap<Uuid, f32>,
+    ) -> Vec<RenderItem> {
+        if self.last_change_check.elapsed() >= TEXTURE_CHANGE_CHECK_INTERVAL {
+            self.last_change_check = Instant::now();
+            self.reload_changed_textures();
+        }
+
         let mut render_queue = Vec::new();
+        // Children are drawn relative to their parents, and follow them between steps
+        let world_transforms = scene.world_transforms_with(positions, rotations);
+
+        // Screen space text is placed in the view of the scene's camera
+        let scene_camera = SceneCamera::active(scene);
 
         for (_, entity) in &scene.entities {
-            if let Ok(image_path) = entity.get_image(0) {
-                let texture_id = Self::path_to_uuid(Path::new(image_path));
-                
-                if !self.texture_cache.contains_key(&texture_id) {
-                    if let Ok(_) = self.load_texture(Path::new(image_path)) {
-                        println!("Loaded texture: {}", image_path.to_string_lossy());
-                    }
+            let transform = world_transforms.get(&entity.id).copied()
+                .unwrap_or_else(|| entity.local_transform());
+
+            if let Some(tilemap) = entity.tilemap.as_deref() {
+                // The tileset image is only drawn through the tiles
+                render_queue.extend(self.tile_items(entity, tilemap, &transform));
+            } else if let Some(item) = self.sprite_item(entity, &transform) {
+                // Viewport culling, on the bounds of the rotated sprite
+                let (min, max) = item.bounds();
+                if min.0 <= self.viewport_size.0
+                    && max.0 >= 0.0
+                    && min.1 <= self.viewport_size.1
+                    && max.1 >= 0.0
+                {
+                    render_queue.push(item);
                 }
+            }
 
-                // Get position including z coordinate
-                let x = entity.get_x();
-                let y = entity.get_y();
-                let z = entity.get_z();
-
-                let transform = Transform {
-                    position: (x, y),
-                    rotation: entity
-                        .get_attribute_by_name("rotation")
-                        .and_then(|attr| match attr.value {
-                            AttributeValue::Float(r) => Ok(r),
-                            _ => Err("Invalid rotation attribute type".to_string())
-                        })
-                        .unwrap_or(0.0),
-
-                    scale: entity
-                        .get_attribute_by_name("scale")
-                        .and_then(|attr| match attr.value {
-                            AttributeValue::Vector2(sx, sy) => Ok((sx, sy)),
-                            _ => Err("Invalid scale attribute type".to_string())
-                        })
-                        .unwrap_or((1.0, 1.0)),
-                };
-
-                if let Some(texture_info) = self.texture_cache.get(&texture_id) {
-                    let screen_pos = self.camera.world_to_screen(transform.position);
-                    let width = texture_info.dimensions.0 as f32 * self.camera.zoom * transform.scale.0;
-                    let height = texture_info.dimensions.1 as f32 * self.camera.zoom * transform.scale.1;
-
-                    // Viewport culling
-                    if screen_pos.0 <= self.viewport_size.0
-                        && screen_pos.0 + width >= 0.0
-                        && screen_pos.1 <= self.viewport_size.1
-                        && screen_pos.1 + height >= 0.0
-                    {
-                        render_queue.push((
-                            texture_id,
-                            screen_pos,
-                            (width, height),
-                            z  // Use z coordinate directly for ordering
-                        ));
-                    }
-                }
+            // Drawn over the entity's sprite, the sort keeps that order
+            if let Some(text) = entity.text.as_deref().filter(|text| !text.text.is_empty()) {
+                render_queue.push(self.text_item(text, &transform, scene_camera.as_ref()));
             }
         }
 
         // Sort by z coordinate (lower z values are rendered first)
-        render_queue.sort_by(|a, b| a.3.partial_cmp(&b.3).unwrap_or(std::cmp::Ordering::Equal));
+        render_queue.sort_by(|a, b| a.z.partial_cmp(&b.z).unwrap_or(std::cmp::Ordering::Equal));
         render_queue
     }
 
-    // collider_data:
-    // - (f32, f32): The world coordinate of the collider (x, y).
-    // - (f32, f32): The size of the collider in world coordinate (width, height).
-    // - String: The shape of the collider (e.g., "Circle", "Rectangle").
-    pub fn render_colliders(
-        &mut self,
-        collider_data: &[((f32, f32), (f32, f32), String)],
-    ) -> Vec<((f32, f32), (f32, f32), String)> {
+    // The entity's sprite on screen, if it has an image that could be loaded
+    fn sprite_item(&mut self, entity: &Entity, transform: &Transform) -> Option<RenderItem> {
+        let style = SpriteStyle::from_entity(entity);
+        // Animated entities draw the region of their current frame, others their source rect
+        // of the first image
+        let frame = match &entity.animation {
+            Some(animation) => animation.source.frame(animation.current_frame(), &entity.images),
+            None => None,
+        };
+        let (image_path, region) = match frame {
+            Some(frame) => frame,
+            None => (
+                entity.get_image(0).ok()?.as_path(),
+                style.source_rect.map_or(FrameRegion::Whole, FrameRegion::Rect),
+            ),
+        };
+
+        let texture_id = Self::path_to_uuid(image_path);
+        if !self.texture_cache.contains_key(&texture_id) {
+            if let Ok(_) = self.load_texture(image_path) {
+                println!("Loaded texture: {}", image_path.to_string_lossy());
+            }
+        }
+        let texture_info = self.texture_cache.get(&texture_id)?;
+
+        let frame_size = region.pixel_size(texture_info.dimensions);
+        let world_size = (frame_size.0 * transform.scale.0, frame_size.1 * transform.scale.1);
+        let width = world_size.0 * self.camera.zoom;
+        let height = world_size.1 * self.camera.zoom;
+        let pivot = (style.pivot.0 * width, style.pivot.1 * height);
+
+        // The pivot stays in place when rotating, so the sprite is placed from it
+        let world_pivot = (
+            transform.position.0 + style.pivot.0 * world_size.0,
+            transform.position.1 + style.pivot.1 * world_size.1,
+        );
+        let screen_pivot = self.camera.world_to_screen(world_pivot);
+
+        Some(RenderItem {
+            texture_id,
+            position: (screen_pivot.0 - pivot.0, screen_pivot.1 - pivot.1),
+            size: (width, height),
+            z: transform.z,  // Use z coordinate directly for ordering
+            uv: region.uv_rect(texture_info.dimensions),
+            rotation: transform.rotation - self.camera.rotation,
+            pivot,
+            flip: style.flip,
+            tint: style.color(),
+            text: None,
+        })
+    }
+
+    // Visible tiles of a tilemap on screen, layer by layer. Only the cells under the viewport
+    // are looked at, so large maps cost what is on screen.
+    fn tile_items(&mut self, entity: &Entity, tilemap: &Tilemap, transform: &Transform) -> Vec<RenderItem> {
+        let Some(image_path) = entity.images.get(tilemap.tileset.image) else {
+            return Vec::new();
+        };
+        let texture_id = Self::path_to_uuid(image_path);
+        if !self.texture_cache.contains_key(&texture_id) && self.load_texture(image_path).is_ok() {
+            println!("Loaded texture: {}", image_path.to_string_lossy());
+        }
+        if !self.texture_cache.contains_key(&texture_id) || tilemap.width == 0 || tilemap.height == 0 {
+            return Vec::new();
+        }
+
+        // Cells under the corners of the viewport bound the cells to draw
+        let (width, height) = self.viewport_size;
+        let mut min = (f32::INFINITY, f32::INFINITY);
+        let mut max = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for corner in [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)] {
+            let cell = tilemap.local_point(transform, self.camera.screen_to_world(corner));
+            min = (min.0.min(cell.0), min.1.min(cell.1));
+            max = (max.0.max(cell.0), max.1.max(cell.1));
+        }
+        let x_range = (min.0.floor().max(0.0) as i32)..=(max.0.floor().min(tilemap.width as f32 - 1.0) as i32);
+        let y_range = (min.1.floor().max(0.0) as i32)..=(max.1.floor().min(tilemap.height as f32 - 1.0) as i32);
+
+        let style = SpriteStyle::from_entity(entity);
+        let size = (
+            tilemap.tile_size.0 * transform.scale.0 * self.camera.zoom,
+            tilemap.tile_size.1 * transform.scale.1 * self.camera.zoom,
+        );
+        let tile_count = tilemap.tileset.tile_count();
+
+        let mut items = Vec::new();
+        for (index, layer) in tilemap.layers.iter().enumerate() {
+            if !layer.visible {
+                continue;
+            }
+            for y in y_range.clone() {
+                for x in x_range.clone() {
+                    let Some(tile) = tilemap.get(index, x, y).filter(|tile| *tile < tile_count) else {
+                        continue;
+                    };
+                    items.push(RenderItem {
+                        texture_id,
+                        position: self.camera.world_to_screen(tilemap.cell_corner(transform, x, y)),
+                        size,
+                        z: transform.z,
+                        uv: tilemap.tileset.uv_rect(tile),
+                        rotation: transform.rotation - self.camera.rotation,
+                        pivot: (0.0, 0.0),
+                        flip: (false, false),
+                        tint: style.color(),
+                        text: None,
+                    });
+                }
+            }
+        }
+        items
+    }
+
+    // An entity's text on screen. It isn't culled, its size is only known once it's laid out.
+    fn text_item(&self, text: &Text, transform: &Transform, scene_camera: Option<&SceneCamera>) -> RenderItem {
+        let scale = transform.scale.1;
+        let (position, size, rotation) = match (text.space, scene_camera) {
+            // Pixels of the camera's view, turned and zoomed with it
+            (TextSpace::Screen, Some(view)) => {
+                let (sin, cos) = view.rotation.sin_cos();
+                let (x, y) = (transform.position.0 / view.zoom, transform.position.1 / view.zoom);
+                let top_left = view.corners()[0];
+                let world = (top_left.0 + x * cos - y * sin, top_left.1 + x * sin + y * cos);
+                (
+                    self.camera.world_to_screen(world),
+                    text.size * scale / view.zoom * self.camera.zoom,
+                    view.rotation + transform.rotation - self.camera.rotation,
+                )
+            }
+            // Without a camera the screen is the viewport
+            (TextSpace::Screen, None) => (transform.position, text.size * scale, transform.rotation),
+            (TextSpace::World, _) => (
+                self.camera.world_to_screen(transform.position),
+                text.size * scale * self.camera.zoom,
+                transform.rotation - self.camera.rotation,
+            ),
+        };
+        let screen_scale = size / text.size.max(f32::EPSILON);
+
+        RenderItem {
+            texture_id: Uuid::nil(),
+            position,
+            size: (0.0, 0.0),
+            z: transform.z,
+            uv: ((0.0, 0.0), (1.0, 1.0)),
+            rotation,
+            pivot: (0.0, 0.0),
+            flip: (false, false),
+            tint: text.color,
+            text: Some(Box::new(TextDraw {
+                text: text.text.clone(),
+                font: text.font.clone(),
+                size,
+                align: text.align,
+                outline: text.outline.map(|outline| TextOutline { width: outline.width * screen_scale, ..outline }),
+            })),
+        }
+    }
+
+    /// Draws an item of the render queue, moved by `offset` (e.g. the top left corner of the viewport)
+    pub fn paint(&mut self, painter: &egui::Painter, item: &RenderItem, offset: egui::Vec2) {
+        match &item.text {
+            Some(text) => self.paint_text(painter, item, text, offset),
+            // Uploaded once and reused on later frames
+            None => {
+                if let Some(texture) = self.gpu_texture(painter.ctx(), &item.texture_id) {
+                    painter.add(item.mesh(texture, offset));
+                }
+            }
+        }
+    }
+
+    fn paint_text(&mut self, painter: &egui::Painter, item: &RenderItem, text: &TextDraw, offset: egui::Vec2) {
+        // egui can't draw text this small, and it isn't readable anyway
+        if text.size < 0.5 {
+            return;
+        }
+        let family = match &text.font {
+            Some(font) => self.font_family(painter.ctx(), font),
+            None => egui::FontFamily::Proportional,
+        };
+        let color = |[r, g, b, a]: [u8; 4]| egui::Color32::from_rgba_unmultiplied(r, g, b, a);
+
+        // Lines are aligned around the position, which is where the text turns about
+        let mut job = egui::text::LayoutJob::single_section(
+            text.text.clone(),
+            egui::TextFormat::simple(egui::FontId::new(text.size, family), color(item.tint)),
+        );
+        job.halign = match text.align {
+            TextAlign::Left => egui::Align::LEFT,
+            TextAlign::Center => egui::Align::Center,
+            TextAlign::Right => egui::Align::RIGHT,
+        };
+        let galley = painter.layout_job(job);
+        let position = egui::pos2(item.position.0, item.position.1) + offset;
+
+        // The outline is the text drawn around itself in its color
+        if let Some(outline) = text.outline.filter(|outline| outline.width > 0.0) {
+            let directions = 8;
+            for i in 0..directions {
+                let angle = i as f32 * std::f32::consts::TAU / directions as f32;
+                let shift = egui::vec2(angle.cos(), angle.sin()) * outline.width;
+                painter.add(
+                    egui::epaint::TextShape::new(position + shift, galley.clone(), color(outline.color))
+                        .with_override_text_color(color(outline.color))
+                        .with_angle(item.rotation),
+                );
+            }
+        }
+        painter.add(egui::epaint::TextShape::new(position, galley, color(item.tint)).with_angle(item.rotation));
+    }
+
+    /// Font family of a font file. The font is added to egui's fonts the first time it is used,
+    /// which only takes effect on the next frame, so the default font is used until then.
+    pub fn font_family(&mut self, ctx: &egui::Context, path: &Path) -> egui::FontFamily {
+        let name = path.to_string_lossy().to_string();
+        let family = egui::FontFamily::Name(name.as_str().into());
+        if ctx.fonts(|fonts| fonts.families().contains(&family)) {
+            return family;
+        }
+
+        let data = self.font_cache.entry(path.to_path_buf()).or_insert_with(|| match Self::load_font(path) {
+            Ok(data) => {
+                println!("Loaded font: {}", path.to_string_lossy());
+                Some(data)
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                None
+            }
+        });
+
+        if let Some(data) = data {
+            // Other render engines may have added fonts too, so start from the context's fonts.
+            // Characters missing from the font are drawn with the default ones.
+            let mut definitions = ctx.fonts(|fonts| fonts.lock().fonts.definitions().clone());
+            let mut fallbacks = definitions.families.get(&egui::FontFamily::Proportional).cloned().unwrap_or_default();
+            fallbacks.insert(0, name.clone());
+            definitions.font_data.insert(name, egui::FontData::from_owned(data.clone()));
+            definitions.families.insert(family, fallbacks);
+            ctx.set_fonts(definitions);
+        }
+        egui::FontFamily::Proportional
+    }
+
+    // Reads a font file, checking it is a TrueType or OpenType font since egui panics on other data
+    fn load_font(path: &Path) -> Result<Vec<u8>, String> {
+        let data = fs::read(path).map_err(|e| format!("Failed to load font {}: {}", path.display(), e))?;
+        let font_tags: [&[u8]; 4] = [&[0, 1, 0, 0], b"OTTO", b"true", b"ttcf"];
+        if !font_tags.iter().any(|tag| data.starts_with(tag)) {
+            return Err(format!("Failed to load font {}: not a TrueType or OpenType font", path.display()));
+        }
+        Ok(data)
+    }
+
+    // Transforms colliders to screen space and culls the ones outside the viewport.
+    // Positions, sizes and points of the returned colliders are in screen coordinates.
+    pub fn render_colliders(&mut self, collider_data: &[ColliderData]) -> Vec<ColliderData> {
         let mut render_queue = Vec::new();
 
-        for &(world_position, world_size, ref shape) in collider_data {
-            // Transform position to screen space
-            let screen_position = self.camera.world_to_screen(world_position);
+        for collider in collider_data {
+            // Transform position and outline to screen space
+            let screen_position = self.camera.world_to_screen(collider.position);
+            let screen_points: Vec<(f32, f32)> = collider.points.iter()
+                .map(|&point| self.camera.world_to_screen(point))
+                .collect();
 
             // Adjust size based on zoom level
             let screen_size = (
-                world_size.0 * self.camera.zoom,
-                world_size.1 * self.camera.zoom,
+                collider.size.0 * self.camera.zoom,
+                collider.size.1 * self.camera.zoom,
             );
 
-            // Perform viewport culling
-            if screen_position.0 + screen_size.0 >= 0.0
-                && screen_position.0 <= self.viewport_size.0
-                && screen_position.1 + screen_size.1 >= 0.0
-                && screen_position.1 <= self.viewport_size.1
+            // Perform viewport culling, the bounding radius covers any rotation
+            let radius = (screen_size.0.powi(2) + screen_size.1.powi(2)).sqrt() / 2.0;
+            if screen_position.0 + radius >= 0.0
+                && screen_position.0 - radius <= self.viewport_size.0
+                && screen_position.1 + radius >= 0.0
+                && screen_position.1 - radius <= self.viewport_size.1
             {
-                render_queue.push((screen_position, screen_size, shape.clone()));
+                render_queue.push(ColliderData {
+                    position: screen_position,
+                    rotation: collider.rotation - self.camera.rotation,
+                    size: screen_size,
+                    points: screen_points,
+                    ..collider.clone()
+                });
             }
         }
 
         render_queue
     }
 
+    // Joints with their anchors in screen space
+    pub fn render_joints(&self, joint_data: &[JointData]) -> Vec<JointData> {
+        joint_data.iter()
+            .map(|joint| JointData {
+                anchor1: self.camera.world_to_screen(joint.anchor1),
+                anchor2: self.camera.world_to_screen(joint.anchor2),
+                ..joint.clone()
+            })
+            .collect()
+    }
+
     pub fn new() -> Self {
         Self {
             viewport_size: (0.0, 0.0),
             last_frame_time: std::time::Instant::now(),
             texture_cache: HashMap::new(),
+            gpu_textures: HashMap::new(),
+            texture_sources: HashMap::new(),
+            last_change_check: Instant::now(),
+            font_cache: HashMap::new(),
             camera: Camera::new(),
         }
     }
 
     // Memory management
     pub fn cleanup_direct_textures(&mut self) {
-        self.texture_cache.clear();
+        self.clear_cache();
     }
 
     // Keep existing methods unchanged
@@ -340,26 +754,36 @@ impl RenderEngine {
 
     // Full cleanup including camera reset
     pub fn cleanup(&mut self) {
-        self.texture_cache.clear();
+        self.clear_cache();
         self.camera.reset();
     }
 
-    // Remove specific texture
+    // Remove specific texture, dropping its GPU texture frees it
     pub fn unload_texture(&mut self, path: &Path) {
         let texture_id = Self::path_to_uuid(path);
         self.texture_cache.remove(&texture_id);
+        self.gpu_textures.remove(&texture_id);
+        self.texture_sources.remove(&texture_id);
     }
 
     // Just clear caches
     pub fn clear_cache(&mut self) {
         self.texture_cache.clear();
+        self.gpu_textures.clear();
+        self.texture_sources.clear();
+        self.font_cache.clear();
     }
 
     // Monitor memory usage
-    pub fn get_memory_usage(&self) -> usize {
-        self.texture_cache.values()
-            .map(|tex| tex.data.len())
-            .sum()
+    pub fn get_memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            cpu_bytes: self.texture_cache.values().map(|tex| tex.data.len()).sum(),
+            gpu_bytes: self.gpu_textures.values().map(|handle| handle.byte_size()).sum(),
+        }
+    }
+
+    pub fn gpu_texture_count(&self) -> usize {
+        self.gpu_textures.len()
     }
 
     // Add this public method
@@ -420,25 +844,17 @@ impl RenderEngine {
     pub fn get_game_camera_bounds(&self, scene: &Scene) -> Vec<((f32, f32), (f32, f32))> {
         let mut lines = Vec::new();
         
-        if let Some(camera_id) = scene.default_camera {
-            if let Ok(camera_entity) = scene.get_entity(camera_id) {
-                let x = camera_entity.get_x();
-                let y = camera_entity.get_y();
-                let width = camera_entity.get_camera_width();
-                let height = camera_entity.get_camera_height();
-                
-                // Convert game camera bounds to screen space using editor camera
-                let top_left = self.camera.world_to_screen((x - width/2.0, y - height/2.0));
-                let top_right = self.camera.world_to_screen((x + width/2.0, y - height/2.0));
-                let bottom_left = self.camera.world_to_screen((x - width/2.0, y + height/2.0));
-                let bottom_right = self.camera.world_to_screen((x + width/2.0, y + height/2.0));
-                
-                // Add the lines for the rectangle
-                lines.push((top_left, top_right));
-                lines.push((bottom_left, bottom_right));
-                lines.push((top_left, bottom_left));
-                lines.push((top_right, bottom_right));
-            }
+        if let Some(scene_camera) = SceneCamera::active(scene) {
+            // Convert the area the game camera sees, with its zoom and rotation,
+            // to screen space using editor camera
+            let [top_left, top_right, bottom_right, bottom_left] = scene_camera.corners()
+                .map(|corner| self.camera.world_to_screen(corner));
+
+            // Add the lines for the rectangle
+            lines.push((top_left, top_right));
+            lines.push((bottom_left, bottom_right));
+            lines.push((top_left, bottom_left));
+            lines.push((top_right, bottom_right));
         }
         
         lines
@@ -464,42 +880,3 @@ impl RenderEngine {
         }
     }
 }
-
-// Transform component for positioning, scaling, and rotating
-#[derive(Debug, Clone, Copy)]
-pub struct Transform {
-    pub position: (f32, f32),
-    pub rotation: f32, // In radians
-    pub scale: (f32, f32),
-}
-
-impl Transform {
-    pub fn new() -> Self {
-        Self {
-            position: (0.0, 0.0),
-            rotation: 0.0,
-            scale: (1.0, 1.0),
-        }
-    }
-
-    pub fn with_position(mut self, x: f32, y: f32) -> Self {
-        self.position = (x, y);
-        self
-    }
-
-    pub fn with_rotation(mut self, angle: f32) -> Self {
-        self.rotation = angle;
-        self
-    }
-
-    pub fn with_scale(mut self, sx: f32, sy: f32) -> Self {
-        self.scale = (sx, sy);
-        self
-    }
-
-    // Helper for uniform scaling
-    pub fn with_uniform_scale(mut self, scale: f32) -> Self {
-        self.scale = (scale, scale);
-        self
-    }
-}
@@ -0,0 +1,281 @@
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+// Scene file format
+// └── scenes/index.json lists the scenes and their files, each scene is in scenes/<name>.scene and
+//     the shared entities in scenes/shared_entities.json. Up to format 2 all of it was in a single
+//     scenes/scene_manager.json document, which is still loaded and split up on the next save.
+// └── Every file starts with `format_version`, files saved before it existed are version 1
+// └── Loading runs the migrations from the file's version up to FORMAT_VERSION on the raw JSON,
+//     before it is deserialized, so old projects keep loading after Entity or AttributeValue change.
+//     The next save keeps a copy of each older file it replaces, e.g. scenes/menu.scene.v2.bak.
+//     Migrations see a scene_manager.json document, a scene file is wrapped into one holding only
+//     that scene (scene_file_document) and a prefab file into one holding only that prefab
+//     (prefab_file_document).
+// └── Changing the format: bump FORMAT_VERSION, register a migration from the previous version
+//     in MIGRATIONS and add a file of the previous version to tests/scene_corpus
+
+pub const FORMAT_VERSION: u32 = 3;
+pub const FORMAT_VERSION_KEY: &str = "format_version";
+
+/// Upgrades the JSON of a scene_manager.json document by one version
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// The migration registered for version n upgrades a file of version n to n + 1
+pub const MIGRATIONS: &[(u32, &str, Migration)] = &[
+    (1, "move position and transform_* attributes into x, y, z, rotation and scale", migrate_v1_transforms),
+    (2, "key entity attributes by name instead of by id", key_attributes_by_name),
+];
+
+/// Version of a scene file, 1 when it has none
+pub fn format_version(json: &Value) -> Result<u32, String> {
+    match json.get(FORMAT_VERSION_KEY) {
+        None => Ok(1),
+        Some(version) => version.as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= 1)
+            .ok_or_else(|| format!("Invalid {}: {}", FORMAT_VERSION_KEY, version)),
+    }
+}
+
+/// Brings a scene file up to FORMAT_VERSION. Returns the version the file had.
+pub fn migrate(json: &mut Value) -> Result<u32, String> {
+    if !json.is_object() {
+        return Err("The scene file is not a JSON object".to_string());
+    }
+    let version = format_version(json)?;
+    if version > FORMAT_VERSION {
+        return Err(format!(
+            "The scene file was saved by a newer version of the engine (format {}, this engine reads up to format {})",
+            version, FORMAT_VERSION
+        ));
+    }
+
+    for (from, description, migration) in MIGRATIONS.iter().filter(|(from, _, _)| *from >= version) {
+        migration(json).map_err(|e| format!("Failed to migrate the scene file from format {} ({}): {}", from, description, e))?;
+    }
+    set_format_version(json);
+    Ok(version)
+}
+
+/// Marks a scene file as being of the current version, before it is written
+pub fn set_format_version(json: &mut Value) {
+    if let Some(object) = json.as_object_mut() {
+        object.shift_remove(FORMAT_VERSION_KEY);
+        // First in the file, so it can be seen at a glance
+        let mut versioned = Map::new();
+        versioned.insert(FORMAT_VERSION_KEY.to_string(), json!(FORMAT_VERSION));
+        versioned.append(object);
+        *object = versioned;
+    }
+}
+
+/// A scene file as a document holding only that scene, under its id in the index
+pub fn scene_file_document(id: Uuid, mut scene: Value) -> Value {
+    let version = scene.as_object_mut().and_then(|scene| scene.shift_remove(FORMAT_VERSION_KEY));
+    let mut document = Map::new();
+    if let Some(version) = version {
+        document.insert(FORMAT_VERSION_KEY.to_string(), version);
+    }
+    document.insert("scenes".to_string(), json!({ id.to_string(): scene }));
+    Value::Object(document)
+}
+
+/// Takes the scene back out of a document made by scene_file_document
+pub fn take_scene(document: &mut Value, id: Uuid) -> Option<Value> {
+    document.get_mut("scenes")?.as_object_mut()?.shift_remove(&id.to_string())
+}
+
+/// A prefab file as a document holding only that prefab
+pub fn prefab_file_document(mut prefab: Value) -> Value {
+    let version = prefab.as_object_mut().and_then(|prefab| prefab.shift_remove(FORMAT_VERSION_KEY));
+    let mut document = Map::new();
+    if let Some(version) = version {
+        document.insert(FORMAT_VERSION_KEY.to_string(), version);
+    }
+    document.insert("prefabs".to_string(), json!([prefab]));
+    Value::Object(document)
+}
+
+/// Takes the prefab back out of a document made by prefab_file_document
+pub fn take_prefab(document: &mut Value) -> Option<Value> {
+    document.get_mut("prefabs")?.as_array_mut()?.pop()
+}
+
+/// Keys the attributes of every entity by name and drops their ids, like scene files store them.
+/// Ids only identify attributes while a project is open, saved they changed with every new attribute.
+pub fn key_attributes_by_name(json: &mut Value) -> Result<(), String> {
+    for entity in entities_mut(json) {
+        let Some(attributes) = entity.get_mut("attributes").and_then(Value::as_object_mut) else {
+            continue;
+        };
+        let mut by_name = Map::new();
+        for (_, mut attribute) in std::mem::take(attributes) {
+            let Some(fields) = attribute.as_object_mut() else {
+                return Err("An attribute is not a JSON object".to_string());
+            };
+            fields.shift_remove("id");
+            let name = match fields.shift_remove("name") {
+                Some(Value::String(name)) => name,
+                _ => return Err("An attribute has no name".to_string()),
+            };
+            if by_name.insert(name.clone(), attribute).is_some() {
+                return Err(format!("Attribute '{}' appears twice", name));
+            }
+        }
+        *attributes = by_name;
+    }
+    Ok(())
+}
+
+/// Gives the attributes of every entity a new id again, before the JSON is deserialized
+pub fn key_attributes_by_id(json: &mut Value) {
+    for entity in entities_mut(json) {
+        let Some(attributes) = entity.get_mut("attributes").and_then(Value::as_object_mut) else {
+            continue;
+        };
+        let mut by_id = Map::new();
+        for (name, attribute) in std::mem::take(attributes) {
+            let id = Uuid::new_v4().to_string();
+            let mut fields = Map::new();
+            fields.insert("id".to_string(), json!(id));
+            fields.insert("name".to_string(), json!(name));
+            if let Value::Object(mut rest) = attribute {
+                fields.append(&mut rest);
+            }
+            by_id.insert(id, Value::Object(fields));
+        }
+        *attributes = by_id;
+    }
+}
+
+// Entities of the scenes, the shared entities and the prefabs
+fn entities_mut(json: &mut Value) -> Vec<&mut Value> {
+    let mut entities = Vec::new();
+    let Some(object) = json.as_object_mut() else {
+        return entities;
+    };
+    for (key, value) in object.iter_mut() {
+        match (key.as_str(), value) {
+            ("scenes", Value::Object(scenes)) => {
+                for scene in scenes.values_mut() {
+                    if let Some(Value::Object(scene_entities)) = scene.get_mut("entities") {
+                        entities.extend(scene_entities.values_mut());
+                    }
+                }
+            }
+            ("shared_entities", Value::Object(shared)) => entities.extend(shared.values_mut()),
+            ("prefabs", Value::Array(prefabs)) => {
+                entities.extend(prefabs.iter_mut().filter_map(|prefab| prefab.get_mut("entity")));
+            }
+            _ => {}
+        }
+    }
+    entities
+}
+
+// Version 1 kept the physics position in a `position` vector and the camera's transform
+// in `transform_position_*`, `transform_rotation_*` and `transform_scale_*` floats
+fn migrate_v1_transforms(json: &mut Value) -> Result<(), String> {
+    for entity in entities_mut(json) {
+        migrate_entity_transform(entity)?;
+    }
+    Ok(())
+}
+
+/// Moves the legacy transform attributes of an entity's JSON into x, y, z, rotation and scale.
+/// Returns true if anything changed.
+pub fn migrate_entity_transform(entity: &mut Value) -> Result<bool, String> {
+    let Some(attributes) = entity.get_mut("attributes").and_then(Value::as_object_mut) else {
+        return Ok(false);
+    };
+    let legacy: Vec<String> = attributes.iter()
+        .filter(|(_, attribute)| attribute_name(attribute).is_some_and(|name| name == "position" || name.starts_with("transform_")))
+        .map(|(key, _)| key.clone())
+        .collect();
+    if legacy.is_empty() {
+        return Ok(false);
+    }
+
+    let mut position = None;
+    let mut floats: Vec<(&str, f32)> = Vec::new();
+    let mut scale = vector2_value(attributes, "scale").unwrap_or((1.0, 1.0));
+    let mut scaled = false;
+    for key in legacy {
+        let Some(attribute) = attributes.shift_remove(&key) else {
+            continue;
+        };
+        let value = &attribute["value"];
+        match (attribute_name(&attribute).unwrap_or_default(), float_of(value), vector2_of(value)) {
+            ("position", _, Some(vector)) => position = Some(vector),
+            ("transform_position_x", Some(x), _) => floats.push(("x", x)),
+            ("transform_position_y", Some(y), _) => floats.push(("y", y)),
+            ("transform_position_z", Some(z), _) => floats.push(("z", z)),
+            // Like Entity::set_local_transform, rotation and scale are only added when they aren't the default
+            ("transform_rotation_z", Some(rotation), _) if rotation != 0.0 || find_attribute(attributes, "rotation").is_some() => {
+                floats.push(("rotation", rotation));
+            }
+            ("transform_scale_x", Some(x), _) => (scale.0, scaled) = (x, true),
+            ("transform_scale_y", Some(y), _) => (scale.1, scaled) = (y, true),
+            // Rotations out of the 2D plane and the z scale have no meaning here
+            _ => {}
+        }
+    }
+
+    for (name, value) in floats {
+        set_attribute(attributes, name, "Float", json!({ "Float": value }))?;
+    }
+    // x and y are what the editor showed, `position` only fills in an entity left at the origin
+    let at_origin = float_value(attributes, "x").unwrap_or(0.0) == 0.0 && float_value(attributes, "y").unwrap_or(0.0) == 0.0;
+    if let Some((x, y)) = position.filter(|_| at_origin) {
+        set_attribute(attributes, "x", "Float", json!({ "Float": x }))?;
+        set_attribute(attributes, "y", "Float", json!({ "Float": y }))?;
+    }
+    if scaled && (scale != (1.0, 1.0) || find_attribute(attributes, "scale").is_some()) {
+        set_attribute(attributes, "scale", "Vector2", json!({ "Vector2": [scale.0, scale.1] }))?;
+    }
+    Ok(true)
+}
+
+fn attribute_name(attribute: &Value) -> Option<&str> {
+    attribute.get("name").and_then(Value::as_str)
+}
+
+fn float_of(value: &Value) -> Option<f32> {
+    value.get("Float").and_then(Value::as_f64).map(|value| value as f32)
+}
+
+fn vector2_of(value: &Value) -> Option<(f32, f32)> {
+    match value.get("Vector2").and_then(Value::as_array)?.as_slice() {
+        [x, y] => Some((x.as_f64()? as f32, y.as_f64()? as f32)),
+        _ => None,
+    }
+}
+
+fn find_attribute<'a>(attributes: &'a Map<String, Value>, name: &str) -> Option<&'a Value> {
+    attributes.values().find(|attribute| attribute_name(attribute) == Some(name))
+}
+
+fn float_value(attributes: &Map<String, Value>, name: &str) -> Option<f32> {
+    find_attribute(attributes, name).and_then(|attribute| float_of(&attribute["value"]))
+}
+
+fn vector2_value(attributes: &Map<String, Value>, name: &str) -> Option<(f32, f32)> {
+    find_attribute(attributes, name).and_then(|attribute| vector2_of(&attribute["value"]))
+}
+
+// Sets the value of the attribute called `name`, adding it if the entity has none
+fn set_attribute(attributes: &mut Map<String, Value>, name: &str, data_type: &str, value: Value) -> Result<(), String> {
+    match attributes.values_mut().find(|attribute| attribute_name(attribute) == Some(name)) {
+        Some(attribute) if attribute.get("data_type").and_then(Value::as_str) == Some(data_type) => {
+            attribute["value"] = value;
+            Ok(())
+        }
+        Some(_) => Err(format!("Attribute '{}' is not a {}", name, data_type)),
+        None => {
+            let id = Uuid::new_v4().to_string();
+            attributes.insert(id.clone(), json!({ "id": id, "name": name, "data_type": data_type, "value": value }));
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Text (component of an Entity)
+// └── Drawn at the entity's transform, in z order with the sprites
+// └── World space text moves with the camera, screen space text (HUD) stays in the camera's view
+
+/// Which side of the text is at the entity's position, lines are aligned the same way
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    pub const ALL: [TextAlign; 3] = [TextAlign::Left, TextAlign::Center, TextAlign::Right];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextAlign::Left => "left",
+            TextAlign::Center => "center",
+            TextAlign::Right => "right",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|align| align.as_str() == name)
+    }
+}
+
+/// What the entity's position is relative to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TextSpace {
+    /// A position in the world, like sprites
+    #[default]
+    World,
+    /// Pixels from the top left corner of the camera's view, at the camera's width and height
+    Screen,
+}
+
+impl TextSpace {
+    pub const ALL: [TextSpace; 2] = [TextSpace::World, TextSpace::Screen];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextSpace::World => "world",
+            TextSpace::Screen => "screen",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|space| space.as_str() == name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TextOutline {
+    pub width: f32,
+    pub color: [u8; 4], // RGBA
+}
+
+/// Text drawn by an entity. The size is the height of a line in pixels, scaled by
+/// the entity's vertical scale.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Text {
+    pub text: String,
+    pub font: Option<PathBuf>, // Font asset, the editor's font if None
+    pub size: f32,
+    pub color: [u8; 4], // RGBA
+    pub align: TextAlign,
+    pub outline: Option<TextOutline>,
+    pub space: TextSpace,
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            font: None,
+            size: 24.0,
+            color: [255, 255, 255, 255],
+            align: TextAlign::Left,
+            outline: None,
+            space: TextSpace::World,
+        }
+    }
+}
+
+impl Text {
+    pub fn new(text: &str) -> Self {
+        Self { text: text.to_string(), ..Self::default() }
+    }
+}
@@ -0,0 +1,334 @@
+use serde::{Deserialize, Serialize};
+use crate::ecs::Transform;
+
+// Tilemap (component of an Entity)
+// └── Tileset: one of the entity's images sliced into a grid of tiles,
+//     numbered from 0 left to right, then top to bottom
+// └── TileLayer: a tile number (or nothing) per cell, layers are drawn in order
+//      └── Saved as runs of tiles, e.g. "40*. 3*7 8 12*." for 40 empty cells, three 7s, an 8...
+// Tiles of collision layers are solid; neighbouring solid tiles are merged into rectangles.
+
+/// A tile number in the tileset, None for an empty cell
+pub type Tile = Option<u32>;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tileset {
+    pub image: usize, // Index in the entity's images
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl Default for Tileset {
+    fn default() -> Self {
+        Self { image: 0, columns: 1, rows: 1 }
+    }
+}
+
+impl Tileset {
+    pub fn tile_count(&self) -> u32 {
+        self.columns.max(1) * self.rows.max(1)
+    }
+
+    /// Texture coordinates (min, max) of a tile in the tileset image
+    pub fn uv_rect(&self, tile: u32) -> ((f32, f32), (f32, f32)) {
+        let (columns, rows) = (self.columns.max(1), self.rows.max(1));
+        let (column, row) = ((tile % columns) as f32, (tile / columns) as f32);
+        let (width, height) = (1.0 / columns as f32, 1.0 / rows as f32);
+        ((column * width, row * height), ((column + 1.0) * width, (row + 1.0) * height))
+    }
+}
+
+// Layers are only read as part of a tilemap, which bounds their runs by its size
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TileLayer {
+    pub name: String,
+    pub visible: bool,
+    pub collision: bool, // Its tiles are solid
+    #[serde(serialize_with = "serialize_tile_runs")]
+    pub tiles: Vec<Tile>, // Row by row, width * height of the tilemap
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+impl TileLayer {
+    pub fn new(name: &str, cell_count: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            visible: true,
+            collision: false,
+            tiles: vec![None; cell_count],
+        }
+    }
+}
+
+/// Grid of `width` by `height` tiles whose top left corner is at the entity's position.
+/// Tiles are `tile_size` in the world, scaled and turned with the entity.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "TilemapFile")]
+pub struct Tilemap {
+    pub tileset: Tileset,
+    pub tile_size: (f32, f32),
+    pub width: u32,
+    pub height: u32,
+    pub layers: Vec<TileLayer>,
+}
+
+// A tilemap as saved, its layers are still runs of tiles
+#[derive(Deserialize)]
+struct TilemapFile {
+    tileset: Tileset,
+    tile_size: (f32, f32),
+    width: u32,
+    height: u32,
+    layers: Vec<TileLayerFile>,
+}
+
+#[derive(Deserialize)]
+struct TileLayerFile {
+    name: String,
+    #[serde(default = "default_visible")]
+    visible: bool,
+    #[serde(default)]
+    collision: bool,
+    tiles: String,
+}
+
+impl TryFrom<TilemapFile> for Tilemap {
+    type Error = String;
+
+    fn try_from(file: TilemapFile) -> Result<Self, String> {
+        let cell_count = cell_count(file.width, file.height)?;
+        let layers = file.layers.into_iter()
+            .map(|layer| Ok(TileLayer {
+                tiles: parse_tile_runs(&layer.tiles, cell_count)
+                    .map_err(|e| format!("Invalid tile layer '{}': {}", layer.name, e))?,
+                name: layer.name,
+                visible: layer.visible,
+                collision: layer.collision,
+            }))
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            tileset: file.tileset,
+            tile_size: file.tile_size,
+            width: file.width,
+            height: file.height,
+            layers,
+        })
+    }
+}
+
+/// Number of cells of a `width` by `height` map
+pub fn cell_count(width: u32, height: u32) -> Result<usize, String> {
+    width.checked_mul(height)
+        .map(|count| count as usize)
+        .ok_or_else(|| format!("A {} by {} tilemap has too many tiles", width, height))
+}
+
+impl Tilemap {
+    /// Empty tilemap with a single collision layer
+    pub fn new(width: u32, height: u32, tile_size: (f32, f32)) -> Result<Self, String> {
+        let mut ground = TileLayer::new("ground", cell_count(width, height)?);
+        ground.collision = true;
+        Ok(Self {
+            tileset: Tileset::default(),
+            tile_size,
+            width,
+            height,
+            layers: vec![ground],
+        })
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let inside = x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height;
+        inside.then(|| y as usize * self.width as usize + x as usize)
+    }
+
+    pub fn get(&self, layer: usize, x: i32, y: i32) -> Tile {
+        let index = self.index(x, y)?;
+        self.layers.get(layer)?.tiles.get(index).copied().flatten()
+    }
+
+    /// Sets a tile, returning whether it changed. Cells outside the map are ignored.
+    pub fn set(&mut self, layer: usize, x: i32, y: i32, tile: Tile) -> bool {
+        let Ok(cell_count) = cell_count(self.width, self.height) else {
+            return false;
+        };
+        let (Some(index), Some(layer)) = (self.index(x, y), self.layers.get_mut(layer)) else {
+            return false;
+        };
+        // Layers loaded from a file may be shorter than the map
+        layer.tiles.resize(cell_count, None);
+        let changed = layer.tiles[index] != tile;
+        layer.tiles[index] = tile;
+        changed
+    }
+
+    /// Replaces the area of equal tiles around a cell, connected by their sides
+    pub fn fill(&mut self, layer: usize, x: i32, y: i32, tile: Tile) -> bool {
+        if self.index(x, y).is_none() || layer >= self.layers.len() {
+            return false;
+        }
+        let target = self.get(layer, x, y);
+        if target == tile {
+            return false;
+        }
+
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            if self.index(x, y).is_none() || self.get(layer, x, y) != target {
+                continue;
+            }
+            self.set(layer, x, y, tile);
+            stack.extend([(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
+        }
+        true
+    }
+
+    /// Sets every tile of the rectangle between two corner cells, clamped to the map
+    pub fn fill_rect(&mut self, layer: usize, from: (i32, i32), to: (i32, i32), tile: Tile) -> bool {
+        if self.width == 0 || self.height == 0 {
+            return false;
+        }
+        let (max_x, max_y) = (self.width as i32 - 1, self.height as i32 - 1);
+        let (x0, x1) = (from.0.min(to.0).max(0), from.0.max(to.0).min(max_x));
+        let (y0, y1) = (from.1.min(to.1).max(0), from.1.max(to.1).min(max_y));
+
+        let mut changed = false;
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                changed |= self.set(layer, x, y, tile);
+            }
+        }
+        changed
+    }
+
+    /// Changes the size of the map, tiles keep their cell and the ones outside are dropped
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), String> {
+        let cell_count = cell_count(width, height)?;
+        let (old_width, old_height) = (self.width as usize, self.height as usize);
+        let (width, height) = (width as usize, height as usize);
+        for layer in &mut self.layers {
+            let mut tiles = vec![None; cell_count];
+            for y in 0..height.min(old_height) {
+                for x in 0..width.min(old_width) {
+                    tiles[y * width + x] = layer.tiles.get(y * old_width + x).copied().flatten();
+                }
+            }
+            layer.tiles = tiles;
+        }
+        self.width = width as u32;
+        self.height = height as u32;
+        Ok(())
+    }
+
+    pub fn add_layer(&mut self, name: &str) -> Result<(), String> {
+        self.layers.push(TileLayer::new(name, cell_count(self.width, self.height)?));
+        Ok(())
+    }
+
+    /// Cell under a world point, for a tilemap with the given world transform.
+    /// It may be outside the map.
+    pub fn cell_at(&self, transform: &Transform, point: (f32, f32)) -> (i32, i32) {
+        let local = self.local_point(transform, point);
+        (local.0.floor() as i32, local.1.floor() as i32)
+    }
+
+    /// A world point in cells from the top left corner of the map, fractional inside a cell
+    pub fn local_point(&self, transform: &Transform, point: (f32, f32)) -> (f32, f32) {
+        let local = Transform::new().with_position(point.0, point.1).relative_to(transform).position;
+        let safe_div = |value: f32, size: f32| if size == 0.0 { 0.0 } else { value / size };
+        (safe_div(local.0, self.tile_size.0), safe_div(local.1, self.tile_size.1))
+    }
+
+    /// World position of the top left corner of a cell
+    pub fn cell_corner(&self, transform: &Transform, x: i32, y: i32) -> (f32, f32) {
+        Transform::new()
+            .with_position(x as f32 * self.tile_size.0, y as f32 * self.tile_size.1)
+            .combine(transform)
+            .position
+    }
+
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        self.layers.iter().enumerate()
+            .any(|(index, layer)| layer.collision && self.get(index, x, y).is_some())
+    }
+
+    /// Solid tiles merged into few rectangles (x, y, width, height) of cells. Each rectangle is
+    /// grown as wide as possible, then down as long as the whole row below is solid.
+    pub fn solid_rects(&self) -> Vec<(u32, u32, u32, u32)> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut covered = vec![false; width * height];
+        let free = |covered: &[bool], x: usize, y: usize| !covered[y * width + x] && self.is_solid(x as i32, y as i32);
+
+        let mut rects = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                if !free(&covered, x, y) {
+                    continue;
+                }
+                let mut rect_width = 1;
+                while x + rect_width < width && free(&covered, x + rect_width, y) {
+                    rect_width += 1;
+                }
+                let mut rect_height = 1;
+                while y + rect_height < height
+                    && (x..x + rect_width).all(|column| free(&covered, column, y + rect_height))
+                {
+                    rect_height += 1;
+                }
+
+                for row in y..y + rect_height {
+                    covered[row * width + x..row * width + x + rect_width].fill(true);
+                }
+                rects.push((x as u32, y as u32, rect_width as u32, rect_height as u32));
+            }
+        }
+        rects
+    }
+}
+
+/// Formats tiles as runs, e.g. "3*. 2*4 7" for three empty cells, two 4s and a 7
+pub fn format_tile_runs(tiles: &[Tile]) -> String {
+    let mut runs: Vec<String> = Vec::new();
+    let mut cells = tiles.iter().peekable();
+    while let Some(tile) = cells.next() {
+        let mut count = 1;
+        while cells.peek() == Some(&tile) {
+            cells.next();
+            count += 1;
+        }
+        let tile = tile.map_or(".".to_string(), |tile| tile.to_string());
+        runs.push(if count == 1 { tile } else { format!("{}*{}", count, tile) });
+    }
+    runs.join(" ")
+}
+
+/// Parses tiles written by `format_tile_runs`, at most `max_tiles` of them
+pub fn parse_tile_runs(text: &str, max_tiles: usize) -> Result<Vec<Tile>, String> {
+    let mut tiles = Vec::new();
+    for run in text.split_whitespace() {
+        let (count, tile) = match run.split_once('*') {
+            Some((count, tile)) => (
+                count.parse::<usize>().map_err(|_| format!("Invalid tile count in '{}'", run))?,
+                tile,
+            ),
+            None => (1, run),
+        };
+        let tile = match tile {
+            "." => None,
+            tile => Some(tile.parse::<u32>().map_err(|_| format!("Invalid tile '{}'", run))?),
+        };
+        if count > max_tiles - tiles.len() {
+            return Err(format!("More than {} tiles", max_tiles));
+        }
+        tiles.extend(std::iter::repeat_n(tile, count));
+    }
+    Ok(tiles)
+}
+
+// Layers are saved as a string of tile runs instead of a long array
+fn serialize_tile_runs<S: serde::Serializer>(tiles: &[Tile], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_tile_runs(tiles))
+}
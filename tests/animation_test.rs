@@ -0,0 +1,188 @@
+use rust_2d_game_engine::animation::*;
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::render_engine::{RenderEngine, TextureInfo};
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Bird with a 4x2 sprite sheet and two clips
+    fn bird_animation() -> SpriteAnimation {
+        let mut animation = SpriteAnimation::new(FrameSource::Grid { image: 0, columns: 4, rows: 2, count: Some(6) });
+        animation.add_clip("idle", AnimationClip::new(vec![0, 1], 0.5)).unwrap();
+        animation.add_clip("flap", AnimationClip::new(vec![2, 3, 4], 0.1).with_loop_mode(LoopMode::Once)).unwrap();
+        animation.autoplay = Some("idle".to_string());
+        animation
+    }
+
+    #[test]
+    fn test_per_frame_durations_and_once() {
+        let mut animation = Animation::with_frame_durations(vec![0.1, 0.3, 0.1]);
+        animation.set_loop_mode(LoopMode::Once);
+
+        assert!(!animation.update(0.15));
+        assert_eq!(animation.get_current_frame_index(), 1);
+        assert!(!animation.update(0.2));
+        assert_eq!(animation.get_current_frame_index(), 1);
+        assert!(!animation.update(0.1));
+        assert_eq!(animation.get_current_frame_index(), 2);
+
+        // Finishes once and stays on the last frame
+        assert!(animation.update(0.1));
+        assert!(animation.is_finished());
+        assert!(!animation.is_playing());
+        assert_eq!(animation.get_current_frame_index(), 2);
+        assert!(!animation.update(1.0));
+
+        // Playing a finished animation starts it over
+        animation.play();
+        assert_eq!(animation.get_current_frame_index(), 0);
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn test_loop_and_ping_pong() {
+        let mut looping = Animation::new(3, 0.1);
+        let mut ping_pong = Animation::new(3, 0.1);
+        ping_pong.set_loop_mode(LoopMode::PingPong);
+
+        let mut looped = Vec::new();
+        let mut bounced = Vec::new();
+        for _ in 0..6 {
+            looping.update(0.1);
+            ping_pong.update(0.1);
+            looped.push(looping.get_current_frame_index());
+            bounced.push(ping_pong.get_current_frame_index());
+        }
+        assert_eq!(looped, vec![1, 2, 0, 1, 2, 0]);
+        assert_eq!(bounced, vec![1, 2, 1, 0, 1, 2]);
+
+        // A long frame time skips frames instead of lagging behind
+        let mut animation = Animation::new(4, 0.1);
+        animation.update(0.25);
+        assert_eq!(animation.get_current_frame_index(), 2);
+    }
+
+    #[test]
+    fn test_frame_sources() {
+        let images = vec![PathBuf::from("sheet.png"), PathBuf::from("other.png")];
+
+        let grid = FrameSource::Grid { image: 0, columns: 4, rows: 2, count: Some(6) };
+        assert_eq!(grid.frame_count(images.len()), 6);
+        let (path, region) = grid.frame(5, &images).unwrap();
+        assert_eq!(path, Path::new("sheet.png"));
+        assert_eq!(region.uv_rect((64, 32)), ((0.25, 0.5), (0.5, 1.0)));
+        assert_eq!(region.pixel_size((64, 32)), (16.0, 16.0));
+        assert!(grid.frame(6, &images).is_none());
+
+        let rects = FrameSource::Rects { image: 1, rects: vec![FrameRect::new(16, 0, 16, 32)] };
+        let (path, region) = rects.frame(0, &images).unwrap();
+        assert_eq!(path, Path::new("other.png"));
+        assert_eq!(region.uv_rect((64, 32)), ((0.25, 0.0), (0.5, 1.0)));
+        assert_eq!(region.pixel_size((64, 32)), (16.0, 32.0));
+
+        let (path, region) = FrameSource::Images.frame(1, &images).unwrap();
+        assert_eq!(path, Path::new("other.png"));
+        assert_eq!(region, FrameRegion::Whole);
+
+        // A missing sheet image has no frames to draw
+        let missing = FrameSource::Grid { image: 3, columns: 2, rows: 1, count: None };
+        assert!(missing.frame(0, &images).is_none());
+    }
+
+    #[test]
+    fn test_clips_and_autoplay() {
+        let mut animation = bird_animation();
+        // The first frame of the autoplay clip is shown before the game starts
+        assert_eq!(animation.current_frame(), 0);
+        assert_eq!(animation.current_clip(), None);
+
+        animation.update(0.5);
+        assert_eq!(animation.current_clip(), Some("idle"));
+        assert_eq!(animation.current_frame(), 1);
+
+        animation.play("flap").unwrap();
+        assert_eq!(animation.current_frame(), 2);
+        assert_eq!(animation.update(0.1), None);
+        // Playing the clip that is already playing doesn't restart it
+        animation.play("flap").unwrap();
+        assert_eq!(animation.current_frame(), 3);
+        assert_eq!(animation.update(0.2), Some("flap".to_string()));
+        assert_eq!(animation.current_frame(), 4);
+        assert!(!animation.is_playing());
+
+        assert!(animation.play("dive").is_err());
+        assert!(animation.add_clip("empty", AnimationClip::new(Vec::new(), 0.1)).is_err());
+        assert!(animation.add_clip("frozen", AnimationClip::new(vec![0], 0.0)).is_err());
+    }
+
+    #[test]
+    fn test_parse_frames_and_rects() {
+        assert_eq!(parse_frame_list("0, 2-4, 1").unwrap(), vec![0, 2, 3, 4, 1]);
+        assert_eq!(parse_frame_list("3-1").unwrap(), vec![3, 2, 1]);
+        assert!(parse_frame_list("0, a").is_err());
+        assert_eq!(format_frame_list(&[0, 2, 3]), "0, 2, 3");
+
+        let rects = parse_frame_rects("0 0 16 16, 16 0 16 16").unwrap();
+        assert_eq!(rects, vec![FrameRect::new(0, 0, 16, 16), FrameRect::new(16, 0, 16, 16)]);
+        assert_eq!(parse_frame_rects(&format_frame_rects(&rects)).unwrap(), rects);
+        assert!(parse_frame_rects("0 0 16").is_err());
+    }
+
+    #[test]
+    fn test_scene_reports_finished_clips() {
+        let mut scene = Scene::new("main").unwrap();
+        let bird_id = scene.create_entity("bird").unwrap();
+        let bird = scene.get_entity_mut(bird_id).unwrap();
+        bird.animation = Some(Box::new(bird_animation()));
+        bird.animation.as_mut().unwrap().play("flap").unwrap();
+
+        assert!(scene.update_animations(0.25).is_empty());
+        let finished = scene.update_animations(0.1);
+        assert_eq!(finished, vec![AnimationFinished { entity_id: bird_id, clip: "flap".to_string() }]);
+        assert!(scene.update_animations(0.1).is_empty());
+    }
+
+    #[test]
+    fn test_render_draws_current_frame() {
+        let mut scene = Scene::new("main").unwrap();
+        let bird_id = scene.create_entity("bird").unwrap();
+        let bird = scene.get_entity_mut(bird_id).unwrap();
+        bird.images = vec![PathBuf::from("bird_sheet.png")];
+        bird.animation = Some(Box::new(bird_animation()));
+        bird.animation.as_mut().unwrap().play("flap").unwrap();
+
+        let mut renderer = RenderEngine::new();
+        renderer.update_viewport_size(800.0, 600.0);
+        let texture_id = RenderEngine::path_to_uuid(Path::new("bird_sheet.png"));
+        renderer.texture_cache.insert(texture_id, TextureInfo {
+            data: vec![255; 64 * 32 * 4],
+            dimensions: (64, 32),
+            aspect_ratio: 2.0,
+        });
+
+        let queue = renderer.render(&scene);
+        let item = queue.iter().find(|item| item.texture_id == texture_id).unwrap();
+        assert_eq!(item.size, (16.0, 16.0));
+        assert_eq!(item.uv, ((0.5, 0.0), (0.75, 0.5)));
+    }
+
+    #[test]
+    fn test_entity_without_animation_still_loads() {
+        let mut entity = Entity::new(uuid::Uuid::new_v4(), "bird").unwrap();
+        let mut json = serde_json::to_value(&entity).unwrap();
+        json.as_object_mut().unwrap().remove("animation");
+        let loaded: Entity = serde_json::from_value(json).unwrap();
+        assert!(loaded.animation.is_none());
+
+        // Playback isn't saved, only the component
+        entity.animation = Some(Box::new(bird_animation()));
+        entity.animation.as_mut().unwrap().play("flap").unwrap();
+        let loaded: Entity = serde_json::from_str(&serde_json::to_string(&entity).unwrap()).unwrap();
+        let animation = loaded.animation.unwrap();
+        assert_eq!(animation.current_clip(), None);
+        assert_eq!(animation.clips["flap"].loop_mode, LoopMode::Once);
+        assert_eq!(animation.source, FrameSource::Grid { image: 0, columns: 4, rows: 2, count: Some(6) });
+    }
+}
@@ -85,4 +85,19 @@ mod tests {
         assert!(audio_engine.is_stopped(sound_id), "Sound should be stopped after cleanup");
         assert_eq!(audio_engine.get_memory_usage(), 0, "Cache should be empty after cleanup");
     }
+
+    #[test]
+    fn test_null_backend() {
+        let mut audio_engine = AudioEngine::new_null();
+        assert!(audio_engine.is_null());
+
+        // Sounds are still loaded and decoded, but finish right away
+        let sound_id = audio_engine.play_sound(Path::new(TEST_AUDIO_FILE)).expect("Failed to play sound");
+        assert!(!audio_engine.is_playing(sound_id));
+        assert!(audio_engine.is_stopped(sound_id));
+        assert!(audio_engine.get_memory_usage() > 0);
+
+        assert!(audio_engine.play_sound(Path::new("tests/missing.mp3")).is_err());
+        audio_engine.cleanup();
+    }
 }
@@ -0,0 +1,174 @@
+use rust_2d_game_engine::camera::*;
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::gui::edit_history::{EditCommand, EditHistory};
+use rust_2d_game_engine::render_engine::{RenderEngine, TextureInfo};
+use std::f32::consts::FRAC_PI_2;
+use std::path::Path;
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3
+    }
+
+    fn camera(center: (f32, f32)) -> SceneCamera {
+        SceneCamera { center, size: (800.0, 600.0), zoom: 1.0, rotation: 0.0 }
+    }
+
+    fn set_attribute(entity: &mut Entity, name: &str, data_type: AttributeType, value: AttributeValue) {
+        match entity.get_attribute_by_name(name).map(|attr| attr.id) {
+            Ok(id) => entity.modify_attribute(id, None, None, Some(value)).unwrap(),
+            Err(_) => {
+                entity.create_attribute(name, data_type, value).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_letterboxed_view() {
+        let view = camera((100.0, 50.0));
+
+        // A wider viewport gets bars on the sides
+        let (render_camera, rect) = view.view((1000.0, 600.0));
+        assert_eq!(rect, (100.0, 0.0, 800.0, 600.0));
+        assert!(close(render_camera.world_to_screen((100.0, 50.0)), (400.0, 300.0)));
+        assert!(close(render_camera.world_to_screen((-300.0, -250.0)), (0.0, 0.0)));
+
+        // A taller one on the top and bottom, scaled down to fit
+        let (render_camera, rect) = view.view((400.0, 600.0));
+        assert_eq!(rect, (0.0, 150.0, 400.0, 300.0));
+        assert_eq!(render_camera.zoom, 0.5);
+        assert!(close(render_camera.world_to_screen((100.0, 50.0)), (200.0, 150.0)));
+    }
+
+    #[test]
+    fn test_zoom_and_rotation() {
+        let mut view = camera((0.0, 0.0));
+        view.zoom = 2.0;
+        assert_eq!(view.visible_size(), (400.0, 300.0));
+        let (render_camera, _) = view.view((800.0, 600.0));
+        assert!(close(render_camera.world_to_screen((100.0, 0.0)), (600.0, 300.0)));
+
+        // Turning the camera a quarter turn makes the world turn the other way on screen
+        view.zoom = 1.0;
+        view.rotation = FRAC_PI_2;
+        let (render_camera, _) = view.view((800.0, 600.0));
+        assert!(close(render_camera.world_to_screen((0.0, 0.0)), (400.0, 300.0)));
+        assert!(close(render_camera.world_to_screen((0.0, 100.0)), (500.0, 300.0)));
+        assert!(close(render_camera.screen_to_world((500.0, 300.0)), (0.0, 100.0)));
+    }
+
+    #[test]
+    fn test_follow_dead_zone_and_smoothing() {
+        let mut follow = CameraFollow { target: None, offset: (0.0, 0.0), dead_zone: (100.0, 50.0), smoothing: 0.0, bounds: None };
+        let visible = (800.0, 600.0);
+
+        // Inside the dead zone nothing moves, outside it the target is kept on its edge
+        assert_eq!(follow.next_center((0.0, 0.0), Some((40.0, -20.0)), visible, 0.1), (0.0, 0.0));
+        assert_eq!(follow.next_center((0.0, 0.0), Some((80.0, -40.0)), visible, 0.1), (30.0, -15.0));
+
+        // Smoothing catches up part of the way each frame
+        follow.dead_zone = (0.0, 0.0);
+        follow.smoothing = 0.5;
+        let center = follow.next_center((0.0, 0.0), Some((100.0, 0.0)), visible, 0.5);
+        assert!((center.0 - 100.0 * (1.0 - (-1.0f32).exp())).abs() < 1e-3);
+        let center = follow.next_center(center, Some((100.0, 0.0)), visible, 0.5);
+        assert!(center.0 > 80.0 && center.0 < 100.0);
+    }
+
+    #[test]
+    fn test_bounds_clamping() {
+        let follow = CameraFollow {
+            target: None,
+            offset: (0.0, 0.0),
+            dead_zone: (0.0, 0.0),
+            smoothing: 0.0,
+            bounds: Some(((0.0, 0.0), (2000.0, 400.0))),
+        };
+
+        // The view stays inside the bounds, or centered on them when they are smaller
+        assert_eq!(follow.next_center((0.0, 0.0), Some((-500.0, 0.0)), (800.0, 600.0), 0.1), (400.0, 200.0));
+        assert_eq!(follow.next_center((0.0, 0.0), Some((1900.0, 0.0)), (800.0, 600.0), 0.1), (1600.0, 200.0));
+        assert_eq!(follow.next_center((1000.0, 0.0), None, (800.0, 600.0), 0.1), (1000.0, 200.0));
+    }
+
+    #[test]
+    fn test_update_follow_moves_camera() {
+        let mut scene = Scene::new("main").unwrap();
+        let camera_id = scene.default_camera.unwrap();
+        let player_id = scene.create_entity("player").unwrap();
+        scene.get_entity_mut(player_id).unwrap().set_position(500.0, 20.0, 0.0).unwrap();
+
+        // The target can be given by name, as typed in the inspector
+        let camera = scene.get_entity_mut(camera_id).unwrap();
+        set_attribute(camera, "follow_target", AttributeType::String, AttributeValue::String("player".to_string()));
+        set_attribute(camera, "follow_offset", AttributeType::Vector2, AttributeValue::Vector2(16.0, 16.0));
+        let follow = CameraFollow::from_entity(&scene, scene.get_entity(camera_id).unwrap());
+        assert_eq!(follow.target, Some(player_id));
+
+        update_follow(&mut scene, 1.0 / 60.0).unwrap();
+        let camera = scene.get_entity(camera_id).unwrap();
+        assert_eq!((camera.get_x(), camera.get_y()), (516.0, 36.0));
+
+        // A camera with a parent keeps its attributes relative to it
+        let rig_id = scene.create_entity("rig").unwrap();
+        scene.get_entity_mut(rig_id).unwrap().set_position(100.0, 0.0, 0.0).unwrap();
+        scene.set_parent(camera_id, Some(rig_id)).unwrap();
+        scene.get_entity_mut(player_id).unwrap().set_position(300.0, 0.0, 0.0).unwrap();
+        update_follow(&mut scene, 1.0 / 60.0).unwrap();
+        assert_eq!(scene.world_transform(camera_id).unwrap().position, (316.0, 16.0));
+        assert_eq!(scene.get_entity(camera_id).unwrap().get_x(), 216.0);
+    }
+
+    #[test]
+    fn test_render_through_scene_camera() {
+        let mut scene = Scene::new("main").unwrap();
+        let camera_id = scene.default_camera.unwrap();
+        scene.get_entity_mut(camera_id).unwrap().set_position(1000.0, 1000.0, 0.0).unwrap();
+        let entity_id = scene.create_entity("tree").unwrap();
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        entity.images = vec![Path::new("tree.png").to_path_buf()];
+        entity.set_position(1000.0, 1000.0, 0.0).unwrap();
+
+        let mut renderer = RenderEngine::new();
+        let texture_id = RenderEngine::path_to_uuid(Path::new("tree.png"));
+        renderer.texture_cache.insert(texture_id, TextureInfo { data: vec![255; 10 * 10 * 4], dimensions: (10, 10), aspect_ratio: 1.0 });
+        let (render_camera, rect) = SceneCamera::active(&scene).unwrap().view((1600.0, 1200.0));
+        renderer.camera = render_camera;
+        renderer.update_viewport_size(rect.2, rect.3);
+
+        // The sprite at the camera's center is drawn in the middle, scaled to the viewport
+        let queue = renderer.render(&scene);
+        let item = queue.iter().find(|item| item.texture_id == texture_id).unwrap();
+        assert!(close(item.position, (800.0, 600.0)));
+        assert_eq!(item.size, (20.0, 20.0));
+
+        // The editor draws what the camera sees
+        let bounds = RenderEngine::new().get_game_camera_bounds(&scene);
+        assert!(close(bounds[0].0, (600.0, 700.0)));
+        assert!(close(bounds[1].1, (1400.0, 1300.0)));
+    }
+
+    #[test]
+    fn test_set_default_camera() {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("main").unwrap();
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let main_camera = scene.default_camera.unwrap();
+        let other_camera = scene.create_camera("other_camera").unwrap();
+        let player = scene.create_entity("player").unwrap();
+        assert!(scene.set_default_camera(player).is_err());
+        assert!(scene.set_default_camera(Uuid::new_v4()).is_err());
+
+        let mut history = EditHistory::default();
+        let command = EditCommand::set_default_camera(&scene_manager, scene_id, other_camera).unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().default_camera, Some(other_camera));
+
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().default_camera, Some(main_camera));
+    }
+}
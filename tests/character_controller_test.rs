@@ -0,0 +1,223 @@
+mod common;
+
+use rust_2d_game_engine::character_controller::*;
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::gui::edit_history::{EditCommand, EditHistory};
+use rust_2d_game_engine::physics_engine::PhysicsEngine;
+use uuid::Uuid;
+use common::{add_body, position_of};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(size: (f32, f32)) -> PhysicsProperties {
+        PhysicsProperties { collider_size: size, ..Default::default() }
+    }
+
+    // A 20x40 character standing on a 1000x20 floor whose top is at y = 0
+    fn character_scene(controller: CharacterController) -> (Scene, Uuid) {
+        let mut scene = Scene::new("test_scene").unwrap();
+        add_body(&mut scene, "floor", (0.0, 10.0), wall((1000.0, 20.0)));
+        let props = PhysicsProperties { affected_by_gravity: true, ..wall((20.0, 40.0)) };
+        let character_id = add_body(&mut scene, "character", (0.0, -21.0), props);
+        scene.get_entity_mut(character_id).unwrap().character_controller = Some(controller);
+        (scene, character_id)
+    }
+
+    // Moves the character by `translation` on every step, returning the last movement
+    fn walk(physics_engine: &mut PhysicsEngine, scene: &mut Scene, character_id: Uuid, translation: (f32, f32), steps: usize) -> CharacterMovement {
+        let mut movement = CharacterMovement::default();
+        for _ in 0..steps {
+            movement = physics_engine.move_and_slide(character_id, translation).unwrap();
+            physics_engine.step(scene);
+        }
+        movement
+    }
+
+    #[test]
+    fn test_body_types() {
+        assert_eq!(BodyType::from_name(" Kinematic_Velocity "), Some(BodyType::KinematicVelocity));
+        assert_eq!(BodyType::from_name(""), None);
+        for body_type in BodyType::ALL {
+            assert_eq!(BodyType::from_name(body_type.as_str()), Some(body_type));
+        }
+
+        let mut scene = Scene::new("test_scene").unwrap();
+        let movable = PhysicsProperties { is_movable: true, ..Default::default() };
+        let dynamic_id = add_body(&mut scene, "dynamic", (0.0, 0.0), movable.clone());
+        let fixed_id = add_body(&mut scene, "fixed", (100.0, 0.0), Default::default());
+        let kinematic = PhysicsProperties { body_type: Some(BodyType::KinematicPosition), ..movable };
+        let kinematic_id = add_body(&mut scene, "kinematic", (200.0, 0.0), kinematic);
+        let (mut character_scene, character_id) = character_scene(CharacterController::default());
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        assert_eq!(physics_engine.body_type(&dynamic_id), Some(BodyType::Dynamic));
+        assert_eq!(physics_engine.body_type(&fixed_id), Some(BodyType::Fixed));
+        assert_eq!(physics_engine.body_type(&kinematic_id), Some(BodyType::KinematicPosition));
+
+        // A character's body is kinematic, whatever its body type
+        let entity = character_scene.get_entity_mut(character_id).unwrap();
+        let body_type_id = entity.get_attribute_by_name("body_type").unwrap().id;
+        entity.modify_attribute(body_type_id, None, None, Some(AttributeValue::String("dynamic".to_string()))).unwrap();
+        physics_engine.load_scene(&character_scene);
+        assert_eq!(physics_engine.body_type(&character_id), Some(BodyType::KinematicPosition));
+    }
+
+    #[test]
+    fn test_kinematic_velocity_body() {
+        // Moves at its velocity, without falling and without being stopped by a dynamic body
+        let mut scene = Scene::new("test_scene").unwrap();
+        let props = PhysicsProperties {
+            body_type: Some(BodyType::KinematicVelocity),
+            affected_by_gravity: true,
+            ..wall((20.0, 20.0))
+        };
+        let platform_id = add_body(&mut scene, "platform", (0.0, 0.0), props);
+        let entity = scene.get_entity_mut(platform_id).unwrap();
+        let velocity_id = entity.get_attribute_by_name("velocity").unwrap().id;
+        entity.modify_attribute(velocity_id, None, None, Some(AttributeValue::Vector2(60.0, 0.0))).unwrap();
+        let crate_props = PhysicsProperties { is_movable: true, ..wall((20.0, 20.0)) };
+        let crate_id = add_body(&mut scene, "crate", (40.0, 0.0), crate_props);
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.set_contact_parameters(5.0, 30.0);
+        physics_engine.load_scene(&scene);
+        for _ in 0..60 {
+            physics_engine.step(&mut scene);
+        }
+
+        let position = position_of(&physics_engine, platform_id);
+        assert!((position.0 - 60.0).abs() < 0.5 && position.1.abs() < 0.01, "The platform is at {:?}", position);
+        assert!(position_of(&physics_engine, crate_id).0 > 75.0, "The platform should push the crate");
+    }
+
+    #[test]
+    fn test_move_and_slide_on_the_floor() {
+        let (mut scene, character_id) = character_scene(CharacterController::default());
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        assert!(!physics_engine.is_grounded(&character_id));
+
+        // Pushing down into the floor while walking right, it slides along it
+        let movement = walk(&mut physics_engine, &mut scene, character_id, (2.0, 2.0), 30);
+        assert!(movement.grounded);
+        assert!(physics_engine.is_grounded(&character_id));
+        assert!((movement.translation.0 - 2.0).abs() < 0.01 && movement.translation.1.abs() < 0.5);
+        let position = position_of(&physics_engine, character_id);
+        assert!((position.0 - 60.0).abs() < 0.5, "The character is at {:?}", position);
+        assert!(position.1 < -20.0 && position.1 > -21.5, "The character should stand on the floor, it is at {:?}", position);
+
+        // Jumping up leaves the ground
+        let movement = walk(&mut physics_engine, &mut scene, character_id, (0.0, -10.0), 1);
+        assert!(!movement.grounded);
+    }
+
+    #[test]
+    fn test_walls_stop_characters() {
+        let (mut scene, character_id) = character_scene(CharacterController::default());
+        add_body(&mut scene, "wall", (100.0, -50.0), wall((20.0, 100.0)));
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+
+        walk(&mut physics_engine, &mut scene, character_id, (5.0, 1.0), 60);
+        let position = position_of(&physics_engine, character_id);
+        // The wall's left side is at x = 90, the character is 20 wide
+        assert!(position.0 < 80.0 && position.0 > 79.0, "The character should stop at the wall, it is at {:?}", position);
+    }
+
+    #[test]
+    fn test_slope_limit() {
+        // A 60 degree slope going up to the right of x = 100
+        let slope_scene = |max_slope: f32| {
+            let (mut scene, character_id) = character_scene(CharacterController { max_slope, ..Default::default() });
+            let slope_id = add_body(&mut scene, "slope", (200.0, 0.0), wall((200.0, 200.0)));
+            let entity = scene.get_entity_mut(slope_id).unwrap();
+            entity.create_attribute("rotation", AttributeType::Float, AttributeValue::Float(-60f32.to_radians())).unwrap();
+            let mut physics_engine = PhysicsEngine::new();
+            physics_engine.load_scene(&scene);
+            walk(&mut physics_engine, &mut scene, character_id, (3.0, 1.0), 60);
+            position_of(&physics_engine, character_id)
+        };
+
+        let blocked = slope_scene(45.0);
+        assert!(blocked.1 > -22.0, "The character should not climb the slope, it is at {:?}", blocked);
+        let climbed = slope_scene(70.0);
+        assert!(climbed.1 < -35.0, "The character should climb the slope, it is at {:?}", climbed);
+    }
+
+    #[test]
+    fn test_step_height() {
+        // A 10 high step starting at x = 50
+        let step_scene = |step_height: f32| {
+            let (mut scene, character_id) = character_scene(CharacterController { step_height, ..Default::default() });
+            add_body(&mut scene, "step", (150.0, -5.0), wall((200.0, 10.0)));
+            let mut physics_engine = PhysicsEngine::new();
+            physics_engine.load_scene(&scene);
+            walk(&mut physics_engine, &mut scene, character_id, (3.0, 1.0), 40);
+            position_of(&physics_engine, character_id)
+        };
+
+        let blocked = step_scene(0.0);
+        assert!(blocked.0 < 41.0, "The character should stop at the step, it is at {:?}", blocked);
+        let climbed = step_scene(15.0);
+        assert!(climbed.0 > 100.0 && climbed.1 < -30.0, "The character should be on the step, it is at {:?}", climbed);
+    }
+
+    #[test]
+    fn test_snap_to_ground() {
+        // Walking down a 20 degree slope
+        let downhill = |snap_to_ground: f32| {
+            let mut scene = Scene::new("test_scene").unwrap();
+            let slope_id = add_body(&mut scene, "slope", (0.0, 100.0), wall((400.0, 200.0)));
+            scene.get_entity_mut(slope_id).unwrap()
+                .create_attribute("rotation", AttributeType::Float, AttributeValue::Float(20f32.to_radians())).unwrap();
+            let props = PhysicsProperties { collider_shape: ColliderShape::Capsule, ..wall((20.0, 40.0)) };
+            let character_id = add_body(&mut scene, "character", (0.0, -40.0), props);
+            let controller = CharacterController { snap_to_ground, ..Default::default() };
+            scene.get_entity_mut(character_id).unwrap().character_controller = Some(controller);
+
+            let mut physics_engine = PhysicsEngine::new();
+            physics_engine.load_scene(&scene);
+            // Falls onto the slope, then walks down it falling slower than the slope goes down
+            assert!(walk(&mut physics_engine, &mut scene, character_id, (0.0, 2.0), 30).grounded);
+            let mut grounded_steps = 0;
+            for _ in 0..20 {
+                grounded_steps += walk(&mut physics_engine, &mut scene, character_id, (3.0, 0.5), 1).grounded as usize;
+            }
+            grounded_steps
+        };
+
+        assert_eq!(downhill(4.0), 20);
+        assert!(downhill(0.0) < 5, "Without snapping, the character should leave the ground");
+    }
+
+    #[test]
+    fn test_character_controller_errors_and_undo() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let crate_id = add_body(&mut scene, "crate", (0.0, 0.0), Default::default());
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        assert!(physics_engine.move_and_slide(crate_id, (1.0, 0.0)).is_err());
+        assert!(physics_engine.move_and_slide(Uuid::new_v4(), (1.0, 0.0)).is_err());
+
+        // Only the max slope is required when loading
+        let controller: CharacterController = serde_json::from_str("{}").unwrap();
+        assert_eq!((controller.max_slope, controller.step_height, controller.snap_to_ground), (45.0, 0.0, 0.0));
+
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("test_scene").unwrap();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("player").unwrap();
+        let mut history = EditHistory::default();
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        let command = EditCommand::set_character_controller(entity, scene_id, Some(CharacterController::default()));
+        assert_eq!(command.description(), "Add character controller");
+        history.execute(command, &mut scene_manager).unwrap();
+
+        let controller = |scene_manager: &SceneManager| scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().character_controller.clone();
+        assert_eq!(controller(&scene_manager), Some(CharacterController::default()));
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(controller(&scene_manager), None);
+    }
+}
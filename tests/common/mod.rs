@@ -0,0 +1,25 @@
+// Fixtures shared by the physics test files, each file uses only some of them
+#![allow(dead_code)]
+
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::physics_engine::PhysicsEngine;
+use uuid::Uuid;
+
+pub fn add_body(scene: &mut Scene, name: &str, position: (f32, f32), props: PhysicsProperties) -> Uuid {
+    scene.create_physical_entity(name, (position.0, position.1, 0.0), props).unwrap()
+}
+
+// A 10x10 body that falls under gravity
+pub fn falling() -> PhysicsProperties {
+    PhysicsProperties { is_movable: true, affected_by_gravity: true, collider_size: (10.0, 10.0), ..Default::default() }
+}
+
+pub fn run(scene: &mut Scene, physics_engine: &mut PhysicsEngine, steps: usize) {
+    for _ in 0..steps {
+        physics_engine.step(scene);
+    }
+}
+
+pub fn position_of(physics_engine: &PhysicsEngine, entity_id: Uuid) -> (f32, f32) {
+    physics_engine.interpolated_positions(1.0)[&entity_id]
+}
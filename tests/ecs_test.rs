@@ -1,5 +1,6 @@
 // Import the ECS module from the main project
 use rust_2d_game_engine::ecs::*;
+use uuid::Uuid;
 
 #[cfg(test)]
 mod tests {
@@ -116,4 +117,93 @@ mod tests {
         // Verify shared entity reference
         assert!(scene.shared_entity_refs.contains(&shared_id));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_world_transform_follows_parents() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let parent_id = scene.create_entity("parent").unwrap();
+        let child_id = scene.create_entity("child").unwrap();
+        let grandchild_id = scene.create_entity("grandchild").unwrap();
+
+        scene.get_entity_mut(parent_id).unwrap().set_position(100.0, 50.0, 1.0).unwrap();
+        scene.set_parent(child_id, Some(parent_id)).unwrap();
+        scene.set_parent(grandchild_id, Some(child_id)).unwrap();
+        scene.get_entity_mut(child_id).unwrap().set_position(10.0, 0.0, 1.0).unwrap();
+        scene.get_entity_mut(grandchild_id).unwrap().set_position(0.0, 5.0, 0.0).unwrap();
+
+        let world = scene.world_transform(grandchild_id).unwrap();
+        assert_eq!(world.position, (110.0, 55.0));
+        assert_eq!(world.z, 2.0);
+        assert_eq!(scene.world_transforms()[&grandchild_id], world);
+
+        // Rotating and scaling the parent moves the child around it
+        let parent = scene.get_entity_mut(parent_id).unwrap();
+        parent.set_local_transform(&Transform::new()
+            .with_position(100.0, 50.0)
+            .with_z(1.0)
+            .with_rotation(std::f32::consts::FRAC_PI_2)
+            .with_uniform_scale(2.0)).unwrap();
+        let world = scene.world_transform(child_id).unwrap();
+        assert!((world.position.0 - 100.0).abs() < 1e-4);
+        assert!((world.position.1 - 70.0).abs() < 1e-4);
+        assert_eq!(world.scale, (2.0, 2.0));
+
+        assert_eq!(scene.children_of(parent_id), vec![child_id]);
+        assert_eq!(scene.descendants_of(parent_id), vec![child_id, grandchild_id]);
+        assert!(!scene.root_entities().contains(&child_id));
+    }
+
+    #[test]
+    fn test_set_parent_keeps_world_position() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let parent_id = scene.create_entity("parent").unwrap();
+        let child_id = scene.create_entity("child").unwrap();
+        scene.get_entity_mut(parent_id).unwrap().set_position(30.0, 40.0, 0.0).unwrap();
+        scene.get_entity_mut(child_id).unwrap().set_position(50.0, 50.0, 0.0).unwrap();
+
+        scene.set_parent(child_id, Some(parent_id)).unwrap();
+        let child = scene.get_entity(child_id).unwrap();
+        assert_eq!((child.get_x(), child.get_y()), (20.0, 10.0));
+        assert_eq!(scene.world_transform(child_id).unwrap().position, (50.0, 50.0));
+
+        scene.set_parent(child_id, None).unwrap();
+        let child = scene.get_entity(child_id).unwrap();
+        assert_eq!((child.get_x(), child.get_y()), (50.0, 50.0));
+        assert_eq!(child.parent, None);
+    }
+
+    #[test]
+    fn test_set_parent_rejects_cycles() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let a = scene.create_entity("a").unwrap();
+        let b = scene.create_entity("b").unwrap();
+        scene.set_parent(b, Some(a)).unwrap();
+
+        assert!(scene.set_parent(a, Some(b)).is_err());
+        assert!(scene.set_parent(a, Some(a)).is_err());
+        assert!(scene.set_parent(a, Some(Uuid::new_v4())).is_err());
+        assert_eq!(scene.parent_of(a), None);
+    }
+
+    #[test]
+    fn test_delete_entity_cascades() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let parent_id = scene.create_entity("parent").unwrap();
+        let child_id = scene.create_entity("child").unwrap();
+        let grandchild_id = scene.create_entity("grandchild").unwrap();
+        let other_id = scene.create_entity("other").unwrap();
+        scene.set_parent(child_id, Some(parent_id)).unwrap();
+        scene.set_parent(grandchild_id, Some(child_id)).unwrap();
+
+        assert!(scene.delete_entity(parent_id).unwrap());
+        assert!(scene.get_entity(child_id).is_err());
+        assert!(scene.get_entity(grandchild_id).is_err());
+        assert!(scene.get_entity(other_id).is_ok());
+
+        // The default camera can't be deleted through its parent either
+        let camera_id = scene.default_camera.unwrap();
+        scene.set_parent(camera_id, Some(other_id)).unwrap();
+        assert!(scene.delete_entity(other_id).is_err());
+        assert!(scene.get_entity(other_id).is_ok());
+    }
+}
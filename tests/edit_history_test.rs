@@ -0,0 +1,255 @@
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::gui::edit_history::{AssetKind, EditCommand, EditHistory};
+use std::path::PathBuf;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (SceneManager, Uuid) {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("main").unwrap();
+        (scene_manager, scene_id)
+    }
+
+    // Names of the scene's entities in order, without the default camera
+    fn entity_names(scene_manager: &SceneManager, scene_id: Uuid) -> Vec<String> {
+        scene_manager.get_scene(scene_id).unwrap().entities.values()
+            .filter(|e| e.name != "main_camera")
+            .map(|e| e.name.clone())
+            .collect()
+    }
+
+    fn modify(history: &mut EditHistory, scene_manager: &mut SceneManager, scene_id: Uuid, entity_id: Uuid, attribute_id: Uuid, value: f32) {
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        let command = EditCommand::modify_attribute(entity, scene_id, attribute_id, AttributeValue::Float(value)).unwrap();
+        history.execute(command, scene_manager).unwrap();
+    }
+
+    #[test]
+    fn test_delete_entity_undo_restores_order() {
+        let (mut scene_manager, scene_id) = setup();
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        scene.create_entity("a").unwrap();
+        let b = scene.create_entity("b").unwrap();
+        scene.create_entity("c").unwrap();
+
+        let mut history = EditHistory::default();
+        let command = EditCommand::delete_entity(&scene_manager, scene_id, b).unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+        assert_eq!(entity_names(&scene_manager, scene_id), vec!["a", "c"]);
+
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(entity_names(&scene_manager, scene_id), vec!["a", "b", "c"]);
+
+        history.redo(&mut scene_manager).unwrap();
+        assert_eq!(entity_names(&scene_manager, scene_id), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_create_entity_undo_redo() {
+        let (mut scene_manager, scene_id) = setup();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("player").unwrap();
+
+        let mut history = EditHistory::default();
+        history.record(EditCommand::create_entity(&scene_manager, scene_id, entity_id).unwrap());
+
+        history.undo(&mut scene_manager).unwrap();
+        assert!(scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).is_err());
+        assert!(history.can_redo());
+
+        history.redo(&mut scene_manager).unwrap();
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        assert_eq!(entity.name, "player");
+    }
+
+    #[test]
+    fn test_rapid_attribute_edits_merge() {
+        let (mut scene_manager, scene_id) = setup();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("player").unwrap();
+        let x_id = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap()
+            .get_attribute_by_name("x").unwrap().id;
+
+        let mut history = EditHistory::default();
+        history.set_merge_window(Duration::from_secs(60));
+        for value in [1.0, 2.0, 3.0] {
+            modify(&mut history, &mut scene_manager, scene_id, entity_id, x_id, value);
+        }
+        assert_eq!(history.undo_len(), 1);
+
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().get_x(), 0.0);
+        assert!(!history.can_undo());
+
+        // Without a merge window every edit is undone on its own
+        let mut history = EditHistory::default();
+        history.set_merge_window(Duration::ZERO);
+        for value in [1.0, 2.0] {
+            modify(&mut history, &mut scene_manager, scene_id, entity_id, x_id, value);
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        assert_eq!(history.undo_len(), 2);
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().get_x(), 1.0);
+    }
+
+    #[test]
+    fn test_max_depth_drops_oldest() {
+        let (mut scene_manager, scene_id) = setup();
+        let mut history = EditHistory::new(3);
+        for i in 0..5 {
+            let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity(&format!("e{}", i)).unwrap();
+            history.record(EditCommand::create_entity(&scene_manager, scene_id, entity_id).unwrap());
+        }
+        assert_eq!(history.undo_len(), 3);
+
+        while history.can_undo() {
+            history.undo(&mut scene_manager).unwrap();
+        }
+        assert_eq!(entity_names(&scene_manager, scene_id), vec!["e0", "e1"]);
+
+        history.set_max_depth(1);
+        assert!(history.redo(&mut scene_manager).is_ok());
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo() {
+        let (mut scene_manager, scene_id) = setup();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("old").unwrap();
+
+        let mut history = EditHistory::default();
+        let command = EditCommand::rename_entity(&scene_manager, scene_id, entity_id, "new").unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(history.redo_description(), Some("Rename entity to 'new'".to_string()));
+
+        let command = EditCommand::rename_entity(&scene_manager, scene_id, entity_id, "other").unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+        assert!(!history.can_redo());
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().name, "other");
+    }
+
+    #[test]
+    fn test_attach_and_detach_assets() {
+        let (mut scene_manager, scene_id) = setup();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("player").unwrap();
+        let entity = scene_manager.get_scene_mut(scene_id).unwrap().get_entity_mut(entity_id).unwrap();
+        entity.images = vec![PathBuf::from("a.png"), PathBuf::from("b.png")];
+        entity.script = Some(PathBuf::from("old.lua"));
+
+        let mut history = EditHistory::default();
+        let command = EditCommand::detach_asset(&scene_manager, scene_id, entity_id, AssetKind::Image, PathBuf::from("a.png")).unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+        let command = EditCommand::attach_asset(&scene_manager, scene_id, entity_id, AssetKind::Script, PathBuf::from("new.lua")).unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        assert_eq!(entity.images, vec![PathBuf::from("b.png")]);
+        assert_eq!(entity.script, Some(PathBuf::from("new.lua")));
+
+        history.undo(&mut scene_manager).unwrap();
+        history.undo(&mut scene_manager).unwrap();
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        assert_eq!(entity.images, vec![PathBuf::from("a.png"), PathBuf::from("b.png")]);
+        assert_eq!(entity.script, Some(PathBuf::from("old.lua")));
+
+        // Detaching an asset the entity doesn't have is rejected up front
+        assert!(EditCommand::detach_asset(&scene_manager, scene_id, entity_id, AssetKind::Sound, PathBuf::from("a.wav")).is_err());
+    }
+
+    #[test]
+    fn test_scene_rename_and_delete() {
+        let (mut scene_manager, scene_id) = setup();
+        scene_manager.get_scene_mut(scene_id).unwrap().create_entity("player").unwrap();
+        let mut history = EditHistory::default();
+
+        let command = EditCommand::rename_scene(&scene_manager, scene_id, "level_1").unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+        let command = EditCommand::delete_scene(&scene_manager, scene_id).unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+        assert!(scene_manager.get_scene(scene_id).is_none());
+
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().name, "level_1");
+        assert_eq!(entity_names(&scene_manager, scene_id), vec!["player"]);
+
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().name, "main");
+    }
+
+    #[test]
+    fn test_attribute_create_and_delete() {
+        let (mut scene_manager, scene_id) = setup();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("player").unwrap();
+        let entity = scene_manager.get_scene_mut(scene_id).unwrap().get_entity_mut(entity_id).unwrap();
+        let health_id = entity.create_attribute("health", AttributeType::Integer, AttributeValue::Integer(3)).unwrap();
+
+        let mut history = EditHistory::default();
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        history.record(EditCommand::create_attribute(entity, scene_id, health_id).unwrap());
+        let command = EditCommand::delete_attribute(entity, scene_id, health_id).unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+        assert!(scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().get_attribute(health_id).is_err());
+
+        history.undo(&mut scene_manager).unwrap();
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        assert_eq!(entity.get_attribute(health_id).unwrap().value, AttributeValue::Integer(3));
+
+        history.undo(&mut scene_manager).unwrap();
+        assert!(scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().get_attribute(health_id).is_err());
+    }
+
+    #[test]
+    fn test_undo_delete_restores_children() {
+        let (mut scene_manager, scene_id) = setup();
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let parent = scene.create_entity("parent").unwrap();
+        let child = scene.create_entity("child").unwrap();
+        scene.create_entity("other").unwrap();
+        scene.set_parent(child, Some(parent)).unwrap();
+
+        let mut history = EditHistory::default();
+        let command = EditCommand::delete_entity(&scene_manager, scene_id, parent).unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+        assert_eq!(entity_names(&scene_manager, scene_id), vec!["other"]);
+
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(entity_names(&scene_manager, scene_id), vec!["parent", "child", "other"]);
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().parent_of(child), Some(parent));
+
+        let command = EditCommand::set_parent(&scene_manager, scene_id, child, None).unwrap();
+        history.execute(command, &mut scene_manager).unwrap();
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().parent_of(child), None);
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().parent_of(child), Some(parent));
+    }
+
+    #[test]
+    fn test_failed_undo_and_redo_keep_the_edit() {
+        let (mut scene_manager, scene_id) = setup();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("player").unwrap();
+        let x_id = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap()
+            .get_attribute_by_name("x").unwrap().id;
+        let mut history = EditHistory::default();
+        modify(&mut history, &mut scene_manager, scene_id, entity_id, x_id, 5.0);
+
+        // The scene is gone while undoing, the edit stays on the undo stack
+        let scene = scene_manager.scenes.shift_remove(&scene_id).unwrap();
+        assert!(history.undo(&mut scene_manager).is_err());
+        assert_eq!((history.undo_len(), history.can_redo()), (1, false));
+        scene_manager.scenes.insert(scene_id, scene);
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().get_x(), 0.0);
+
+        // Same for redo
+        let scene = scene_manager.scenes.shift_remove(&scene_id).unwrap();
+        assert!(history.redo(&mut scene_manager).is_err());
+        assert_eq!((history.undo_len(), history.redo_description()), (0, Some("Set attribute to 5.0".to_string())));
+        scene_manager.scenes.insert(scene_id, scene);
+        history.redo(&mut scene_manager).unwrap();
+        assert_eq!(scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().get_x(), 5.0);
+    }
+}
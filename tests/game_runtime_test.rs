@@ -0,0 +1,280 @@
+#[cfg(test)]
+mod tests {
+    use rust_2d_game_engine::game_runtime::{FixedTimestep, GameRuntime};
+    use rust_2d_game_engine::ecs::{AttributeType, AttributeValue, PhysicsProperties, SceneManager};
+    use rust_2d_game_engine::input_handler::ScriptedInput;
+    use rust_2d_game_engine::project_manager::{ProjectManager, ProjectMetadata};
+    use rust_2d_game_engine::animation::{AnimationClip, FrameSource, LoopMode, SpriteAnimation};
+    use egui::Key;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use uuid::Uuid;
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn test_steps_follow_real_time() {
+        let mut timestep = FixedTimestep::new(STEP);
+
+        // A 144Hz and a 30Hz display simulate the same amount of time per second
+        let fast: u32 = (0..144).map(|_| timestep.advance(1.0 / 144.0)).sum();
+        timestep.reset();
+        let slow: u32 = (0..30).map(|_| timestep.advance(1.0 / 30.0)).sum();
+
+        assert!((59..=60).contains(&fast), "144Hz ran {} steps", fast);
+        assert!((59..=60).contains(&slow), "30Hz ran {} steps", slow);
+    }
+
+    #[test]
+    fn test_short_frames_accumulate() {
+        let mut timestep = FixedTimestep::new(STEP);
+
+        assert_eq!(timestep.advance(STEP * 0.4), 0);
+        assert_eq!(timestep.advance(STEP * 0.4), 0);
+        assert_eq!(timestep.advance(STEP * 0.4), 1);
+        assert!((timestep.alpha() - 0.2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_spiral_of_death_clamp() {
+        let mut timestep = FixedTimestep::new(STEP);
+        timestep.set_max_frame_time(0.1);
+
+        // A two second hitch only simulates the clamped time
+        assert_eq!(timestep.advance(2.0), 6);
+        assert_eq!(timestep.clamp_frame_time(2.0), 0.1);
+        assert_eq!(timestep.clamp_frame_time(-1.0), 0.0);
+    }
+
+    #[test]
+    fn test_alpha_and_reset() {
+        let mut timestep = FixedTimestep::new(0.1);
+
+        assert_eq!(timestep.advance(0.25), 2);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-3);
+
+        timestep.reset();
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn test_step_change() {
+        let mut timestep = FixedTimestep::new(STEP);
+
+        timestep.set_step(0.5);
+        assert_eq!(timestep.step(), 0.5);
+
+        // Invalid steps are ignored
+        timestep.set_step(0.0);
+        assert_eq!(timestep.step(), 0.5);
+        assert_eq!(timestep.advance(0.2), 0);
+    }
+
+    // Creates a project in a temporary directory with one scene, `build` fills the scene.
+    // Scripts are written under assets/scripts like in a real project.
+    fn create_project(scripts: &[(&str, &str)], build: impl FnOnce(&mut SceneManager, Uuid, &Path)) -> PathBuf {
+        let project_path = std::env::temp_dir().join(format!("game_runtime_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(project_path.join("scenes")).unwrap();
+        fs::create_dir_all(project_path.join("assets/scripts")).unwrap();
+        for (name, source) in scripts {
+            fs::write(project_path.join("assets/scripts").join(name), source).unwrap();
+        }
+
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("main").unwrap();
+        scene_manager.set_active_scene(scene_id).unwrap();
+        build(&mut scene_manager, scene_id, &project_path);
+
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        project_path
+    }
+
+    fn entity_attribute(runtime: &GameRuntime, name: &str, attribute: &str) -> AttributeValue {
+        let scene = runtime.get_scene_manager().get_active_scene().unwrap();
+        let entity = scene.entities.values().find(|entity| entity.name == name).unwrap();
+        entity.get_attribute_by_name(attribute).unwrap().value.clone()
+    }
+
+    // An entity running the given script with an integer attribute `count` set to 0
+    fn scripted_entity(scene_manager: &mut SceneManager, scene_id: Uuid, project_path: &Path, script: &str) {
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let entity_id = scene.create_entity("counter").unwrap();
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        entity.create_attribute("count", AttributeType::Integer, AttributeValue::Integer(0)).unwrap();
+        entity.set_script(project_path.join("assets/scripts").join(script)).unwrap();
+    }
+
+    #[test]
+    fn test_headless_runs_scripts() {
+        let project_path = create_project(
+            &[("counter.lua", "function update(self, dt) self.count = self.count + 1 end")],
+            |scene_manager, scene_id, project_path| scripted_entity(scene_manager, scene_id, project_path, "counter.lua"),
+        );
+
+        let mut runtime = GameRuntime::new_headless(&project_path).unwrap();
+        runtime.run_frames(10, &ScriptedInput::new()).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+
+        assert!(runtime.is_playing());
+        assert_eq!(runtime.get_frame_count(), 10);
+        assert_eq!(entity_attribute(&runtime, "counter", "count"), AttributeValue::Integer(10));
+    }
+
+    #[test]
+    fn test_headless_scripted_input() {
+        let project_path = create_project(
+            &[("jumper.lua", r#"
+                function update(self, dt)
+                    if is_key_just_pressed("Space") then
+                        self.count = self.count + 1
+                    end
+                end
+            "#)],
+            |scene_manager, scene_id, project_path| scripted_entity(scene_manager, scene_id, project_path, "jumper.lua"),
+        );
+
+        // A tap and a hold are two presses, holding doesn't press again
+        let input = ScriptedInput::new()
+            .tap(Key::Space, 2)
+            .hold(Key::Space, 5..9);
+
+        let mut runtime = GameRuntime::new_headless(&project_path).unwrap();
+        runtime.run_frames(6, &input).unwrap();
+        // The runtime can be moved between runs
+        let mut runtime = Box::new(runtime);
+        runtime.run_frames(6, &input).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+
+        assert_eq!(entity_attribute(&runtime, "counter", "count"), AttributeValue::Integer(2));
+    }
+
+    #[test]
+    fn test_headless_physics() {
+        let project_path = create_project(&[], |scene_manager, scene_id, _| {
+            let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+            let physics_props = PhysicsProperties {
+                is_movable: true,
+                affected_by_gravity: true,
+                ..Default::default()
+            };
+            scene.create_physical_entity("ball", (0.0, 0.0, 0.0), physics_props).unwrap();
+        });
+
+        let mut runtime = GameRuntime::new_headless(&project_path).unwrap();
+        runtime.run_frames(30, &ScriptedInput::new()).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+
+        // Gravity points down the screen
+        match entity_attribute(&runtime, "ball", "y") {
+            AttributeValue::Float(y) => assert!(y > 1.0, "ball should have fallen, at y = {}", y),
+            other => panic!("unexpected position {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_runtime_moved_between_frames() {
+        let script = "function update(self, dt) set_velocity(self, 0, 100) self.count = self.count + 1 end";
+        let project_path = create_project(&[("push.lua", script)], |scene_manager, scene_id, project_path| {
+            let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+            let physics_props = PhysicsProperties { is_movable: true, ..Default::default() };
+            let entity_id = scene.create_physical_entity("ball", (0.0, 0.0, 0.0), physics_props).unwrap();
+            let entity = scene.get_entity_mut(entity_id).unwrap();
+            entity.create_attribute("count", AttributeType::Integer, AttributeValue::Integer(0)).unwrap();
+            entity.set_script(project_path.join("assets/scripts/push.lua")).unwrap();
+        });
+
+        let mut runtime = GameRuntime::new_headless(&project_path).unwrap();
+        runtime.run_frames(5, &ScriptedInput::new()).unwrap();
+        // The scripts don't keep pointers into the runtime, so it can move between frames
+        let mut runtime = Box::new(runtime);
+        runtime.run_frames(5, &ScriptedInput::new()).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+
+        assert_eq!(entity_attribute(&runtime, "ball", "count"), AttributeValue::Integer(10));
+        match entity_attribute(&runtime, "ball", "y") {
+            AttributeValue::Float(y) => assert!(y > 1.0, "ball should have moved, at y = {}", y),
+            other => panic!("unexpected position {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_headless_without_scenes() {
+        let project_path = std::env::temp_dir().join(format!("game_runtime_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(&project_path).unwrap();
+
+        let mut runtime = GameRuntime::new_headless(&project_path).unwrap();
+        let result = runtime.tick(1.0 / 60.0);
+        fs::remove_dir_all(&project_path).unwrap();
+
+        assert!(result.is_err());
+        assert!(!runtime.is_playing());
+    }
+
+    #[test]
+    fn test_animation_finishes_during_play() {
+        // The clip autoplays and tells the script when it is done
+        let script = r#"
+            function on_animation_finished(self, clip)
+                if clip == "intro" then self.count = self.count + 1 end
+            end
+        "#;
+        let project_path = create_project(&[("intro.lua", script)], |scene_manager, scene_id, project_path| {
+            scripted_entity(scene_manager, scene_id, project_path, "intro.lua");
+            let mut animation = SpriteAnimation::new(FrameSource::Images);
+            animation.add_clip("intro", AnimationClip::new(vec![0, 0, 0], 0.05).with_loop_mode(LoopMode::Once)).unwrap();
+            animation.autoplay = Some("intro".to_string());
+            let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+            let entity = scene.entities.values_mut().find(|entity| entity.name == "counter").unwrap();
+            entity.animation = Some(Box::new(animation));
+        });
+
+        let mut runtime = GameRuntime::new_headless(&project_path).unwrap();
+        runtime.run_frames(6, &ScriptedInput::new()).unwrap();
+        assert_eq!(entity_attribute(&runtime, "counter", "count"), AttributeValue::Integer(0));
+        runtime.run_frames(6, &ScriptedInput::new()).unwrap();
+        assert_eq!(entity_attribute(&runtime, "counter", "count"), AttributeValue::Integer(1));
+
+        let _ = fs::remove_dir_all(&project_path);
+    }
+
+    #[test]
+    fn test_headless_uses_the_project_it_opens() {
+        // An existing project with its own collision layers, opened without going through the editor
+        let script = r#"
+            function init(self)
+                self:add_image("assets/images/ship.png")
+            end
+            function update(self, dt)
+                local ok, hits = pcall(overlap_circle, 0, 0, 50, { layers = "ships" })
+                if ok then self.count = #hits end
+            end
+        "#;
+        let project_path = create_project(&[("scanner.lua", script)], |scene_manager, scene_id, project_path| {
+            scripted_entity(scene_manager, scene_id, project_path, "scanner.lua");
+            let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+            let physics_props = PhysicsProperties { collision_layer: "ships".to_string(), collider_size: (10.0, 10.0), ..Default::default() };
+            scene.create_physical_entity("ship", (0.0, 0.0, 0.0), physics_props).unwrap();
+        });
+        let metadata = ProjectMetadata {
+            project_name: "fleet".to_string(),
+            version: "1.0.0".to_string(),
+            project_path: project_path.to_string_lossy().to_string(),
+            default_scene: "main".to_string(),
+            active_scene_id: None,
+            collision_layers: vec!["default".to_string(), "ships".to_string()],
+        };
+        ProjectManager::save_project(&project_path, &metadata).unwrap();
+        // Whatever project another part of the process opened last doesn't matter
+        ProjectManager::set_project_path(std::env::temp_dir().to_string_lossy().to_string());
+
+        let mut runtime = GameRuntime::new_headless(&project_path).unwrap();
+        runtime.run_frames(2, &ScriptedInput::new()).unwrap();
+        let scene = runtime.get_scene_manager().get_active_scene().unwrap();
+        let counter = scene.entities.values().find(|entity| entity.name == "counter").unwrap();
+        assert_eq!(runtime.get_project_path(), Some(project_path.as_path()));
+        assert_eq!(counter.images, [project_path.join("assets/images/ship.png")]);
+        assert_eq!(entity_attribute(&runtime, "counter", "count"), AttributeValue::Integer(1));
+
+        let _ = fs::remove_dir_all(&project_path);
+    }
+}
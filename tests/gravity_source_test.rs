@@ -0,0 +1,190 @@
+mod common;
+
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::gravity_source::*;
+use rust_2d_game_engine::gui::edit_history::{EditCommand, EditHistory};
+use rust_2d_game_engine::physics_engine::PhysicsEngine;
+use uuid::Uuid;
+use common::{add_body, falling, run};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ship(density: f32) -> PhysicsProperties {
+        PhysicsProperties { density, ..falling() }
+    }
+
+    // A scene without global gravity and with a planet at the origin
+    fn space(source: GravitySource) -> (Scene, Uuid) {
+        let mut scene = Scene::new("test_scene").unwrap();
+        scene.physics.gravity = (0.0, 0.0);
+        let planet_id = add_body(&mut scene, "planet", (0.0, 0.0), PhysicsProperties { collider_size: (20.0, 20.0), ..Default::default() });
+        scene.get_entity_mut(planet_id).unwrap().gravity_source = Some(source);
+        (scene, planet_id)
+    }
+
+    // Horizontal velocity of each body after `steps` steps
+    fn velocities_after(scene: &mut Scene, bodies: &[Uuid], steps: usize) -> Vec<f32> {
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.set_collision_layers(vec!["default".to_string(), "ships".to_string()]);
+        physics_engine.load_scene(scene);
+        run(scene, &mut physics_engine, steps);
+        bodies.iter().map(|id| physics_engine.get_velocity(id).unwrap().x).collect()
+    }
+
+    #[test]
+    fn test_serialization() {
+        let source = GravitySource { max_radius: 300.0, falloff: GravityFalloff::Linear, repel: true, ..Default::default() };
+        let json = serde_json::to_string(&source).unwrap();
+        assert_eq!(serde_json::from_str::<GravitySource>(&json).unwrap(), source);
+
+        let source: GravitySource = serde_json::from_str("{}").unwrap();
+        assert_eq!(source, GravitySource::default());
+        assert_eq!(source.affected_layers, "all");
+
+        assert_eq!(GravityFalloff::from_name(" Inverse_Square "), Some(GravityFalloff::InverseSquare));
+        assert_eq!(GravityFalloff::from_name("cubic"), None);
+        for falloff in GravityFalloff::ALL {
+            assert_eq!(GravityFalloff::from_name(falloff.as_str()), Some(falloff));
+        }
+    }
+
+    #[test]
+    fn test_falloff() {
+        let source = |falloff: GravityFalloff, max_radius: f32| GravitySource { strength: 100.0, falloff, max_radius, ..Default::default() };
+
+        let inverse_square = source(GravityFalloff::InverseSquare, 0.0);
+        assert_eq!(inverse_square.acceleration(10.0), 1.0);
+        assert_eq!(inverse_square.acceleration(0.5), 100.0);
+        assert_eq!(source(GravityFalloff::Linear, 200.0).acceleration(50.0), 75.0);
+        // Without a max radius, the pull ends where 100 / distance² is negligible
+        assert_eq!(source(GravityFalloff::Linear, 0.0).radius(), 100.0);
+        assert_eq!(source(GravityFalloff::Linear, 0.0).acceleration(50.0), 50.0);
+        assert_eq!(source(GravityFalloff::Constant, 0.0).acceleration(1000.0), 0.0);
+        assert_eq!(inverse_square.acceleration(101.0), 0.0);
+        assert_eq!(source(GravityFalloff::Constant, 200.0).acceleration(150.0), 100.0);
+        assert_eq!(source(GravityFalloff::Constant, 200.0).acceleration(250.0), 0.0);
+
+        let repel = GravitySource { repel: true, ..source(GravityFalloff::Constant, 0.0) };
+        assert_eq!(repel.acceleration(10.0), -100.0);
+    }
+
+    #[test]
+    fn test_pull_does_not_depend_on_mass() {
+        // Two ships 100 to the left of the planet, one five times heavier, fall towards it alike
+        let source = GravitySource { strength: 1.0e5, ..Default::default() };
+        let (mut scene, _) = space(source);
+        let light_id = add_body(&mut scene, "light", (-100.0, -50.0), ship(1.0));
+        let heavy_id = add_body(&mut scene, "heavy", (-100.0, 50.0), ship(5.0));
+        let velocities = velocities_after(&mut scene, &[light_id, heavy_id], 10);
+
+        // 10 (the scene's multiplier) × 1e5 / distance², for a sixth of a second
+        let expected = 10.0 * 1.0e5 / (100.0f32.powi(2) + 50.0f32.powi(2)) * 100.0 / 12500.0f32.sqrt() / 6.0;
+        assert!((velocities[0] - expected).abs() < 0.05 * expected, "{} instead of {}", velocities[0], expected);
+        assert!((velocities[0] - velocities[1]).abs() < 0.01, "{:?}", velocities);
+    }
+
+    #[test]
+    fn test_max_radius_and_repel() {
+        let source = GravitySource { strength: 100.0, max_radius: 150.0, falloff: GravityFalloff::Constant, ..Default::default() };
+        let (mut scene, _) = space(source.clone());
+        let near_id = add_body(&mut scene, "near", (-100.0, 0.0), ship(1.0));
+        let far_id = add_body(&mut scene, "far", (-200.0, 0.0), ship(1.0));
+        let velocities = velocities_after(&mut scene, &[near_id, far_id], 6);
+        assert!((velocities[0] - 100.0).abs() < 1.0, "{:?}", velocities);
+        assert_eq!(velocities[1], 0.0);
+
+        let (mut scene, _) = space(GravitySource { repel: true, ..source });
+        let near_id = add_body(&mut scene, "near", (-100.0, 0.0), ship(1.0));
+        let velocities = velocities_after(&mut scene, &[near_id], 6);
+        assert!((velocities[0] + 100.0).abs() < 1.0, "{:?}", velocities);
+    }
+
+    #[test]
+    fn test_affected_layers() {
+        for max_radius in [0.0, 500.0] {
+            let source = GravitySource { max_radius, affected_layers: "ships".to_string(), ..Default::default() };
+            let (mut scene, _) = space(source);
+            let ship_props = PhysicsProperties { collision_layer: "ships".to_string(), ..ship(1.0) };
+            let ship_id = add_body(&mut scene, "ship", (-100.0, 0.0), ship_props);
+            let rock_id = add_body(&mut scene, "rock", (-100.0, 100.0), ship(1.0));
+            // Without gravity, the ship is not pulled either
+            let anchor_props = PhysicsProperties { collision_layer: "ships".to_string(), affected_by_gravity: false, ..ship(1.0) };
+            let anchor_id = add_body(&mut scene, "anchor", (-100.0, -100.0), anchor_props);
+
+            let velocities = velocities_after(&mut scene, &[ship_id, rock_id, anchor_id], 5);
+            assert!(velocities[0] > 0.0, "{:?}", velocities);
+            assert_eq!((velocities[1], velocities[2]), (0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_bodies_without_collider() {
+        // Pulled within the radius like any other body, with or without a max radius
+        for max_radius in [0.0, 150.0] {
+            let source = GravitySource { strength: 100.0, max_radius, falloff: GravityFalloff::Constant, ..Default::default() };
+            let (mut scene, _) = space(source);
+            let ghost = PhysicsProperties { has_collision: false, ..ship(1.0) };
+            let near_id = add_body(&mut scene, "near", (-90.0, 0.0), ghost.clone());
+            let far_id = add_body(&mut scene, "far", (-200.0, 0.0), ghost);
+            let velocities = velocities_after(&mut scene, &[near_id, far_id], 6);
+            assert!((velocities[0] - 100.0).abs() < 1.0, "{:?}", velocities);
+            assert_eq!(velocities[1], 0.0);
+        }
+
+        // They aren't in the layers other than the default one
+        let (mut scene, _) = space(GravitySource { affected_layers: "ships".to_string(), ..Default::default() });
+        let ghost_id = add_body(&mut scene, "ghost", (-100.0, 0.0), PhysicsProperties { has_collision: false, ..ship(1.0) });
+        assert_eq!(velocities_after(&mut scene, &[ghost_id], 5), [0.0]);
+    }
+
+    #[test]
+    fn test_legacy_and_bodiless_sources() {
+        // `creates_gravity` on is a default source
+        let mut scene = Scene::new("test_scene").unwrap();
+        scene.physics.gravity = (0.0, 0.0);
+        add_body(&mut scene, "planet", (0.0, 0.0), PhysicsProperties { creates_gravity: true, ..Default::default() });
+        let ship_id = add_body(&mut scene, "ship", (-100.0, 0.0), ship(1.0));
+        let velocities = velocities_after(&mut scene, &[ship_id], 6);
+        let expected = 10.0 * GravitySource::default().acceleration(100.0) / 10.0;
+        assert!((velocities[0] - expected).abs() < 0.05 * expected, "{} instead of {}", velocities[0], expected);
+
+        // A source without a body pulls from its world position
+        let mut scene = Scene::new("test_scene").unwrap();
+        scene.physics.gravity = (0.0, 0.0);
+        let well_id = scene.create_entity("well").unwrap();
+        let well = scene.get_entity_mut(well_id).unwrap();
+        well.set_x(-200.0).unwrap();
+        well.gravity_source = Some(GravitySource { falloff: GravityFalloff::Constant, ..Default::default() });
+        let ship_id = add_body(&mut scene, "ship", (-100.0, 0.0), ship(1.0));
+        let velocities = velocities_after(&mut scene, &[ship_id], 1);
+        assert!(velocities[0] < 0.0, "{:?}", velocities);
+    }
+
+    #[test]
+    fn test_gravity_source_undo() {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("test_scene").unwrap();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("planet").unwrap();
+        let mut history = EditHistory::default();
+
+        let source = |scene_manager: &SceneManager| scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().gravity_source.clone();
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        let command = EditCommand::set_gravity_source(entity, scene_id, Some(GravitySource::default()));
+        assert_eq!(command.description(), "Add gravity source");
+        history.execute(command, &mut scene_manager).unwrap();
+
+        // Dragging the strength is a single edit
+        for strength in [2000.0, 3000.0] {
+            let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+            let command = EditCommand::set_gravity_source(entity, scene_id, Some(GravitySource { strength, ..Default::default() }));
+            history.execute(command, &mut scene_manager).unwrap();
+        }
+        assert_eq!(source(&scene_manager).unwrap().strength, 3000.0);
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(source(&scene_manager), Some(GravitySource::default()));
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(source(&scene_manager), None);
+    }
+}
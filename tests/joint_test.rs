@@ -0,0 +1,208 @@
+mod common;
+
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::gui::edit_history::{EditCommand, EditHistory};
+use rust_2d_game_engine::joint::*;
+use rust_2d_game_engine::physics_engine::PhysicsEngine;
+use uuid::Uuid;
+use common::{add_body, falling, position_of, run};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+        ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+    }
+
+    // A fixed pivot without a collider at the origin and a falling bob at `bob_position`,
+    // connected by `joint` (whose `other` is set to the bob)
+    fn pendulum(bob_position: (f32, f32), mut joint: Joint, bob: PhysicsProperties) -> (Scene, PhysicsEngine, Uuid, Uuid) {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let pivot_props = PhysicsProperties { has_collision: false, ..Default::default() };
+        let pivot_id = add_body(&mut scene, "pivot", (0.0, 0.0), pivot_props);
+        let bob_id = add_body(&mut scene, "bob", bob_position, bob);
+
+        joint.other = bob_id;
+        scene.get_entity_mut(pivot_id).unwrap().joints.push(joint);
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        (scene, physics_engine, pivot_id, bob_id)
+    }
+
+    #[test]
+    fn test_joint_serialization() {
+        let mut joint = Joint::new(JointKind::Prismatic, Uuid::new_v4());
+        joint.limits = Some((0.0, 50.0));
+        joint.motor = Some(JointMotor { speed: 2.0, max_force: 10.0 });
+        let json = serde_json::to_string(&joint).unwrap();
+        assert_eq!(serde_json::from_str::<Joint>(&json).unwrap(), joint);
+
+        // Only the kind, the ID and the other entity are required
+        let other = Uuid::new_v4();
+        let json = format!(r#"{{ "id": "{}", "kind": "Revolute", "other": "{}" }}"#, Uuid::new_v4(), other);
+        let joint: Joint = serde_json::from_str(&json).unwrap();
+        assert_eq!(joint.other, other);
+        assert_eq!(joint.axis, (1.0, 0.0));
+        assert_eq!((joint.limits, joint.motor, joint.collide_connected), (None, None, false));
+
+        assert_eq!(JointKind::from_name(" Rope "), Some(JointKind::Rope));
+        assert_eq!(JointKind::from_name("hinge"), None);
+        for kind in JointKind::ALL {
+            assert_eq!(JointKind::from_name(kind.as_str()), Some(kind));
+        }
+    }
+
+    #[test]
+    fn test_fixed_joint_holds_body() {
+        let mut joint = Joint::new(JointKind::Fixed, Uuid::nil());
+        joint.anchor = (0.0, 50.0);
+        let (mut scene, mut physics_engine, _, bob_id) = pendulum((0.0, 50.0), joint, falling());
+        run(&mut scene, &mut physics_engine, 60);
+
+        let position = position_of(&physics_engine, bob_id);
+        assert!(distance(position, (0.0, 50.0)) < 1.0, "The bob moved to {:?}", position);
+    }
+
+    #[test]
+    fn test_revolute_joint_swings() {
+        // Pivoting around the pivot's center, the bob swings down and keeps its distance
+        let mut joint = Joint::new(JointKind::Revolute, Uuid::nil());
+        joint.other_anchor = (-100.0, 0.0);
+        let bob = PhysicsProperties { can_rotate: true, ..falling() };
+        let (mut scene, mut physics_engine, _, bob_id) = pendulum((100.0, 0.0), joint, bob);
+        run(&mut scene, &mut physics_engine, 60);
+
+        let position = position_of(&physics_engine, bob_id);
+        assert!(position.1 > 20.0, "The bob should swing down, it is at {:?}", position);
+        assert!((distance(position, (0.0, 0.0)) - 100.0).abs() < 2.0, "The bob is at {:?}", position);
+    }
+
+    #[test]
+    fn test_revolute_motor() {
+        let mut joint = Joint::new(JointKind::Revolute, Uuid::nil());
+        joint.motor = Some(JointMotor { speed: 2.0, max_force: 1.0e6 });
+        let bob = PhysicsProperties { is_movable: true, can_rotate: true, ..Default::default() };
+        let (mut scene, mut physics_engine, pivot_id, bob_id) = pendulum((0.0, 0.0), joint, bob);
+        run(&mut scene, &mut physics_engine, 30);
+
+        let angle = physics_engine.interpolated_rotations(1.0)[&bob_id];
+        assert!((angle - 1.0).abs() < 0.1, "Half a second at 2 rad/s should turn 1 rad, not {}", angle);
+
+        // Turning the other way
+        let joint_id = scene.get_entity(pivot_id).unwrap().joints[0].id;
+        physics_engine.set_joint_motor(joint_id, Some(JointMotor { speed: -2.0, max_force: 1.0e6 })).unwrap();
+        run(&mut scene, &mut physics_engine, 30);
+        let angle = physics_engine.interpolated_rotations(1.0)[&bob_id];
+        assert!(angle.abs() < 0.1, "The bob should be back at 0 rad, not {}", angle);
+    }
+
+    #[test]
+    fn test_prismatic_joint_limits() {
+        // The bob slides down the vertical axis until the upper limit
+        let mut joint = Joint::new(JointKind::Prismatic, Uuid::nil());
+        joint.axis = (0.0, 1.0);
+        joint.limits = Some((0.0, 50.0));
+        let (mut scene, mut physics_engine, _, bob_id) = pendulum((0.0, 0.0), joint, falling());
+        run(&mut scene, &mut physics_engine, 120);
+
+        let position = position_of(&physics_engine, bob_id);
+        assert!(distance(position, (0.0, 50.0)) < 1.0, "The bob should rest at the limit, it is at {:?}", position);
+    }
+
+    #[test]
+    fn test_rope_and_distance_joints() {
+        // The rope is slack until the bob has fallen to its length
+        let mut rope = Joint::new(JointKind::Rope, Uuid::nil());
+        rope.length = 150.0;
+        let (mut scene, mut physics_engine, _, bob_id) = pendulum((0.0, 100.0), rope, falling());
+        run(&mut scene, &mut physics_engine, 120);
+        let position = position_of(&physics_engine, bob_id);
+        assert!((distance(position, (0.0, 0.0)) - 150.0).abs() < 2.0, "The bob is at {:?}", position);
+
+        // A distance joint also keeps the bob from getting closer
+        let mut rod = Joint::new(JointKind::Distance, Uuid::nil());
+        rod.length = 100.0;
+        let (mut scene, mut physics_engine, _, bob_id) = pendulum((0.0, -100.0), rod, falling());
+        run(&mut scene, &mut physics_engine, 10);
+        let position = position_of(&physics_engine, bob_id);
+        assert!((distance(position, (0.0, 0.0)) - 100.0).abs() < 2.0, "The bob is at {:?}", position);
+    }
+
+    #[test]
+    fn test_spring_joint() {
+        let mut spring = Joint::new(JointKind::Spring, Uuid::nil());
+        spring.length = 50.0;
+        // The bob weighs 100, critically damped
+        spring.stiffness = 1000.0;
+        spring.damping = 600.0;
+        let bob = PhysicsProperties { is_movable: true, ..Default::default() };
+        let (mut scene, mut physics_engine, _, bob_id) = pendulum((200.0, 0.0), spring, bob);
+        run(&mut scene, &mut physics_engine, 300);
+
+        let position = position_of(&physics_engine, bob_id);
+        assert!((distance(position, (0.0, 0.0)) - 50.0).abs() < 5.0, "The bob is at {:?}", position);
+    }
+
+    #[test]
+    fn test_joint_data_and_removal() {
+        let mut joint = Joint::new(JointKind::Revolute, Uuid::nil());
+        joint.anchor = (10.0, 0.0);
+        joint.other_anchor = (-90.0, 0.0);
+        let (scene, mut physics_engine, pivot_id, bob_id) = pendulum((100.0, 0.0), joint, falling());
+        let joint_id = scene.get_entity(pivot_id).unwrap().joints[0].id;
+
+        let joints = physics_engine.get_joint_data();
+        assert_eq!(joints.len(), 1);
+        assert_eq!((joints[0].joint_id, joints[0].kind), (joint_id, JointKind::Revolute));
+        assert_eq!((joints[0].entity1, joints[0].entity2), (pivot_id, bob_id));
+        assert_eq!((joints[0].anchor1, joints[0].anchor2), ((10.0, 0.0), (10.0, 0.0)));
+
+        // The joint goes with either entity
+        physics_engine.remove_entity(bob_id);
+        assert!(!physics_engine.has_joint(&joint_id));
+        assert!(physics_engine.get_joint_data().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_joints() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let body_id = add_body(&mut scene, "body", (0.0, 0.0), falling());
+        let plain_id = scene.create_entity("plain").unwrap();
+        let deleted_id = add_body(&mut scene, "deleted", (50.0, 0.0), falling());
+        scene.get_entity_mut(body_id).unwrap().joints.push(Joint::new(JointKind::Fixed, deleted_id));
+        scene.delete_entity(deleted_id).unwrap();
+
+        // A joint to a deleted entity is left out
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        assert!(physics_engine.get_joint_data().is_empty());
+
+        assert!(physics_engine.add_joint(body_id, &Joint::new(JointKind::Fixed, plain_id)).is_err());
+        assert!(physics_engine.add_joint(body_id, &Joint::new(JointKind::Fixed, body_id)).is_err());
+        let fixed = Joint::new(JointKind::Fixed, body_id);
+        assert!(physics_engine.set_joint_motor(fixed.id, None).is_err());
+    }
+
+    #[test]
+    fn test_set_joints_undo() {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("test_scene").unwrap();
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let entity_id = scene.create_entity("a").unwrap();
+        let other_id = scene.create_entity("b").unwrap();
+
+        let mut history = EditHistory::default();
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        let joint = Joint::new(JointKind::Rope, other_id);
+        let command = EditCommand::set_joints(entity, scene_id, vec![joint.clone()]);
+        assert_eq!(command.description(), "Add joint");
+        history.execute(command, &mut scene_manager).unwrap();
+
+        let joints = |scene_manager: &SceneManager| scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().joints.clone();
+        assert_eq!(joints(&scene_manager), vec![joint]);
+        history.undo(&mut scene_manager).unwrap();
+        assert!(joints(&scene_manager).is_empty());
+    }
+}
@@ -0,0 +1,717 @@
+use rust_2d_game_engine::animation::{AnimationClip, AnimationFinished, FrameSource, LoopMode, SpriteAnimation};
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::input_handler::InputHandler;
+use rust_2d_game_engine::joint::JointKind;
+use rust_2d_game_engine::lua_scripting::LuaScripting;
+use rust_2d_game_engine::physics_engine::{CollisionEvent, CollisionEventKind, PhysicsEngine};
+use std::fs;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Write a script to a unique temporary file
+    fn write_script(source: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("lua_scripting_test_{}.lua", Uuid::new_v4()));
+        fs::write(&path, source).unwrap();
+        path
+    }
+
+    fn setup_scene(scripts: &[(&str, &PathBuf)]) -> (SceneManager, Uuid, Vec<Uuid>) {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("test_scene").unwrap();
+        scene_manager.set_active_scene(scene_id).unwrap();
+
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let mut entity_ids = Vec::new();
+        for (name, script) in scripts {
+            let entity_id = scene.create_entity(name).unwrap();
+            scene.get_entity_mut(entity_id).unwrap().set_script((*script).clone()).unwrap();
+            entity_ids.push(entity_id);
+        }
+
+        (scene_manager, scene_id, entity_ids)
+    }
+
+    // Runs a frame of scripts with the physics engine lent to the physics bindings
+    fn run_with_physics(lua_scripting: &mut LuaScripting, scene_manager: &mut SceneManager, scene_id: Uuid, physics_engine: &mut PhysicsEngine) {
+        let input_handler = InputHandler::new();
+        lua_scripting.with_engines(physics_engine, &input_handler, |lua_scripting| {
+            lua_scripting.run_scripts_for_scene(scene_manager, scene_id, 1.0 / 60.0)
+        }).unwrap();
+    }
+
+    fn global_number(lua_scripting: &LuaScripting, name: &str) -> f64 {
+        lua_scripting.lua.globals().get::<Option<f64>>(name).unwrap().unwrap_or(0.0)
+    }
+
+    #[test]
+    fn test_script_state_persists_between_frames() {
+        let script = write_script(r#"
+            local count = 0
+            function update(self, dt)
+                count = count + 1
+                _G["count_" .. self.name] = count
+            end
+        "#);
+        let (mut scene_manager, scene_id, _) = setup_scene(&[("a", &script), ("b", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        for _ in 0..3 {
+            lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+        }
+
+        // Each instance keeps its own counter
+        assert_eq!(global_number(&lua_scripting, "count_a"), 3.0);
+        assert_eq!(global_number(&lua_scripting, "count_b"), 3.0);
+    }
+
+    #[test]
+    fn test_init_runs_once_and_receives_self() {
+        let script = write_script(r#"
+            _G.loads = (_G.loads or 0) + 1
+            function init(self)
+                _G.inits = (_G.inits or 0) + 1
+                _G.init_id = self.id
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("player", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.start_session(&mut scene_manager, scene_id).unwrap();
+        for _ in 0..5 {
+            lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+        }
+
+        assert_eq!(global_number(&lua_scripting, "loads"), 1.0);
+        assert_eq!(global_number(&lua_scripting, "inits"), 1.0);
+        let init_id: String = lua_scripting.lua.globals().get("init_id").unwrap();
+        assert_eq!(init_id, entity_ids[0].to_string());
+    }
+
+    #[test]
+    fn test_update_receives_delta_time() {
+        let script = write_script(r#"
+            function update(self, dt)
+                _G.last_dt = dt
+            end
+        "#);
+        let (mut scene_manager, scene_id, _) = setup_scene(&[("player", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 0.25).unwrap();
+
+        assert_eq!(global_number(&lua_scripting, "last_dt"), 0.25);
+    }
+
+    #[test]
+    fn test_scripts_compiled_once_per_session() {
+        let script = write_script("function update(self, dt) _G.version = 1 end");
+        let (mut scene_manager, scene_id, _) = setup_scene(&[("a", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.compile_scripts(&scene_manager, scene_id).unwrap();
+
+        // Editing the file mid-session has no effect, and a second entity reuses the cached chunk
+        fs::write(&script, "function update(self, dt) _G.version = 2 end").unwrap();
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let second = scene.create_entity("b").unwrap();
+        scene.get_entity_mut(second).unwrap().set_script(script.clone()).unwrap();
+
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+        assert_eq!(global_number(&lua_scripting, "version"), 1.0);
+    }
+
+    #[test]
+    fn test_enable_disable_and_destroy_callbacks() {
+        let script = write_script(r#"
+            function update(self, dt) _G.updates = (_G.updates or 0) + 1 end
+            function on_enable(self) _G.enables = (_G.enables or 0) + 1 end
+            function on_disable(self) _G.disables = (_G.disables or 0) + 1 end
+            function on_destroy(self) _G.destroyed = self.id end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("enemy", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+        assert_eq!(global_number(&lua_scripting, "enables"), 1.0);
+
+        lua_scripting.set_script_enabled(&mut scene_manager, entity_ids[0], false).unwrap();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+        assert_eq!(global_number(&lua_scripting, "disables"), 1.0);
+        assert_eq!(global_number(&lua_scripting, "updates"), 1.0);
+
+        // Removing the entity destroys its instance
+        scene_manager.get_scene_mut(scene_id).unwrap().delete_entity(entity_ids[0]).unwrap();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+        assert!(!lua_scripting.has_instance(&entity_ids[0]));
+        let destroyed: String = lua_scripting.lua.globals().get("destroyed").unwrap();
+        assert_eq!(destroyed, entity_ids[0].to_string());
+    }
+
+    #[test]
+    fn test_end_session_resets_state() {
+        let script = write_script("function update(self, dt) end");
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("a", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+        assert!(lua_scripting.is_session_active());
+        assert!(lua_scripting.has_instance(&entity_ids[0]));
+
+        lua_scripting.end_session(&mut scene_manager);
+        assert!(!lua_scripting.is_session_active());
+        assert!(!lua_scripting.has_instance(&entity_ids[0]));
+    }
+
+    #[test]
+    fn test_script_error_is_reported() {
+        let script = write_script("function update(self, dt) error('boom') end");
+        let (mut scene_manager, scene_id, _) = setup_scene(&[("a", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        let result = lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0);
+        assert!(result.unwrap_err().contains("boom"));
+
+        // A faulted instance is not called again
+        assert!(lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).is_ok());
+    }
+
+    #[test]
+    fn test_entity_fields_and_attributes() {
+        let script = write_script(r#"
+            function init(self)
+                self.x = 10
+                self:set("score", 3)
+                self:set("label", "bird")
+                self:set("velocity", { x = 1.5, y = -2 })
+                self.name = "renamed"
+            end
+            function update(self, dt)
+                self:set("score", self:get("score") + 1)
+                _G.velocity_y = self.velocity.y
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("bird", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_ids[0]).unwrap();
+        assert_eq!(entity.name, "renamed");
+        assert_eq!(entity.get_x(), 10.0);
+        assert_eq!(entity.get_attribute_by_name("score").unwrap().value, AttributeValue::Integer(4));
+        assert_eq!(entity.get_attribute_by_name("label").unwrap().value, AttributeValue::String("bird".to_string()));
+        assert_eq!(entity.get_attribute_by_name("velocity").unwrap().value, AttributeValue::Vector2(1.5, -2.0));
+        assert_eq!(global_number(&lua_scripting, "velocity_y"), -2.0);
+    }
+
+    #[test]
+    fn test_attribute_writes_are_type_checked() {
+        let script = write_script(r#"
+            function init(self)
+                _G.string_to_float = pcall(function() self.x = "ten" end)
+                _G.float_to_int = pcall(function() self:set("lives", 1.5) end)
+                _G.unknown_field = pcall(function() self.missing = 1 end)
+                _G.read_only_id = pcall(function() self.id = "other" end)
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("player", &script)]);
+        scene_manager
+            .get_scene_mut(scene_id).unwrap()
+            .get_entity_mut(entity_ids[0]).unwrap()
+            .create_attribute("lives", AttributeType::Integer, AttributeValue::Integer(3))
+            .unwrap();
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+
+        for name in ["string_to_float", "float_to_int", "unknown_field", "read_only_id"] {
+            let succeeded: bool = lua_scripting.lua.globals().get(name).unwrap();
+            assert!(!succeeded, "{} should have failed", name);
+        }
+
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_ids[0]).unwrap();
+        assert_eq!(entity.get_x(), 0.0);
+        assert_eq!(entity.get_attribute_by_name("lives").unwrap().value, AttributeValue::Integer(3));
+    }
+
+    #[test]
+    fn test_scene_find_and_add_entity() {
+        let script = write_script(r#"
+            function init(self)
+                local target = scene:find("target")
+                target.y = 42
+                _G.found_missing = scene:find("nobody") ~= nil
+                _G.same_entity = scene:get(target.id) == target
+
+                local spawned = scene:add_entity("spawned")
+                spawned:set("hp", 7)
+                _G.entity_count = #scene:entities()
+            end
+        "#);
+        let (mut scene_manager, scene_id, _) = setup_scene(&[("spawner", &script)]);
+        let target_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("target").unwrap();
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+
+        let scene = scene_manager.get_scene(scene_id).unwrap();
+        assert_eq!(scene.get_entity(target_id).unwrap().get_y(), 42.0);
+        let spawned = scene.entities.values().find(|entity| entity.name == "spawned").unwrap();
+        assert_eq!(spawned.get_attribute_by_name("hp").unwrap().value, AttributeValue::Integer(7));
+
+        let globals = lua_scripting.lua.globals();
+        assert!(!globals.get::<bool>("found_missing").unwrap());
+        assert!(globals.get::<bool>("same_entity").unwrap());
+        assert_eq!(global_number(&lua_scripting, "entity_count"), scene.entities.len() as f64);
+    }
+
+    #[test]
+    fn test_entity_handle_unusable_outside_callbacks() {
+        let script = write_script("function init(self) _G.saved = self end");
+        let (mut scene_manager, scene_id, _) = setup_scene(&[("a", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+
+        let result = lua_scripting.lua.load("return saved.x").eval::<f64>();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_collision_callbacks() {
+        let script = write_script(r#"
+            function on_collision_enter(self, other, collision)
+                _G["enter_" .. self.name] = other.name
+                _G["normal_x_" .. self.name] = collision.normal.x
+            end
+            function on_collision_exit(self, other, collision)
+                _G["exit_" .. self.name] = other.name
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("bird", &script), ("pipe", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+
+        let mut event = CollisionEvent {
+            kind: CollisionEventKind::Started,
+            entity1: entity_ids[0],
+            entity2: entity_ids[1],
+            normal: (1.0, 0.0),
+            impulse: 2.0,
+        };
+        lua_scripting.handle_collision_events(&mut scene_manager, &[event]).unwrap();
+        event.kind = CollisionEventKind::Stopped;
+        lua_scripting.handle_collision_events(&mut scene_manager, &[event]).unwrap();
+
+        let globals = lua_scripting.lua.globals();
+        assert_eq!(globals.get::<String>("enter_bird").unwrap(), "pipe");
+        assert_eq!(globals.get::<String>("enter_pipe").unwrap(), "bird");
+        assert_eq!(globals.get::<String>("exit_bird").unwrap(), "pipe");
+        // Each side sees the normal pointing away from itself
+        assert_eq!(global_number(&lua_scripting, "normal_x_bird"), 1.0);
+        assert_eq!(global_number(&lua_scripting, "normal_x_pipe"), -1.0);
+    }
+
+    #[test]
+    fn test_trigger_callbacks() {
+        let script = write_script(r#"
+            function on_trigger_enter(self, other)
+                _G["trigger_enter_" .. self.name] = other.name
+            end
+            function on_trigger_exit(self, other)
+                _G["trigger_exit_" .. self.name] = other.name
+            end
+            function on_collision_enter(self, other, collision)
+                _G.collided = true
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("coin", &script), ("player", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+
+        let mut event = CollisionEvent {
+            kind: CollisionEventKind::SensorStarted,
+            entity1: entity_ids[0],
+            entity2: entity_ids[1],
+            normal: (0.0, 0.0),
+            impulse: 0.0,
+        };
+        lua_scripting.handle_collision_events(&mut scene_manager, &[event]).unwrap();
+        event.kind = CollisionEventKind::SensorStopped;
+        lua_scripting.handle_collision_events(&mut scene_manager, &[event]).unwrap();
+
+        let globals = lua_scripting.lua.globals();
+        assert_eq!(globals.get::<String>("trigger_enter_coin").unwrap(), "player");
+        assert_eq!(globals.get::<String>("trigger_enter_player").unwrap(), "coin");
+        assert_eq!(globals.get::<String>("trigger_exit_player").unwrap(), "coin");
+        assert_eq!(globals.get::<Option<bool>>("collided").unwrap(), None);
+    }
+
+    #[test]
+    fn test_spawn_prefab() {
+        let script = write_script(r#"
+            function init(self)
+                local coin = spawn_prefab("coin", 3, 4)
+                _G.coin_x = coin.x
+                _G.coin_value = coin:get("value")
+                _G.missing_failed = not pcall(spawn_prefab, "nothing", 0, 0)
+            end
+        "#);
+        let (mut scene_manager, scene_id, _) = setup_scene(&[("spawner", &script)]);
+        let template_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("coin").unwrap();
+        let template = scene_manager.get_scene_mut(scene_id).unwrap().get_entity_mut(template_id).unwrap();
+        template.create_attribute("value", AttributeType::Integer, AttributeValue::Integer(5)).unwrap();
+        template.create_attribute("has_collision", AttributeType::Boolean, AttributeValue::Boolean(true)).unwrap();
+        let prefab_id = scene_manager.create_prefab(scene_id, template_id, "coin").unwrap();
+
+        let mut physics_engine = PhysicsEngine::new();
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.initialize_bindings_physics_engine().unwrap();
+        run_with_physics(&mut lua_scripting, &mut scene_manager, scene_id, &mut physics_engine);
+
+        assert_eq!(global_number(&lua_scripting, "coin_x"), 3.0);
+        assert_eq!(global_number(&lua_scripting, "coin_value"), 5.0);
+        assert!(lua_scripting.lua.globals().get::<bool>("missing_failed").unwrap());
+
+        let (_, coin_id) = scene_manager.instances_of(prefab_id).into_iter()
+            .find(|(_, entity_id)| *entity_id != template_id)
+            .unwrap();
+        assert!(physics_engine.has_rigid_body(&coin_id));
+    }
+
+    #[test]
+    fn test_play_animation_and_finished_callback() {
+        let script = write_script(r#"
+            function init(self)
+                self:play_animation("flap")
+                _G.playing = self:current_animation()
+                _G.missing_failed = not pcall(function() self:play_animation("dive") end)
+            end
+            function on_animation_finished(self, clip)
+                _G.finished_clip = clip
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("bird", &script)]);
+        let mut animation = SpriteAnimation::new(FrameSource::Images);
+        animation.add_clip("flap", AnimationClip::new(vec![0], 0.1).with_loop_mode(LoopMode::Once)).unwrap();
+        let bird = scene_manager.get_scene_mut(scene_id).unwrap().get_entity_mut(entity_ids[0]).unwrap();
+        bird.animation = Some(Box::new(animation));
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+        let globals = lua_scripting.lua.globals();
+        assert_eq!(globals.get::<String>("playing").unwrap(), "flap");
+        assert!(globals.get::<bool>("missing_failed").unwrap());
+
+        let finished = scene_manager.get_scene_mut(scene_id).unwrap().update_animations(0.2);
+        assert_eq!(finished, vec![AnimationFinished { entity_id: entity_ids[0], clip: "flap".to_string() }]);
+        lua_scripting.handle_animation_events(&mut scene_manager, &finished).unwrap();
+        assert_eq!(lua_scripting.lua.globals().get::<String>("finished_clip").unwrap(), "flap");
+    }
+
+    #[test]
+    fn test_camera_follow_and_switch() {
+        let script = write_script(r#"
+            function init(self)
+                local camera = scene:camera()
+                camera:follow(self)
+                _G.follow_target = camera.follow_target
+                _G.not_camera_failed = not pcall(function() self:follow(camera) end)
+
+                local other = scene:find("other_camera")
+                scene:set_camera(other)
+                _G.switched = scene:camera() == other
+                _G.switch_failed = not pcall(function() scene:set_camera(self) end)
+                camera:follow(nil)
+                _G.stopped = camera.follow_target == ""
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("player", &script)]);
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let main_camera = scene.default_camera.unwrap();
+        let other_camera = scene.create_camera("other_camera").unwrap();
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+
+        let globals = lua_scripting.lua.globals();
+        assert_eq!(globals.get::<String>("follow_target").unwrap(), entity_ids[0].to_string());
+        assert!(globals.get::<bool>("not_camera_failed").unwrap());
+        assert!(globals.get::<bool>("switched").unwrap());
+        assert!(globals.get::<bool>("switch_failed").unwrap());
+        assert!(globals.get::<bool>("stopped").unwrap());
+
+        let scene = scene_manager.get_scene(scene_id).unwrap();
+        assert_eq!(scene.default_camera, Some(other_camera));
+        assert_ne!(scene.default_camera, Some(main_camera));
+    }
+
+    #[test]
+    fn test_set_text() {
+        let script = write_script(r##"
+            function init(self)
+                _G.before = self:get_text()
+                _G.color_failed = not pcall(function() self:set_text_color("#FF0000") end)
+                self.score = 0
+            end
+
+            function update(self, dt)
+                self.score = self.score + 1
+                self:set_text("Score: " .. self.score)
+                self:set_text_color("#FFCC00")
+                _G.after = self:get_text()
+            end
+        "##);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("score", &script)]);
+        scene_manager.get_scene_mut(scene_id).unwrap()
+            .get_entity_mut(entity_ids[0]).unwrap()
+            .create_attribute("score", AttributeType::Integer, AttributeValue::Integer(0)).unwrap();
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+
+        let globals = lua_scripting.lua.globals();
+        assert!(globals.get::<Option<String>>("before").unwrap().is_none());
+        assert!(globals.get::<bool>("color_failed").unwrap());
+        assert_eq!(globals.get::<String>("after").unwrap(), "Score: 2");
+
+        // The text is added with default settings the first time
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_ids[0]).unwrap();
+        let text = entity.text.as_ref().unwrap();
+        assert_eq!(text.text, "Score: 2");
+        assert_eq!(text.color, [255, 204, 0, 255]);
+        assert_eq!(text.size, 24.0);
+    }
+
+    #[test]
+    fn test_physics_queries() {
+        let script = write_script(r#"
+            function init(self)
+                local hit = raycast(self.x, 0, 1, 0, 500, { exclude = self })
+                _G.hit_name = hit.entity.name
+                _G.hit_distance = hit.distance
+                _G.hit_normal_x = hit.normal.x
+                _G.miss = raycast(self.x, 0, 0, 1, 500, { exclude = self }) == nil
+                _G.hits_itself = raycast(self.x, 0, 0, 1, 500).entity == self
+                _G.hit_count = #raycast_all(self.x, 0, 1, 0, 500, { exclude = { self } })
+                _G.no_layer_hits = #raycast_all(self.x, 0, 1, 0, 500, { layers = 0 })
+                _G.circle_name = overlap_circle(100, 25, 16)[1].name
+                _G.box_count = #overlap_box(150, 0, 120, 4, nil, { exclude = self })
+                _G.point_name = point_query(205, 0)[1].name
+                local cast = shape_cast({ radius = 5 }, self.x, 0, 1, 0, 500, { exclude = self })
+                _G.cast_distance = cast.distance
+                _G.bad_shape_failed = not pcall(shape_cast, {}, 0, 0, 1, 0, 500)
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("eye", &script)]);
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        for (name, x, size) in [("eye", 0.0, 2.0), ("wall", 100.0, 20.0), ("far_wall", 200.0, 20.0)] {
+            let entity_id = match name {
+                "eye" => entity_ids[0],
+                _ => scene.create_entity(name).unwrap(),
+            };
+            let entity = scene.get_entity_mut(entity_id).unwrap();
+            entity.set_position(x, 0.0, 0.0).unwrap();
+            entity.create_attribute("has_collision", AttributeType::Boolean, AttributeValue::Boolean(true)).unwrap();
+            entity.create_attribute("collider_size", AttributeType::Vector2, AttributeValue::Vector2(size, size)).unwrap();
+        }
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(scene_manager.get_scene(scene_id).unwrap());
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.initialize_bindings_physics_engine().unwrap();
+        run_with_physics(&mut lua_scripting, &mut scene_manager, scene_id, &mut physics_engine);
+
+        let globals = lua_scripting.lua.globals();
+        assert_eq!(globals.get::<String>("hit_name").unwrap(), "wall");
+        assert!((global_number(&lua_scripting, "hit_distance") - 90.0).abs() < 1e-3);
+        assert_eq!(global_number(&lua_scripting, "hit_normal_x"), -1.0);
+        assert!(globals.get::<bool>("miss").unwrap());
+        assert!(globals.get::<bool>("hits_itself").unwrap());
+        assert_eq!(global_number(&lua_scripting, "hit_count"), 2.0);
+        assert_eq!(global_number(&lua_scripting, "no_layer_hits"), 0.0);
+        assert_eq!(globals.get::<String>("circle_name").unwrap(), "wall");
+        assert_eq!(global_number(&lua_scripting, "box_count"), 2.0);
+        assert_eq!(globals.get::<String>("point_name").unwrap(), "far_wall");
+        assert!((global_number(&lua_scripting, "cast_distance") - 85.0).abs() < 1e-2);
+        assert!(globals.get::<bool>("bad_shape_failed").unwrap());
+    }
+
+    #[test]
+    fn test_joints() {
+        let script = write_script(r#"
+            function init(self)
+                local door = scene:find("door")
+                local joint_id = add_joint(self, door, "revolute", { anchor = { 10, 0 }, limits = { -1, 1 } })
+                _G.motor_ok = pcall(set_joint_motor, joint_id, 2, 500)
+                _G.bad_kind_failed = not pcall(add_joint, self, door, "hinge")
+                _G.spring_id = add_joint(self, door, "spring", { length = 20, stiffness = 5 })
+                _G.removed = remove_joint(spring_id)
+                _G.removed_again = remove_joint(spring_id)
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("frame", &script)]);
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let door_id = scene.create_entity("door").unwrap();
+        for entity_id in [entity_ids[0], door_id] {
+            let entity = scene.get_entity_mut(entity_id).unwrap();
+            entity.create_attribute("has_collision", AttributeType::Boolean, AttributeValue::Boolean(false)).unwrap();
+        }
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(scene_manager.get_scene(scene_id).unwrap());
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.initialize_bindings_physics_engine().unwrap();
+        run_with_physics(&mut lua_scripting, &mut scene_manager, scene_id, &mut physics_engine);
+
+        let globals = lua_scripting.lua.globals();
+        assert!(globals.get::<bool>("motor_ok").unwrap());
+        assert!(globals.get::<bool>("bad_kind_failed").unwrap());
+        assert!(globals.get::<bool>("removed").unwrap());
+        assert!(!globals.get::<bool>("removed_again").unwrap());
+
+        // The joint is saved with the entity and added to the physics engine
+        let joints = &scene_manager.get_scene(scene_id).unwrap().get_entity(entity_ids[0]).unwrap().joints;
+        assert_eq!(joints.len(), 1);
+        assert_eq!((joints[0].kind, joints[0].other), (JointKind::Revolute, door_id));
+        assert_eq!((joints[0].anchor, joints[0].limits), ((10.0, 0.0), Some((-1.0, 1.0))));
+        assert!(physics_engine.has_joint(&joints[0].id));
+        assert_eq!(physics_engine.get_joint_data().len(), 1);
+    }
+
+    #[test]
+    fn test_move_and_slide() {
+        let script = write_script(r#"
+            function update(self, dt)
+                _G.movement = move_and_slide(self, 3, 1)
+                _G.grounded = is_grounded(self)
+                _G.floor_failed = not pcall(move_and_slide, scene:find("floor"), 1, 0)
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("player", &script)]);
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let floor_id = scene.create_entity("floor").unwrap();
+        for (entity_id, position, size) in [(entity_ids[0], (0.0, -21.0), (20.0, 40.0)), (floor_id, (0.0, 10.0), (1000.0, 20.0))] {
+            let entity = scene.get_entity_mut(entity_id).unwrap();
+            entity.create_attribute("has_collision", AttributeType::Boolean, AttributeValue::Boolean(true)).unwrap();
+            entity.set_position(position.0, position.1, 0.0).unwrap();
+            entity.create_attribute("collider_size", AttributeType::Vector2, AttributeValue::Vector2(size.0, size.1)).unwrap();
+        }
+        scene.get_entity_mut(entity_ids[0]).unwrap().character_controller = Some(Default::default());
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(scene_manager.get_scene(scene_id).unwrap());
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.initialize_bindings_physics_engine().unwrap();
+        for _ in 0..3 {
+            run_with_physics(&mut lua_scripting, &mut scene_manager, scene_id, &mut physics_engine);
+            physics_engine.step(scene_manager.get_scene_mut(scene_id).unwrap());
+        }
+
+        let globals = lua_scripting.lua.globals();
+        let movement = globals.get::<mlua::Table>("movement").unwrap();
+        assert!((movement.get::<f32>("x").unwrap() - 3.0).abs() < 0.01);
+        assert!(movement.get::<f32>("y").unwrap().abs() < 0.5);
+        assert!(movement.get::<bool>("grounded").unwrap());
+        assert!(globals.get::<bool>("grounded").unwrap());
+        assert!(globals.get::<bool>("floor_failed").unwrap());
+        let position = physics_engine.interpolated_positions(1.0)[&entity_ids[0]];
+        assert!((position.0 - 9.0).abs() < 0.1, "The player is at {:?}", position);
+    }
+
+    #[test]
+    fn test_physics_settings() {
+        let script = write_script(r#"
+            function update(self, dt)
+                local settings = get_physics_settings()
+                _G.gravity_y = settings.gravity.y
+                set_physics_settings({ gravity = { x = 0, y = -20 }, ccd = true })
+                _G.ccd = get_physics_settings().ccd
+                _G.invalid_failed = not pcall(set_physics_settings, { time_step = 0 })
+            end
+        "#);
+        let (mut scene_manager, scene_id, _) = setup_scene(&[("player", &script)]);
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(scene_manager.get_scene(scene_id).unwrap());
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.initialize_bindings_physics_engine().unwrap();
+        run_with_physics(&mut lua_scripting, &mut scene_manager, scene_id, &mut physics_engine);
+
+        let globals = lua_scripting.lua.globals();
+        assert_eq!(globals.get::<f32>("gravity_y").unwrap(), 50.0);
+        assert!(globals.get::<bool>("ccd").unwrap());
+        assert!(globals.get::<bool>("invalid_failed").unwrap());
+
+        // Only the given fields change, in the physics engine and in the scene
+        let settings = physics_engine.settings();
+        assert_eq!((settings.gravity, settings.ccd, settings.time_step), ((0.0, -20.0), true, 1.0 / 60.0));
+        assert_eq!(&scene_manager.get_scene(scene_id).unwrap().physics, settings);
+    }
+
+    #[test]
+    fn test_transform_fields() {
+        let script = write_script(r#"
+            function update(self, dt)
+                _G.default_rotation = self.rotation
+                _G.default_scale_x = self.scale.x
+                self.x = 12
+                self.rotation = 0.5
+                self.scale = { x = 2, y = 3 }
+                _G.scale_y = self.scale.y
+                _G.bad_scale_failed = not pcall(function() self.scale = 4 end)
+            end
+        "#);
+        let (mut scene_manager, scene_id, entity_ids) = setup_scene(&[("player", &script)]);
+
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+
+        let globals = lua_scripting.lua.globals();
+        assert_eq!(globals.get::<f32>("default_rotation").unwrap(), 0.0);
+        assert_eq!(globals.get::<f32>("default_scale_x").unwrap(), 1.0);
+        assert_eq!(globals.get::<f32>("scale_y").unwrap(), 3.0);
+        assert!(globals.get::<bool>("bad_scale_failed").unwrap());
+
+        // The entity had no rotation or scale attribute, they are created
+        let transform = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_ids[0]).unwrap().local_transform();
+        assert_eq!((transform.position, transform.rotation, transform.scale), ((12.0, 0.0), 0.5, (2.0, 3.0)));
+    }
+
+    #[test]
+    fn test_engines_are_only_reachable_while_lent() {
+        let script = write_script(r#"
+            function update(self, dt)
+                local ok, message = pcall(remove_entity_from_physics_engine, self)
+                _G.ok, _G.message = ok, tostring(message)
+                _G.key_ok = pcall(is_key_just_pressed, "Space")
+                _G.raycast_ok = pcall(raycast, 0, 0, 1, 0, 10)
+            end
+        "#);
+        let (mut scene_manager, scene_id, _) = setup_scene(&[("player", &script)]);
+
+        // The bindings exist, but no physics engine or input handler has been lent
+        let mut physics_engine = PhysicsEngine::new();
+        let mut lua_scripting = LuaScripting::new();
+        lua_scripting.initialize_bindings_physics_engine().unwrap();
+        lua_scripting.initialize_bindings_input_handler().unwrap();
+        lua_scripting.run_scripts_for_scene(&mut scene_manager, scene_id, 1.0 / 60.0).unwrap();
+        let globals = lua_scripting.lua.globals();
+        assert!(!globals.get::<bool>("ok").unwrap());
+        assert!(globals.get::<String>("message").unwrap().contains("only be accessed while scripts are running"));
+        assert!(!globals.get::<bool>("key_ok").unwrap());
+        assert!(!globals.get::<bool>("raycast_ok").unwrap());
+
+        run_with_physics(&mut lua_scripting, &mut scene_manager, scene_id, &mut physics_engine);
+        assert!(lua_scripting.lua.globals().get::<bool>("ok").unwrap());
+        assert!(lua_scripting.lua.globals().get::<bool>("key_ok").unwrap());
+        assert!(lua_scripting.lua.globals().get::<bool>("raycast_ok").unwrap());
+    }
+}
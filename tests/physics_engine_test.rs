@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use rust_2d_game_engine::physics_engine::PhysicsEngine;
-    use rust_2d_game_engine::ecs::{Scene, Entity, PhysicsProperties};
+    use rust_2d_game_engine::physics_engine::{PhysicsEngine, CollisionEventKind, ColliderData, QueryFilter, QueryShape, parse_collider_points, trace_sprite_outline};
+    use rust_2d_game_engine::physics_engine::{collision_layer_mask, format_collision_layers, CollisionEvent};
+    use rust_2d_game_engine::ecs::{Scene, Entity, PhysicsProperties, AttributeType, AttributeValue, ColliderShape};
     use rapier2d::prelude::*;
 
     #[test]
@@ -69,10 +70,10 @@ mod tests {
             scene.update_entity_attributes(updates).unwrap();
         }
 
-        // Get final position and check with a reasonable threshold
+        // Get final position and check with a reasonable threshold, gravity points down the screen (+y)
         let final_y = scene.get_entity(entity_id).unwrap().get_y();
         assert!(
-            final_y < initial_y - 1.0, // Ensure significant movement
+            final_y > initial_y + 1.0, // Ensure significant movement
             "Entity should have fallen due to gravity. Initial Y: {}, Final Y: {}",
             initial_y,
             final_y
@@ -106,9 +107,10 @@ mod tests {
             ..Default::default()
         };
 
+        // Above the ground, +y points down
         let falling_id = scene.create_physical_entity(
             "falling_object",
-            (0.0, 5.0, 0.0),
+            (0.0, -5.0, 0.0),
             falling_props
         ).unwrap();
 
@@ -154,4 +156,530 @@ mod tests {
         // Verify everything is cleared
         assert!(physics_engine.is_empty());
     }
+
+    #[test]
+    fn test_collision_events() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+
+        let ground_props = PhysicsProperties {
+            is_movable: false,
+            affected_by_gravity: false,
+            has_collision: true,
+            ..Default::default()
+        };
+        let ground_id = scene.create_physical_entity("ground", (0.0, 0.0, 0.0), ground_props).unwrap();
+
+        // The ball starts just above the ground and falls onto it
+        let ball_props = PhysicsProperties {
+            is_movable: true,
+            affected_by_gravity: true,
+            has_collision: true,
+            ..Default::default()
+        };
+        let ball_id = scene.create_physical_entity("ball", (0.0, -1.5, 0.0), ball_props).unwrap();
+
+        physics_engine.add_entity(scene.get_entity(ground_id).unwrap());
+        physics_engine.add_entity(scene.get_entity(ball_id).unwrap());
+
+        let mut started = None;
+        for _ in 0..60 {
+            physics_engine.step(&mut scene);
+            started = physics_engine
+                .drain_collision_events()
+                .into_iter()
+                .find(|event| event.kind == CollisionEventKind::Started);
+            if started.is_some() {
+                break;
+            }
+        }
+
+        let started = started.expect("A started event should have been reported");
+        let pair = [started.entity1, started.entity2];
+        assert!(pair.contains(&ground_id) && pair.contains(&ball_id));
+        assert!(started.normal.0.abs() < 1e-3 && started.normal.1.abs() > 0.99, "Unexpected normal {:?}", started.normal);
+
+        // Removing one of the entities reports the contact as stopped
+        physics_engine.remove_entity(ball_id);
+        physics_engine.step(&mut scene);
+        let events = physics_engine.drain_collision_events();
+        assert!(events.iter().any(|event| event.kind == CollisionEventKind::Stopped
+            && (event.entity1 == ball_id || event.entity2 == ball_id)));
+    }
+
+    // Adds a physical entity with the given collider and returns what the engine reports for it
+    fn collider_for(shape: ColliderShape, size: (f32, f32), offset: (f32, f32), points: &str) -> ColliderData {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+
+        let physics_props = PhysicsProperties {
+            collider_shape: shape,
+            collider_size: size,
+            collider_offset: offset,
+            ..Default::default()
+        };
+        let entity_id = scene.create_physical_entity("collider", (0.0, 0.0, 0.0), physics_props).unwrap();
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        let points_id = entity.get_attribute_by_name("collider_points").unwrap().id;
+        entity.modify_attribute(points_id, None, None, Some(AttributeValue::String(points.to_string()))).unwrap();
+
+        physics_engine.add_entity(scene.get_entity(entity_id).unwrap());
+        let mut colliders = physics_engine.get_collider_data();
+        assert_eq!(colliders.len(), 1);
+        colliders.remove(0)
+    }
+
+    #[test]
+    fn test_collider_shapes_and_sizes() {
+        let rectangle = collider_for(ColliderShape::Box, (4.0, 2.0), (0.0, 0.0), "");
+        assert_eq!(rectangle.shape, "Rectangle");
+        assert_eq!(rectangle.size, (4.0, 2.0));
+        assert_eq!(rectangle.points.len(), 4);
+
+        let circle = collider_for(ColliderShape::Circle, (6.0, 6.0), (0.0, 0.0), "");
+        assert_eq!(circle.shape, "Circle");
+        assert_eq!(circle.size, (6.0, 6.0));
+
+        // A tall capsule keeps its full height including the rounded ends
+        let capsule = collider_for(ColliderShape::Capsule, (2.0, 6.0), (0.0, 0.0), "");
+        assert_eq!(capsule.shape, "Capsule");
+        assert!((capsule.size.0 - 2.0).abs() < 1e-4 && (capsule.size.1 - 6.0).abs() < 1e-4, "{:?}", capsule.size);
+    }
+
+    #[test]
+    fn test_collider_offset() {
+        let collider = collider_for(ColliderShape::Box, (2.0, 2.0), (3.0, -1.0), "");
+        assert_eq!(collider.position, (3.0, -1.0));
+        assert!(collider.points.contains(&(4.0, 0.0)));
+        assert!(collider.points.contains(&(2.0, -2.0)));
+    }
+
+    #[test]
+    fn test_explicit_collider_points() {
+        // The point inside the triangle is dropped from the convex hull
+        let convex = collider_for(ColliderShape::ConvexPolygon, (0.0, 0.0), (0.0, 0.0), "0 -2, 2 2, -2 2, 0 1");
+        assert_eq!(convex.shape, "ConvexPolygon");
+        assert_eq!(convex.points.len(), 3);
+        assert_eq!(convex.size, (4.0, 4.0));
+
+        let polyline = collider_for(ColliderShape::Polyline, (0.0, 0.0), (0.0, 0.0), "-3 0, 0 1, 3 0");
+        assert_eq!(polyline.shape, "Polyline");
+        assert_eq!(polyline.points, vec![(-3.0, 0.0), (0.0, 1.0), (3.0, 0.0)]);
+
+        // Unusable points fall back to a box
+        let fallback = collider_for(ColliderShape::ConvexPolygon, (2.0, 2.0), (0.0, 0.0), "1 1");
+        assert_eq!(fallback.shape, "Rectangle");
+    }
+
+    #[test]
+    fn test_parse_collider_points() {
+        assert_eq!(parse_collider_points("1 2, -3.5 4").unwrap(), vec![(1.0, 2.0), (-3.5, 4.0)]);
+        assert_eq!(parse_collider_points("  ").unwrap(), Vec::new());
+        assert!(parse_collider_points("1, 2").is_err());
+        assert!(parse_collider_points("a b").is_err());
+    }
+
+    #[test]
+    fn test_trace_sprite_outline() {
+        // A 20x10 transparent sprite with an opaque 10x6 block starting at (4, 2)
+        let mut image = image::RgbaImage::new(20, 10);
+        for x in 4..14 {
+            for y in 2..8 {
+                image.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
+            }
+        }
+        let path = std::env::temp_dir().join(format!("trace_{}.png", uuid::Uuid::new_v4()));
+        image.save(&path).unwrap();
+
+        let outline = trace_sprite_outline(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The corners of the block, relative to the sprite's center (10, 5)
+        assert_eq!(outline.len(), 4);
+        for corner in [(-6.0, -3.0), (4.0, -3.0), (4.0, 3.0), (-6.0, 3.0)] {
+            assert!(outline.contains(&corner), "{:?} not in {:?}", corner, outline);
+        }
+    }
+
+    #[test]
+    fn test_interpolated_positions() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+
+        let physics_props = PhysicsProperties {
+            is_movable: true,
+            has_collision: true,
+            ..Default::default()
+        };
+        let entity_id = scene.create_physical_entity("mover", (0.0, 0.0, 0.0), physics_props).unwrap();
+        physics_engine.add_entity(scene.get_entity(entity_id).unwrap());
+        physics_engine.set_velocity(&entity_id, vector![60.0, 0.0]);
+
+        // Before any step, bodies are where they are
+        assert_eq!(physics_engine.interpolated_positions(0.5).get(&entity_id), Some(&(0.0, 0.0)));
+
+        physics_engine.step(&mut scene);
+        let previous = physics_engine.interpolated_positions(0.0)[&entity_id];
+        let current = physics_engine.interpolated_positions(1.0)[&entity_id];
+        let halfway = physics_engine.interpolated_positions(0.5)[&entity_id];
+
+        assert_eq!(previous, (0.0, 0.0));
+        assert!(current.0 > 0.5, "body should have moved, at {:?}", current);
+        assert!((halfway.0 - current.0 / 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_child_body_uses_world_position() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+
+        let parent_id = scene.create_entity("parent").unwrap();
+        scene.get_entity_mut(parent_id).unwrap().set_position(100.0, 0.0, 0.0).unwrap();
+
+        let child_id = scene.create_physical_entity("child", (0.0, 0.0, 0.0), PhysicsProperties {
+            is_movable: true,
+            affected_by_gravity: true,
+            ..Default::default()
+        }).unwrap();
+        let child = scene.get_entity_mut(child_id).unwrap();
+        child.set_position(5.0, 0.0, 0.0).unwrap();
+        child.parent = Some(parent_id);
+
+        physics_engine.load_scene(&scene);
+        let collider = physics_engine.get_collider_data().into_iter().find(|c| c.entity_id == child_id).unwrap();
+        assert!((collider.position.0 - 105.0).abs() < 1.0, "Child body should start at the parent's offset: {:?}", collider.position);
+
+        // Positions written back stay relative to the parent
+        for _ in 0..30 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+        let child = scene.get_entity(child_id).unwrap();
+        assert!((child.get_x() - 5.0).abs() < 1e-3);
+        assert!(child.get_y() != 0.0);
+    }
+
+    #[test]
+    fn test_interpolated_rotations() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+
+        let spinning_props = PhysicsProperties {
+            is_movable: true,
+            has_collision: true,
+            can_rotate: true,
+            ..Default::default()
+        };
+        let spinner = scene.create_physical_entity("spinner", (0.0, 0.0, 0.0), spinning_props).unwrap();
+        let locked = scene.create_physical_entity("locked", (100.0, 0.0, 0.0), PhysicsProperties {
+            is_movable: true,
+            has_collision: true,
+            ..Default::default()
+        }).unwrap();
+        for (entity_id, x) in [(spinner, 0.0), (locked, 100.0)] {
+        }
+        // Bodies start at their entity's rotation
+        scene.get_entity_mut(spinner).unwrap()
+            .create_attribute("rotation", AttributeType::Float, AttributeValue::Float(0.5)).unwrap();
+        physics_engine.load_scene(&scene);
+        assert!((physics_engine.interpolated_rotations(1.0)[&spinner] - 0.5).abs() < 1e-5);
+
+        physics_engine.set_angular_velocity(&spinner, 6.0);
+        physics_engine.step(&mut scene);
+        let previous = physics_engine.interpolated_rotations(0.0)[&spinner];
+        let current = physics_engine.interpolated_rotations(1.0)[&spinner];
+        let halfway = physics_engine.interpolated_rotations(0.5)[&spinner];
+        assert!((previous - 0.5).abs() < 1e-5);
+        assert!(current > previous, "body should have turned, at {}", current);
+        assert!((halfway - (previous + current) / 2.0).abs() < 1e-4);
+
+        // Bodies that can't rotate keep the rotation of their entity
+        assert!(!physics_engine.interpolated_rotations(1.0).contains_key(&locked));
+    }
+
+    // Two 20x20 walls on the x axis, centered at x = 100 and x = 200
+    fn query_scene() -> (Scene, PhysicsEngine, uuid::Uuid, uuid::Uuid) {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut walls = Vec::new();
+        for (name, x) in [("wall", 100.0), ("far_wall", 200.0)] {
+            let props = PhysicsProperties { collider_size: (20.0, 20.0), ..Default::default() };
+            let wall = scene.create_physical_entity(name, (x, 0.0, 0.0), props).unwrap();
+            walls.push(wall);
+        }
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        (scene, physics_engine, walls[0], walls[1])
+    }
+
+    #[test]
+    fn test_raycast() {
+        // Queries work before the first step
+        let (_, physics_engine, wall, far_wall) = query_scene();
+        let all = QueryFilter::default();
+
+        let hit = physics_engine.raycast((0.0, 0.0), (2.0, 0.0), 500.0, &all).unwrap();
+        assert_eq!(hit.entity, wall);
+        assert!((hit.distance - 90.0).abs() < 1e-3);
+        assert!((hit.point.0 - 90.0).abs() < 1e-3 && hit.point.1.abs() < 1e-3);
+        assert_eq!(hit.normal, (-1.0, 0.0));
+
+        assert!(physics_engine.raycast((0.0, 0.0), (1.0, 0.0), 50.0, &all).is_none());
+        assert!(physics_engine.raycast((0.0, 0.0), (0.0, 1.0), 500.0, &all).is_none());
+        assert!(physics_engine.raycast((0.0, 0.0), (0.0, 0.0), 500.0, &all).is_none());
+
+        // All hits, nearest first
+        let hits = physics_engine.raycast_all((300.0, 0.0), (-1.0, 0.0), 500.0, &all);
+        assert_eq!(hits.iter().map(|hit| hit.entity).collect::<Vec<_>>(), vec![far_wall, wall]);
+        assert!((hits[1].distance - 190.0).abs() < 1e-3);
+
+        // Excluded entities and other layers aren't hit
+        let exclude = QueryFilter { exclude: vec![wall], ..Default::default() };
+        assert_eq!(physics_engine.raycast((0.0, 0.0), (1.0, 0.0), 500.0, &exclude).unwrap().entity, far_wall);
+        let no_layers = QueryFilter { layers: 0, ..Default::default() };
+        assert!(physics_engine.raycast((0.0, 0.0), (1.0, 0.0), 500.0, &no_layers).is_none());
+    }
+
+    #[test]
+    fn test_overlap_and_point_queries() {
+        let (_, physics_engine, wall, far_wall) = query_scene();
+        let all = QueryFilter::default();
+
+        assert_eq!(physics_engine.overlap_circle((100.0, 25.0), 16.0, &all), vec![wall]);
+        assert!(physics_engine.overlap_circle((100.0, 25.0), 14.0, &all).is_empty());
+
+        let mut both = physics_engine.overlap_box((150.0, 0.0), (120.0, 4.0), 0.0, &all);
+        both.sort();
+        let mut walls = vec![wall, far_wall];
+        walls.sort();
+        assert_eq!(both, walls);
+        // Turned upright, the box fits between the walls
+        assert!(physics_engine.overlap_box((150.0, 0.0), (120.0, 4.0), std::f32::consts::FRAC_PI_2, &all).is_empty());
+
+        assert_eq!(physics_engine.point_query((105.0, 5.0), &all), vec![wall]);
+        assert!(physics_engine.point_query((150.0, 0.0), &all).is_empty());
+        let exclude = QueryFilter { exclude: vec![wall], ..Default::default() };
+        assert!(physics_engine.point_query((105.0, 5.0), &exclude).is_empty());
+    }
+
+    #[test]
+    fn test_shape_cast() {
+        let (_, physics_engine, wall, far_wall) = query_scene();
+        let all = QueryFilter::default();
+
+        let hit = physics_engine.shape_cast(QueryShape::Circle { radius: 5.0 }, (0.0, 0.0), (1.0, 0.0), 500.0, &all).unwrap();
+        assert_eq!(hit.entity, wall);
+        assert!((hit.distance - 85.0).abs() < 1e-2);
+        assert!((hit.point.0 - 90.0).abs() < 1e-2 && hit.point.1.abs() < 1e-2);
+        assert!((hit.normal.0 + 1.0).abs() < 1e-3);
+
+        // A box passing just above the walls misses them, a bit lower it hits them
+        let box_shape = QueryShape::Box { width: 10.0, height: 4.0 };
+        let hit = physics_engine.shape_cast(box_shape, (0.0, 13.0), (1.0, 0.0), 500.0, &all);
+        assert!(hit.is_none());
+        let hit = physics_engine.shape_cast(box_shape, (0.0, 11.0), (1.0, 0.0), 500.0, &all).unwrap();
+        assert_eq!(hit.entity, wall);
+        let exclude = QueryFilter { exclude: vec![wall], ..Default::default() };
+        let hit = physics_engine.shape_cast(box_shape, (0.0, 11.0), (1.0, 0.0), 500.0, &exclude).unwrap();
+        assert_eq!(hit.entity, far_wall);
+        assert!((hit.distance - 185.0).abs() < 1e-2);
+    }
+
+    fn layer_names() -> Vec<String> {
+        ["default", "player", "enemy", "pickup"].iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_collision_layer_mask() {
+        let layers = layer_names();
+        assert_eq!(collision_layer_mask(&layers, "default"), Ok(0b1));
+        assert_eq!(collision_layer_mask(&layers, "player, enemy"), Ok(0b110));
+        assert_eq!(collision_layer_mask(&layers, "all"), Ok(u32::MAX));
+        assert_eq!(collision_layer_mask(&layers, "none"), Ok(0));
+        assert!(collision_layer_mask(&layers, "player, ghost").is_err());
+
+        assert_eq!(format_collision_layers(&layers, 0b1010), "player, pickup");
+        assert_eq!(format_collision_layers(&layers, u32::MAX), "all");
+        assert_eq!(format_collision_layers(&layers, 0), "none");
+        let mask = collision_layer_mask(&layers, &format_collision_layers(&layers, 0b101)).unwrap();
+        assert_eq!(mask, 0b101);
+    }
+
+    // Drops a ball onto a fixed ground for a second and returns the events and where the ball ended
+    fn drop_ball(ground: PhysicsProperties, ball: PhysicsProperties) -> (Vec<CollisionEvent>, f32) {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let ground = PhysicsProperties { is_movable: false, affected_by_gravity: false, ..ground };
+        scene.create_physical_entity("ground", (0.0, 0.0, 0.0), ground).unwrap();
+        let ball = PhysicsProperties { is_movable: true, affected_by_gravity: true, ..ball };
+        let ball_id = scene.create_physical_entity("ball", (0.0, -1.5, 0.0), ball).unwrap();
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.set_contact_parameters(5.0, 30.0);
+        physics_engine.set_collision_layers(layer_names());
+        physics_engine.load_scene(&scene);
+
+        let mut events = Vec::new();
+        for _ in 0..60 {
+            physics_engine.step(&mut scene);
+            events.extend(physics_engine.drain_collision_events());
+        }
+        let (_, y) = physics_engine.get_collider_data().into_iter()
+            .find(|collider| collider.entity_id == ball_id)
+            .unwrap()
+            .position;
+        (events, y)
+    }
+
+    #[test]
+    fn test_collision_layers_filter_contacts() {
+        let player = |mask: &str| PhysicsProperties {
+            collision_layer: "player".to_string(),
+            collision_mask: mask.to_string(),
+            ..Default::default()
+        };
+        let enemy = PhysicsProperties { collision_layer: "enemy".to_string(), ..Default::default() };
+
+        // Both sides must accept each other's layer
+        let (events, y) = drop_ball(enemy.clone(), player("default, enemy"));
+        assert!(events.iter().any(|event| event.kind == CollisionEventKind::Started));
+        assert!(y < 0.0, "The ball should rest on the ground, it is at {}", y);
+
+        let (events, y) = drop_ball(enemy.clone(), player("default"));
+        assert!(events.is_empty());
+        assert!(y > 10.0, "The ball should fall through the ground, it is at {}", y);
+
+        let ground = PhysicsProperties { collision_mask: "enemy".to_string(), ..enemy };
+        let (events, _) = drop_ball(ground, player("all"));
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_trigger_colliders() {
+        let trigger = PhysicsProperties { is_trigger: true, ..Default::default() };
+        let (events, y) = drop_ball(trigger, PhysicsProperties::default());
+
+        // The ball passes through the trigger, entering and leaving it
+        assert!(y > 10.0, "The ball should fall through the trigger, it is at {}", y);
+        let kinds: Vec<_> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(kinds, vec![CollisionEventKind::SensorStarted, CollisionEventKind::SensorStopped]);
+        assert!(events.iter().all(|event| event.is_sensor()));
+    }
+
+    #[test]
+    fn test_queries_by_layer_and_sensor() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        for (name, x, layer, is_trigger) in [("wall", 100.0, "enemy", false), ("zone", 50.0, "pickup", true)] {
+            let props = PhysicsProperties {
+                collider_size: (20.0, 20.0),
+                collision_layer: layer.to_string(),
+                is_trigger,
+                ..Default::default()
+            };
+            let id = scene.create_physical_entity(name, (x, 0.0, 0.0), props).unwrap();
+        }
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.set_collision_layers(layer_names());
+        physics_engine.load_scene(&scene);
+
+        let ray = |filter: &QueryFilter| physics_engine.raycast((0.0, 0.0), (1.0, 0.0), 500.0, filter)
+            .map(|hit| hit.distance.round());
+        assert_eq!(ray(&QueryFilter::default()), Some(90.0));
+        let with_sensors = QueryFilter { include_sensors: true, ..Default::default() };
+        assert_eq!(ray(&with_sensors), Some(40.0));
+        let pickups = QueryFilter { layers: physics_engine.layer_mask("pickup").unwrap(), include_sensors: true, ..Default::default() };
+        assert_eq!(ray(&pickups), Some(40.0));
+        let players = QueryFilter { layers: physics_engine.layer_mask("player").unwrap(), include_sensors: true, ..Default::default() };
+        assert_eq!(ray(&players), None);
+    }
+
+    fn vector2(scene: &Scene, entity_id: uuid::Uuid, name: &str) -> (f32, f32) {
+        match scene.get_entity(entity_id).unwrap().get_attribute_by_name(name).unwrap().value {
+            AttributeValue::Vector2(x, y) => (x, y),
+            ref value => panic!("{} is not a Vector2: {:?}", name, value),
+        }
+    }
+
+    fn float(scene: &Scene, entity_id: uuid::Uuid, name: &str) -> f32 {
+        match scene.get_entity(entity_id).unwrap().get_attribute_by_name(name).unwrap().value {
+            AttributeValue::Float(value) => value,
+            ref value => panic!("{} is not a Float: {:?}", name, value),
+        }
+    }
+
+    // Steps the engine and applies its updates to the scene, like the game runtime does
+    fn step_and_apply(physics_engine: &mut PhysicsEngine, scene: &mut Scene) {
+        let updates = physics_engine.step(scene);
+        scene.update_entity_attributes(updates).unwrap();
+    }
+
+    fn moving_body(scene: &mut Scene) -> uuid::Uuid {
+        let props = PhysicsProperties { is_movable: true, can_rotate: true, ..Default::default() };
+        scene.create_physical_entity("body", (0.0, 0.0, 0.0), props).unwrap()
+    }
+
+    #[test]
+    fn test_velocity_and_rotation_write_back() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let entity_id = moving_body(&mut scene);
+        assert_eq!(vector2(&scene, entity_id, "velocity"), (0.0, 0.0));
+        assert_eq!(float(&scene, entity_id, "angular_velocity"), 0.0);
+
+        // Bodies start with the velocities of their entity
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        let velocity_id = entity.get_attribute_by_name("velocity").unwrap().id;
+        let angular_velocity_id = entity.get_attribute_by_name("angular_velocity").unwrap().id;
+        entity.modify_attribute(velocity_id, None, None, Some(AttributeValue::Vector2(60.0, 0.0))).unwrap();
+        entity.modify_attribute(angular_velocity_id, None, None, Some(AttributeValue::Float(3.0))).unwrap();
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        for _ in 0..60 {
+            step_and_apply(&mut physics_engine, &mut scene);
+        }
+
+        let x = float(&scene, entity_id, "x");
+        assert!((x - 60.0).abs() < 1.0, "The body should have moved 60 in a second, it is at x = {}", x);
+        let velocity = vector2(&scene, entity_id, "velocity");
+        assert!((velocity.0 - 60.0).abs() < 0.5 && velocity.1.abs() < 0.5, "Velocity is {:?}", velocity);
+        assert!((float(&scene, entity_id, "angular_velocity") - 3.0).abs() < 0.1);
+        // The rotation attribute is created once the body turns
+        assert!((float(&scene, entity_id, "rotation") - 3.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_attribute_edits_apply_to_bodies() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let entity_id = moving_body(&mut scene);
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        step_and_apply(&mut physics_engine, &mut scene);
+
+        // Edits made between steps, by a script or the inspector, move the body instead of being overwritten
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        for (name, value) in [
+            ("x", AttributeValue::Float(100.0)),
+            ("y", AttributeValue::Float(50.0)),
+            ("velocity", AttributeValue::Vector2(0.0, -30.0)),
+            ("angular_velocity", AttributeValue::Float(-2.0)),
+        ] {
+            let attr_id = entity.get_attribute_by_name(name).unwrap().id;
+            entity.modify_attribute(attr_id, None, None, Some(value)).unwrap();
+        }
+        for _ in 0..30 {
+            step_and_apply(&mut physics_engine, &mut scene);
+        }
+
+        let position = scene.get_entity(entity_id).unwrap().local_transform().position;
+        assert!((position.0 - 100.0).abs() < 0.5 && (position.1 - 35.0).abs() < 1.0, "The body is at {:?}", position);
+        assert!((vector2(&scene, entity_id, "velocity").1 + 30.0).abs() < 0.5);
+        assert!((float(&scene, entity_id, "rotation") + 1.0).abs() < 0.1);
+
+        // Turning the entity sets the body's rotation
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        let rotation_id = entity.get_attribute_by_name("rotation").unwrap().id;
+        entity.modify_attribute(rotation_id, None, None, Some(AttributeValue::Float(0.25))).unwrap();
+        let angular_velocity_id = entity.get_attribute_by_name("angular_velocity").unwrap().id;
+        entity.modify_attribute(angular_velocity_id, None, None, Some(AttributeValue::Float(0.0))).unwrap();
+        step_and_apply(&mut physics_engine, &mut scene);
+        assert!((physics_engine.interpolated_rotations(1.0)[&entity_id] - 0.25).abs() < 1e-4);
+    }
 }
@@ -0,0 +1,186 @@
+mod common;
+
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::gui::edit_history::{EditCommand, EditHistory};
+use rust_2d_game_engine::physics_engine::PhysicsEngine;
+use rust_2d_game_engine::physics_settings::PhysicsSettings;
+use uuid::Uuid;
+use common::{add_body, falling, position_of, run};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_and_old_scenes() {
+        let settings = PhysicsSettings::default();
+        assert_eq!(settings.gravity, (0.0, 50.0));
+        assert_eq!(settings.time_step, 1.0 / 60.0);
+        assert_eq!(settings.custom_gravity_strength, 10.0);
+        assert!(settings.validate().is_ok());
+        assert_eq!(PhysicsEngine::new().settings(), &settings);
+
+        // Scenes saved before the settings existed get the defaults
+        let mut json = serde_json::to_value(Scene::new("old_scene").unwrap()).unwrap();
+        json.as_object_mut().unwrap().remove("physics");
+        let scene: Scene = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(scene.physics, settings);
+
+        // Missing fields also get their default
+        json["physics"] = serde_json::json!({ "gravity": [0.0, -9.8], "ccd": true });
+        let scene: Scene = serde_json::from_value(json).unwrap();
+        assert_eq!(scene.physics.gravity, (0.0, -9.8));
+        assert!(scene.physics.ccd);
+        assert_eq!(scene.physics.solver_iterations, settings.solver_iterations);
+
+        let saved = serde_json::to_string(&scene).unwrap();
+        assert_eq!(serde_json::from_str::<Scene>(&saved).unwrap().physics, scene.physics);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let invalid = [
+            PhysicsSettings { time_step: 0.0, ..Default::default() },
+            PhysicsSettings { solver_iterations: 0, ..Default::default() },
+            PhysicsSettings { gravity: (f32::NAN, 0.0), ..Default::default() },
+            PhysicsSettings { time_until_sleep: -1.0, ..Default::default() },
+        ];
+        let mut physics_engine = PhysicsEngine::new();
+        for settings in &invalid {
+            assert!(settings.validate().is_err());
+            assert!(physics_engine.apply_settings(settings).is_err());
+        }
+        assert_eq!(physics_engine.settings(), &PhysicsSettings::default());
+
+        // Loading a scene with invalid settings keeps the current ones
+        let mut scene = Scene::new("test_scene").unwrap();
+        scene.physics.time_step = -1.0;
+        physics_engine.load_scene(&scene);
+        assert_eq!(physics_engine.get_time_step(), 1.0 / 60.0);
+    }
+
+    #[test]
+    fn test_load_scene_applies_gravity_and_time_step() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        scene.physics.gravity = (0.0, -100.0);
+        scene.physics.time_step = 1.0 / 120.0;
+        let body_id = add_body(&mut scene, "body", (0.0, 0.0), falling());
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        assert_eq!(physics_engine.get_time_step(), 1.0 / 120.0);
+
+        // Half a second at 120 steps per second, falling up: y = -g t² / 2
+        run(&mut scene, &mut physics_engine, 60);
+        let position = position_of(&physics_engine, body_id);
+        assert!((position.1 + 12.5).abs() < 0.5, "The body is at {:?}", position);
+    }
+
+    #[test]
+    fn test_custom_gravity_strength() {
+        // A gravity source pulls a body 100 to its right
+        let pull = |strength: f32| {
+            let mut scene = Scene::new("test_scene").unwrap();
+            scene.physics.gravity = (0.0, 0.0);
+            scene.physics.custom_gravity_strength = strength;
+            add_body(&mut scene, "planet", (100.0, 0.0), PhysicsProperties { creates_gravity: true, ..Default::default() });
+            let body_id = add_body(&mut scene, "body", (0.0, 0.0), falling());
+
+            let mut physics_engine = PhysicsEngine::new();
+            physics_engine.load_scene(&scene);
+            run(&mut scene, &mut physics_engine, 10);
+            physics_engine.get_velocity(&body_id).unwrap().x
+        };
+
+        assert_eq!(pull(0.0), 0.0);
+        let weak = pull(10.0);
+        let strong = pull(20.0);
+        assert!(weak > 0.0, "The body should be pulled right, its velocity is {}", weak);
+        assert!((strong / weak - 2.0).abs() < 0.05, "Twice the strength should pull twice as hard: {} and {}", weak, strong);
+    }
+
+    #[test]
+    fn test_sleep_thresholds() {
+        // A body at rest without gravity falls asleep after `time_until_sleep`
+        let sleeps_after_a_second = |time_until_sleep: f32| {
+            let mut scene = Scene::new("test_scene").unwrap();
+            scene.physics.time_until_sleep = time_until_sleep;
+            let props = PhysicsProperties { affected_by_gravity: false, ..falling() };
+            let body_id = add_body(&mut scene, "body", (0.0, 0.0), props);
+
+            let mut physics_engine = PhysicsEngine::new();
+            physics_engine.load_scene(&scene);
+            run(&mut scene, &mut physics_engine, 60);
+            physics_engine.is_sleeping(&body_id)
+        };
+
+        assert!(sleeps_after_a_second(0.5));
+        assert!(!sleeps_after_a_second(5.0));
+    }
+
+    #[test]
+    fn test_ccd() {
+        // A fast body heads for a 2 wide wall, which it only hits with CCD
+        let passes_through = |ccd: bool| {
+            let mut scene = Scene::new("test_scene").unwrap();
+            scene.physics.gravity = (0.0, 0.0);
+            scene.physics.ccd = ccd;
+            add_body(&mut scene, "wall", (100.0, 0.0), PhysicsProperties { collider_size: (2.0, 100.0), ..Default::default() });
+            let bullet_id = add_body(&mut scene, "bullet", (0.0, 0.0), falling());
+            let entity = scene.get_entity_mut(bullet_id).unwrap();
+            let velocity_id = entity.get_attribute_by_name("velocity").unwrap().id;
+            entity.modify_attribute(velocity_id, None, None, Some(AttributeValue::Vector2(6000.0, 0.0))).unwrap();
+
+            let mut physics_engine = PhysicsEngine::new();
+            physics_engine.set_contact_parameters(5.0, 30.0);
+            physics_engine.load_scene(&scene);
+            run(&mut scene, &mut physics_engine, 5);
+            position_of(&physics_engine, bullet_id).0 > 100.0
+        };
+
+        assert!(passes_through(false));
+        assert!(!passes_through(true));
+    }
+
+    #[test]
+    fn test_apply_settings_at_runtime() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let body_id = add_body(&mut scene, "body", (0.0, 0.0), falling());
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+
+        // Turning gravity off leaves the body at its speed
+        run(&mut scene, &mut physics_engine, 30);
+        let speed = physics_engine.get_velocity(&body_id).unwrap().y;
+        let settings = PhysicsSettings { gravity: (0.0, 0.0), solver_iterations: 8, ..Default::default() };
+        physics_engine.apply_settings(&settings).unwrap();
+        run(&mut scene, &mut physics_engine, 30);
+        assert!((physics_engine.get_velocity(&body_id).unwrap().y - speed).abs() < 0.01);
+        assert_eq!(physics_engine.settings(), &settings);
+
+        physics_engine.set_time_step(1.0 / 30.0);
+        assert_eq!(physics_engine.settings().time_step, 1.0 / 30.0);
+    }
+
+    #[test]
+    fn test_physics_settings_undo() {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("test_scene").unwrap();
+        let mut history = EditHistory::default();
+
+        // Dragging the gravity is a single edit
+        for y in [60.0, 70.0, 80.0] {
+            let settings = PhysicsSettings { gravity: (0.0, y), ..Default::default() };
+            let command = EditCommand::set_physics_settings(scene_manager.get_scene(scene_id).unwrap(), scene_id, settings);
+            assert_eq!(command.description(), "Edit physics settings");
+            history.execute(command, &mut scene_manager).unwrap();
+        }
+
+        let gravity = |scene_manager: &SceneManager| scene_manager.get_scene(scene_id).unwrap().physics.gravity;
+        assert_eq!(gravity(&scene_manager), (0.0, 80.0));
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(gravity(&scene_manager), (0.0, 50.0));
+        history.redo(&mut scene_manager).unwrap();
+        assert_eq!(gravity(&scene_manager), (0.0, 80.0));
+    }
+}
@@ -0,0 +1,269 @@
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::gui::edit_history::{EditCommand, EditHistory, PrefabSnapshot};
+use rust_2d_game_engine::joint::{Joint, JointKind};
+use rust_2d_game_engine::physics_engine::PhysicsEngine;
+use rust_2d_game_engine::project_manager::ProjectManager;
+use std::fs;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A scene with an "enemy" entity (health 3, one image) turned into a prefab
+    fn setup() -> (SceneManager, Uuid, Uuid, Uuid) {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("main").unwrap();
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let entity_id = scene.create_entity("enemy").unwrap();
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        entity.create_attribute("health", AttributeType::Integer, AttributeValue::Integer(3)).unwrap();
+        entity.images = vec![PathBuf::from("enemy.png")];
+
+        let prefab_id = scene_manager.create_prefab(scene_id, entity_id, "enemy").unwrap();
+        (scene_manager, scene_id, entity_id, prefab_id)
+    }
+
+    fn entity(scene_manager: &SceneManager, scene_id: Uuid, entity_id: Uuid) -> &Entity {
+        scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap()
+    }
+
+    fn health(entity: &Entity) -> AttributeValue {
+        entity.get_attribute_by_name("health").unwrap().value.clone()
+    }
+
+    fn set_health(entity: &mut Entity, value: i32) {
+        let id = entity.get_attribute_by_name("health").unwrap().id;
+        entity.modify_attribute(id, None, None, Some(AttributeValue::Integer(value))).unwrap();
+    }
+
+    #[test]
+    fn test_instantiate_prefab() {
+        let (mut scene_manager, scene_id, entity_id, prefab_id) = setup();
+        let instance_id = scene_manager.instantiate_prefab(scene_id, prefab_id, 4.0, 5.0).unwrap();
+
+        let instance = entity(&scene_manager, scene_id, instance_id);
+        assert_eq!(instance.name, "enemy");
+        assert_eq!((instance.get_x(), instance.get_y()), (4.0, 5.0));
+        assert_eq!(health(instance), AttributeValue::Integer(3));
+        assert_eq!(instance.prefab.as_ref().unwrap().prefab_id, prefab_id);
+
+        // The original entity is the first instance
+        let mut instances = scene_manager.instances_of(prefab_id);
+        instances.sort();
+        let mut expected = vec![(scene_id, entity_id), (scene_id, instance_id)];
+        expected.sort();
+        assert_eq!(instances, expected);
+
+        // Names must be unique and usable as file names
+        assert!(scene_manager.create_prefab(scene_id, instance_id, "other").is_err());
+        let plain = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("plain").unwrap();
+        assert!(scene_manager.create_prefab(scene_id, plain, "enemy").is_err());
+        assert!(scene_manager.create_prefab(scene_id, plain, "../enemy").is_err());
+    }
+
+    #[test]
+    fn test_prefab_edits_propagate_except_overrides() {
+        let (mut scene_manager, scene_id, _, prefab_id) = setup();
+        let a = scene_manager.instantiate_prefab(scene_id, prefab_id, 0.0, 0.0).unwrap();
+        let b = scene_manager.instantiate_prefab(scene_id, prefab_id, 10.0, 0.0).unwrap();
+
+        let instance = scene_manager.get_scene_mut(scene_id).unwrap().get_entity_mut(b).unwrap();
+        set_health(instance, 9);
+        instance.mark_override("health");
+
+        scene_manager.edit_prefab(prefab_id, |template| {
+            set_health(template, 5);
+            template.create_attribute("speed", AttributeType::Float, AttributeValue::Float(2.0)).unwrap();
+        }).unwrap();
+
+        let a = entity(&scene_manager, scene_id, a);
+        let b = entity(&scene_manager, scene_id, b);
+        assert_eq!(health(a), AttributeValue::Integer(5));
+        assert_eq!(health(b), AttributeValue::Integer(9));
+        assert_eq!(b.get_attribute_by_name("speed").unwrap().value, AttributeValue::Float(2.0));
+        // Positions are never taken from the prefab
+        assert_eq!(b.get_x(), 10.0);
+    }
+
+    #[test]
+    fn test_prefab_asset_changes_propagate() {
+        let (mut scene_manager, scene_id, _, prefab_id) = setup();
+        let a = scene_manager.instantiate_prefab(scene_id, prefab_id, 0.0, 0.0).unwrap();
+        let b = scene_manager.instantiate_prefab(scene_id, prefab_id, 0.0, 0.0).unwrap();
+        scene_manager.get_scene_mut(scene_id).unwrap().get_entity_mut(b).unwrap().images = vec![PathBuf::from("boss.png")];
+
+        scene_manager.edit_prefab(prefab_id, |template| {
+            template.images = vec![PathBuf::from("enemy_v2.png")];
+            template.script = Some(PathBuf::from("enemy.lua"));
+        }).unwrap();
+
+        let a = entity(&scene_manager, scene_id, a);
+        let b = entity(&scene_manager, scene_id, b);
+        assert_eq!(a.images, vec![PathBuf::from("enemy_v2.png")]);
+        assert_eq!(a.script, Some(PathBuf::from("enemy.lua")));
+        // b changed its images itself, so it keeps them
+        assert_eq!(b.images, vec![PathBuf::from("boss.png")]);
+        assert_eq!(b.script, Some(PathBuf::from("enemy.lua")));
+    }
+
+    #[test]
+    fn test_apply_overrides_and_revert() {
+        let (mut scene_manager, scene_id, entity_id, prefab_id) = setup();
+        let other = scene_manager.instantiate_prefab(scene_id, prefab_id, 0.0, 0.0).unwrap();
+
+        let instance = scene_manager.get_scene_mut(scene_id).unwrap().get_entity_mut(entity_id).unwrap();
+        set_health(instance, 7);
+        instance.mark_override("health");
+        scene_manager.apply_overrides_to_prefab(scene_id, entity_id).unwrap();
+
+        assert_eq!(health(&scene_manager.get_prefab(prefab_id).unwrap().entity), AttributeValue::Integer(7));
+        assert_eq!(health(entity(&scene_manager, scene_id, other)), AttributeValue::Integer(7));
+        assert!(entity(&scene_manager, scene_id, entity_id).prefab.as_ref().unwrap().overrides.is_empty());
+
+        let instance = scene_manager.get_scene_mut(scene_id).unwrap().get_entity_mut(other).unwrap();
+        set_health(instance, 1);
+        instance.mark_override("health");
+        scene_manager.revert_prefab_instance(scene_id, other).unwrap();
+        let other = entity(&scene_manager, scene_id, other);
+        assert_eq!(health(other), AttributeValue::Integer(7));
+        assert!(other.prefab.as_ref().unwrap().overrides.is_empty());
+    }
+
+    #[test]
+    fn test_prefab_names_differ_by_more_than_case() {
+        let (mut scene_manager, scene_id, _, _) = setup();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("boss").unwrap();
+        let error = scene_manager.create_prefab(scene_id, entity_id, " Enemy ").unwrap_err();
+        assert_eq!(error, "Prefab 'enemy' already exists");
+        assert!(scene_manager.create_prefab(scene_id, entity_id, "Boss").is_ok());
+    }
+
+    #[test]
+    fn test_instances_have_their_own_joints() {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("main").unwrap();
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let anchor_id = scene.create_physical_entity("anchor", (0.0, 0.0, 0.0), PhysicsProperties::default()).unwrap();
+        let movable = PhysicsProperties { is_movable: true, affected_by_gravity: true, collider_size: (10.0, 10.0), ..Default::default() };
+        let lamp_id = scene.create_physical_entity("lamp", (0.0, 50.0, 0.0), movable).unwrap();
+        scene.get_entity_mut(lamp_id).unwrap().joints.push(Joint::new(JointKind::Fixed, anchor_id));
+
+        // The template leaves the joint to the original
+        let prefab_id = scene_manager.create_prefab(scene_id, lamp_id, "lamp").unwrap();
+        assert!(scene_manager.get_prefab(prefab_id).unwrap().entity.joints.is_empty());
+        let instance_ids = [-50.0, 50.0].map(|x| scene_manager.instantiate_prefab(scene_id, prefab_id, x, 50.0).unwrap());
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let mut joint_ids = vec![scene.get_entity(lamp_id).unwrap().joints[0].id];
+        for instance_id in instance_ids {
+            let instance = scene.get_entity_mut(instance_id).unwrap();
+            assert!(instance.joints.is_empty());
+            let joint = Joint::new(JointKind::Fixed, anchor_id);
+            joint_ids.push(joint.id);
+            instance.joints.push(joint);
+        }
+
+        // Prefab edits keep each instance's joints
+        scene_manager.edit_prefab(prefab_id, |template| template.images = vec![PathBuf::from("lamp.png")]).unwrap();
+        let scene = scene_manager.get_scene(scene_id).unwrap();
+        assert!(instance_ids.iter().all(|id| scene.get_entity(*id).unwrap().joints.len() == 1));
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(scene);
+        for joint_id in joint_ids {
+            assert!(physics_engine.has_joint(&joint_id));
+        }
+    }
+
+    #[test]
+    fn test_prefab_file_names_are_valid_everywhere() {
+        let project_path = std::env::temp_dir().join(format!("prefab_test_{}", Uuid::new_v4()));
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("main").unwrap();
+        let names = ["enemy:boss", "a?b", "con", "a*b"];
+        for name in names {
+            let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity(name).unwrap();
+            scene_manager.create_prefab(scene_id, entity_id, name).unwrap();
+        }
+        ProjectManager::save_prefabs(&project_path, &scene_manager).unwrap();
+
+        let mut files: Vec<String> = fs::read_dir(project_path.join("assets/prefabs")).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        let loaded = ProjectManager::load_prefabs(&project_path).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+        assert_eq!(files, ["a_b.prefab", "a_b_2.prefab", "con_.prefab", "enemy_boss.prefab"]);
+        let mut loaded_names: Vec<&str> = loaded.values().map(|prefab| prefab.name.as_str()).collect();
+        loaded_names.sort();
+        assert_eq!(loaded_names, ["a*b", "a?b", "con", "enemy:boss"]);
+    }
+
+    #[test]
+    fn test_delete_prefab_requires_no_instances() {
+        let (mut scene_manager, scene_id, entity_id, prefab_id) = setup();
+        assert!(scene_manager.delete_prefab(prefab_id).is_err());
+
+        scene_manager.get_scene_mut(scene_id).unwrap().delete_entity(entity_id).unwrap();
+        assert_eq!(scene_manager.delete_prefab(prefab_id), Ok(true));
+        assert!(scene_manager.get_prefab_by_name("enemy").is_none());
+    }
+
+    #[test]
+    fn test_prefab_change_undo() {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("main").unwrap();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("coin").unwrap();
+
+        let mut before = PrefabSnapshot::capture(&scene_manager, Uuid::nil(), Some((scene_id, entity_id)));
+        let prefab_id = scene_manager.create_prefab(scene_id, entity_id, "coin").unwrap();
+        before.prefab_id = prefab_id;
+        let after = PrefabSnapshot::capture(&scene_manager, prefab_id, Some((scene_id, entity_id)));
+
+        let mut history = EditHistory::default();
+        history.record(EditCommand::PrefabChange {
+            description: "Create prefab 'coin'".to_string(),
+            before: Box::new(before),
+            after: Box::new(after),
+        });
+
+        history.undo(&mut scene_manager).unwrap();
+        assert!(scene_manager.get_prefab(prefab_id).is_none());
+        assert!(entity(&scene_manager, scene_id, entity_id).prefab.is_none());
+
+        history.redo(&mut scene_manager).unwrap();
+        assert!(scene_manager.get_prefab(prefab_id).is_some());
+        assert_eq!(entity(&scene_manager, scene_id, entity_id).prefab.as_ref().unwrap().prefab_id, prefab_id);
+    }
+
+    #[test]
+    fn test_prefabs_save_and_load() {
+        let project_path = std::env::temp_dir().join(format!("prefab_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(project_path.join("scenes")).unwrap();
+
+        let (mut scene_manager, scene_id, entity_id, prefab_id) = setup();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        let prefab_file = project_path.join("assets/prefabs/enemy.prefab");
+        let saved = (fs::read_to_string(&prefab_file).unwrap(), fs::metadata(&prefab_file).unwrap().modified().unwrap());
+        assert!(saved.0.ends_with("}\n"));
+
+        // Saving again leaves the file untouched
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        assert_eq!(saved, (fs::read_to_string(&prefab_file).unwrap(), fs::metadata(&prefab_file).unwrap().modified().unwrap()));
+
+        let loaded = ProjectManager::load_scene_hierarchy(&project_path).unwrap();
+        let prefab = loaded.get_prefab(prefab_id).unwrap();
+        assert_eq!(prefab.name, "enemy");
+        assert_eq!(health(&prefab.entity), AttributeValue::Integer(3));
+        assert_eq!(entity(&loaded, scene_id, entity_id).prefab.as_ref().unwrap().prefab_id, prefab_id);
+
+        // Files of deleted prefabs are removed on the next save
+        scene_manager.get_scene_mut(scene_id).unwrap().delete_entity(entity_id).unwrap();
+        scene_manager.delete_prefab(prefab_id).unwrap();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        assert!(!prefab_file.exists());
+
+        let _ = fs::remove_dir_all(&project_path);
+    }
+}
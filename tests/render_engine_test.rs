@@ -1,8 +1,12 @@
 #[cfg(test)]
 mod tests {
-    use rust_2d_game_engine::render_engine::{RenderEngine, Camera, Transform, TextureInfo};
-    use rust_2d_game_engine::ecs::Scene;
+    use rust_2d_game_engine::render_engine::{RenderEngine, Camera, Transform, TextureInfo, MemoryUsage, parse_color};
+    use rust_2d_game_engine::ecs::{AttributeType, AttributeValue, Scene};
+    use std::collections::HashMap;
+    use std::f32::consts::FRAC_PI_2;
+    use std::fs;
     use std::path::Path;
+    use std::time::{Duration, SystemTime};
 
     #[test]
     fn test_camera_operations() {
@@ -117,6 +121,163 @@ mod tests {
         let texture_id = RenderEngine::path_to_uuid(test_path);
         renderer.texture_cache.insert(texture_id, texture_info);
         
-        assert_eq!(renderer.get_memory_usage(), 1024);
+        assert_eq!(renderer.get_memory_usage(), MemoryUsage { cpu_bytes: 1024, gpu_bytes: 0 });
+
+        // Drawing it uploads a GPU copy, counted separately
+        let ctx = egui::Context::default();
+        renderer.gpu_texture(&ctx, &texture_id).unwrap();
+        let usage = renderer.get_memory_usage();
+        assert_eq!(usage.cpu_bytes, 1024);
+        assert!(usage.gpu_bytes > 0);
+        assert_eq!(usage.total(), usage.cpu_bytes + usage.gpu_bytes);
+    }
+
+    #[test]
+    fn test_gpu_texture_uploaded_once() {
+        let mut renderer = RenderEngine::new();
+        let ctx = egui::Context::default();
+        let texture_id = RenderEngine::path_to_uuid(Path::new("test.png"));
+        assert!(renderer.gpu_texture(&ctx, &texture_id).is_none());
+
+        renderer.texture_cache.insert(texture_id, TextureInfo {
+            data: vec![255; 4],
+            dimensions: (1, 1),
+            aspect_ratio: 1.0,
+        });
+        let first = renderer.gpu_texture(&ctx, &texture_id).unwrap();
+        let second = renderer.gpu_texture(&ctx, &texture_id).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(renderer.gpu_texture_count(), 1);
+
+        // Unloading frees the GPU texture too
+        renderer.unload_texture(Path::new("test.png"));
+        assert_eq!(renderer.gpu_texture_count(), 0);
+        assert_eq!(renderer.get_memory_usage(), MemoryUsage::default());
+    }
+
+    #[test]
+    fn test_changed_texture_file_is_reloaded() {
+        let dir = std::env::temp_dir().join(format!("render_test_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let image_path = dir.join("player.png");
+        image::RgbaImage::new(2, 2).save(&image_path).unwrap();
+
+        let mut scene = Scene::new("test_scene").unwrap();
+        let entity_id = scene.create_entity("player").unwrap();
+        scene.get_entity_mut(entity_id).unwrap().images = vec![image_path.clone()];
+
+        let mut renderer = RenderEngine::new();
+        renderer.update_viewport_size(800.0, 600.0);
+        renderer.render(&scene);
+        let texture_id = RenderEngine::path_to_uuid(&image_path);
+        let ctx = egui::Context::default();
+        let gpu_id = renderer.gpu_texture(&ctx, &texture_id).unwrap();
+        assert!(renderer.reload_changed_textures().is_empty());
+
+        image::RgbaImage::new(4, 2).save(&image_path).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        fs::File::options().write(true).open(&image_path).unwrap().set_modified(later).unwrap();
+
+        assert_eq!(renderer.reload_changed_textures(), vec![texture_id]);
+        assert_eq!(renderer.get_texture_info(&texture_id).unwrap().dimensions, (4, 2));
+        // The GPU texture is updated in place
+        assert_eq!(renderer.gpu_texture(&ctx, &texture_id), Some(gpu_id));
+        assert_eq!(renderer.get_memory_usage().gpu_bytes, 4 * 2 * 4);
+        assert!(renderer.reload_changed_textures().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // Renderer with a 40x20 texture for "sprite.png" cached
+    fn sprite_renderer() -> (RenderEngine, uuid::Uuid) {
+        let mut renderer = RenderEngine::new();
+        renderer.update_viewport_size(800.0, 600.0);
+        let texture_id = RenderEngine::path_to_uuid(Path::new("sprite.png"));
+        renderer.texture_cache.insert(texture_id, TextureInfo {
+            data: vec![255; 40 * 20 * 4],
+            dimensions: (40, 20),
+            aspect_ratio: 2.0,
+        });
+        (renderer, texture_id)
+    }
+
+    #[test]
+    fn test_sprite_description() {
+        let (mut renderer, texture_id) = sprite_renderer();
+        let mut scene = Scene::new("test_scene").unwrap();
+        let entity_id = scene.create_entity("sprite").unwrap();
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        entity.images = vec![Path::new("sprite.png").to_path_buf()];
+        entity.set_position(100.0, 100.0, 0.0).unwrap();
+        entity.create_attribute("rotation", AttributeType::Float, AttributeValue::Float(FRAC_PI_2)).unwrap();
+        entity.create_attribute("pivot", AttributeType::Vector2, AttributeValue::Vector2(0.5, 0.5)).unwrap();
+        entity.create_attribute("flip_x", AttributeType::Boolean, AttributeValue::Boolean(true)).unwrap();
+        entity.create_attribute("tint", AttributeType::String, AttributeValue::String("#FF800080".to_string())).unwrap();
+        entity.create_attribute("opacity", AttributeType::Float, AttributeValue::Float(0.5)).unwrap();
+        entity.create_attribute("source_rect", AttributeType::String, AttributeValue::String("10 0 20 10".to_string())).unwrap();
+
+        let queue = renderer.render(&scene);
+        let item = queue.iter().find(|item| item.texture_id == texture_id).unwrap();
+        assert_eq!(item.position, (100.0, 100.0));
+        assert_eq!(item.size, (20.0, 10.0));
+        assert_eq!(item.uv, ((0.25, 0.0), (0.75, 0.5)));
+        assert_eq!(item.rotation, FRAC_PI_2);
+        assert_eq!(item.pivot, (10.0, 5.0));
+        assert_eq!(item.flip, (true, false));
+        assert_eq!(item.tint, [255, 128, 0, 64]);
+
+        // A quarter turn about the center swaps the extents around the same center
+        let (min, max) = item.bounds();
+        assert!((min.0 - 105.0).abs() < 1e-4 && (max.0 - 115.0).abs() < 1e-4, "{:?}", (min, max));
+        assert!((min.1 - 95.0).abs() < 1e-4 && (max.1 - 115.0).abs() < 1e-4, "{:?}", (min, max));
+    }
+
+    #[test]
+    fn test_sprite_mesh() {
+        let (mut renderer, texture_id) = sprite_renderer();
+        let mut scene = Scene::new("test_scene").unwrap();
+        let entity_id = scene.create_entity("sprite").unwrap();
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        entity.images = vec![Path::new("sprite.png").to_path_buf()];
+        entity.set_position(10.0, 10.0, 0.0).unwrap();
+        entity.create_attribute("flip_y", AttributeType::Boolean, AttributeValue::Boolean(true)).unwrap();
+
+        let queue = renderer.render(&scene);
+        let item = queue.iter().find(|item| item.texture_id == texture_id).unwrap();
+        let mesh = item.mesh(egui::TextureId::default(), egui::vec2(5.0, 0.0));
+        assert_eq!(mesh.indices.len(), 6);
+        let positions: Vec<_> = mesh.vertices.iter().map(|v| (v.pos.x, v.pos.y)).collect();
+        assert_eq!(positions, vec![(15.0, 10.0), (55.0, 10.0), (55.0, 30.0), (15.0, 30.0)]);
+        // Flipped vertically, the top corners sample the bottom of the texture
+        let uvs: Vec<_> = mesh.vertices.iter().map(|v| (v.uv.x, v.uv.y)).collect();
+        assert_eq!(uvs, vec![(0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)]);
+        assert!(mesh.vertices.iter().all(|v| v.color == egui::Color32::WHITE));
+    }
+
+    #[test]
+    fn test_rotation_override_and_culling() {
+        let (mut renderer, texture_id) = sprite_renderer();
+        let mut scene = Scene::new("test_scene").unwrap();
+        let entity_id = scene.create_entity("sprite").unwrap();
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        entity.images = vec![Path::new("sprite.png").to_path_buf()];
+        entity.set_position(-5.0, 100.0, 0.0).unwrap();
+
+        // Physics bodies are drawn at their body's rotation, a half turn about the top left
+        // corner puts the sprite left of the viewport
+        let rotations = HashMap::from([(entity_id, std::f32::consts::PI)]);
+        let queue = renderer.render_interpolated(&scene, &HashMap::new(), &rotations);
+        assert!(queue.iter().all(|item| item.texture_id != texture_id));
+
+        let queue = renderer.render(&scene);
+        assert!(queue.iter().any(|item| item.texture_id == texture_id));
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#102030"), Ok([16, 32, 48, 255]));
+        assert_eq!(parse_color("10203040"), Ok([16, 32, 48, 64]));
+        assert!(parse_color("#12345").is_err());
+        assert!(parse_color("#GG0000").is_err());
     }
 }
@@ -0,0 +1,296 @@
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::project_manager::ProjectManager;
+use rust_2d_game_engine::scene_format;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_project() -> PathBuf {
+        let project_path = std::env::temp_dir().join(format!("scene_files_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(project_path.join("scenes")).unwrap();
+        project_path
+    }
+
+    fn read_json(path: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn scene_files(project_path: &Path) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(project_path.join("scenes")).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        files
+    }
+
+    // Scenes "menu", "level 1" and "level 2" with a player and a shared hud, "level 1" is active
+    fn three_scenes() -> (SceneManager, [Uuid; 3]) {
+        let mut scene_manager = SceneManager::new();
+        let hud_id = scene_manager.create_shared_entity("hud").unwrap();
+        let ids = ["menu", "level 1", "level 2"].map(|name| {
+            let scene_id = scene_manager.create_scene(name).unwrap();
+            let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+            let player_id = scene.create_entity("player").unwrap();
+            let player = scene.get_entity_mut(player_id).unwrap();
+            player.create_attribute("lives", AttributeType::Integer, AttributeValue::Integer(3)).unwrap();
+            player.set_x(12.5).unwrap();
+            scene.add_shared_entity_ref(hud_id).unwrap();
+            scene_id
+        });
+        scene_manager.set_active_scene(ids[1]).unwrap();
+        (scene_manager, ids)
+    }
+
+    #[test]
+    fn test_each_scene_has_its_own_file() {
+        let project_path = temp_project();
+        let (scene_manager, ids) = three_scenes();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+
+        assert_eq!(scene_files(&project_path), ["index.json", "level_1.scene", "level_2.scene", "menu.scene", "shared_entities.json"]);
+        let index = read_json(&project_path.join("scenes/index.json"));
+        fs::remove_dir_all(&project_path).unwrap();
+
+        // The index keeps the order of the scenes and the ids they are known by
+        let entries: Vec<(&str, &str)> = index["scenes"].as_array().unwrap().iter()
+            .map(|entry| (entry["name"].as_str().unwrap(), entry["file"].as_str().unwrap()))
+            .collect();
+        assert_eq!(entries, [("menu", "menu.scene"), ("level 1", "level_1.scene"), ("level 2", "level_2.scene")]);
+        assert_eq!(index["scenes"][1]["id"], ids[1].to_string());
+        assert_eq!(index["active_scene"], ids[1].to_string());
+    }
+
+    #[test]
+    fn test_scene_file_names_are_unique() {
+        let project_path = temp_project();
+        let mut scene_manager = SceneManager::new();
+        for name in ["level", "Level", "level/", "", "  "] {
+            scene_manager.create_scene(name).unwrap();
+        }
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        let index = read_json(&project_path.join("scenes/index.json"));
+        let reloaded = ProjectManager::load_scene_hierarchy(&project_path).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+
+        let files: Vec<&str> = index["scenes"].as_array().unwrap().iter().map(|entry| entry["file"].as_str().unwrap()).collect();
+        assert_eq!(files, ["level.scene", "Level_2.scene", "level_.scene", "scene.scene", "scene_2.scene"]);
+        assert_eq!(reloaded.list_scene(), scene_manager.list_scene());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let project_path = temp_project();
+        let (scene_manager, ids) = three_scenes();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        let reloaded = ProjectManager::load_scene_hierarchy(&project_path).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+
+        assert_eq!(reloaded.list_scene(), scene_manager.list_scene());
+        assert_eq!(reloaded.active_scene, Some(ids[1]));
+        assert!(reloaded.unloaded_scenes.is_empty());
+        let hud_id = reloaded.get_shared_entity_by_name("hud").unwrap().id;
+        for scene_id in ids {
+            let scene = reloaded.get_scene(scene_id).unwrap();
+            let player = scene.entities.values().find(|entity| entity.name == "player").unwrap();
+            assert_eq!(player.get_attribute_by_name("lives").unwrap().value, AttributeValue::Integer(3));
+            assert_eq!(player.get_x(), 12.5);
+            assert_eq!(scene.shared_entity_refs, [hud_id]);
+            assert_eq!(scene.default_camera, scene_manager.get_scene(scene_id).unwrap().default_camera);
+        }
+    }
+
+    #[test]
+    fn test_attributes_are_saved_by_name() {
+        let project_path = temp_project();
+        let (scene_manager, _) = three_scenes();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        let scene = read_json(&project_path.join("scenes/menu.scene"));
+        let shared = read_json(&project_path.join("scenes/shared_entities.json"));
+        fs::remove_dir_all(&project_path).unwrap();
+
+        let player = scene["entities"].as_object().unwrap().values().find(|entity| entity["name"] == "player").unwrap();
+        let lives = &player["attributes"]["lives"];
+        assert_eq!(lives["value"]["Integer"], 3);
+        assert!(lives.get("id").is_none() && lives.get("name").is_none());
+        let hud = shared["shared_entities"].as_object().unwrap().values().next().unwrap();
+        assert!(hud["attributes"].as_object().unwrap().contains_key("x"));
+    }
+
+    #[test]
+    fn test_saving_again_changes_nothing() {
+        let project_path = temp_project();
+        let (scene_manager, _) = three_scenes();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        let read_all = || -> Vec<(String, String, std::time::SystemTime)> {
+            scene_files(&project_path).into_iter().map(|file| {
+                let path = project_path.join("scenes").join(&file);
+                (file, fs::read_to_string(&path).unwrap(), fs::metadata(&path).unwrap().modified().unwrap())
+            }).collect()
+        };
+        let saved = read_all();
+
+        // Neither saving the same scenes nor a loaded copy of them writes any file
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        let reloaded = ProjectManager::load_scene_hierarchy(&project_path).unwrap();
+        ProjectManager::save_scene_hierarchy(&project_path, &reloaded).unwrap();
+        let saved_again = read_all();
+        fs::remove_dir_all(&project_path).unwrap();
+        assert_eq!(saved, saved_again);
+        assert!(saved.iter().all(|(_, contents, _)| contents.ends_with("}\n")));
+    }
+
+    #[test]
+    fn test_removed_and_renamed_scenes_lose_their_file() {
+        let project_path = temp_project();
+        let (mut scene_manager, ids) = three_scenes();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+
+        scene_manager.delete_scene(ids[0]).unwrap();
+        scene_manager.get_scene_mut(ids[2]).unwrap().name = "boss".to_string();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        let files = scene_files(&project_path);
+        fs::remove_dir_all(&project_path).unwrap();
+        assert_eq!(files, ["boss.scene", "index.json", "level_1.scene", "shared_entities.json"]);
+    }
+
+    #[test]
+    fn test_asset_paths_are_saved_relative_to_the_project() {
+        let project_path = temp_project();
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("main").unwrap();
+        let scene = scene_manager.get_scene_mut(scene_id).unwrap();
+        let entity_id = scene.create_entity("bird").unwrap();
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        entity.add_image(PathBuf::from("/home/someone/game/assets/images/bird.png")).unwrap();
+        entity.set_script(PathBuf::from("/home/someone/game/assets/scripts/bird.lua")).unwrap();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+
+        let saved = read_json(&project_path.join("scenes/main.scene"));
+        let bird = &saved["entities"][entity_id.to_string()];
+        assert_eq!(bird["images"][0], "assets/images/bird.png");
+        assert_eq!(bird["script"], "assets/scripts/bird.lua");
+
+        // Loading points them into the project folder, wherever it is
+        let reloaded = ProjectManager::load_scene_hierarchy(&project_path).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+        let bird = reloaded.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        assert_eq!(bird.images[0], PathBuf::from(format!("{}/assets/images/bird.png", project_path.display())));
+    }
+
+    #[test]
+    fn test_lazy_loading_reads_only_the_active_scene() {
+        let project_path = temp_project();
+        let (scene_manager, ids) = three_scenes();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        // A broken scene file is only noticed once that scene is needed
+        fs::write(project_path.join("scenes/level_2.scene"), "{").unwrap();
+
+        let mut lazy = ProjectManager::load_scene_hierarchy_lazily(&project_path).unwrap();
+        assert!(ProjectManager::load_scene_hierarchy(&project_path).is_err());
+        assert_eq!(lazy.list_scene(), [(ids[1], "level 1")]);
+        assert_eq!(lazy.get_active_scene().unwrap().name, "level 1");
+        assert!(lazy.get_shared_entity_by_name("hud").is_some());
+        let error = lazy.set_active_scene(ids[0]).unwrap_err();
+        assert!(error.contains("'menu' isn't loaded"), "{}", error);
+
+        // Scenes loaded later take their place in the index
+        ProjectManager::load_scene(&project_path, &mut lazy, ids[0]).unwrap();
+        assert!(ProjectManager::load_scene(&project_path, &mut lazy, ids[2]).is_err());
+        fs::remove_dir_all(&project_path).unwrap();
+        assert_eq!(lazy.list_scene(), [(ids[0], "menu"), (ids[1], "level 1")]);
+        assert_eq!(lazy.unloaded_scenes.keys().collect::<Vec<_>>(), [&ids[2]]);
+        lazy.set_active_scene(ids[0]).unwrap();
+    }
+
+    #[test]
+    fn test_saving_a_lazily_loaded_project_keeps_unloaded_scenes() {
+        let project_path = temp_project();
+        let (scene_manager, ids) = three_scenes();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        let index = fs::read_to_string(project_path.join("scenes/index.json")).unwrap();
+
+        let mut lazy = ProjectManager::load_scene_hierarchy_lazily(&project_path).unwrap();
+        let scene = lazy.get_active_scene_mut().unwrap();
+        scene.create_entity("enemy").unwrap();
+        ProjectManager::save_scene_hierarchy(&project_path, &lazy).unwrap();
+
+        // Only the edited scene changed
+        assert_eq!(fs::read_to_string(project_path.join("scenes/index.json")).unwrap(), index);
+        let reloaded = ProjectManager::load_scene_hierarchy(&project_path).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+        assert_eq!(reloaded.list_scene(), scene_manager.list_scene());
+        assert!(reloaded.get_scene(ids[1]).unwrap().entities.values().any(|entity| entity.name == "enemy"));
+        assert_eq!(reloaded.get_scene(ids[2]).unwrap().entities.len(), scene_manager.get_scene(ids[2]).unwrap().entities.len());
+    }
+
+    #[test]
+    fn test_older_files_are_backed_up_before_they_are_replaced() {
+        let project_path = temp_project();
+        let (scene_manager, ids) = three_scenes();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+
+        // The menu as a format 2 file, attributes keyed by id
+        let menu_file = project_path.join("scenes/menu.scene");
+        let mut document = scene_format::scene_file_document(ids[0], read_json(&menu_file));
+        scene_format::key_attributes_by_id(&mut document);
+        let mut menu = scene_format::take_scene(&mut document, ids[0]).unwrap();
+        menu["format_version"] = 2.into();
+        let downgraded = serde_json::to_string_pretty(&menu).unwrap();
+        fs::write(&menu_file, &downgraded).unwrap();
+
+        let reloaded = ProjectManager::load_scene_hierarchy(&project_path).unwrap();
+        ProjectManager::save_scene_hierarchy(&project_path, &reloaded).unwrap();
+        let backup_file = project_path.join("scenes/menu.scene.v2.bak");
+        assert_eq!(fs::read_to_string(&backup_file).unwrap(), downgraded);
+        assert_eq!(read_json(&menu_file)["format_version"], scene_format::FORMAT_VERSION);
+
+        // A later downgrade doesn't replace the first backup, current files aren't backed up
+        fs::write(&menu_file, downgraded.replace("player", "hero")).unwrap();
+        ProjectManager::save_scene_hierarchy(&project_path, &reloaded).unwrap();
+        let backup = fs::read_to_string(&backup_file).unwrap();
+        let files = scene_files(&project_path);
+        fs::remove_dir_all(&project_path).unwrap();
+        assert_eq!(backup, downgraded);
+        assert_eq!(files, ["index.json", "level_1.scene", "level_2.scene", "menu.scene", "menu.scene.v2.bak", "shared_entities.json"]);
+    }
+
+    #[test]
+    fn test_references_are_only_checked_with_every_scene_loaded() {
+        let project_path = std::env::temp_dir().join(format!("scene_files_test_{}", Uuid::new_v4()));
+        ProjectManager::create_project(&project_path).unwrap();
+        let (mut scene_manager, ids) = three_scenes();
+        let hud_id = scene_manager.get_shared_entity_by_name("hud").unwrap().id;
+        let menu = scene_manager.get_scene_mut(ids[0]).unwrap();
+        let coin_id = menu.create_entity("coin").unwrap();
+        let prefab_id = scene_manager.create_prefab(ids[0], coin_id, "coin").unwrap();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+
+        // The editor opens the project with only the active scene
+        let mut lazy = ProjectManager::load_project_full(&project_path).unwrap().scene_manager;
+        assert_eq!(lazy.list_scene(), [(ids[1], "level 1")]);
+        for error in [
+            lazy.get_scenes_using_entity(hud_id).map(|_| ()).unwrap_err(),
+            lazy.get_scenes_using_entity(Uuid::new_v4()).map(|_| ()).unwrap_err(),
+            lazy.delete_prefab(prefab_id).map(|_| ()).unwrap_err(),
+            lazy.edit_prefab(prefab_id, |_| {}).unwrap_err(),
+        ] {
+            assert!(error.starts_with("Load every scene before"), "{}", error);
+        }
+        // Even once level 1 stops using the hud, the other scenes still do
+        lazy.get_active_scene_mut().unwrap().shared_entity_refs.clear();
+        let error = lazy.delete_shared_entity(hud_id).unwrap_err();
+        assert!(error.contains("'menu' isn't loaded yet"), "{}", error);
+
+        ProjectManager::load_all_scenes(&project_path, &mut lazy).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+        assert!(lazy.unloaded_scenes.is_empty());
+        assert_eq!(lazy.get_scenes_using_entity(hud_id).unwrap().len(), 2);
+        assert!(lazy.delete_shared_entity(hud_id).is_err());
+        assert!(lazy.delete_prefab(prefab_id).is_err());
+    }
+}
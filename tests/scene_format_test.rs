@@ -0,0 +1,225 @@
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::project_manager::ProjectManager;
+use rust_2d_game_engine::scene_format::{self, FORMAT_VERSION, MIGRATIONS};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: &str = "tests/scene_corpus";
+
+    fn corpus_file(name: &str) -> PathBuf {
+        Path::new(CORPUS).join(name)
+    }
+
+    // A project whose scene_manager.json is a copy of `scene_file`
+    fn project_with(scene_file: &Path) -> PathBuf {
+        let project_path = std::env::temp_dir().join(format!("scene_format_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(project_path.join("scenes")).unwrap();
+        fs::copy(scene_file, project_path.join("scenes/scene_manager.json")).unwrap();
+        project_path
+    }
+
+    fn read_json(path: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn has_legacy_attributes(entity: &Entity) -> bool {
+        entity.attributes.values().any(|attr| attr.name == "position" || attr.name.starts_with("transform_"))
+    }
+
+    fn find<'a>(scene_manager: &'a SceneManager, name: &str) -> &'a Entity {
+        scene_manager.scenes.values()
+            .flat_map(|scene| scene.entities.values())
+            .chain(scene_manager.shared_entities.values())
+            .find(|entity| entity.name == name)
+            .unwrap()
+    }
+
+    fn float_attribute(name: &str, value: f32) -> Value {
+        let id = Uuid::new_v4().to_string();
+        json!({ "id": id, "name": name, "data_type": "Float", "value": { "Float": value } })
+    }
+
+    fn entity_json(attributes: Vec<Value>) -> Value {
+        let attributes: serde_json::Map<String, Value> = attributes.into_iter()
+            .map(|attribute| (attribute["id"].as_str().unwrap().to_string(), attribute))
+            .collect();
+        json!({ "id": Uuid::new_v4().to_string(), "name": "entity", "attributes": attributes, "images": [], "sounds": [], "script": null })
+    }
+
+    #[test]
+    fn test_corpus_loads() {
+        let mut files: Vec<PathBuf> = fs::read_dir(CORPUS).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+        assert!(files.len() >= 3);
+
+        for file in files {
+            let project_path = project_with(&file);
+            let loaded = ProjectManager::load_scene_hierarchy(&project_path);
+            fs::remove_dir_all(&project_path).unwrap();
+            let scene_manager = loaded.unwrap_or_else(|e| panic!("{} failed to load: {}", file.display(), e));
+
+            // Every entity survives, in the order of the file, without legacy attributes
+            let json = read_json(&file);
+            for (scene_id, scene) in json["scenes"].as_object().unwrap() {
+                let expected: Vec<&str> = scene["entities"].as_object().unwrap().values().map(|entity| entity["name"].as_str().unwrap()).collect();
+                let loaded_scene = scene_manager.get_scene(scene_id.parse().unwrap()).unwrap();
+                let names: Vec<&str> = loaded_scene.entities.values().map(|entity| entity.name.as_str()).collect();
+                assert_eq!(names, expected, "{}", file.display());
+                assert!(loaded_scene.entities.values().all(|entity| !has_legacy_attributes(entity)), "{}", file.display());
+            }
+        }
+    }
+
+    #[test]
+    fn test_corpus_prefabs_load() {
+        let project_path = std::env::temp_dir().join(format!("scene_format_test_{}", Uuid::new_v4()));
+        let prefab_dir = project_path.join("assets/prefabs");
+        fs::create_dir_all(&prefab_dir).unwrap();
+        for entry in fs::read_dir(CORPUS).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "prefab") {
+                fs::copy(&path, prefab_dir.join(path.file_name().unwrap())).unwrap();
+            }
+        }
+        let prefabs = ProjectManager::load_prefabs(&project_path).unwrap();
+        assert!(prefabs.values().all(|prefab| !has_legacy_attributes(&prefab.entity)));
+        let crate_prefab = prefabs.values().find(|prefab| prefab.name == "crate").unwrap();
+        assert_eq!(crate_prefab.entity.get_position().unwrap(), (150.0, -40.0, 0.0));
+        assert_eq!(crate_prefab.entity.get_attribute_by_name("score").unwrap().value, AttributeValue::Integer(3));
+
+        // Saved again in the current format, with attributes keyed by name
+        let mut scene_manager = SceneManager::new();
+        scene_manager.prefabs = prefabs.clone();
+        ProjectManager::save_prefabs(&project_path, &scene_manager).unwrap();
+        let saved = read_json(&prefab_dir.join("crate.prefab"));
+        let reloaded = ProjectManager::load_prefabs(&project_path).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+        assert_eq!(saved["format_version"], FORMAT_VERSION);
+        assert_eq!(saved["entity"]["attributes"]["score"]["value"]["Integer"], 3);
+        assert_eq!(reloaded[&crate_prefab.id].entity.get_position().unwrap(), (150.0, -40.0, 0.0));
+    }
+
+    #[test]
+    fn test_v1_transform_attributes() {
+        let project_path = project_with(&corpus_file("v1_transform_attributes.json"));
+        let scene_manager = ProjectManager::load_scene_hierarchy(&project_path).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+
+        let camera = find(&scene_manager, "camera").local_transform();
+        assert_eq!((camera.position, camera.z), ((64.0, -32.0), 2.0));
+        assert_eq!((camera.rotation, camera.scale), (0.25, (2.0, 2.0)));
+
+        // The physics position fills in entities left at the origin, shared ones too
+        let crate_entity = find(&scene_manager, "crate");
+        assert_eq!(crate_entity.get_position().unwrap(), (150.0, -40.0, 0.0));
+        assert_eq!(crate_entity.get_attribute_by_name("score").unwrap().value, AttributeValue::Integer(3));
+        assert_eq!(find(&scene_manager, "hud").get_position().unwrap(), (10.0, 10.0, 5.0));
+    }
+
+    #[test]
+    fn test_position_does_not_replace_moved_entities() {
+        // The flappy bird ground was moved in the editor, its stale physics position is dropped
+        let mut json = read_json(&corpus_file("v1_flappy_bird.json"));
+        assert_eq!(scene_format::migrate(&mut json).unwrap(), 1);
+        scene_format::key_attributes_by_id(&mut json);
+        let scene_manager: SceneManager = serde_json::from_value(json).unwrap();
+        assert_eq!(find(&scene_manager, "ground_1").local_transform().position, (-5.0, 250.0));
+        assert_eq!(find(&scene_manager, "bird").local_transform().position, (30.0, 120.0));
+
+        // Rotation and scale are only added when they aren't the default
+        let mut entity = entity_json(vec![float_attribute("transform_rotation_z", 0.0), float_attribute("transform_scale_x", 1.0)]);
+        assert!(scene_format::migrate_entity_transform(&mut entity).unwrap());
+        assert!(entity["attributes"].as_object().unwrap().is_empty());
+        assert!(!scene_format::migrate_entity_transform(&mut entity).unwrap());
+    }
+
+    #[test]
+    fn test_mismatched_attribute_type_fails() {
+        let entity = || entity_json(vec![
+            json!({ "id": "a", "name": "scale", "data_type": "String", "value": { "String": "big" } }),
+            float_attribute("transform_scale_x", 2.0),
+        ]);
+        let error = scene_format::migrate_entity_transform(&mut entity()).unwrap_err();
+        assert!(error.contains("'scale' is not a Vector2"), "{}", error);
+
+        let mut json = json!({ "scenes": { Uuid::new_v4().to_string(): { "entities": { "e": entity() } } } });
+        let error = scene_format::migrate(&mut json).unwrap_err();
+        assert!(error.contains("from format 1"), "{}", error);
+    }
+
+    #[test]
+    fn test_newer_and_invalid_versions() {
+        let mut json = read_json(&corpus_file("v2_gravity_source.json"));
+        json["format_version"] = json!(FORMAT_VERSION + 1);
+        let project_path = std::env::temp_dir().join(format!("scene_format_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(project_path.join("scenes")).unwrap();
+        fs::write(project_path.join("scenes/scene_manager.json"), json.to_string()).unwrap();
+
+        let error = ProjectManager::load_scene_hierarchy(&project_path).unwrap_err();
+        fs::remove_dir_all(&project_path).unwrap();
+        assert!(error.contains("newer version of the engine"), "{}", error);
+        assert!(error.contains(&format!("format {}", FORMAT_VERSION + 1)), "{}", error);
+
+        for version in [json!(0), json!("2"), json!(-1)] {
+            assert!(scene_format::format_version(&json!({ "format_version": version })).is_err());
+        }
+        assert_eq!(scene_format::format_version(&json!({})), Ok(1));
+        assert!(scene_format::migrate(&mut json!([])).is_err());
+    }
+
+    #[test]
+    fn test_current_format_is_not_migrated() {
+        let mut original = read_json(&corpus_file("v2_gravity_source.json"));
+        assert_eq!(scene_format::migrate(&mut original).unwrap(), 2);
+        let mut json = original.clone();
+        assert_eq!(scene_format::migrate(&mut json).unwrap(), FORMAT_VERSION);
+        assert_eq!(json, original);
+    }
+
+    #[test]
+    fn test_migrations_are_registered_in_order() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|(from, _, _)| *from).collect();
+        assert_eq!(versions, (1..FORMAT_VERSION).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_save_writes_version_and_backs_up_old_file() {
+        let original = corpus_file("v1_flappy_bird.json");
+        let project_path = project_with(&original);
+        let scene_file = project_path.join("scenes/scene_manager.json");
+        let backup_file = project_path.join("scenes/scene_manager.v1.json.bak");
+
+        let mut scene_manager = ProjectManager::load_scene_hierarchy(&project_path).unwrap();
+        // Loading alone doesn't touch the project
+        assert!(!backup_file.exists());
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        assert_eq!(fs::read_to_string(&backup_file).unwrap(), fs::read_to_string(&original).unwrap());
+        // The scenes now have their own files
+        assert!(!scene_file.exists());
+
+        // The version comes first in every saved file
+        for file in ["index.json", "shared_entities.json", "game_main.scene"] {
+            let saved = read_json(&project_path.join("scenes").join(file));
+            assert_eq!(saved.as_object().unwrap().keys().next().map(String::as_str), Some("format_version"), "{}", file);
+            assert_eq!(scene_format::format_version(&saved), Ok(FORMAT_VERSION));
+        }
+
+        // Later saves are already in the current format, the backup stays as it was
+        scene_manager.create_scene("level_2").unwrap();
+        ProjectManager::save_scene_hierarchy(&project_path, &scene_manager).unwrap();
+        let backup = fs::read_to_string(&backup_file).unwrap();
+        let reloaded = ProjectManager::load_scene_hierarchy(&project_path).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+        assert_eq!(backup, fs::read_to_string(&original).unwrap());
+        assert_eq!(reloaded.scenes.len(), 2);
+    }
+}
@@ -0,0 +1,202 @@
+use rust_2d_game_engine::camera::SceneCamera;
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::gui::edit_history::{EditCommand, EditHistory};
+use rust_2d_game_engine::render_engine::{RenderEngine, TextureInfo};
+use rust_2d_game_engine::text::*;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3
+    }
+
+    fn add_text(scene: &mut Scene, name: &str, text: Text, position: (f32, f32, f32)) -> Uuid {
+        let entity_id = scene.create_entity(name).unwrap();
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        entity.set_position(position.0, position.1, position.2).unwrap();
+        entity.text = Some(Box::new(text));
+        entity_id
+    }
+
+    #[test]
+    fn test_text_serialization() {
+        let mut entity = Entity::new(Uuid::new_v4(), "label").unwrap();
+        let json = serde_json::to_value(&entity).unwrap();
+        let mut old = json.clone();
+        old.as_object_mut().unwrap().remove("text");
+        assert!(serde_json::from_value::<Entity>(old).unwrap().text.is_none());
+
+        entity.text = Some(Box::new(Text {
+            font: Some(PathBuf::from("assets/fonts/Comica Boom.otf")),
+            align: TextAlign::Center,
+            outline: Some(TextOutline { width: 2.0, color: [0, 0, 0, 255] }),
+            space: TextSpace::Screen,
+            ..Text::new("Score: 0")
+        }));
+        let loaded: Entity = serde_json::from_str(&serde_json::to_string(&entity).unwrap()).unwrap();
+        assert_eq!(loaded.text, entity.text);
+
+        // Settings left out of the file keep their defaults
+        let text: Text = serde_json::from_str(r#"{ "text": "Hi", "align": "right" }"#).unwrap();
+        assert_eq!(text, Text { align: TextAlign::Right, ..Text::new("Hi") });
+    }
+
+    #[test]
+    fn test_world_text_in_z_order() {
+        let mut scene = Scene::new("main").unwrap();
+        for (name, z) in [("back", 0.0), ("front", 2.0)] {
+            let entity_id = scene.create_entity(name).unwrap();
+            let entity = scene.get_entity_mut(entity_id).unwrap();
+            entity.images = vec![PathBuf::from(format!("{}.png", name))];
+            entity.set_position(0.0, 0.0, z).unwrap();
+        }
+        add_text(&mut scene, "label", Text::new("Hello"), (100.0, 50.0, 1.0));
+        add_text(&mut scene, "empty", Text::new(""), (0.0, 0.0, 1.0));
+
+        let mut renderer = RenderEngine::new();
+        renderer.update_viewport_size(800.0, 600.0);
+        for name in ["back", "front"] {
+            let texture_id = RenderEngine::path_to_uuid(Path::new(&format!("{}.png", name)));
+            renderer.texture_cache.insert(texture_id, TextureInfo { data: vec![255; 4 * 4 * 4], dimensions: (4, 4), aspect_ratio: 1.0 });
+        }
+        renderer.camera.zoom = 2.0;
+
+        // Drawn between the two sprites, moved and scaled by the camera; empty text isn't drawn
+        let queue = renderer.render(&scene);
+        assert_eq!(queue.len(), 3);
+        let item = &queue[1];
+        let text = item.text.as_ref().unwrap();
+        assert_eq!(text.text, "Hello");
+        assert_eq!(text.size, 48.0);
+        assert!(close(item.position, (200.0, 100.0)));
+        assert!(queue[0].text.is_none() && queue[2].text.is_none());
+    }
+
+    #[test]
+    fn test_screen_text_stays_in_view() {
+        let mut scene = Scene::new("main").unwrap();
+        let camera_id = scene.default_camera.unwrap();
+        scene.get_entity_mut(camera_id).unwrap().set_position(1000.0, 1000.0, 0.0).unwrap();
+        let text = Text {
+            space: TextSpace::Screen,
+            outline: Some(TextOutline { width: 1.0, color: [0, 0, 0, 255] }),
+            ..Text::new("Score: 0")
+        };
+        add_text(&mut scene, "score", text, (10.0, 20.0, 5.0));
+
+        // In a view twice the camera's size, the text is twice as far and as large
+        let mut renderer = RenderEngine::new();
+        let (render_camera, rect) = SceneCamera::active(&scene).unwrap().view((1600.0, 1200.0));
+        renderer.camera = render_camera;
+        renderer.update_viewport_size(rect.2, rect.3);
+        let queue = renderer.render(&scene);
+        let item = &queue[0];
+        let text = item.text.as_ref().unwrap();
+        assert!(close(item.position, (20.0, 40.0)));
+        assert_eq!(text.size, 48.0);
+        assert_eq!(text.outline.unwrap().width, 2.0);
+
+        // Moving the camera doesn't move it
+        scene.get_entity_mut(camera_id).unwrap().set_position(-500.0, 300.0, 0.0).unwrap();
+        renderer.camera = SceneCamera::active(&scene).unwrap().view((1600.0, 1200.0)).0;
+        assert!(close(renderer.render(&scene)[0].position, (20.0, 40.0)));
+
+        // In the editor it is drawn where the camera's view is
+        let mut editor = RenderEngine::new();
+        editor.update_viewport_size(4000.0, 4000.0);
+        editor.camera.position = (-1000.0, -1000.0);
+        let item = &editor.render(&scene)[0];
+        assert!(close(item.position, (-900.0 + 10.0 + 1000.0, 0.0 + 20.0 + 1000.0)));
+        assert_eq!(item.text.as_ref().unwrap().size, 24.0);
+    }
+
+    #[test]
+    fn test_paint_text_with_outline() {
+        let mut scene = Scene::new("main").unwrap();
+        scene.default_camera = None;
+        let text = Text {
+            align: TextAlign::Center,
+            outline: Some(TextOutline { width: 2.0, color: [0, 0, 0, 255] }),
+            ..Text::new("Game Over")
+        };
+        add_text(&mut scene, "label", text, (400.0, 300.0, 0.0));
+        let mut renderer = RenderEngine::new();
+        renderer.update_viewport_size(800.0, 600.0);
+        let queue = renderer.render(&scene);
+
+        // The outline is the text drawn 8 times around it, under the text itself
+        let ctx = egui::Context::default();
+        let output = ctx.run(egui::RawInput::default(), |ctx| {
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            for item in &queue {
+                renderer.paint(&painter, item, egui::Vec2::ZERO);
+            }
+        });
+        assert_eq!(output.shapes.len(), 9);
+        let egui::Shape::Text(shape) = &output.shapes[8].shape else {
+            panic!("Expected a text shape");
+        };
+        // Centered lines are laid out around the position
+        assert_eq!(shape.pos, egui::pos2(400.0, 300.0));
+        assert!(shape.galley.rect.min.x < 0.0 && shape.galley.rect.max.x > 0.0);
+    }
+
+    #[test]
+    fn test_project_font_is_registered() {
+        let font = Path::new(env!("CARGO_MANIFEST_DIR")).join("demo/flappy_bird/assets/fonts/Comica Boom.otf");
+        let mut renderer = RenderEngine::new();
+        let ctx = egui::Context::default();
+
+        // The default font is used on the frame the font is added
+        let mut families = Vec::new();
+        for _ in 0..2 {
+            let _ = ctx.run(egui::RawInput::default(), |ctx| {
+                families.push(renderer.font_family(ctx, &font));
+            });
+        }
+        assert_eq!(families[0], egui::FontFamily::Proportional);
+        assert_eq!(families[1], egui::FontFamily::Name(font.to_string_lossy().to_string().into()));
+
+        // Files that aren't fonts are never handed to egui
+        let not_a_font = std::env::temp_dir().join(format!("text_test_{}.ttf", Uuid::new_v4()));
+        std::fs::write(&not_a_font, b"not a font").unwrap();
+        for _ in 0..2 {
+            let _ = ctx.run(egui::RawInput::default(), |ctx| {
+                assert_eq!(renderer.font_family(ctx, &not_a_font), egui::FontFamily::Proportional);
+            });
+        }
+        std::fs::remove_file(not_a_font).unwrap();
+    }
+
+    #[test]
+    fn test_text_edits_merge_and_undo() {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("main").unwrap();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("label").unwrap();
+        let mut history = EditHistory::default();
+
+        let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+        let command = EditCommand::set_text(entity, scene_id, Some(Box::new(Text::new("S"))));
+        history.execute(command, &mut scene_manager).unwrap();
+
+        // Typing the rest of the text is one edit
+        for typed in ["Sc", "Sco", "Score"] {
+            let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+            let command = EditCommand::set_text(entity, scene_id, Some(Box::new(Text::new(typed))));
+            history.execute(command, &mut scene_manager).unwrap();
+        }
+        let text = |scene_manager: &SceneManager| {
+            scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().text.as_ref().map(|text| text.text.clone())
+        };
+        assert_eq!(text(&scene_manager), Some("Score".to_string()));
+
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(text(&scene_manager), Some("S".to_string()));
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(text(&scene_manager), None);
+    }
+}
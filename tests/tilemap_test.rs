@@ -0,0 +1,284 @@
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::gui::edit_history::{EditCommand, EditHistory};
+use rust_2d_game_engine::gui::tilemap_editor::{TileBrush, TileTool};
+use rust_2d_game_engine::physics_engine::PhysicsEngine;
+use rust_2d_game_engine::render_engine::{RenderEngine, TextureInfo};
+use rust_2d_game_engine::tilemap::*;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3
+    }
+
+    fn add_tilemap(scene: &mut Scene, tilemap: Tilemap) -> Uuid {
+        let entity_id = scene.create_tilemap("level").unwrap();
+        let entity = scene.get_entity_mut(entity_id).unwrap();
+        entity.images = vec![PathBuf::from("tiles.png")];
+        entity.tilemap = Some(Box::new(tilemap));
+        entity_id
+    }
+
+    fn renderer_with_tileset() -> RenderEngine {
+        let mut renderer = RenderEngine::new();
+        renderer.update_viewport_size(800.0, 600.0);
+        let texture_id = RenderEngine::path_to_uuid(Path::new("tiles.png"));
+        renderer.texture_cache.insert(texture_id, TextureInfo { data: vec![255; 8 * 8 * 4], dimensions: (8, 8), aspect_ratio: 1.0 });
+        renderer
+    }
+
+    #[test]
+    fn test_tile_runs() {
+        let tiles = vec![None, None, None, Some(4), Some(4), Some(7)];
+        assert_eq!(format_tile_runs(&tiles), "3*. 2*4 7");
+        assert_eq!(parse_tile_runs("3*. 2*4 7", 6).unwrap(), tiles);
+        assert_eq!(parse_tile_runs("", 6).unwrap(), Vec::<Tile>::new());
+
+        assert!(parse_tile_runs("x*4", 6).is_err());
+        assert!(parse_tile_runs("2*a", 6).is_err());
+        assert!(parse_tile_runs("3*. 2*4 7", 5).is_err());
+        assert!(parse_tile_runs("18446744073709551615*. 2*4", 6).is_err());
+    }
+
+    #[test]
+    fn test_tilemap_serialization() {
+        let mut entity = Entity::new(Uuid::new_v4(), "level").unwrap();
+        let json = serde_json::to_value(&entity).unwrap();
+        let mut old = json.clone();
+        old.as_object_mut().unwrap().remove("tilemap");
+        assert!(serde_json::from_value::<Entity>(old).unwrap().tilemap.is_none());
+
+        let mut tilemap = Tilemap::new(4, 2, (16.0, 16.0)).unwrap();
+        tilemap.tileset = Tileset { image: 0, columns: 4, rows: 2 };
+        tilemap.fill_rect(0, (0, 1), (3, 1), Some(2));
+        entity.tilemap = Some(Box::new(tilemap));
+
+        // Layers are saved as a short string of runs
+        let json = serde_json::to_value(&entity).unwrap();
+        assert_eq!(json["tilemap"]["layers"][0]["tiles"], "4*. 4*2");
+        let loaded: Entity = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(loaded.tilemap, entity.tilemap);
+
+        // Runs longer than the map and sizes without a cell count are refused
+        let mut too_long = json.clone();
+        too_long["tilemap"]["layers"][0]["tiles"] = "4*. 4000000000*2".into();
+        let error = serde_json::from_value::<Entity>(too_long).unwrap_err().to_string();
+        assert!(error.contains("More than 8 tiles"), "{}", error);
+        let mut too_large = json;
+        too_large["tilemap"]["width"] = 65536.into();
+        too_large["tilemap"]["height"] = 65536.into();
+        assert!(serde_json::from_value::<Entity>(too_large).is_err());
+        assert!(Tilemap::new(65536, 65536, (16.0, 16.0)).is_err());
+    }
+
+    #[test]
+    fn test_paint_fill_and_erase() {
+        let mut tilemap = Tilemap::new(5, 5, (32.0, 32.0)).unwrap();
+        assert!(tilemap.set(0, 2, 2, Some(1)));
+        assert!(!tilemap.set(0, 2, 2, Some(1)));
+        assert!(!tilemap.set(0, 5, 0, Some(1)));
+        assert!(!tilemap.set(0, -1, 0, Some(1)));
+        assert_eq!(tilemap.get(0, 2, 2), Some(1));
+
+        // A wall across the map stops the flood fill
+        tilemap.fill_rect(0, (0, 3), (9, 3), Some(9));
+        assert!(tilemap.fill(0, 0, 0, Some(5)));
+        assert_eq!(tilemap.get(0, 4, 2), Some(5));
+        assert_eq!(tilemap.get(0, 2, 2), Some(1));
+        assert_eq!(tilemap.get(0, 0, 4), None);
+        assert_eq!(tilemap.get(0, 4, 3), Some(9));
+        assert!(!tilemap.fill(0, 0, 0, Some(5)));
+
+        assert!(tilemap.set(0, 2, 2, None));
+        assert_eq!(tilemap.get(0, 2, 2), None);
+    }
+
+    #[test]
+    fn test_resize_keeps_cells() {
+        let mut tilemap = Tilemap::new(3, 3, (32.0, 32.0)).unwrap();
+        tilemap.set(0, 1, 1, Some(3));
+        tilemap.set(0, 2, 2, Some(4));
+        tilemap.add_layer("decor").unwrap();
+
+        tilemap.resize(5, 2).unwrap();
+        assert_eq!(tilemap.get(0, 1, 1), Some(3));
+        assert_eq!(tilemap.get(0, 2, 2), None);
+        assert!(tilemap.layers.iter().all(|layer| layer.tiles.len() == 10));
+
+        // Layers loaded shorter than the map are grown when painted
+        tilemap.layers[1].tiles.clear();
+        assert!(tilemap.set(1, 4, 1, Some(1)));
+        assert_eq!(tilemap.layers[1].tiles.len(), 10);
+    }
+
+    #[test]
+    fn test_solid_rects() {
+        // . # # .
+        // . # # #
+        // # # # #
+        let mut tilemap = Tilemap::new(4, 3, (32.0, 32.0)).unwrap();
+        tilemap.fill_rect(0, (1, 0), (2, 1), Some(0));
+        tilemap.set(0, 3, 1, Some(0));
+        tilemap.fill_rect(0, (0, 2), (3, 2), Some(0));
+        // Tiles of layers without collision aren't solid
+        tilemap.add_layer("decor").unwrap();
+        tilemap.set(1, 0, 0, Some(0));
+
+        let rects = tilemap.solid_rects();
+        assert_eq!(rects.len(), 3);
+        let mut cells = Vec::new();
+        for (x, y, width, height) in rects {
+            for cell_y in y..y + height {
+                for cell_x in x..x + width {
+                    cells.push((cell_x as i32, cell_y as i32));
+                }
+            }
+        }
+        cells.sort();
+        let mut solid: Vec<(i32, i32)> = (0..3).flat_map(|y| (0..4).map(move |x| (x, y)))
+            .filter(|&(x, y)| tilemap.is_solid(x, y))
+            .collect();
+        solid.sort();
+        assert_eq!(cells, solid);
+        assert!(!tilemap.is_solid(0, 0));
+    }
+
+    #[test]
+    fn test_cells_of_turned_tilemap() {
+        let tilemap = Tilemap::new(10, 10, (32.0, 32.0)).unwrap();
+        let transform = Transform::new()
+            .with_position(100.0, 50.0)
+            .with_rotation(std::f32::consts::FRAC_PI_2)
+            .with_uniform_scale(2.0);
+
+        // Turned a quarter turn, the map's x axis points down
+        assert!(close(tilemap.cell_corner(&transform, 0, 0), (100.0, 50.0)));
+        assert!(close(tilemap.cell_corner(&transform, 1, 0), (100.0, 114.0)));
+        assert!(close(tilemap.cell_corner(&transform, 0, 1), (36.0, 50.0)));
+        assert_eq!(tilemap.cell_at(&transform, (90.0, 180.0)), (2, 0));
+        assert_eq!(tilemap.cell_at(&transform, (110.0, 60.0)), (0, -1));
+    }
+
+    #[test]
+    fn test_render_visible_tiles() {
+        let mut scene = Scene::new("main").unwrap();
+        let mut tilemap = Tilemap::new(200, 200, (32.0, 32.0)).unwrap();
+        tilemap.tileset = Tileset { image: 0, columns: 4, rows: 2 };
+        tilemap.fill_rect(0, (0, 0), (199, 199), Some(5));
+        tilemap.add_layer("hidden").unwrap();
+        tilemap.fill_rect(1, (0, 0), (199, 199), Some(1));
+        tilemap.layers[1].visible = false;
+        add_tilemap(&mut scene, tilemap);
+
+        // Only the tiles in the 800x600 view are drawn
+        let mut renderer = renderer_with_tileset();
+        let queue = renderer.render(&scene);
+        assert_eq!(queue.len(), 26 * 19);
+        let item = &queue[0];
+        assert!(close(item.position, (0.0, 0.0)));
+        assert!(close(item.size, (32.0, 32.0)));
+        assert!(close(item.uv.0, (0.25, 0.5)) && close(item.uv.1, (0.5, 1.0)));
+
+        // Moving into the map draws the tiles around the view
+        renderer.camera.position = (1000.0, 1000.0);
+        let queue = renderer.render(&scene);
+        assert_eq!(queue.len(), 26 * 20);
+        assert!(close(queue[0].position, (-8.0, -8.0)));
+    }
+
+    #[test]
+    fn test_tilemap_colliders() {
+        let mut scene = Scene::new("main").unwrap();
+        let mut tilemap = Tilemap::new(10, 5, (32.0, 32.0)).unwrap();
+        tilemap.fill_rect(0, (0, 4), (9, 4), Some(0));
+        tilemap.fill_rect(0, (0, 0), (0, 3), Some(0));
+        add_tilemap(&mut scene, tilemap);
+
+        let props = PhysicsProperties { is_movable: true, affected_by_gravity: true, has_collision: true, collider_size: (16.0, 16.0), ..Default::default() };
+        let crate_id = scene.create_physical_entity("crate", (100.0, 50.0, 0.0), props).unwrap();
+
+        let mut physics_engine = PhysicsEngine::new();
+        // Resting contacts need some damping, without it the crate's position becomes NaN
+        physics_engine.set_contact_parameters(5.0, 30.0);
+        physics_engine.load_scene(&scene);
+
+        // The wall and the floor are one rectangle each
+        let mut rects: Vec<_> = physics_engine.get_collider_data().into_iter()
+            .filter(|collider| collider.entity_id != crate_id)
+            .map(|collider| (collider.position, collider.size))
+            .collect();
+        rects.sort_by(|a, b| a.0.0.total_cmp(&b.0.0));
+        assert_eq!(rects.len(), 2);
+        assert!(close(rects[0].0, (16.0, 80.0)) && close(rects[0].1, (32.0, 160.0)));
+        assert!(close(rects[1].0, (176.0, 144.0)) && close(rects[1].1, (288.0, 32.0)));
+
+        // A falling crate lands on the floor
+        for _ in 0..300 {
+            physics_engine.step(&mut scene);
+        }
+        let (_, y) = physics_engine.get_collider_data().into_iter()
+            .find(|collider| collider.entity_id == crate_id)
+            .unwrap()
+            .position;
+        assert!((y - 120.0).abs() < 1.0, "crate rests at {}", y);
+    }
+
+    #[test]
+    fn test_brush_tools() {
+        let mut tilemap = Tilemap::new(6, 6, (32.0, 32.0)).unwrap();
+        let mut brush = TileBrush { tool: Some(TileTool::Paint), tile: 2, ..Default::default() };
+        assert!(brush.apply(&mut tilemap, (1, 1)));
+        assert_eq!(tilemap.get(0, 1, 1), Some(2));
+
+        brush.tool = Some(TileTool::Rect);
+        brush.rect_start = Some((4, 4));
+        brush.tile = 3;
+        assert!(brush.apply(&mut tilemap, (10, 2)));
+        assert_eq!(tilemap.get(0, 5, 4), Some(3));
+        assert_eq!(tilemap.get(0, 4, 2), Some(3));
+        assert_eq!(tilemap.get(0, 3, 3), None);
+
+        brush.tool = Some(TileTool::Erase);
+        assert!(brush.apply(&mut tilemap, (1, 1)));
+        assert_eq!(tilemap.get(0, 1, 1), None);
+
+        brush.tool = Some(TileTool::Fill);
+        brush.tile = 1;
+        assert!(brush.apply(&mut tilemap, (0, 0)));
+        assert_eq!(tilemap.get(0, 3, 3), Some(1));
+        assert_eq!(tilemap.get(0, 5, 4), Some(3));
+
+        brush.tool = None;
+        assert!(!brush.apply(&mut tilemap, (0, 0)));
+    }
+
+    #[test]
+    fn test_brush_stroke_is_one_edit() {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("main").unwrap();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_tilemap("level").unwrap();
+        let mut history = EditHistory::default();
+
+        let tiles = |scene_manager: &SceneManager| {
+            let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+            (0..3).filter(|x| entity.tilemap.as_ref().unwrap().get(0, *x, 0).is_some()).count()
+        };
+        for x in 0..3 {
+            let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+            let mut tilemap = entity.tilemap.clone().unwrap();
+            tilemap.set(0, x, 0, Some(1));
+            let command = EditCommand::set_tilemap(entity, scene_id, Some(tilemap));
+            history.execute(command, &mut scene_manager).unwrap();
+        }
+        assert_eq!(tiles(&scene_manager), 3);
+
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(tiles(&scene_manager), 0);
+        history.redo(&mut scene_manager).unwrap();
+        assert_eq!(tiles(&scene_manager), 3);
+    }
+}
@@ -0,0 +1,103 @@
+use rust_2d_game_engine::ecs::*;
+use rust_2d_game_engine::gui::edit_history::{EditCommand, EditHistory};
+use rust_2d_game_engine::physics_engine::PhysicsEngine;
+use rust_2d_game_engine::project_manager::ProjectManager;
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn float_attribute(entity: &mut Entity, name: &str, value: f32) {
+        entity.create_attribute(name, AttributeType::Float, AttributeValue::Float(value)).unwrap();
+    }
+
+    fn has_legacy_attributes(entity: &Entity) -> bool {
+        entity.attributes.values().any(|attr| attr.name == "position" || attr.name.starts_with("transform_"))
+    }
+
+    fn falling() -> PhysicsProperties {
+        PhysicsProperties { is_movable: true, affected_by_gravity: true, collider_size: (10.0, 10.0), ..Default::default() }
+    }
+
+    #[test]
+    fn test_prefab_files_are_migrated() {
+        let project_path = std::env::temp_dir().join(format!("transform_test_{}", Uuid::new_v4()));
+        let mut template = Entity::new(Uuid::new_v4(), "coin").unwrap();
+        template.create_attribute("position", AttributeType::Vector2, AttributeValue::Vector2(8.0, 16.0)).unwrap();
+        float_attribute(&mut template, "transform_rotation_z", 0.5);
+        let prefab = Prefab::from_entity("coin", &template);
+        fs::create_dir_all(project_path.join("assets/prefabs")).unwrap();
+        fs::write(project_path.join("assets/prefabs/coin.prefab"), serde_json::to_string(&prefab).unwrap()).unwrap();
+
+        let prefabs = ProjectManager::load_prefabs(&project_path).unwrap();
+        fs::remove_dir_all(&project_path).unwrap();
+        let entity = &prefabs[&prefab.id].entity;
+        assert!(!has_legacy_attributes(entity));
+        let transform = entity.local_transform();
+        assert_eq!((transform.position, transform.rotation), ((8.0, 16.0), 0.5));
+    }
+
+    #[test]
+    fn test_demo_project_loads_without_legacy_attributes() {
+        let scene_manager = ProjectManager::load_scene_hierarchy(Path::new("demo/flappy_bird")).unwrap();
+        let entities: Vec<&Entity> = scene_manager.scenes.values().flat_map(|scene| scene.entities.values()).collect();
+        assert!(entities.iter().all(|entity| !has_legacy_attributes(entity)));
+
+        let position_of = |name: &str| entities.iter().find(|entity| entity.name == name).unwrap().local_transform().position;
+        assert_eq!(position_of("bird"), (30.0, 120.0));
+        assert_eq!(position_of("ground_1"), (-5.0, 250.0));
+    }
+
+    #[test]
+    fn test_bodies_start_at_entity_transform() {
+        // No `position` attribute: the body is placed from x, y and rotation
+        let mut scene = Scene::new("test_scene").unwrap();
+        let props = PhysicsProperties { can_rotate: true, ..falling() };
+        let entity_id = scene.create_physical_entity("crate", (120.0, -30.0, 0.0), props).unwrap();
+        scene.get_entity_mut(entity_id).unwrap()
+            .set_local_transform(&Transform::new().with_position(120.0, -30.0).with_rotation(0.5))
+            .unwrap();
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        assert_eq!(physics_engine.interpolated_positions(1.0)[&entity_id], (120.0, -30.0));
+        assert!((physics_engine.interpolated_rotations(1.0)[&entity_id] - 0.5).abs() < 1e-5);
+
+        // Steps write the body back to x and y only
+        for _ in 0..30 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+        let entity = scene.get_entity(entity_id).unwrap();
+        assert_eq!(entity.get_x(), 120.0);
+        assert!(entity.get_y() > -30.0, "The crate should have fallen, it is at y = {}", entity.get_y());
+        assert!(entity.get_attribute_by_name("position").is_err());
+    }
+
+    #[test]
+    fn test_set_transform_undo() {
+        let mut scene_manager = SceneManager::new();
+        let scene_id = scene_manager.create_scene("test_scene").unwrap();
+        let entity_id = scene_manager.get_scene_mut(scene_id).unwrap().create_entity("player").unwrap();
+        let mut history = EditHistory::default();
+
+        // Dragging the rotation is a single edit
+        for rotation in [0.1, 0.2, 0.3] {
+            let entity = scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap();
+            let transform = Transform::new().with_position(5.0, 6.0).with_rotation(rotation).with_scale(2.0, 2.0);
+            let command = EditCommand::set_transform(entity, scene_id, transform);
+            assert_eq!(command.description(), "Edit transform");
+            history.execute(command, &mut scene_manager).unwrap();
+        }
+
+        let transform = |scene_manager: &SceneManager| scene_manager.get_scene(scene_id).unwrap().get_entity(entity_id).unwrap().local_transform();
+        assert_eq!((transform(&scene_manager).rotation, transform(&scene_manager).scale), (0.3, (2.0, 2.0)));
+        history.undo(&mut scene_manager).unwrap();
+        assert_eq!(transform(&scene_manager), Transform::new());
+        history.redo(&mut scene_manager).unwrap();
+        assert_eq!(transform(&scene_manager).position, (5.0, 6.0));
+    }
+}